@@ -0,0 +1,111 @@
+//! Peak heap of parsing a large generated `.ax` file whole, as a normal run
+//! does, against block by block, as `--stream` does.
+//!
+//!     cargo bench --bench parse_memory [-- <blocks>]
+//!
+//! Block by block is measured twice: keeping every test, like a streamed
+//! run that keeps results for its summary, and dropping each block's tests
+//! once parsed, which leaves only what parsing itself holds. With the
+//! default 10,000 blocks (1.4 MiB of source) this gave 84 MiB whole,
+//! 43 MiB with tests kept and 5.6 MiB with tests dropped.
+
+use axotly::parser::AxParser;
+use axotly::template::Variables;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+/// The system allocator, keeping count of the bytes in use and their peak
+struct Counting;
+
+static IN_USE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let in_use = IN_USE.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(in_use, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        IN_USE.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// A file of `blocks` tests, each a request with a header and three checks
+fn generate(blocks: usize) -> String {
+    let mut source = String::from("VAR base = https://api.example.com\n\n");
+    for i in 0..blocks {
+        source.push_str(&format!(
+            "TEST Get user {i}\nGET {{{{base}}}}/users/{i}\nAccept: application/json\n\n\
+             EXPECT status == 200\nEXPECT body.id == {i}\nEXPECT body.email EXISTS\nEND\n\n"
+        ));
+    }
+    source
+}
+
+/// Heap allocated by `run` at its peak, beyond what was in use before, and
+/// how long it took
+fn measure(run: impl FnOnce()) -> (usize, f64) {
+    let before = IN_USE.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+    let start = Instant::now();
+    run();
+    (PEAK.load(Ordering::Relaxed) - before, start.elapsed().as_secs_f64())
+}
+
+fn main() {
+    let blocks = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse().ok())
+        .unwrap_or(10_000);
+    let source = generate(blocks);
+    let variables = Variables::new();
+    let base_dir = Path::new("");
+    println!(
+        "{} blocks, {:.1} MiB of source\n",
+        blocks,
+        source.len() as f64 / (1 << 20) as f64
+    );
+
+    let whole = measure(|| {
+        let tests = AxParser::parse_file_in(&source, &variables, base_dir).unwrap();
+        assert_eq!(tests.len(), blocks);
+    });
+    let kept = measure(|| {
+        let mut tests = Vec::new();
+        for block in AxParser::blocks(&source, &variables, base_dir).unwrap() {
+            tests.extend(block.unwrap());
+        }
+        assert_eq!(tests.len(), blocks);
+    });
+    let dropped = measure(|| {
+        let mut count = 0;
+        for block in AxParser::blocks(&source, &variables, base_dir).unwrap() {
+            count += block.unwrap().len();
+        }
+        assert_eq!(count, blocks);
+    });
+
+    for (mode, (peak, seconds)) in [
+        ("whole file", whole),
+        ("blocks, tests kept", kept),
+        ("blocks, tests dropped", dropped),
+    ] {
+        println!(
+            "{:<22} peak heap {:>8.1} MiB  {:>6.2}s",
+            mode,
+            peak as f64 / (1 << 20) as f64,
+            seconds
+        );
+    }
+}
@@ -0,0 +1,18 @@
+//! Any input, however malformed, parses to tests and skipped blocks
+//! without panicking.
+//!
+//! cargo +nightly fuzz run parse -- -dict=fuzz/ax.dict
+
+#![no_main]
+
+use axotly::parser::AxParser;
+use axotly::template::Variables;
+use libfuzzer_sys::fuzz_target;
+use std::path::Path;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(source) = std::str::from_utf8(data) else {
+        return;
+    };
+    let _ = AxParser::parse_file_recovering(source, &Variables::new(), Path::new(""));
+});
@@ -0,0 +1,19 @@
+//! The syntax tree of a file serializes back to exactly its source, which
+//! `axotly migrate` relies on to leave what it does not rewrite untouched.
+//!
+//! cargo +nightly fuzz run roundtrip -- -dict=fuzz/ax.dict
+
+#![no_main]
+
+use axotly::parser::{syntax, tree::Node};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(source) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(tree) = Node::parse(source) {
+        assert_eq!(tree.serialize(), source);
+    }
+    let _ = syntax::migrate(source);
+});
@@ -0,0 +1,153 @@
+//! Runs of a suite against several live versions of an API
+//! (`--api-versions v1,v2`).
+//!
+//! The suite runs once per version, each request sending the `header` of
+//! `[api_versions]` in the config with the version's value, and the tests
+//! templated with the version's `variables` over the environment's: an
+//! expectation such as `EXPECT body.{{total_field}} == 3` follows the version
+//! it runs against. Each run prints its own summary, and a table of every
+//! version's outcome closes the whole run.
+
+use anyhow::Result;
+use owo_colors::OwoColorize;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::domain::Renderer;
+use crate::renderers::output::Output;
+use crate::runner::{RunOptions, RunOutcome, Runner};
+
+/// Run the suite at `path` once per version in `names`
+pub async fn run(
+    path: &Path,
+    options: &RunOptions,
+    config: &Config,
+    names: &[String],
+    renderer: &dyn Renderer,
+) -> Result<RunOutcome> {
+    let header = &config.api_versions.header;
+    let mut outcomes = Vec::with_capacity(names.len());
+    let console = Output::console(renderer);
+
+    for name in names {
+        let version = config.api_version(name);
+        let value = version.value.unwrap_or_else(|| name.clone());
+        writeln!(
+            console,
+            "\n{} {} {}",
+            "▶ API version".cyan().bold(),
+            name.bold(),
+            format!("({}: {})", header, value).dimmed()
+        );
+
+        let mut options = options.clone();
+        options.default_headers.push((header.clone(), value));
+        options.overrides.extend(version.variables);
+        outcomes.push(Runner::run_path(path, &options, renderer).await?);
+    }
+
+    writeln!(console, "\n{}", "API versions".bold());
+    for (name, outcome) in names.iter().zip(&outcomes) {
+        let status = match outcome {
+            RunOutcome::Completed => "passed".green().to_string(),
+            RunOutcome::Failed => "failed".red().to_string(),
+            RunOutcome::Unreachable => "unreachable".red().to_string(),
+            RunOutcome::TimedOut => "timed out".red().to_string(),
+            RunOutcome::Invalid => "invalid".red().to_string(),
+        };
+        writeln!(console, "  {:<12} {}", name, status);
+    }
+
+    Ok(overall(&outcomes))
+}
+
+/// Outcome of the whole run: that of the first version that did not pass
+fn overall(outcomes: &[RunOutcome]) -> RunOutcome {
+    outcomes
+        .iter()
+        .copied()
+        .find(|outcome| *outcome != RunOutcome::Completed)
+        .unwrap_or(RunOutcome::Completed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::TestCase;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    struct Silent;
+
+    impl Renderer for Silent {
+        fn start(&self, _total: Option<usize>) {}
+        fn test(&self, _test: &TestCase, _file: Option<&PathBuf>) {}
+        fn summary(&self, _tests: &[TestCase], _total_duration: &Duration) {}
+    }
+
+    #[tokio::test]
+    async fn runs_every_version_with_pre_resolved_hosts() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let heads = Arc::clone(&seen);
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut head = [0; 1024];
+                let read = stream.read(&mut head).await.unwrap_or_default();
+                heads
+                    .lock()
+                    .unwrap()
+                    .push(String::from_utf8_lossy(&head[..read]).to_ascii_lowercase());
+                let _ = stream
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                    .await;
+            }
+        });
+
+        let file = std::env::temp_dir().join(format!("axotly-versions-{}.ax", std::process::id()));
+        let test = format!(
+            "TEST Health\nGET http://localhost:{}/health\n\nEXPECT status == 200\nEND\n",
+            port
+        );
+        std::fs::write(&file, test).unwrap();
+
+        let options = RunOptions {
+            max_concurrency: 1,
+            preresolve: true,
+            ..RunOptions::default()
+        };
+        let names = ["v1".to_string(), "v2".to_string()];
+        let outcome = run(&file, &options, &Config::default(), &names, &Silent)
+            .await
+            .unwrap();
+        std::fs::remove_file(file).unwrap();
+
+        assert_eq!(outcome, RunOutcome::Completed);
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 2);
+        assert!(seen[0].contains("api-version: v1\r\n"));
+        assert!(seen[1].contains("api-version: v2\r\n"));
+    }
+
+    #[test]
+    fn first_version_not_passing_decides() {
+        assert_eq!(overall(&[]), RunOutcome::Completed);
+        assert_eq!(
+            overall(&[RunOutcome::Completed, RunOutcome::Completed]),
+            RunOutcome::Completed
+        );
+        assert_eq!(
+            overall(&[
+                RunOutcome::Completed,
+                RunOutcome::Failed,
+                RunOutcome::TimedOut
+            ]),
+            RunOutcome::Failed
+        );
+    }
+}
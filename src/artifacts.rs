@@ -0,0 +1,420 @@
+//! Post-mortem artifacts of failed tests (`--artifacts <dir>`).
+//!
+//! CI logs get truncated and colored output is not meant to be parsed, so
+//! every test that fails or does not run gets its own folder,
+//! `<dir>/<test-id>/`, holding:
+//!
+//! - `request.http`: the request as sent (method, URL, headers, body)
+//! - `response.http`: status line, headers and body as received
+//! - `result.json`: outcome, errors and timing, as the ndjson renderer
+//!   reports them
+//! - `variables.json`: the environment's variables and the values captured
+//!   so far in the run
+//!
+//! Artifacts end up in CI storage, so credentials stay out of them:
+//! `Authorization`, `Proxy-Authorization`, cookie and signature headers are
+//! masked, variables that hold credentials are left out, and the bodies of
+//! tests with `SENSITIVE` assertions are not written.
+//!
+//! Multi-step tests also get `step-<n>.request.http` and
+//! `step-<n>.response.http` for each step that was sent.
+//!
+//! The test id is `<file stem>-<index>-<name>`, lowercased with anything but
+//! letters and digits turned into `-`, so it is stable across runs and safe
+//! on every filesystem. A test that passes has its folder from earlier runs
+//! removed.
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::domain::http_request::{Body, FormPart, HttpRequest, HttpResponse};
+use crate::domain::test_case::{TestCase, TestResult};
+use crate::renderers::ndjson::test_to_json;
+use crate::template::{self, Variables};
+
+/// Headers that carry credentials, whatever the request
+const CREDENTIAL_HEADERS: [&str; 4] = ["authorization", "proxy-authorization", "cookie", "set-cookie"];
+
+/// Words marking a variable name as holding a credential (`access_token`)
+const CREDENTIAL_NAMES: [&str; 6] = ["token", "secret", "password", "apikey", "api_key", "session"];
+
+/// Shortest variable value looked for in credential headers; shorter ones
+/// (ids, flags) turn up in tokens by chance
+const MIN_CREDENTIAL: usize = 6;
+
+/// Written instead of the bodies of tests with `SENSITIVE` assertions
+const HIDDEN_BODY: &str = "<body hidden: test has SENSITIVE assertions>";
+
+pub struct Artifacts {
+    dir: PathBuf,
+    /// Headers masked besides [`CREDENTIAL_HEADERS`], e.g. the signer's
+    masked: Vec<String>,
+}
+
+impl Artifacts {
+    pub fn new(dir: PathBuf, masked: Vec<String>) -> Self {
+        Self { dir, masked }
+    }
+
+    fn is_masked(&self, header: &str) -> bool {
+        CREDENTIAL_HEADERS.iter().any(|name| name.eq_ignore_ascii_case(header))
+            || self.masked.iter().any(|name| name.eq_ignore_ascii_case(header))
+    }
+
+    /// `request` with credential headers masked and, for a sensitive test,
+    /// without its body
+    fn scrub_request(&self, request: &HttpRequest, sensitive: bool) -> HttpRequest {
+        let mut request = request.clone();
+        for (name, value) in &mut request.headers {
+            if self.is_masked(name) {
+                *value = mask(value);
+            }
+        }
+        if sensitive && request.body.is_some() {
+            request.body = Some(Body::Text(HIDDEN_BODY.to_string()));
+        }
+        request
+    }
+
+    /// `response` with credential headers masked and, for a sensitive test,
+    /// without its body
+    fn scrub_response(&self, response: &HttpResponse, sensitive: bool) -> HttpResponse {
+        let mut response = response.clone();
+        for (name, value) in &mut response.headers {
+            if self.is_masked(name) {
+                *value = mask(value);
+            }
+        }
+        if sensitive && (response.body.is_some() || !response.bytes.is_empty()) {
+            response.body = Some(HIDDEN_BODY.to_string());
+        }
+        response
+    }
+
+    /// Whether `name` holds a credential: it says so, one of `test`'s
+    /// credential headers is rendered from it, or its value was sent in one
+    fn is_credential(&self, test: &TestCase, name: &str, value: &str) -> bool {
+        let lower = name.to_ascii_lowercase();
+        if CREDENTIAL_NAMES.iter().any(|word| lower.contains(word)) {
+            return true;
+        }
+
+        let exchanges = std::iter::once((&test.request, &test.response))
+            .chain(test.steps.iter().map(|step| (&step.request, &step.response)));
+        for (request, response) in exchanges {
+            let templated = request.deferred.iter().flat_map(|deferred| &deferred.headers);
+            let basic = request.deferred.iter().flat_map(|deferred| &deferred.basic);
+            let rendered_from = templated
+                .filter(|(header, _)| self.is_masked(header))
+                .map(|(_, text)| text)
+                .chain(basic.flat_map(|(user, password)| [user, password]))
+                .any(|text| template::placeholders(text).contains(&name));
+            let carried = sent(request, response)
+                .headers
+                .iter()
+                .filter(|(header, _)| self.is_masked(header))
+                .any(|(_, header)| value.len() >= MIN_CREDENTIAL && carries(header, value));
+            if rendered_from || carried {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Write the artifacts of `test`, the `index`-th test of `file`, unless
+    /// it passed or was skipped. Returns the folder written to, if any.
+    pub fn write(
+        &self,
+        file: &Path,
+        index: usize,
+        test: &TestCase,
+        variables: &Variables,
+    ) -> Result<Option<PathBuf>> {
+        let dir = self.dir.join(test_id(file, index, test.name.as_deref()));
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)
+                .with_context(|| format!("Failed to clear {}", dir.display()))?;
+        }
+        if !matches!(
+            test.result,
+            Some(TestResult::Failed { .. } | TestResult::NotRun { .. })
+        ) {
+            return Ok(None);
+        }
+
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let write = |name: &str, contents: String| {
+            let path = dir.join(name);
+            std::fs::write(&path, contents)
+                .with_context(|| format!("Failed to write {}", path.display()))
+        };
+
+        let sensitive = test.is_sensitive();
+        let request_file = |request: &HttpRequest, response: &Option<HttpResponse>| {
+            request_text(&self.scrub_request(sent(request, response), sensitive))
+        };
+        let response_file =
+            |response: &HttpResponse| response_text(&self.scrub_response(response, sensitive));
+
+        write("request.http", request_file(&test.request, &test.response))?;
+        if let Some(response) = &test.response {
+            write("response.http", response_file(response))?;
+        }
+        for (number, step) in test.steps.iter().enumerate().map(|(i, s)| (i + 1, s)) {
+            // Steps after a failing one are never sent
+            let Some(response) = &step.response else {
+                break;
+            };
+            write(
+                &format!("step-{}.request.http", number),
+                request_file(&step.request, &step.response),
+            )?;
+            write(
+                &format!("step-{}.response.http", number),
+                response_file(response),
+            )?;
+        }
+
+        let mut result = test_to_json(test);
+        result["file"] = file.display().to_string().into();
+        write("result.json", serde_json::to_string_pretty(&result)?)?;
+
+        let variables: std::collections::BTreeMap<_, _> = variables
+            .iter()
+            .filter(|(name, value)| !self.is_credential(test, name, value))
+            .collect();
+        write("variables.json", serde_json::to_string_pretty(&variables)?)?;
+
+        Ok(Some(dir))
+    }
+}
+
+/// Folder name of the `index`-th test of `file`
+pub fn test_id(file: &Path, index: usize, name: Option<&str>) -> String {
+    let stem = file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let raw = format!("{}-{}-{}", stem, index + 1, name.unwrap_or("unnamed"));
+
+    let mut id = String::with_capacity(raw.len());
+    for c in raw.chars() {
+        if c.is_ascii_alphanumeric() {
+            id.push(c.to_ascii_lowercase());
+        } else if !id.is_empty() && !id.ends_with('-') {
+            id.push('-');
+        }
+    }
+    id.trim_end_matches('-').to_string()
+}
+
+/// The request as it went out (placeholders filled in, signed), when known
+fn sent<'a>(request: &'a HttpRequest, response: &'a Option<HttpResponse>) -> &'a HttpRequest {
+    response
+        .as_ref()
+        .and_then(|response| response.request.as_ref())
+        .unwrap_or(request)
+}
+
+/// Whether the header value `header` contains `value`, as is or inside
+/// Basic credentials
+fn carries(header: &str, value: &str) -> bool {
+    if header.contains(value) {
+        return true;
+    }
+    let basic = header
+        .strip_prefix("Basic ")
+        .and_then(|encoded| base64::engine::general_purpose::STANDARD.decode(encoded.trim()).ok())
+        .and_then(|decoded| String::from_utf8(decoded).ok());
+    basic.is_some_and(|credentials| credentials.contains(value))
+}
+
+/// `value` with its credential replaced, keeping the scheme (`Bearer`) when
+/// there is one
+fn mask(value: &str) -> String {
+    match value.split_once(' ') {
+        Some((scheme, _)) if !scheme.contains('=') => format!("{} <masked>", scheme),
+        _ => "<masked>".to_string(),
+    }
+}
+
+fn sorted_headers(out: &mut String, headers: &std::collections::HashMap<String, String>) {
+    let mut headers: Vec<_> = headers.iter().collect();
+    headers.sort();
+    for (name, value) in headers {
+        let _ = writeln!(out, "{}: {}", name, value);
+    }
+}
+
+/// The request as HTTP-like text: request line, sorted headers and body
+pub fn request_text(request: &HttpRequest) -> String {
+    let mut out = format!("{} {}\n", request.method, request.url);
+    sorted_headers(&mut out, &request.headers);
+    match &request.body {
+        Some(Body::Text(text)) => {
+            let _ = write!(out, "\n{}\n", text);
+        }
+        Some(Body::Json(json)) => {
+            let _ = write!(out, "\n{:#}\n", json);
+        }
+        Some(Body::Bytes(bytes)) => {
+            let _ = write!(out, "\n<{} bytes>\n", bytes.len());
+        }
+        Some(Body::Stream(stream)) => {
+            let _ = write!(out, "\n<stream: {}>\n", stream);
+        }
+        Some(Body::Multipart(parts)) => {
+            out.push('\n');
+            for part in parts {
+                let _ = match part {
+                    FormPart::Text { name, value } => writeln!(out, "{} = {}", name, value),
+                    FormPart::File { name, path } => {
+                        writeln!(out, "{} file={}", name, path.display())
+                    }
+                };
+            }
+        }
+        None => {}
+    }
+    out
+}
+
+fn response_text(response: &HttpResponse) -> String {
+    let mut out = format!(
+        "{} ({} ms)\n",
+        response.status,
+        response.duration.as_millis()
+    );
+    sorted_headers(&mut out, &response.headers);
+    match &response.body {
+        Some(body) => {
+            let _ = write!(out, "\n{}\n", body);
+        }
+        None if !response.bytes.is_empty() => {
+            let _ = write!(out, "\n<{} bytes>\n", response.bytes.len());
+        }
+        None => {}
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::AxParser;
+    use std::time::Duration;
+
+    #[test]
+    fn test_ids_are_filesystem_safe() {
+        assert_eq!(
+            test_id(Path::new("tests/users.ax"), 0, Some("Create user [row 2]")),
+            "users-1-create-user-row-2"
+        );
+        assert_eq!(test_id(Path::new("api.ax"), 4, None), "api-5-unnamed");
+    }
+
+    #[test]
+    fn writes_failed_tests_only() {
+        let input = r#"TEST Login
+POST https://example.com/login
+BODY
+{"user": "ada"}
+BODYEND
+
+EXPECT status == 200
+END
+"#
+        .to_string();
+        let mut test = AxParser::parse_file(&input).unwrap().remove(0);
+        test.result = Some(TestResult::Failed {
+            duration: Duration::from_millis(12),
+            errors: Vec::new(),
+        });
+
+        let dir = std::env::temp_dir().join(format!("axotly-artifacts-{}", std::process::id()));
+        let artifacts = Artifacts::new(dir.clone(), Vec::new());
+        let variables = Variables::from([("host".to_string(), "example.com".to_string())]);
+
+        let written = artifacts
+            .write(Path::new("auth.ax"), 0, &test, &variables)
+            .unwrap()
+            .unwrap();
+        let request = std::fs::read_to_string(written.join("request.http")).unwrap();
+        assert!(request.starts_with("POST https://example.com/login\n"));
+        assert!(request.contains("\"user\""));
+        let saved = std::fs::read_to_string(written.join("variables.json")).unwrap();
+        assert!(saved.contains("example.com"));
+        assert!(written.join("result.json").exists());
+
+        test.result = Some(TestResult::Passed {
+            duration: Duration::ZERO,
+        });
+        assert!(artifacts
+            .write(Path::new("auth.ax"), 0, &test, &variables)
+            .unwrap()
+            .is_none());
+        assert!(!written.exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_credentials_out() {
+        let input = r#"TEST Profile
+GET https://example.com/me
+Authorization: Bearer {{access}}
+X-Signature: 0badc0ffee
+Cookie: sid=abcdef123456
+
+EXPECT body.ssn == "000" SENSITIVE
+END
+"#;
+        let variables = Variables::from([
+            ("access".to_string(), "eyJhbGciOi.payload".to_string()),
+            ("sid".to_string(), "abcdef123456".to_string()),
+            ("api_token".to_string(), "unused-elsewhere".to_string()),
+            ("host".to_string(), "example.com".to_string()),
+        ]);
+        let mut test = AxParser::parse_file_with(input, &variables).unwrap().remove(0);
+        test.result = Some(TestResult::Failed {
+            duration: Duration::ZERO,
+            errors: Vec::new(),
+        });
+        test.response = Some(HttpResponse {
+            request: None,
+            duration: Duration::ZERO,
+            status: 200,
+            headers: [("Set-Cookie".to_string(), "sid=fedcba654321".to_string())].into(),
+            body: Some(r#"{"ssn": "123-45-6789"}"#.to_string()),
+            bytes: Vec::new(),
+            cookies: Vec::new(),
+            trailers: Default::default(),
+        });
+
+        let dir = std::env::temp_dir().join(format!("axotly-masked-{}", std::process::id()));
+        let artifacts = Artifacts::new(dir.clone(), vec!["X-Signature".to_string()]);
+        let written = artifacts
+            .write(Path::new("me.ax"), 0, &test, &variables)
+            .unwrap()
+            .unwrap();
+        let read = |name: &str| std::fs::read_to_string(written.join(name)).unwrap();
+
+        let request = read("request.http");
+        assert!(request.contains("Authorization: Bearer <masked>\n"));
+        assert!(request.contains("Cookie: <masked>\n"));
+        assert!(request.contains("X-Signature: <masked>\n"));
+        let response = read("response.http");
+        assert!(response.contains("Set-Cookie: <masked>\n"));
+        assert!(response.contains(HIDDEN_BODY));
+        assert!(!response.contains("123-45-6789"));
+
+        let saved: std::collections::BTreeMap<String, String> =
+            serde_json::from_str(&read("variables.json")).unwrap();
+        assert_eq!(saved.keys().collect::<Vec<_>>(), ["host"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
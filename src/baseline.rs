@@ -0,0 +1,179 @@
+//! Comparison of a run with a baseline report, for `--baseline`.
+//!
+//! The baseline is the NDJSON report of an earlier run, usually of the main
+//! branch in CI (`--renderer ndjson=results-main.json`, or a `--log`). Each
+//! test that fails now is annotated `pre-existing` when it already failed
+//! there, or `new` otherwise, and the run ends with the count of each and the
+//! names of the new failures, so a branch is only answerable for those.
+//! The exit code is not affected.
+//!
+//! Tests are identified by their file, as given on the command line, and
+//! name.
+
+use anyhow::{Context, Result};
+use owo_colors::OwoColorize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+use crate::domain::test_case::{TestCase, TestResult};
+use crate::renderers::output::Output;
+
+/// Annotation of failures the baseline already had
+pub const PRE_EXISTING: &str = "pre-existing";
+/// Annotation of failures new since the baseline
+pub const NEW: &str = "new";
+
+#[derive(Debug, Default)]
+pub struct Baseline {
+    path: PathBuf,
+    /// File and name of each test that failed in the baseline
+    failed: HashSet<(PathBuf, Option<String>)>,
+}
+
+/// `path` without `.` components, so `./api/a.ax` and `api/a.ax` match
+fn normalized(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+impl Baseline {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read baseline {}", path.display()))?;
+        let mut baseline =
+            Self::parse(&text).with_context(|| format!("Invalid baseline {}", path.display()))?;
+        baseline.path = path.to_path_buf();
+        Ok(baseline)
+    }
+
+    /// Read the failed tests of an NDJSON report
+    fn parse(text: &str) -> Result<Self> {
+        let mut failed = HashSet::new();
+        for (number, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event: Value = serde_json::from_str(line)
+                .with_context(|| format!("Line {} is not JSON", number + 1))?;
+            if event["event"] != "test" || event["status"] != "failed" {
+                continue;
+            }
+            let file = event["file"]
+                .as_str()
+                .map(Path::new)
+                .unwrap_or(Path::new(""));
+            let name = event["name"].as_str().map(str::to_string);
+            failed.insert((normalized(file), name));
+        }
+        Ok(Self {
+            path: PathBuf::new(),
+            failed,
+        })
+    }
+
+    /// Annotate `test` from `file` as a new or pre-existing failure
+    pub fn mark(&self, file: &Path, test: &mut TestCase) {
+        if !matches!(test.result, Some(TestResult::Failed { .. })) {
+            return;
+        }
+        let key = (normalized(file), test.name.clone());
+        let label = if self.failed.contains(&key) {
+            PRE_EXISTING
+        } else {
+            NEW
+        };
+        test.annotations.push(label.to_string());
+    }
+
+    /// Write to `out` how the failures of `tests`, once marked, compare
+    pub fn report(&self, tests: &[TestCase], out: &Output) {
+        let marked = |label: &'static str| {
+            tests
+                .iter()
+                .filter(move |test| test.annotations.iter().any(|a| a == label))
+        };
+        let pre_existing = marked(PRE_EXISTING).count();
+        let new: Vec<_> = marked(NEW).collect();
+
+        writeln!(
+            out,
+            "\n{} {} new, {} pre-existing failure(s) against {}",
+            "≡ Baseline:".cyan().bold(),
+            if new.is_empty() {
+                "0".green().to_string()
+            } else {
+                new.len().to_string().red().bold().to_string()
+            },
+            pre_existing,
+            self.path.display()
+        );
+        for test in new {
+            let file = test
+                .location
+                .as_ref()
+                .map(|l| l.to_string())
+                .unwrap_or_default();
+            writeln!(
+                out,
+                "  {} {} {}",
+                "✗".red(),
+                test.name.as_deref().unwrap_or("<unnamed>"),
+                file.dimmed()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::AxParser;
+    use std::time::Duration;
+
+    #[test]
+    fn marks_new_and_pre_existing_failures() {
+        let report = r#"{"event":"start","total":3}
+{"event":"test","file":"./api/users.ax","name":"Login","status":"failed"}
+{"event":"test","file":"api/users.ax","name":"Health","status":"passed"}
+
+{"event":"summary","total":3}
+"#;
+        let baseline = Baseline::parse(report).unwrap();
+
+        let input = r#"TEST Login
+GET https://example.com/login
+
+END
+
+TEST Health
+GET https://example.com/health
+
+END
+
+TEST Profile
+GET https://example.com/profile
+
+END
+"#;
+        let mut tests = AxParser::parse_file(input).unwrap();
+        for test in &mut tests[..2] {
+            test.result = Some(TestResult::Failed {
+                duration: Duration::ZERO,
+                errors: Vec::new(),
+            });
+        }
+        tests[2].result = Some(TestResult::Passed {
+            duration: Duration::ZERO,
+        });
+        for test in &mut tests {
+            baseline.mark(Path::new("api/users.ax"), test);
+        }
+
+        assert_eq!(tests[0].annotations, vec![PRE_EXISTING]);
+        assert_eq!(tests[1].annotations, vec![NEW]);
+        assert!(tests[2].annotations.is_empty());
+        assert!(Baseline::parse("not json\n").is_err());
+    }
+}
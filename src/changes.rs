@@ -0,0 +1,122 @@
+//! Selective execution from git changes (`--changed-since <rev>`).
+//!
+//! Only tests affected by the files changed since a revision run: every test
+//! of a changed `.ax` file, plus tests reading a changed file (a `WITH DATA`
+//! dataset, an `EQUALS FILE` fixture, a protobuf descriptor). Changes are
+//! taken from `git diff --name-only <rev>`, so uncommitted edits count, as do
+//! untracked files. `<rev>` may be a range such as `main...HEAD`.
+
+use anyhow::{bail, Context, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::domain::TestCase;
+
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Output lines of `git <args>` run in `dir`
+fn git(dir: &Path, args: &[&str]) -> Result<Vec<String>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Files changed since `since`, as canonical paths
+pub fn changed_files(since: &str) -> Result<HashSet<PathBuf>> {
+    let root = git(Path::new("."), &["rev-parse", "--show-toplevel"])?
+        .pop()
+        .context("Not inside a git repository")?;
+    let root = Path::new(&root);
+
+    // Both list paths relative to the repository root
+    let changed = git(root, &["diff", "--name-only", since, "--"])?;
+    let untracked = git(root, &["ls-files", "--others", "--exclude-standard"])?;
+
+    Ok(changed
+        .iter()
+        .chain(&untracked)
+        .map(|file| canonical(&root.join(file)))
+        .collect())
+}
+
+/// Keep the tests affected by `changed`, dropping files left empty
+pub fn retain(all_tests: &mut Vec<(PathBuf, Vec<TestCase>)>, changed: &HashSet<PathBuf>) {
+    for (file, tests) in all_tests.iter_mut() {
+        if changed.contains(&canonical(file)) {
+            continue;
+        }
+        tests.retain(|test| {
+            test.inputs()
+                .into_iter()
+                .any(|input| changed.contains(&canonical(input)))
+        });
+    }
+    all_tests.retain(|(_, tests)| !tests.is_empty());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::AxParser;
+
+    #[test]
+    fn keeps_changed_files_and_tests_reading_changed_inputs() {
+        let input = r#"TEST Users match fixture
+GET https://example.com/users
+
+EXPECT body EQUALS FILE "fixtures/users.json"
+END
+
+TEST Health
+GET https://example.com/health
+
+END
+"#
+        .to_string();
+        let parse = || {
+            let mut tests = AxParser::parse_file(&input).unwrap();
+            for test in &mut tests {
+                test.resolve_paths(Path::new("/suite"));
+            }
+            tests
+        };
+
+        let mut all_tests = vec![
+            (PathBuf::from("/suite/changed.ax"), parse()),
+            (PathBuf::from("/suite/other.ax"), parse()),
+        ];
+        let changed = HashSet::from([
+            PathBuf::from("/suite/changed.ax"),
+            PathBuf::from("/suite/fixtures/users.json"),
+        ]);
+        retain(&mut all_tests, &changed);
+
+        assert_eq!(all_tests[0].1.len(), 2);
+        assert_eq!(all_tests[1].1.len(), 1);
+        assert_eq!(
+            all_tests[1].1[0].name.as_deref(),
+            Some("Users match fixture")
+        );
+
+        retain(&mut all_tests, &HashSet::new());
+        assert!(all_tests.is_empty());
+    }
+}
@@ -1,22 +1,78 @@
 use clap::{Parser, ArgGroup};
-use crate::cli::RendererKind;
+use crate::cli::{Command, RendererSpec};
+use crate::domain::impersonate::Profile;
+use crate::duration::parse_duration;
+use crate::runner::Order;
+use crate::size::parse_size;
+use crate::shell_env::{parse_capture, ShellCapture};
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser, Debug)]
 #[command(
     name = "axotly",
     about = "Fast, reliable, and expressive API testing — designed for developer happiness.",
+    after_help = crate::exit::HELP,
     group(
         ArgGroup::new("input")
             .args(["file", "url"])
     )
 )]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Run requests from a .http file
     #[arg(short, long)]
     pub file: Option<String>,
 
+    /// Load variables from axotly.<ENV>.env (e.g. --env dev)
+    #[arg(long, value_name = "ENV", requires = "file")]
+    pub env: Option<String>,
+
+    /// Configuration file (default: ./axotly.toml when present)
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Output renderer (human, diff, junit, ndjson, template, markdown, csv, exec:COMMAND); repeatable,
+    /// append =PATH to write to a file
     #[arg(short, long, default_value = "human", requires = "file")]
-    pub renderer: RendererKind,
+    pub renderer: Vec<RendererSpec>,
+
+    /// Tera template used by the template renderer
+    #[arg(long, value_name = "PATH", requires = "file")]
+    pub template: Option<PathBuf>,
+
+    /// Print long expected/actual values in full instead of eliding what
+    /// they have in common
+    #[arg(long, requires = "file")]
+    pub full_diff: bool,
+
+    /// Also write a self-contained HTML report to this file
+    #[arg(long, value_name = "PATH", requires = "file")]
+    pub report_html: Option<PathBuf>,
+
+    /// Write the request, response, timing and variables of each failed test
+    /// to <DIR>/<test-id>/
+    #[arg(long, value_name = "DIR", requires = "file")]
+    pub artifacts: Option<PathBuf>,
+
+    /// Also append NDJSON results of every run to this log, rotated instead of
+    /// growing without bound (for scheduled runs)
+    #[arg(long, value_name = "PATH", requires = "file")]
+    pub log: Option<PathBuf>,
+
+    /// Rotate the log once it would grow past this size (e.g. 10MB)
+    #[arg(long, value_name = "SIZE", requires = "log", default_value = "10MB", value_parser = parse_size)]
+    pub log_max_size: u64,
+
+    /// Also rotate the log when a new day (UTC) starts
+    #[arg(long, requires = "log")]
+    pub log_daily: bool,
+
+    /// Rotated logs to keep (PATH.1 to PATH.N); older ones are deleted
+    #[arg(long, value_name = "COUNT", requires = "log", default_value_t = 5)]
+    pub log_keep: usize,
 
     /// Number of concurrent requests (min: 1, default: CPU cores)
     #[arg(
@@ -30,10 +86,113 @@ pub struct Cli {
     )]
     pub concurrently: usize,
 
-    /// Show responses 
+    /// Show responses
     #[arg(long, requires="file")]
     pub show_response: bool,
 
+    /// Sort JSON object keys in rendered responses
+    #[arg(long, requires = "file")]
+    pub sort_keys: bool,
+
+    /// Strip a volatile field (e.g. body.updated_at, body.items[*].id) from
+    /// rendered responses
+    #[arg(long, value_name = "PATH", requires = "file")]
+    pub ignore_path: Vec<String>,
+
+    /// Cancel tests still running after this long (e.g. 10m) and exit with 124
+    #[arg(long, value_name = "DURATION", requires = "file", value_parser = parse_duration)]
+    pub run_timeout: Option<Duration>,
+
+    /// Fail remaining tests against a host fast after this many consecutive
+    /// transport failures
+    #[arg(
+        long,
+        value_name = "FAILURES",
+        requires = "file",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub circuit_breaker: Option<u32>,
+
+    /// Run only tests in .ax files changed since this git revision or range
+    /// (e.g. HEAD~1, main...HEAD), and tests reading changed files
+    #[arg(long, value_name = "REV", requires = "file")]
+    pub changed_since: Option<String>,
+
+    /// Run only the tests that failed or did not run last time
+    #[arg(long, requires = "file")]
+    pub rerun_failed: bool,
+
+    /// Run the suite once per API version (e.g. v1,v2), as set up under
+    /// [api_versions] in the config
+    #[arg(long, value_name = "VERSIONS", requires = "file", value_delimiter = ',')]
+    pub api_versions: Vec<String>,
+
+    /// Mark failures as new or pre-existing against the NDJSON report of an
+    /// earlier run (e.g. results-main.json from the main branch)
+    #[arg(long, value_name = "PATH", requires = "file")]
+    pub baseline: Option<PathBuf>,
+
+    /// Send browser- or curl-like User-Agent, Accept and header order
+    #[arg(long, value_enum, value_name = "PROFILE")]
+    pub impersonate: Option<Profile>,
+
+    /// After the summary, chart when each test started and finished
+    #[arg(long, requires = "file")]
+    pub timeline: bool,
+
+    /// Parse very large files block by block, running tests as soon as they
+    /// are parsed. ONLY markers and the DNS pre-flight are not applied.
+    #[arg(long, requires = "file", conflicts_with_all = ["changed_since", "rerun_failed"])]
+    pub stream: bool,
+
+    /// Run order: `file` as written, or `priority` to run first the tests
+    /// that failed recently or changed since the last commit
+    #[arg(long, value_enum, value_name = "ORDER", requires = "file", conflicts_with = "stream")]
+    pub order: Option<Order>,
+
+    /// Only check the .ax files for risky or pointless tests (see `[check]`
+    /// in the config), without sending anything; fails on warnings
+    #[arg(long, requires = "file")]
+    pub check: bool,
+
+    /// Show each POST, PUT, PATCH or DELETE as it will be sent and ask
+    /// before sending it
+    #[arg(long, requires = "file")]
+    pub confirm: bool,
+
+    /// Skip the DNS pre-flight and let each request resolve its host
+    #[arg(long, requires = "file")]
+    pub no_preresolve: bool,
+
+    /// Delay every request by this duration (e.g. 200ms) before sending
+    #[arg(long, value_name = "DURATION", requires = "file", value_parser = parse_duration)]
+    pub chaos_delay: Option<Duration>,
+
+    /// Drop this percentage of requests client-side, failing their tests
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        requires = "file",
+        default_value_t = 0,
+        value_parser = clap::value_parser!(u8).range(0..=100)
+    )]
+    pub chaos_drop: u8,
+
+    /// Send this percentage of requests twice
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        requires = "file",
+        default_value_t = 0,
+        value_parser = clap::value_parser!(u8).range(0..=100)
+    )]
+    pub chaos_duplicate: u8,
+
+    /// Do not make file paths and URLs clickable, even in terminals that
+    /// support hyperlinks
+    #[arg(long)]
+    pub no_hyperlinks: bool,
+
     /// URL to fetch (positional, curl-style)
     #[arg(
         value_name = "URL",
@@ -54,5 +213,21 @@ pub struct Cli {
     #[arg(short = 'j', long)]
     pub json: Option<String>,
 
+    /// Response value to export with --emit-env, as PATH=NAME (e.g.
+    /// body.token=TOKEN, headers.Location=URL); repeatable
+    #[arg(
+        long,
+        value_name = "PATH=NAME",
+        requires = "emit_env",
+        conflicts_with = "file",
+        value_parser = parse_capture
+    )]
+    pub capture: Vec<ShellCapture>,
+
+    /// Print `export NAME=value` lines (STATUS and each --capture) instead
+    /// of the response
+    #[arg(long, conflicts_with = "file")]
+    pub emit_env: bool,
+
 }
 
@@ -0,0 +1,123 @@
+use clap::Subcommand;
+
+use crate::commands::inventory::Format;
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Manage tokens stored in the OS keyring
+    Auth {
+        #[command(subcommand)]
+        action: AuthCommand,
+    },
+
+    /// Manage response fixtures of tests marked FIXTURE
+    Fixtures {
+        #[command(subcommand)]
+        action: FixturesCommand,
+    },
+
+    /// Open an interactive dashboard that runs a suite and re-runs tests on demand
+    Tui {
+        /// .ax file or folder to run
+        #[arg(short, long)]
+        file: String,
+
+        /// Load variables from axotly.<ENV>.env (e.g. --env dev)
+        #[arg(long, value_name = "ENV")]
+        env: Option<String>,
+
+        /// Number of concurrent requests (default: CPU cores)
+        #[arg(
+            short,
+            long,
+            default_value_t = std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+            value_parser = clap::builder::RangedI64ValueParser::<usize>::new().range(1..200)
+        )]
+        concurrently: usize,
+    },
+
+    /// Upgrade .ax files in place to the current SYNTAX version
+    Migrate {
+        /// .ax file or folder to upgrade
+        #[arg(short, long)]
+        file: String,
+
+        /// Only list files that need upgrading; fail if there are any
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// List every method and URL the tests send, with counts and tags
+    Inventory {
+        /// .ax file or folder to scan
+        #[arg(short, long)]
+        file: String,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = Format::Csv)]
+        format: Format,
+    },
+
+    /// Replace this binary with the latest release, after verifying its signed checksum
+    SelfUpdate {
+        /// Only report whether a newer release is available (required while
+        /// this build has no release key to verify updates with)
+        #[arg(long, required = !crate::commands::self_update::CAN_UPDATE)]
+        check: bool,
+    },
+
+    /// Start a local HTTP proxy that records traffic into .ax test blocks
+    Proxy {
+        /// Port to listen on (127.0.0.1)
+        #[arg(short, long, default_value_t = 8888)]
+        port: u16,
+
+        /// Folder where recorded .ax files are written (one per host)
+        #[arg(short, long, default_value = "recorded")]
+        out: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AuthCommand {
+    /// Store a token, available in .ax files as {{secret.<name>}}
+    Login {
+        /// Name the token is stored under
+        #[arg(short, long)]
+        name: String,
+    },
+
+    /// Remove a stored token
+    Logout {
+        /// Name the token is stored under
+        #[arg(short, long)]
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum FixturesCommand {
+    /// Execute GET tests marked FIXTURE and save their response bodies
+    Pull {
+        /// .ax file or folder to scan
+        #[arg(short, long)]
+        file: String,
+
+        /// Load variables from axotly.<ENV>.env (e.g. --env dev)
+        #[arg(long, value_name = "ENV")]
+        env: Option<String>,
+    },
+
+    /// Remove files in fixtures/ folders that no test references
+    Clean {
+        /// .ax file or folder to scan
+        #[arg(short, long)]
+        file: String,
+
+        /// Only list the files that would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
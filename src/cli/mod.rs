@@ -1,5 +1,7 @@
 pub mod args;
+pub mod command;
 pub mod renderer_kind;
 
 pub use args::Cli;
-pub use renderer_kind::RendererKind;
+pub use command::{AuthCommand, Command, FixturesCommand};
+pub use renderer_kind::{RendererKind, RendererSpec};
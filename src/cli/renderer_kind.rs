@@ -1,9 +1,52 @@
 use clap::ValueEnum;
+use std::path::PathBuf;
+use std::str::FromStr;
 
 #[derive(Clone, Debug, ValueEnum)]
 pub enum RendererKind {
     Human,
     Diff,
+    Junit,
+    Ndjson,
+    Template,
+    Markdown,
+    Csv,
 }
 
+/// A renderer and its destination: `kind` writes to stdout, `kind=PATH` to a
+/// file, and `exec:COMMAND` streams NDJSON events into an external process.
+#[derive(Clone, Debug)]
+pub struct RendererSpec {
+    pub kind: RendererKind,
+    pub path: Option<PathBuf>,
+    pub command: Option<String>,
+}
+
+impl FromStr for RendererSpec {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        if let Some(command) = input.strip_prefix("exec:") {
+            if command.trim().is_empty() {
+                return Err("exec: requires a command, e.g. exec:./my-renderer".into());
+            }
 
+            return Ok(Self {
+                kind: RendererKind::Ndjson,
+                path: None,
+                command: Some(command.to_string()),
+            });
+        }
+
+        let (kind, path) = match input.split_once('=') {
+            Some((kind, path)) => (kind, Some(PathBuf::from(path))),
+            None => (input, None),
+        };
+
+        Ok(Self {
+            kind: <RendererKind as ValueEnum>::from_str(kind, true)?,
+            path,
+            command: None,
+        })
+    }
+}
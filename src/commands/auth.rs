@@ -0,0 +1,32 @@
+use anyhow::Result;
+use owo_colors::OwoColorize;
+
+use crate::cli::AuthCommand;
+use crate::secrets;
+
+pub fn run(action: AuthCommand) -> Result<()> {
+    match action {
+        AuthCommand::Login { name } => {
+            let token = rpassword::prompt_password(format!("Token for '{}': ", name))?;
+            let token = token.trim();
+
+            if token.is_empty() {
+                anyhow::bail!("No token provided");
+            }
+
+            secrets::store(&name, token)?;
+            println!(
+                "{} Stored token '{}'. Use it as {}",
+                "✓".green().bold(),
+                name.bold(),
+                format!("{{{{secret.{}}}}}", name).cyan()
+            );
+        }
+        AuthCommand::Logout { name } => {
+            secrets::delete(&name)?;
+            println!("{} Removed token '{}'", "✓".green().bold(), name.bold());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,24 @@
+use anyhow::Result;
+use std::path::Path;
+
+use crate::cli::FixturesCommand;
+use crate::config::Config;
+use crate::fixtures;
+use crate::runner::RunOptions;
+
+pub async fn run(action: FixturesCommand, config: &Config) -> Result<()> {
+    match action {
+        FixturesCommand::Pull { file, env } => {
+            let options = RunOptions {
+                env,
+                default_headers: config.default_headers()?,
+                signer: config.signer()?,
+                refresher: config.refresher()?,
+                sources: config.sources()?,
+                ..Default::default()
+            };
+            fixtures::pull(Path::new(&file), &options).await
+        }
+        FixturesCommand::Clean { file, dry_run } => fixtures::clean(Path::new(&file), dry_run),
+    }
+}
@@ -0,0 +1,145 @@
+//! `axotly inventory`: the endpoints a suite exercises.
+//!
+//! Lists every distinct method and URL template the tests send, steps
+//! included, with how many requests use it and the tags of those tests. URLs
+//! are listed as written when they hold placeholders (`{{base}}/users/{{id}}`)
+//! and without their query string, so one endpoint reads as one line. Diffing
+//! the list against an API spec shows uncovered endpoints, and tests left
+//! calling removed ones.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::domain::http_request::HttpRequest;
+use crate::domain::TestCase;
+use crate::runner::Runner;
+use crate::template::Variables;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    #[default]
+    Csv,
+    Json,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Endpoint {
+    pub method: String,
+    pub url: String,
+    /// Requests sent to it, counting steps and each test once
+    pub count: usize,
+    pub tags: BTreeSet<String>,
+}
+
+/// URL of `request` as written, without its query string
+fn template(request: &HttpRequest) -> String {
+    let url = match &request.deferred {
+        Some(deferred) => deferred.url.clone(),
+        None => request.url.as_str().to_string(),
+    };
+    match url.split_once('?') {
+        Some((url, _)) => url.to_string(),
+        None => url,
+    }
+}
+
+/// Endpoints of `tests`, by method then URL
+pub fn collect<'a>(tests: impl IntoIterator<Item = &'a TestCase>) -> Vec<Endpoint> {
+    let mut endpoints: BTreeMap<(String, String), Endpoint> = BTreeMap::new();
+    for test in tests {
+        for request in test.requests() {
+            let key = (request.method.clone(), template(request));
+            let endpoint = endpoints.entry(key).or_insert_with(|| Endpoint {
+                method: request.method.clone(),
+                url: template(request),
+                count: 0,
+                tags: BTreeSet::new(),
+            });
+            endpoint.count += 1;
+            endpoint.tags.extend(test.tags.iter().cloned());
+        }
+    }
+    endpoints.into_values().collect()
+}
+
+/// Print the endpoints of the tests under `file` as `format`
+pub fn run(file: String, format: Format) -> Result<()> {
+    // No environment: placeholders stay as written
+    let all_tests = Runner::discover(Path::new(&file), &Variables::new())?;
+    let endpoints = collect(all_tests.iter().flat_map(|(_, tests)| tests));
+
+    match format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&endpoints)?),
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(std::io::stdout());
+            writer.write_record(["method", "url", "count", "tags"])?;
+            for endpoint in &endpoints {
+                let tags: Vec<&str> = endpoint.tags.iter().map(String::as_str).collect();
+                writer.write_record([
+                    endpoint.method.as_str(),
+                    endpoint.url.as_str(),
+                    &endpoint.count.to_string(),
+                    &tags.join(" "),
+                ])?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::AxParser;
+
+    #[test]
+    fn endpoints_are_grouped_by_method_and_template() {
+        let input = r#"TEST List users
+TAGS users, smoke
+GET https://api.example.com/users?page=1
+
+END
+
+TEST Next page
+TAGS users
+GET https://api.example.com/users?page=2
+
+END
+
+TEST Create user
+TAGS users
+POST https://api.example.com/users
+
+CAPTURE id = body.id
+END
+
+TEST Fetch user
+GET https://api.example.com/users/{{id}}
+
+END
+"#;
+        let tests = AxParser::parse_file(input).unwrap();
+        let endpoints = collect(&tests);
+
+        let lines: Vec<(&str, &str, usize)> = endpoints
+            .iter()
+            .map(|e| (e.method.as_str(), e.url.as_str(), e.count))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("GET", "https://api.example.com/users", 2),
+                ("GET", "https://api.example.com/users/{{id}}", 1),
+                ("POST", "https://api.example.com/users", 1),
+            ]
+        );
+        assert_eq!(
+            endpoints[0].tags,
+            BTreeSet::from(["smoke".to_string(), "users".to_string()])
+        );
+    }
+}
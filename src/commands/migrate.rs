@@ -0,0 +1,55 @@
+use anyhow::{bail, Context, Result};
+use owo_colors::OwoColorize;
+use std::path::Path;
+use walkdir::WalkDir;
+
+use crate::parser::syntax;
+
+/// Upgrade every `.ax` file under `file` to the current syntax in place; with
+/// `check`, only list the files that need it and fail if there are any
+pub fn run(file: String, check: bool) -> Result<()> {
+    let path = Path::new(&file);
+    if !path.exists() {
+        bail!("{} is neither a file nor a folder", path.display());
+    }
+
+    let mut pending = 0;
+    for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "ax") {
+            continue;
+        }
+
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read file {}", path.display()))?;
+        let Some((migrated, applied)) = syntax::migrate(&source)
+            .with_context(|| format!("Cannot migrate {}", path.display()))?
+        else {
+            continue;
+        };
+        pending += 1;
+
+        if !check {
+            std::fs::write(path, migrated)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        let verb = if check { "needs" } else { "now" };
+        println!(
+            "{} {} {} SYNTAX {}",
+            "↑".green().bold(),
+            path.display(),
+            verb,
+            syntax::CURRENT
+        );
+        for change in applied {
+            println!("    {}", change.dimmed());
+        }
+    }
+
+    if pending == 0 {
+        println!("All files use SYNTAX {}", syntax::CURRENT);
+    } else if check {
+        bail!("{} file(s) need `axotly migrate`", pending);
+    }
+    Ok(())
+}
@@ -0,0 +1,11 @@
+//! Handlers for `axotly <subcommand>` invocations.
+//!
+//! Each subcommand lives in its own module and exposes a single `run`
+//! function taking the parsed clap arguments.
+
+pub mod auth;
+pub mod fixtures;
+pub mod inventory;
+pub mod migrate;
+pub mod self_update;
+pub mod tui;
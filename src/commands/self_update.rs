@@ -0,0 +1,262 @@
+//! `axotly self-update`: replace this binary with the latest release.
+//!
+//! The GitHub release feed gives the latest version. When it is newer, the
+//! bare binary for this platform is downloaded, checked against the
+//! release's `SHA256SUMS`, and swapped in for the running executable.
+//! `SHA256SUMS` itself must carry a minisign signature by [`RELEASE_KEY`],
+//! made by the release workflow, so a release whose assets were swapped
+//! together with their checksums is refused too, as is one without a
+//! checksum for the binary. Until the maintainers publish that key, nothing
+//! can be verified, so the command requires `--check` (see [`CAN_UPDATE`]).
+
+use anyhow::{bail, Context, Result};
+use minisign_verify::{PublicKey, Signature};
+use owo_colors::OwoColorize;
+use reqwest::Client;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+const LATEST_RELEASE: &str = "https://api.github.com/repos/JapArt/axotly/releases/latest";
+
+/// Release asset listing the SHA-256 of every other asset
+const CHECKSUMS: &str = "SHA256SUMS";
+
+/// Release asset with the minisign signature of [`CHECKSUMS`]
+const SIGNATURE: &str = "SHA256SUMS.minisig";
+
+/// Minisign public key the release workflow signs [`CHECKSUMS`] with; unset
+/// until the maintainers generate the key pair and store its secret half as
+/// the `MINISIGN_SECRET_KEY` repository secret
+const RELEASE_KEY: Option<&str> = None;
+
+/// Whether this build can verify and install updates; without it, `axotly
+/// self-update` only accepts `--check`
+pub const CAN_UPDATE: bool = RELEASE_KEY.is_some();
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+impl Release {
+    fn asset(&self, name: &str) -> Result<&Asset> {
+        self.assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .with_context(|| format!("Release {} has no {} asset", self.tag_name, name))
+    }
+}
+
+/// Name of the release binary built for this platform
+fn platform_asset() -> Result<&'static str> {
+    Ok(match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("linux", "x86_64") => "axotly-x86_64-unknown-linux-musl",
+        ("macos", "x86_64") => "axotly-x86_64-apple-darwin",
+        ("macos", "aarch64") => "axotly-aarch64-apple-darwin",
+        ("windows", "x86_64") => "axotly-x86_64-pc-windows-msvc.exe",
+        (os, arch) => bail!("No release binary is published for {}-{}", os, arch),
+    })
+}
+
+/// `1.2.3` (or `v1.2.3`) as comparable numbers
+fn version(text: &str) -> Result<Vec<u64>> {
+    text.trim_start_matches('v')
+        .split('.')
+        .map(|part| {
+            part.parse()
+                .with_context(|| format!("Invalid version '{}'", text))
+        })
+        .collect()
+}
+
+/// SHA-256 of `name` in a `sha256sum`-style listing
+fn expected_checksum<'a>(listing: &'a str, name: &str) -> Option<&'a str> {
+    listing.lines().find_map(|line| {
+        let (checksum, file) = line.split_once(char::is_whitespace)?;
+        // `sha256sum` marks binary-mode entries with a leading `*`
+        (file.trim_start().trim_start_matches('*') == name).then_some(checksum)
+    })
+}
+
+/// Check that `listing` is signed by the holder of `key`
+fn verify_signature(listing: &[u8], signature: &str, key: &str) -> Result<()> {
+    let key = PublicKey::from_base64(key).context("Invalid release key")?;
+    let signature = Signature::decode(signature)
+        .with_context(|| format!("Invalid {}", SIGNATURE))?;
+    key.verify(listing, &signature, false).with_context(|| {
+        format!("{} is not signed by the axotly release key; nothing was replaced", CHECKSUMS)
+    })
+}
+
+async fn download(client: &Client, url: &str) -> Result<Vec<u8>> {
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .with_context(|| format!("Failed to download {}", url))?
+        .error_for_status()?;
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// Update to the latest release; with `check`, only report whether one is
+/// available
+pub async fn run(check: bool) -> Result<()> {
+    let client = Client::builder()
+        .user_agent(concat!("axotly/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+
+    let release: Release = client
+        .get(LATEST_RELEASE)
+        .send()
+        .await
+        .context("Failed to reach the release feed")?
+        .error_for_status()?
+        .json()
+        .await
+        .context("Invalid release feed")?;
+
+    let current = env!("CARGO_PKG_VERSION");
+    if version(&release.tag_name)? <= version(current)? {
+        println!("{} axotly {} is up to date", "✓".green().bold(), current);
+        return Ok(());
+    }
+    if check {
+        let how = if CAN_UPDATE {
+            "run `axotly self-update`"
+        } else {
+            "download it from https://github.com/JapArt/axotly/releases"
+        };
+        println!(
+            "{} axotly {} is available (installed: {}); {}",
+            "↑".yellow().bold(),
+            release.tag_name,
+            current,
+            how
+        );
+        return Ok(());
+    }
+
+    let key = RELEASE_KEY.context(
+        "This build has no release key to verify updates with; download the release from \
+         https://github.com/JapArt/axotly/releases",
+    )?;
+    let name = platform_asset()?;
+    let binary = download(&client, &release.asset(name)?.browser_download_url).await?;
+    let checksums = download(&client, &release.asset(CHECKSUMS)?.browser_download_url).await?;
+    let signature = download(&client, &release.asset(SIGNATURE)?.browser_download_url).await?;
+    verify_signature(&checksums, &String::from_utf8_lossy(&signature), key)?;
+    let checksums = String::from_utf8_lossy(&checksums);
+
+    let expected = expected_checksum(&checksums, name)
+        .with_context(|| format!("{} has no checksum for {}", CHECKSUMS, name))?;
+    let actual = format!("{:x}", Sha256::digest(&binary));
+    if !actual.eq_ignore_ascii_case(expected) {
+        bail!(
+            "Checksum mismatch for {}: expected {}, got {}; nothing was replaced",
+            name,
+            expected,
+            actual
+        );
+    }
+
+    let executable = std::env::current_exe().context("Cannot locate the running executable")?;
+    replace(&executable, &binary)?;
+
+    println!(
+        "{} Updated axotly {} → {}",
+        "✓".green().bold(),
+        current,
+        release.tag_name
+    );
+    Ok(())
+}
+
+/// Swap `executable` for `binary`, written next to it first so the final
+/// rename is atomic
+fn replace(executable: &Path, binary: &[u8]) -> Result<()> {
+    let staged = executable.with_extension("new");
+    std::fs::write(&staged, binary)
+        .with_context(|| format!("Failed to write {}", staged.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    // Windows cannot overwrite a running executable, but it can rename it
+    #[cfg(windows)]
+    {
+        let old = executable.with_extension("old");
+        let _ = std::fs::remove_file(&old);
+        std::fs::rename(executable, &old)
+            .with_context(|| format!("Failed to move {} aside", executable.display()))?;
+    }
+
+    std::fs::rename(&staged, executable)
+        .with_context(|| format!("Failed to replace {}", executable.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_compare_numerically() {
+        assert!(version("v0.1.10").unwrap() > version("0.1.9").unwrap());
+        assert!(version("v0.1.6").unwrap() <= version("0.1.6").unwrap());
+        assert!(version("latest").is_err());
+    }
+
+    #[test]
+    fn checksum_lookup() {
+        let listing = "\
+abc123  axotly-x86_64-apple-darwin
+def456 *axotly-x86_64-pc-windows-msvc.exe
+";
+        assert_eq!(
+            expected_checksum(listing, "axotly-x86_64-apple-darwin"),
+            Some("abc123")
+        );
+        assert_eq!(
+            expected_checksum(listing, "axotly-x86_64-pc-windows-msvc.exe"),
+            Some("def456")
+        );
+        assert_eq!(
+            expected_checksum(listing, "axotly-aarch64-apple-darwin"),
+            None
+        );
+    }
+
+    #[test]
+    fn checksums_must_be_signed_by_the_key() {
+        let key = "RWRgyknfl11G4UCnwgLp5xIoxQitmTpE0G+rxhhNTWvOopZ1ztcrsAeO";
+        let signature = "\
+untrusted comment: signature from rsign secret key
+RURgyknfl11G4Uq7IvJQ3C5DgDf1/miCbJZ5hsRm97O5nLVTFAFxVxhXOlOC/wsrSUZosO9Nm+3hizKPoLMe0yqmXt5Pm95FnQ8=
+trusted comment: axotly test
+VB/NBxsbPrFOImuQk/nIVH/9aU/yKJSi1GUKMfvPqfjyuiv2M34Oe3cmmR3+vDbSHbh2IgUy1+V9pT01ciARBQ==
+";
+        let listing = b"abc123  axotly-x86_64-apple-darwin\n";
+        assert!(verify_signature(listing, signature, key).is_ok());
+        assert!(verify_signature(b"evil00  axotly-x86_64-apple-darwin\n", signature, key).is_err());
+        assert!(verify_signature(listing, "not a signature", key).is_err());
+    }
+
+    #[test]
+    fn updating_needs_a_release_key() {
+        use clap::Parser;
+
+        let parse = |args: &[&str]| crate::cli::Cli::try_parse_from(args);
+        assert!(parse(&["axotly", "self-update", "--check"]).is_ok());
+        assert_eq!(parse(&["axotly", "self-update"]).is_ok(), CAN_UPDATE);
+    }
+}
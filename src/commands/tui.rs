@@ -0,0 +1,25 @@
+use anyhow::Result;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::runner::RunOptions;
+use crate::tui;
+
+pub async fn run(
+    file: String,
+    env: Option<String>,
+    concurrently: usize,
+    config: &Config,
+) -> Result<()> {
+    let options = RunOptions {
+        max_concurrency: concurrently,
+        env,
+        default_headers: config.default_headers()?,
+        signer: config.signer()?,
+        refresher: config.refresher()?,
+        sources: config.sources()?,
+        ..Default::default()
+    };
+
+    tui::run(Path::new(&file), options).await
+}
@@ -0,0 +1,472 @@
+//! Project configuration loaded from `axotly.toml`.
+//!
+//! The file is looked up in the working directory unless `--config` points
+//! elsewhere; a missing default file simply means "no configuration".
+//!
+//! ```toml
+//! # Send a fake clock to backends with test-clock support
+//! [time_travel]
+//! header = "X-Test-Time"
+//! value = "{{now + 30d}}"
+//!
+//! # Mail catcher for MAILBOX assertions (see `domain::source`)
+//! [mailbox]
+//! url = "http://localhost:8025"
+//!
+//! # Sign requests (see `domain::signing` for all keys)
+//! [signing]
+//! scheme = "hmac-sha256"
+//! secret = "{{secret.exchange_key}}"
+//!
+//! # Latency objectives reported in the summary (see `renderers::slo`)
+//! [[slo]]
+//! tag = "checkout"
+//! target = "p95 < 400ms"
+//!
+//! # Versions `--api-versions v1,v2` runs the suite against: the header
+//! # carrying the version, and per version its value and the variables its
+//! # expectations differ by (see `api_versions`)
+//! [api_versions]
+//! header = "Api-Version"
+//! v1 = { value = "2023-10", variables = { total_field = "count" } }
+//! v2 = { value = "2024-04" }
+//!
+//! # Hosts `--check` warns about sending mutating requests to
+//! [check]
+//! prod_hosts = ["*.prod.example.com", "api.example.com"]
+//!
+//! # Keep `{{token}}` fresh during long runs (see `domain::token`)
+//! [token_refresh]
+//! variable = "token"
+//! url = "https://auth.example.com/oauth/token"
+//! body = "grant_type=client_credentials&client_secret={{secret.ci}}"
+//! headers = { Content-Type = "application/x-www-form-urlencoded" }
+//! ```
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use url::Url;
+
+use crate::domain::signing::{HmacSigner, RequestSigner, SignatureEncoding};
+use crate::domain::source::{ExternalSource, MailHog};
+use crate::domain::token::TokenRefresher;
+use crate::duration::parse_duration;
+use crate::renderers::slo::{Scope, Slo};
+use crate::template;
+
+/// File name looked up in the working directory
+pub const DEFAULT_FILE: &str = "axotly.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub time_travel: Option<TimeTravel>,
+    pub signing: Option<Signing>,
+    pub token_refresh: Option<TokenRefresh>,
+    pub check: Check,
+    pub api_versions: ApiVersions,
+    pub slo: Vec<SloTarget>,
+    pub mailbox: Option<Mailbox>,
+    pub kafka: Option<Kafka>,
+    pub amqp: Option<Amqp>,
+}
+
+/// One `[[slo]]`: a target for the tests with `tag`, or calling `host`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SloTarget {
+    pub tag: Option<String>,
+    pub host: Option<String>,
+    /// e.g. `p95 < 400ms`
+    pub target: String,
+}
+
+/// Settings of `--check` (see [`crate::lint`])
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Check {
+    /// Host patterns (`*` wildcards) mutating requests should not reach
+    pub prod_hosts: Vec<String>,
+}
+
+/// Versions of the API `--api-versions` runs the suite against (see
+/// [`crate::api_versions`])
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ApiVersions {
+    /// Header sent with the version's value
+    pub header: String,
+    /// Settings of each version, by name
+    #[serde(flatten)]
+    pub versions: HashMap<String, ApiVersion>,
+}
+
+impl Default for ApiVersions {
+    fn default() -> Self {
+        Self {
+            header: "Api-Version".to_string(),
+            versions: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiVersion {
+    /// Value of the version header; the version's name when unset
+    pub value: Option<String>,
+    /// Variables overriding the environment's for this version, so
+    /// expectations written as `{{...}}` can differ between versions
+    pub variables: HashMap<String, String>,
+}
+
+/// MailHog-compatible server queried by `MAILBOX` assertions
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Mailbox {
+    pub url: Url,
+}
+
+/// Cluster read by `KAFKA` assertions
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Kafka {
+    #[cfg_attr(not(feature = "kafka"), allow(dead_code))]
+    pub brokers: Vec<String>,
+}
+
+/// Broker read by `AMQP` assertions
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Amqp {
+    #[cfg_attr(not(feature = "amqp"), allow(dead_code))]
+    pub url: String,
+}
+
+/// Profile injecting a fake "current time" header into every request
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TimeTravel {
+    #[serde(default = "default_time_header")]
+    pub header: String,
+    /// Template for the header value, e.g. `{{now + 30d}}`
+    pub value: String,
+}
+
+fn default_time_header() -> String {
+    "X-Test-Time".to_string()
+}
+
+/// Signature added to every request right before it is sent
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Signing {
+    pub scheme: SigningScheme,
+    /// Template for the key, e.g. `{{secret.exchange_key}}`
+    pub secret: String,
+    #[serde(default = "default_signature_header")]
+    pub header: String,
+    #[serde(default = "default_signature_payload")]
+    pub payload: String,
+    pub timestamp_header: Option<String>,
+    #[serde(default)]
+    pub encoding: SignatureEncoding,
+    #[serde(default)]
+    pub prefix: String,
+    #[serde(default)]
+    pub hosts: Vec<String>,
+}
+
+/// Request fetching a new access token into a captured variable
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenRefresh {
+    pub variable: String,
+    #[serde(default = "default_token_method")]
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    pub body: Option<String>,
+    /// Where the token is in the response
+    #[serde(default = "default_token_path")]
+    pub path: String,
+    /// Refresh a JWT this long before its `exp`, e.g. `30s`
+    #[serde(default = "default_token_margin")]
+    pub margin: String,
+}
+
+fn default_token_method() -> String {
+    "POST".to_string()
+}
+
+fn default_token_path() -> String {
+    "body.access_token".to_string()
+}
+
+fn default_token_margin() -> String {
+    "30s".to_string()
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum SigningScheme {
+    #[serde(rename = "hmac-sha256")]
+    HmacSha256,
+}
+
+#[cfg(feature = "kafka")]
+fn kafka_source(kafka: &Kafka) -> Result<Arc<dyn ExternalSource>> {
+    Ok(Arc::new(crate::domain::kafka::Kafka::new(
+        kafka.brokers.clone(),
+    )))
+}
+
+#[cfg(not(feature = "kafka"))]
+fn kafka_source(_: &Kafka) -> Result<Arc<dyn ExternalSource>> {
+    anyhow::bail!("[kafka] needs axotly built with `--features kafka`")
+}
+
+#[cfg(feature = "amqp")]
+fn amqp_source(amqp: &Amqp) -> Result<Arc<dyn ExternalSource>> {
+    Ok(Arc::new(crate::domain::amqp::Amqp::new(amqp.url.clone())))
+}
+
+#[cfg(not(feature = "amqp"))]
+fn amqp_source(_: &Amqp) -> Result<Arc<dyn ExternalSource>> {
+    anyhow::bail!("[amqp] needs axotly built with `--features amqp`")
+}
+
+fn default_signature_header() -> String {
+    "X-Signature".to_string()
+}
+
+fn default_signature_payload() -> String {
+    "{timestamp}{method}{path}{body}".to_string()
+}
+
+impl Config {
+    /// Load `path`, or `axotly.toml` from the working directory if present
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path,
+            None if Path::new(DEFAULT_FILE).is_file() => Path::new(DEFAULT_FILE),
+            None => return Ok(Self::default()),
+        };
+
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("Invalid config {}", path.display()))
+    }
+
+    /// Headers every request gets unless it sets them itself
+    pub fn default_headers(&self) -> Result<Vec<(String, String)>> {
+        let mut headers = Vec::new();
+
+        if let Some(time_travel) = &self.time_travel {
+            let value = template::render(&time_travel.value)
+                .context("Failed to render time_travel.value")?;
+            headers.push((time_travel.header.clone(), value));
+        }
+
+        Ok(headers)
+    }
+
+    /// External sources for assertions, by keyword
+    pub fn sources(&self) -> Result<HashMap<String, Arc<dyn ExternalSource>>> {
+        let mut sources: HashMap<String, Arc<dyn ExternalSource>> = HashMap::new();
+
+        if let Some(mailbox) = &self.mailbox {
+            sources.insert(
+                "MAILBOX".to_string(),
+                Arc::new(MailHog {
+                    url: mailbox.url.clone(),
+                }),
+            );
+        }
+
+        if let Some(kafka) = &self.kafka {
+            sources.insert("KAFKA".to_string(), kafka_source(kafka)?);
+        }
+        if let Some(amqp) = &self.amqp {
+            sources.insert("AMQP".to_string(), amqp_source(amqp)?);
+        }
+
+        Ok(sources)
+    }
+
+    /// Signer configured by `[signing]`, if any
+    pub fn signer(&self) -> Result<Option<Arc<dyn RequestSigner>>> {
+        let Some(signing) = &self.signing else {
+            return Ok(None);
+        };
+
+        let secret =
+            template::render(&signing.secret).context("Failed to render signing.secret")?;
+        let signer = match signing.scheme {
+            SigningScheme::HmacSha256 => HmacSigner {
+                secret: secret.into_bytes(),
+                header: signing.header.clone(),
+                payload: signing.payload.clone(),
+                timestamp_header: signing.timestamp_header.clone(),
+                encoding: signing.encoding,
+                prefix: signing.prefix.clone(),
+                hosts: signing.hosts.clone(),
+            },
+        };
+        Ok(Some(Arc::new(signer)))
+    }
+
+    /// Objectives configured by `[[slo]]`
+    pub fn slos(&self) -> Result<Vec<Slo>> {
+        self.slo
+            .iter()
+            .map(|slo| {
+                let scope = match (&slo.tag, &slo.host) {
+                    (Some(tag), None) => Scope::Tag(tag.clone()),
+                    (None, Some(host)) => Scope::Host(host.clone()),
+                    _ => anyhow::bail!("Each [[slo]] needs either a tag or a host"),
+                };
+                Slo::parse(scope, &slo.target)
+            })
+            .collect()
+    }
+
+    /// Settings of API version `name`; a version without any runs with its
+    /// name as the header value
+    pub fn api_version(&self, name: &str) -> ApiVersion {
+        self.api_versions
+            .versions
+            .get(name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Refresher configured by `[token_refresh]`, if any
+    pub fn refresher(&self) -> Result<Option<Arc<TokenRefresher>>> {
+        let Some(refresh) = &self.token_refresh else {
+            return Ok(None);
+        };
+
+        Ok(Some(Arc::new(TokenRefresher {
+            variable: refresh.variable.clone(),
+            method: refresh.method.clone(),
+            url: refresh.url.clone(),
+            headers: refresh.headers.clone().into_iter().collect(),
+            body: refresh.body.clone(),
+            path: refresh.path.clone(),
+            margin: parse_duration(&refresh.margin).context("Invalid token_refresh.margin")?,
+            refreshing: Default::default(),
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_time_travel() {
+        let config: Config = toml::from_str(
+            r#"
+            [time_travel]
+            value = "2030-01-01T00:00:00Z"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.default_headers().unwrap(),
+            vec![(
+                "X-Test-Time".to_string(),
+                "2030-01-01T00:00:00Z".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn parse_api_versions() {
+        let config: Config = toml::from_str(
+            r#"
+            [api_versions]
+            v1 = { value = "2023-10", variables = { total_field = "count" } }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.api_versions.header, "Api-Version");
+        let v1 = config.api_version("v1");
+        assert_eq!(v1.value.as_deref(), Some("2023-10"));
+        assert_eq!(v1.variables["total_field"], "count");
+        assert!(config.api_version("v2").value.is_none());
+    }
+
+    #[test]
+    fn parse_slos() {
+        let config: Config = toml::from_str(
+            r#"
+            [[slo]]
+            tag = "checkout"
+            target = "p95 < 400ms"
+
+            [[slo]]
+            host = "api.example.com"
+            target = "p99 < 1s"
+            "#,
+        )
+        .unwrap();
+        let slos = config.slos().unwrap();
+        assert_eq!(slos[0].scope, Scope::Tag("checkout".to_string()));
+        assert_eq!(slos[1].percentile, 99);
+
+        let config: Config = toml::from_str("[[slo]]\ntarget = \"p95 < 1s\"").unwrap();
+        assert!(config.slos().is_err());
+    }
+
+    #[test]
+    fn empty_config_has_no_headers() {
+        let config: Config = toml::from_str("").unwrap();
+        assert!(config.default_headers().unwrap().is_empty());
+        assert!(toml::from_str::<Config>("[unknown]").is_err());
+        assert!(config.signer().unwrap().is_none());
+        assert!(config.refresher().unwrap().is_none());
+        assert!(config.sources().unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_signing() {
+        let config: Config = toml::from_str(
+            r#"
+            [signing]
+            scheme = "hmac-sha256"
+            secret = "key"
+            encoding = "base64"
+            "#,
+        )
+        .unwrap();
+
+        let signing = config.signing.as_ref().unwrap();
+        assert_eq!(signing.header, "X-Signature");
+        assert_eq!(signing.encoding, SignatureEncoding::Base64);
+        assert!(config.signer().unwrap().is_some());
+
+        assert!(toml::from_str::<Config>("[signing]\nscheme = \"md5\"\nsecret = \"k\"").is_err());
+    }
+
+    #[test]
+    #[cfg(not(feature = "kafka"))]
+    fn queue_sources_need_their_feature() {
+        let config: Config = toml::from_str(
+            r#"
+            [kafka]
+            brokers = ["localhost:9092"]
+            "#,
+        )
+        .unwrap();
+
+        let err = config.sources().err().unwrap();
+        assert!(err.to_string().contains("--features kafka"));
+    }
+}
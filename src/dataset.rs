@@ -0,0 +1,114 @@
+//! Datasets for data-driven tests.
+//!
+//! `WITH DATA ./users.csv` runs a test block once per row of a dataset, with
+//! each column available as a `{{column}}` placeholder anywhere in the block:
+//! request, assertions, even the test name. Every row becomes its own test.
+//!
+//! Datasets are CSV files with a header row, or JSON files holding an array
+//! of objects. JSON values other than strings are used as their JSON text,
+//! so `EXPECT status == {{status}}` works with `"status": 201`.
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::path::Path;
+
+use crate::template::Variables;
+
+/// Rows of the dataset at `path`, as placeholder values
+pub fn load(path: &Path) -> Result<Vec<Variables>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read dataset {}", path.display()))?;
+
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let rows = match extension.as_str() {
+        "csv" => parse_csv(&text),
+        "json" => parse_json(&text),
+        _ => bail!(
+            "Unsupported dataset {}: use a .csv or .json file",
+            path.display()
+        ),
+    }
+    .with_context(|| format!("Invalid dataset {}", path.display()))?;
+
+    if rows.is_empty() {
+        bail!("Dataset {} has no rows", path.display());
+    }
+    Ok(rows)
+}
+
+fn parse_csv(text: &str) -> Result<Vec<Variables>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+    let columns = reader.headers()?.clone();
+
+    reader
+        .records()
+        .map(|record| {
+            let record = record?;
+            Ok(columns
+                .iter()
+                .zip(record.iter())
+                .map(|(column, value)| (column.to_string(), value.to_string()))
+                .collect())
+        })
+        .collect()
+}
+
+fn parse_json(text: &str) -> Result<Vec<Variables>> {
+    let Value::Array(rows) = serde_json::from_str(text)? else {
+        bail!("Expected an array of objects");
+    };
+
+    rows.into_iter()
+        .enumerate()
+        .map(|(index, row)| {
+            let Value::Object(fields) = row else {
+                bail!("Row {} is not an object", index + 1);
+            };
+            Ok(fields
+                .into_iter()
+                .map(|(column, value)| {
+                    let value = match value {
+                        Value::String(text) => text,
+                        other => other.to_string(),
+                    };
+                    (column, value)
+                })
+                .collect())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_rows() {
+        let rows =
+            parse_csv("email, status\na@example.com, 201\n\"b,c@example.com\",409\n").unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["email"], "a@example.com");
+        assert_eq!(rows[0]["status"], "201");
+        assert_eq!(rows[1]["email"], "b,c@example.com");
+
+        assert!(parse_csv("email,status\na@example.com\n").is_err());
+    }
+
+    #[test]
+    fn json_rows() {
+        let rows =
+            parse_json(r#"[{"email": "a@example.com", "status": 201, "admin": true}]"#).unwrap();
+        assert_eq!(rows[0]["email"], "a@example.com");
+        assert_eq!(rows[0]["status"], "201");
+        assert_eq!(rows[0]["admin"], "true");
+
+        assert!(parse_json(r#"{"email": "a@example.com"}"#).is_err());
+        assert!(parse_json("[1]").is_err());
+    }
+}
@@ -0,0 +1,163 @@
+//! DNS pre-resolution phase.
+//!
+//! Before any test is sent, every unique hostname of the run is resolved once.
+//! Successful lookups are pinned into the shared HTTP client so tests never
+//! resolve again; failed lookups abort the run with a single pre-flight error
+//! that lists the affected tests, instead of one identical DNS error per test.
+//!
+//! IP-literal hosts are skipped.
+
+use anyhow::{bail, Result};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use tokio::task::JoinSet;
+use url::Host;
+
+use crate::domain::http_request;
+use crate::domain::TestCase;
+
+/// Hosts referenced by the run, with the tests (labelled for output) using them
+fn hosts_by_test(all_tests: &[(PathBuf, Vec<TestCase>)]) -> BTreeMap<String, (u16, Vec<String>)> {
+    let mut hosts: BTreeMap<String, (u16, Vec<String>)> = BTreeMap::new();
+
+    for (file, tests) in all_tests {
+        for test in tests {
+            let label = format!(
+                "{} ({})",
+                test.name.as_deref().unwrap_or("<unnamed>"),
+                file.display()
+            );
+            for request in test.requests() {
+                let url = &request.url;
+                let Some(Host::Domain(domain)) = url.host() else {
+                    continue;
+                };
+                let port = url.port_or_known_default().unwrap_or(80);
+                let tests = &mut hosts
+                    .entry(domain.to_string())
+                    .or_insert_with(|| (port, Vec::new()))
+                    .1;
+                if !tests.contains(&label) {
+                    tests.push(label.clone());
+                }
+            }
+        }
+    }
+
+    hosts
+}
+
+/// Resolve every host of the run and pin the results into the shared client
+pub async fn preresolve(all_tests: &[(PathBuf, Vec<TestCase>)]) -> Result<()> {
+    let hosts = hosts_by_test(all_tests);
+
+    let mut lookups = JoinSet::new();
+    for (host, (port, _)) in &hosts {
+        let (host, port) = (host.clone(), *port);
+        lookups.spawn(async move {
+            let addrs = tokio::net::lookup_host((host.as_str(), port))
+                .await
+                .map(|addrs| addrs.collect::<Vec<SocketAddr>>());
+            (host, addrs)
+        });
+    }
+
+    let mut resolved = Vec::new();
+    let mut failures = BTreeMap::new();
+    while let Some(lookup) = lookups.join_next().await {
+        let (host, addrs) = lookup?;
+        match addrs {
+            Ok(addrs) if !addrs.is_empty() => resolved.push((host, addrs)),
+            Ok(_) => {
+                failures.insert(host, "no addresses found".to_string());
+            }
+            Err(error) => {
+                failures.insert(host, error.to_string());
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        bail!("{}", preflight_report(&hosts, &failures));
+    }
+
+    http_request::pin_resolved_hosts(&resolved)
+}
+
+fn preflight_report(
+    hosts: &BTreeMap<String, (u16, Vec<String>)>,
+    failures: &BTreeMap<String, String>,
+) -> String {
+    let mut report = format!("DNS pre-flight failed for {} host(s):", failures.len());
+
+    for (host, error) in failures {
+        report.push_str(&format!("\n  {}: {}", host, error));
+        for test in hosts
+            .get(host)
+            .map(|(_, tests)| tests.as_slice())
+            .unwrap_or_default()
+        {
+            report.push_str(&format!("\n    - {}", test));
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::http_request::HttpRequest;
+    use url::Url;
+
+    fn test_case(name: &str, url: &str) -> TestCase {
+        TestCase {
+            name: Some(name.to_string()),
+            request: HttpRequest::new("GET".into(), Url::parse(url).unwrap()),
+            response: None,
+            assertions: Vec::new(),
+            result: None,
+            fixture: None,
+            annotations: Vec::new(),
+            captures: Vec::new(),
+            steps: Vec::new(),
+            settle: None,
+            tags: Vec::new(),
+            skip: None,
+            only: false,
+            repeat: 1,
+            retry: None,
+            attempts: 0,
+            pacing: Default::default(),
+            iterations: Vec::new(),
+            dataset: None,
+            span: None,
+            location: None,
+            assertion_lines: Vec::new(),
+            allow: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn groups_tests_by_host_and_reports_failures() {
+        let all_tests = vec![(
+            PathBuf::from("api.ax"),
+            vec![
+                test_case("list", "https://api.example.com/users"),
+                test_case("get", "https://api.example.com/users/1"),
+                test_case("local", "http://127.0.0.1:8080/health"),
+            ],
+        )];
+
+        let hosts = hosts_by_test(&all_tests);
+        assert_eq!(hosts.len(), 1);
+        assert_eq!(hosts["api.example.com"].0, 443);
+
+        let failures = BTreeMap::from([("api.example.com".to_string(), "not found".to_string())]);
+        assert_eq!(
+            preflight_report(&hosts, &failures),
+            "DNS pre-flight failed for 1 host(s):\n  api.example.com: not found\n    - list (api.ax)\n    - get (api.ax)"
+        );
+    }
+}
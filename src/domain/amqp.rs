@@ -0,0 +1,89 @@
+//! AMQP source for `AMQP "<queue>" RECEIVES ...` assertions (behind the
+//! `amqp` feature).
+//!
+//! Each fetch drains the queue with `basic.get` (acknowledging what it
+//! reads) and keeps the messages for the rest of the run. Messages carrying
+//! a timestamp property older than the test's request are skipped.
+
+use chrono::{DateTime, Utc};
+use lapin::options::BasicGetOptions;
+use lapin::{Channel, Connection, ConnectionProperties};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tokio::sync::{Mutex, OnceCell};
+
+use super::source::{ExternalSource, Fetch};
+
+/// Messages read so far, by queue, with their timestamp property if any
+type Received = HashMap<String, Vec<(Option<DateTime<Utc>>, Value)>>;
+
+pub struct Amqp {
+    url: String,
+    channel: OnceCell<(Connection, Channel)>,
+    received: Mutex<Received>,
+}
+
+impl std::fmt::Debug for Amqp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Amqp").finish_non_exhaustive()
+    }
+}
+
+impl Amqp {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            channel: OnceCell::new(),
+            received: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn channel(&self) -> anyhow::Result<&Channel> {
+        let (_, channel) = self
+            .channel
+            .get_or_try_init(|| async {
+                let connection =
+                    Connection::connect(&self.url, ConnectionProperties::default()).await?;
+                let channel = connection.create_channel().await?;
+                Ok::<_, lapin::Error>((connection, channel))
+            })
+            .await?;
+        Ok(channel)
+    }
+}
+
+impl ExternalSource for Amqp {
+    fn fetch<'a>(&'a self, key: &'a str, since: DateTime<Utc>) -> Fetch<'a> {
+        Box::pin(async move {
+            let channel = self.channel().await?;
+            let mut received = self.received.lock().await;
+            let messages = received.entry(key.to_string()).or_default();
+
+            while let Some(message) = channel
+                .basic_get(key, BasicGetOptions { no_ack: true })
+                .await?
+            {
+                let delivery = message.delivery;
+                let timestamp = (*delivery.properties.timestamp())
+                    .and_then(|seconds| DateTime::from_timestamp(seconds as i64, 0));
+                messages.push((
+                    timestamp,
+                    json!({
+                        "key": delivery.routing_key.as_str(),
+                        "body": String::from_utf8_lossy(&delivery.data),
+                    }),
+                ));
+            }
+
+            Ok(messages
+                .iter()
+                // AMQP timestamps have second precision
+                .filter(|(timestamp, _)| match timestamp {
+                    Some(timestamp) => timestamp.timestamp() >= since.timestamp(),
+                    None => true,
+                })
+                .map(|(_, message)| message.clone())
+                .collect())
+        })
+    }
+}
@@ -1,5 +1,10 @@
-use crate::domain::http_request::HttpResponse;
+use crate::domain::http_request::{HttpRequest, HttpResponse};
+use crate::domain::{Location, Normalization};
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
 use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 #[derive(Debug, PartialEq)]
 pub enum Assertion {
@@ -8,6 +13,20 @@ pub enum Assertion {
         op: Operator,
         value: Value,
     },
+    /// At least one of the values a wildcard path (`items[*].status`)
+    /// resolves to satisfies `op value`
+    Any {
+        path: String,
+        op: Operator,
+        value: Value,
+    },
+    /// Number of elements of an array, entries of an object, or characters
+    /// of a string at `path`, compared with `op value`
+    Length {
+        path: String,
+        op: Operator,
+        value: Value,
+    },
     In {
         path: String,
         values: Vec<Value>,
@@ -20,9 +39,164 @@ pub enum Assertion {
     Exists {
         path: String,
     },
+    /// The value at `path` is of type `expected` (`IS ARRAY`)
+    Type {
+        path: String,
+        expected: JsonType,
+    },
+    /// The value at `path` is JSON `null` (or is not, when `negated`); a
+    /// missing path fails either way
+    Null {
+        path: String,
+        negated: bool,
+    },
     Unary {
         path: String,
     },
+    /// Structural comparison of a JSON body (or sub-path) against a fixture file
+    EqualsFile {
+        path: String,
+        file: PathBuf,
+        ignoring: Vec<String>,
+    },
+    /// Structural comparison of a JSON body (or sub-path) against inline
+    /// JSON (`body == JSON {...} END`)
+    EqualsJson {
+        path: String,
+        expected: serde_json::Value,
+    },
+    /// Re-sends the request `count` times and checks how `path` evolves
+    Poll {
+        count: usize,
+        interval: Option<Duration>,
+        path: String,
+        order: PollOrder,
+    },
+    /// Re-sends the request every `interval` until `assertion` holds, for at
+    /// most `timeout` (`POLL EVERY 1s UNTIL ... TIMEOUT 30s`)
+    PollUntil {
+        interval: Duration,
+        timeout: Duration,
+        assertion: Box<Assertion>,
+    },
+    /// Re-sends the request once per Accept header and checks each variant
+    Negotiates {
+        variants: Vec<NegotiationVariant>,
+    },
+    /// Sends GET and HEAD and checks HEAD mirrors GET without a body
+    HeadMatchesGet,
+    /// Probes every other method and expects `405` with an `Allow` header
+    OnlyMethods {
+        allowed: Vec<String>,
+    },
+    /// Re-sends the request with `Expect: 100-continue` and expects the
+    /// server to answer within `within`, before the body is sent
+    ExpectContinue {
+        within: Duration,
+    },
+    /// Sends until a 429 arrives and checks the rate-limit headers, and
+    /// optionally that the endpoint recovers after Retry-After
+    RateLimit {
+        max: usize,
+        interval: Option<Duration>,
+        headers: Vec<String>,
+        recovers: bool,
+    },
+    /// The raw body is valid in `encoding`, or in the declared charset
+    /// (UTF-8 when none is declared)
+    Encoded {
+        encoding: Option<String>,
+    },
+    /// Every operand holds; checking stops at the first that fails
+    And(Vec<Assertion>),
+    /// At least one operand holds; checking stops at the first that does
+    Or(Vec<Assertion>),
+    /// Wraps an assertion whose values must never be printed (e.g. PII)
+    Sensitive {
+        assertion: Box<Assertion>,
+    },
+    /// Wraps an assertion whose failure is reported without failing the run
+    Minor {
+        assertion: Box<Assertion>,
+    },
+    /// Polls an external source (e.g. `MAILBOX`) for up to `within` until an
+    /// item for `key` has a `field` matching `expected`
+    Receives {
+        source: String,
+        key: String,
+        field: String,
+        matcher: TextMatch,
+        expected: String,
+        within: Duration,
+    },
+    /// Runs `query` against the database at `url` and compares the single
+    /// value it selects, retrying for up to `within` if given
+    Query {
+        url: String,
+        query: String,
+        op: Operator,
+        value: Value,
+        within: Option<Duration>,
+    },
+    /// Looks up `key` in the Redis store at `url`: it must exist, and hold a
+    /// value satisfying `expected` when given. Retries for up to `within`
+    KeyValue {
+        url: String,
+        key: String,
+        expected: Option<(Operator, Value)>,
+        within: Option<Duration>,
+    },
+    /// Waits up to `within` for a request to `path` on a local listener and
+    /// checks it with `assertion` (`LISTEN :9099 /callback EXPECT ...`)
+    Callback {
+        port: u16,
+        path: String,
+        within: Duration,
+        assertion: Box<Assertion>,
+    },
+}
+
+/// How the responses an assertion is checked against are gathered
+#[derive(Debug)]
+pub enum Sampling {
+    /// The test's own response
+    Single,
+    /// `count` responses to the test's request, `interval` apart
+    Poll {
+        count: usize,
+        interval: Option<Duration>,
+    },
+    /// One response per derived request, in order
+    Probes(Vec<HttpRequest>),
+    /// Responses to the test's request, `interval` apart, until the
+    /// assertion holds or `timeout` has passed
+    Until {
+        interval: Duration,
+        timeout: Duration,
+    },
+    /// Responses until the first 429 (at most `max`), plus one more sent
+    /// after honoring Retry-After when `recover` is set
+    UntilRateLimited {
+        max: usize,
+        interval: Option<Duration>,
+        recover: bool,
+    },
+    /// Snapshots of an external source's items for `key`, taken until the
+    /// assertion holds or `within` has passed
+    External {
+        source: String,
+        /// Where to open the source, when the test names it (a DB URL)
+        target: Option<String>,
+        key: String,
+        within: Duration,
+    },
+    /// The callback received by the listener registered for the assertion
+    /// (see [`Assertion::callback_target`]), waiting at most `within`
+    Callback { within: Duration },
+    /// The response to the request sent with `Expect: 100-continue`, if
+    /// the server answered within `within` (see
+    /// [`crate::domain::expect_continue`])
+    ExpectContinue { within: Duration },
 }
 
 #[derive(Debug)]
@@ -31,6 +205,10 @@ pub struct AssertionFailure {
     pub expected: Option<String>,
     pub actual: Option<String>,
     pub message: String,
+    /// The assertion that failed, or the test block when the request did
+    pub location: Option<Box<Location>>,
+    /// From a `MINOR` assertion: reported, but does not fail the run
+    pub minor: bool,
 }
 
 #[derive(Debug, PartialEq)]
@@ -41,13 +219,152 @@ pub enum Operator {
     Lt,
     Gte,
     Lte,
+    /// The text begins with the value
+    StartsWith,
+    /// The text ends with the value
+    EndsWith,
+}
+
+/// One content-negotiation probe: `"text/csv" => 200 "text/csv"`
+#[derive(Debug, PartialEq)]
+pub struct NegotiationVariant {
+    pub accept: String,
+    pub status: i64,
+    pub content_type: Option<String>,
 }
 
+/// How a text field of an external item is compared
 #[derive(Debug, PartialEq)]
+pub enum TextMatch {
+    Equals,
+    Contains,
+}
+
+impl TextMatch {
+    fn holds(&self, actual: &str, expected: &str) -> bool {
+        match self {
+            TextMatch::Equals => actual == expected,
+            TextMatch::Contains => actual.contains(expected),
+        }
+    }
+}
+
+impl fmt::Display for TextMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextMatch::Equals => write!(f, "equal to"),
+            TextMatch::Contains => write!(f, "containing"),
+        }
+    }
+}
+
+/// Type of a value, as checked by `IS STRING`, `IS ARRAY`, ...
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JsonType {
+    String,
+    Number,
+    Boolean,
+    Array,
+    Object,
+}
+
+impl JsonType {
+    fn of(json: &serde_json::Value) -> Option<JsonType> {
+        match json {
+            serde_json::Value::String(_) => Some(JsonType::String),
+            serde_json::Value::Number(_) => Some(JsonType::Number),
+            serde_json::Value::Bool(_) => Some(JsonType::Boolean),
+            serde_json::Value::Array(_) => Some(JsonType::Array),
+            serde_json::Value::Object(_) => Some(JsonType::Object),
+            serde_json::Value::Null => None,
+        }
+    }
+}
+
+impl fmt::Display for JsonType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            JsonType::String => "string",
+            JsonType::Number => "number",
+            JsonType::Boolean => "boolean",
+            JsonType::Array => "array",
+            JsonType::Object => "object",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Expected evolution of a numeric value across repeated polls
+#[derive(Debug, PartialEq)]
+pub enum PollOrder {
+    Increasing,
+    NonDecreasing,
+    Decreasing,
+    NonIncreasing,
+}
+
+impl PollOrder {
+    /// Whether `next` follows `previous` in this order; integers and floats
+    /// compare by value
+    fn holds(&self, previous: &Value, next: &Value) -> bool {
+        let Some(ordering) = next.numeric_cmp(previous) else {
+            return false;
+        };
+        match self {
+            PollOrder::Increasing => ordering.is_gt(),
+            PollOrder::NonDecreasing => ordering.is_ge(),
+            PollOrder::Decreasing => ordering.is_lt(),
+            PollOrder::NonIncreasing => ordering.is_le(),
+        }
+    }
+}
+
+impl fmt::Display for PollOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PollOrder::Increasing => "increasing",
+            PollOrder::NonDecreasing => "non-decreasing",
+            PollOrder::Decreasing => "decreasing",
+            PollOrder::NonIncreasing => "non-increasing",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug)]
 pub enum Value {
     String(String),
     Number(i64),
+    /// A number with a fractional part, or beyond `i64`
+    Float(f64),
     Bool(bool),
+    /// JSON `null`, as opposed to a missing path
+    Null,
+}
+
+impl Value {
+    /// Order of two numbers, integers and floats alike; `None` otherwise
+    fn numeric_cmp(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => Some(a.cmp(b)),
+            (Value::Number(a), Value::Float(b)) => (*a as f64).partial_cmp(b),
+            (Value::Float(a), Value::Number(b)) => a.partial_cmp(&(*b as f64)),
+            (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+            _ => None,
+        }
+    }
+}
+
+/// Numbers are equal by value, so `1` matches `1.0`
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Null, Value::Null) => true,
+            _ => self.numeric_cmp(other) == Some(Ordering::Equal),
+        }
+    }
 }
 
 impl fmt::Display for Value {
@@ -55,7 +372,9 @@ impl fmt::Display for Value {
         match self {
             Value::String(s) => write!(f, "\"{}\"", s),
             Value::Number(n) => write!(f, "{}", n),
+            Value::Float(n) => write!(f, "{}", n),
             Value::Bool(b) => write!(f, "{}", b),
+            Value::Null => write!(f, "null"),
         }
     }
 }
@@ -82,38 +401,921 @@ impl fmt::Display for AssertionFailure {
     }
 }
 
-fn resolve_path(response: &HttpResponse, path: &str) -> Option<Value> {
+/// Value at `path` (`status`, `charset`, `headers.x`, `trailers.x`,
+/// `cookies.x`, `body`, `body.a.b`) in `response`
+pub fn resolve_path(response: &HttpResponse, path: &str) -> Option<Value> {
     // status
     if path == "status" {
         return Some(Value::Number(response.status as i64));
     }
 
+    // size: bytes in the body
+    if path == "size" {
+        return Some(Value::Number(response.size() as i64));
+    }
+
+    // charset declared in Content-Type
+    if path == "charset" {
+        return response.charset().map(Value::String);
+    }
+
+    // headers.<name>: header value, name matched case-insensitively
+    if let Some(name) = path.strip_prefix("headers.") {
+        return response.header(name).map(|value| Value::String(value.to_string()));
+    }
+
+    // trailers.<name>: trailer field after the body, name matched
+    // case-insensitively
+    if let Some(name) = path.strip_prefix("trailers.") {
+        return response.trailer(name).map(|value| Value::String(value.to_string()));
+    }
+
+    // cookies.<name>: value of a cookie set by the response
+    if let Some(name) = path.strip_prefix("cookies.") {
+        return response
+            .cookies
+            .iter()
+            .rev()
+            .find(|(cookie, _)| cookie == name)
+            .map(|(_, value)| Value::String(value.clone()));
+    }
+
     // full body as string
     if path == "body" {
         return response.body.as_ref().map(|s| Value::String(s.clone()));
     }
 
-    // body.xxx.yyy → only if JSON
-    if let Some(rest) = path.strip_prefix("body.") {
-        let body_str = response.body.as_ref()?; // &String
+    // body.xxx.yyy, body.items[0].id, body[0] → only if JSON
+    if let Some(rest) = body_path(path) {
+        // Decode JSON, XML, ... → parse failure → None
+        let json = response.decoded_body()?;
+        return scalar(json_at(&json, rest)?);
+    }
+
+    None
+}
+
+/// Length of the value at `path`: elements of a JSON array, entries of a
+/// JSON object, or characters of a string (including the raw `body`)
+pub fn resolve_length(response: &HttpResponse, path: &str) -> Option<Value> {
+    let json = if path == "body" {
+        response.decoded_body()
+    } else {
+        body_path(path).and_then(|rest| json_at(&response.decoded_body()?, rest).cloned())
+    };
+
+    let length = match json {
+        Some(serde_json::Value::Array(items)) => items.len(),
+        Some(serde_json::Value::Object(entries)) => entries.len(),
+        Some(serde_json::Value::String(text)) => text.chars().count(),
+        _ => match resolve_path(response, path)? {
+            Value::String(text) => text.chars().count(),
+            _ => return None,
+        },
+    };
+    Some(Value::Number(length as i64))
+}
+
+/// Value at `path` as JSON, keeping the arrays and objects that
+/// [`resolve_path`] has no [`Value`] for; `Some(Null)` for `null`
+pub fn resolve_json(response: &HttpResponse, path: &str) -> Option<serde_json::Value> {
+    if path == "body" {
+        if let Some(json) = response.decoded_body() {
+            return Some(json);
+        }
+    } else if let Some(rest) = body_path(path) {
+        return json_at(&response.decoded_body()?, rest).cloned();
+    }
+
+    Some(match resolve_path(response, path)? {
+        Value::String(text) => serde_json::Value::String(text),
+        Value::Number(number) => serde_json::Value::from(number),
+        Value::Float(number) => serde_json::Value::from(number),
+        Value::Bool(flag) => serde_json::Value::Bool(flag),
+        Value::Null => serde_json::Value::Null,
+    })
+}
+
+/// A JSON leaf as compared by assertions; objects and arrays have no value
+fn scalar(json: &serde_json::Value) -> Option<Value> {
+    match json {
+        serde_json::Value::String(s) => Some(Value::String(s.clone())),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(n) => Some(Value::Number(n)),
+            None => Some(Value::Float(n.as_f64()?)),
+        },
+        serde_json::Value::Bool(b) => Some(Value::Bool(*b)),
+        serde_json::Value::Null => Some(Value::Null),
+        _ => None,
+    }
+}
 
-        // Try parse JSON
-        let json: serde_json::Value = serde_json::from_str(body_str).ok()?; // parse failure → None
+/// Whether `path` has a `[*]` segment standing for every array element
+pub fn is_wildcard(path: &str) -> bool {
+    path.contains("[*]")
+}
 
-        let mut current = &json;
-        for key in rest.split('.') {
-            current = current.get(key)?;
+/// Values at `path`, one per element matched by its `[*]` segments (`None`
+/// for elements without the rest of the path), or `None` when the array
+/// itself is missing
+pub fn resolve_each(response: &HttpResponse, path: &str) -> Option<Vec<Option<Value>>> {
+    match body_path(path) {
+        Some(rest) if is_wildcard(rest) => {
+            let json = response.decoded_body()?;
+            let values = json_each(&json, rest)?;
+            Some(values.into_iter().map(|json| json.and_then(scalar)).collect())
         }
+        _ => Some(vec![resolve_path(response, path)]),
+    }
+}
 
-        return match current {
-            serde_json::Value::String(s) => Some(Value::String(s.clone())),
-            serde_json::Value::Number(n) => Some(Value::Number(n.as_i64()?)),
-            serde_json::Value::Bool(b) => Some(Value::Bool(*b)),
-            _ => None,
+/// JSON at `path` within `json` for every element matched by `[*]`
+fn json_each<'a>(
+    json: &'a serde_json::Value,
+    path: &str,
+) -> Option<Vec<Option<&'a serde_json::Value>>> {
+    let Some((head, tail)) = path.split_once("[*]") else {
+        return Some(vec![json_at(json, path)]);
+    };
+    let array = if head.is_empty() {
+        json
+    } else {
+        json_at(json, head)?
+    };
+    let tail = tail.strip_prefix('.').unwrap_or(tail);
+
+    let mut values = Vec::new();
+    for element in array.as_array()? {
+        if tail.is_empty() {
+            values.push(Some(element));
+        } else {
+            match json_each(element, tail) {
+                Some(nested) => values.extend(nested),
+                None => values.push(None),
+            }
+        }
+    }
+    Some(values)
+}
+
+/// `path op value` over every value a wildcard path resolves to, or over
+/// at least one of them when `any`
+fn check_each(
+    response: &HttpResponse,
+    path: &str,
+    op: &Operator,
+    value: &Value,
+    any: bool,
+    shown: Shown,
+) -> Result<(), AssertionFailure> {
+    let failure = |message: String, actual: Option<String>| AssertionFailure {
+        path: path.to_string(),
+        expected: Some(value.to_string()),
+        actual,
+        message,
+        location: None,
+        minor: false,
+    };
+    let values = resolve_each(response, path)
+        .ok_or_else(|| failure(format!("Path '{}' not found", path), None))?;
+    let matches = |actual: &Option<Value>| {
+        actual
+            .as_ref()
+            .is_some_and(|actual| compare(op, actual, value))
+    };
+
+    if any {
+        if values.iter().any(matches) {
+            return Ok(());
+        }
+        let actual: Vec<String> = values
+            .iter()
+            .map(|actual| actual.as_ref().map_or("(missing)".into(), |a| shown.value(a)))
+            .collect();
+        return Err(failure(
+            format!("Expected any {} {:?} {}", path, op, value),
+            Some(format!("[{}]", actual.join(", "))),
+        ));
+    }
+
+    let failed: Vec<usize> = (0..values.len())
+        .filter(|&index| !matches(&values[index]))
+        .collect();
+    let actual = |index: usize| {
+        values[index]
+            .as_ref()
+            .map_or("(missing)".into(), |actual| shown.value(actual))
+    };
+    match failed.as_slice() {
+        [] => Ok(()),
+        [index] => Err(failure(
+            format!("Expected every {} {:?} {}, element {} does not match", path, op, value, index),
+            Some(actual(*index)),
+        )),
+        _ => {
+            let indices: Vec<String> = failed.iter().map(usize::to_string).collect();
+            let actual: Vec<String> = failed.iter().map(|&index| actual(index)).collect();
+            Err(failure(
+                format!(
+                    "Expected every {} {:?} {}, elements {} do not match",
+                    path,
+                    op,
+                    value,
+                    indices.join(", ")
+                ),
+                Some(format!("[{}]", actual.join(", "))),
+            ))
+        }
+    }
+}
+
+/// What follows `body` in `path`, when it points inside the body:
+/// `items[0]` for `body.items[0]`, `[0]` for `body[0]`
+pub(crate) fn body_path(path: &str) -> Option<&str> {
+    path.strip_prefix("body.")
+        .or_else(|| path.strip_prefix("body").filter(|rest| rest.starts_with('[')))
+}
+
+/// One step of a path below `body`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Segment<'a> {
+    Key(&'a str),
+    Index(usize),
+    /// `[*]`: every element of an array
+    Each,
+}
+
+/// Steps of a path below `body`: keys separated by dots, each optionally
+/// followed by array indexes or `[*]` (`items[0].id`, `items[*].tags[1]`);
+/// `None` when it is malformed
+pub(crate) fn segments(path: &str) -> Option<Vec<Segment<'_>>> {
+    let mut steps = Vec::new();
+    for segment in path.split('.') {
+        let (key, indexes) = segment.split_at(segment.find('[').unwrap_or(segment.len()));
+        if !key.is_empty() {
+            steps.push(Segment::Key(key));
+        }
+        if !indexes.is_empty() && !indexes.ends_with(']') {
+            return None;
+        }
+        for index in indexes.split_terminator(']') {
+            steps.push(match index.strip_prefix('[')? {
+                "*" => Segment::Each,
+                index => Segment::Index(index.parse().ok()?),
+            });
+        }
+    }
+    Some(steps)
+}
+
+/// JSON at `path` within `json`: keys separated by dots, each optionally
+/// followed by array indexes (`items[0].id`, `matrix[1][0]`)
+fn json_at<'a>(json: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    let mut current = json;
+    for segment in segments(path)? {
+        current = match segment {
+            Segment::Key(key) => current.get(key)?,
+            Segment::Index(index) => current.get(index)?,
+            Segment::Each => return None,
         };
     }
+    Some(current)
+}
 
-    None
+/// Compare the JSON at `path` with the fixture in `file`, after removing
+/// `ignoring` paths from both sides. Ignore paths are written relative to the
+/// response (`body.id`), so they are rebased onto the fixture root.
+fn check_equals_file(
+    response: &HttpResponse,
+    path: &str,
+    file: &Path,
+    ignoring: &[String],
+    shown: Shown,
+) -> Result<(), AssertionFailure> {
+    let failure = |message: String, actual: Option<String>| AssertionFailure {
+        path: path.to_string(),
+        expected: Some(format!("contents of {}", file.display())),
+        actual,
+        message,
+        location: None,
+        minor: false,
+    };
+
+    let fixture = std::fs::read_to_string(file)
+        .map_err(|e| failure(format!("Failed to read fixture {}: {}", file.display(), e), None))?;
+    let mut expected: serde_json::Value = serde_json::from_str(&fixture)
+        .map_err(|e| failure(format!("Invalid JSON in fixture {}: {}", file.display(), e), None))?;
+
+    // Decoded like every other body check (DECODE, or by content type)
+    let Some(mut body) = response.decoded_body() else {
+        let body = response.body.as_deref().map(|body| shown.value(body));
+        return Err(failure("Response body is not valid JSON".into(), body));
+    };
+    Normalization::new(true, ignoring.to_vec()).apply(&mut body);
+
+    let mut actual = &body;
+    if let Some(rest) = body_path(path) {
+        actual = json_at(actual, rest)
+            .ok_or_else(|| failure(format!("Path '{}' not found", path), None))?;
+    }
+
+    let prefix = format!("{}.", path);
+    let fixture_ignoring = ignoring
+        .iter()
+        .filter_map(|p| p.strip_prefix(&prefix))
+        .map(|rest| format!("body.{}", rest))
+        .collect();
+    Normalization::new(true, fixture_ignoring).apply(&mut expected);
+
+    check_json_equal(path, &expected, actual, &file.display().to_string(), shown)
+}
+
+/// Fail with the differences unless `actual`, at `path`, equals `expected`
+/// (`what` names it in the message); object key order does not matter
+fn check_json_equal(
+    path: &str,
+    expected: &serde_json::Value,
+    actual: &serde_json::Value,
+    what: &str,
+    shown: Shown,
+) -> Result<(), AssertionFailure> {
+    if actual == expected {
+        return Ok(());
+    }
+
+    let mut differences = Vec::new();
+    json_differences(expected, actual, path, shown, &mut differences);
+    Err(AssertionFailure {
+        path: path.to_string(),
+        expected: Some(expected.to_string()),
+        actual: Some(shown.value(actual)),
+        message: format!(
+            "Expected '{}' to equal {}:\n  {}",
+            path,
+            what,
+            differences.join("\n  ")
+        ),
+        location: None,
+        minor: false,
+    })
+}
+
+/// One line per difference between `expected` and `actual`, found at `at`,
+/// with the values of `actual` as `shown`
+fn json_differences(
+    expected: &serde_json::Value,
+    actual: &serde_json::Value,
+    at: &str,
+    shown: Shown,
+    out: &mut Vec<String>,
+) {
+    use serde_json::Value as Json;
+    match (expected, actual) {
+        (Json::Object(expected), Json::Object(actual)) => {
+            for (key, value) in expected {
+                let at = format!("{}.{}", at, key);
+                match actual.get(key) {
+                    Some(other) => json_differences(value, other, &at, shown, out),
+                    None => out.push(format!("{}: missing, expected {}", at, value)),
+                }
+            }
+            for (key, value) in actual {
+                if !expected.contains_key(key) {
+                    out.push(format!("{}.{}: unexpected {}", at, key, shown.value(value)));
+                }
+            }
+        }
+        (Json::Array(expected), Json::Array(actual)) => {
+            for index in 0..expected.len().max(actual.len()) {
+                let at = format!("{}[{}]", at, index);
+                match (expected.get(index), actual.get(index)) {
+                    (Some(value), Some(other)) => json_differences(value, other, &at, shown, out),
+                    (Some(value), None) => out.push(format!("{}: missing, expected {}", at, value)),
+                    (None, Some(other)) => {
+                        out.push(format!("{}: unexpected {}", at, shown.value(other)))
+                    }
+                    (None, None) => {}
+                }
+            }
+        }
+        _ if expected != actual => {
+            out.push(format!("{}: expected {}, got {}", at, expected, shown.value(actual)));
+        }
+        _ => {}
+    }
+}
+
+/// Check that the last of the polled `responses` satisfies `assertion`
+fn check_poll_until(
+    responses: &[HttpResponse],
+    timeout: Duration,
+    assertion: &Assertion,
+    shown: Shown,
+) -> Result<(), AssertionFailure> {
+    let Some(last) = responses.last() else {
+        return Err(no_response("POLL UNTIL"));
+    };
+    assertion.check_shown(last, shown).map_err(|mut failure| {
+        failure.message = format!(
+            "Still failing after {} attempt(s) over {:?}: {}",
+            responses.len(),
+            timeout,
+            failure.message
+        );
+        failure
+    })
+}
+
+/// Check that `path` evolves in `order` across the polled `responses`
+fn check_poll(
+    responses: &[HttpResponse],
+    path: &str,
+    order: &PollOrder,
+    shown: Shown,
+) -> Result<(), AssertionFailure> {
+    let mut observed = Vec::new();
+    for response in responses {
+        match resolve_path(response, path) {
+            Some(number @ (Value::Number(_) | Value::Float(_))) => observed.push(number),
+            other => {
+                return Err(AssertionFailure {
+                    path: path.to_string(),
+                    expected: Some(format!("{} number", order)),
+                    actual: other.map(|v| shown.value(v)),
+                    message: format!("Path '{}' is not a number in poll {}", path, observed.len() + 1),
+                    location: None,
+                    minor: false,
+                });
+            }
+        }
+    }
+
+    if let Some(i) = observed.windows(2).position(|w| !order.holds(&w[0], &w[1])) {
+        let observed: Vec<String> = observed.iter().map(Value::to_string).collect();
+        return Err(AssertionFailure {
+            path: path.to_string(),
+            expected: Some(order.to_string()),
+            actual: Some(shown.value(format!("[{}]", observed.join(", ")))),
+            message: format!(
+                "Expected '{}' to be {} across {} polls, but poll {} broke the order",
+                path,
+                order,
+                responses.len(),
+                i + 2
+            ),
+            location: None,
+            minor: false,
+        });
+    }
+
+    Ok(())
+}
+
+/// Media type of a response without parameters, e.g. `application/json`
+fn media_type(response: &HttpResponse) -> Option<String> {
+    response
+        .header("content-type")
+        .map(|value| value.split(';').next().unwrap_or("").trim().to_ascii_lowercase())
+}
+
+/// Check each negotiated response against its variant, in order
+fn check_negotiation(
+    responses: &[HttpResponse],
+    variants: &[NegotiationVariant],
+    shown: Shown,
+) -> Result<(), AssertionFailure> {
+    for (variant, response) in variants.iter().zip(responses) {
+        let path = format!("Accept: {}", variant.accept);
+
+        if response.status as i64 != variant.status {
+            return Err(AssertionFailure {
+                path,
+                expected: Some(format!("status {}", variant.status)),
+                actual: Some(shown.value(format!("status {}", response.status))),
+                message: format!(
+                    "Expected status {} for Accept '{}'",
+                    variant.status, variant.accept
+                ),
+                location: None,
+                minor: false,
+            });
+        }
+
+        if let Some(expected) = &variant.content_type {
+            let actual = media_type(response);
+            if actual.as_deref() != Some(expected.to_ascii_lowercase().as_str()) {
+                return Err(AssertionFailure {
+                    path,
+                    expected: Some(expected.clone()),
+                    actual: actual.map(|actual| shown.value(actual)),
+                    message: format!(
+                        "Expected Content-Type '{}' for Accept '{}'",
+                        expected, variant.accept
+                    ),
+                    location: None,
+                    minor: false,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that a HEAD response mirrors the GET response to the same URL:
+/// same status, same Content-Length and ETag (when GET sends them), no body
+fn check_head_matches_get(responses: &[HttpResponse], shown: Shown) -> Result<(), AssertionFailure> {
+    let [get, head] = responses else {
+        return Err(AssertionFailure {
+            path: "HEAD".into(),
+            expected: None,
+            actual: None,
+            message: "HEAD_MATCHES_GET needs both a GET and a HEAD response".into(),
+            location: None,
+            minor: false,
+        });
+    };
+
+    let mismatch = |what: &str, expected: Option<&str>, actual: Option<&str>| AssertionFailure {
+        path: format!("HEAD {}", what),
+        expected: expected.map(str::to_string),
+        actual: actual.map(|actual| shown.value(actual)),
+        message: format!("Expected HEAD {} to match GET", what),
+        location: None,
+        minor: false,
+    };
+
+    if get.status != head.status {
+        return Err(mismatch(
+            "status",
+            Some(get.status.to_string().as_str()),
+            Some(head.status.to_string().as_str()),
+        ));
+    }
+
+    for name in ["content-length", "etag"] {
+        if let Some(expected) = get.header(name) {
+            if head.header(name) != Some(expected) {
+                return Err(mismatch(name, Some(expected), head.header(name)));
+            }
+        }
+    }
+
+    if head.body.as_deref().is_some_and(|body| !body.is_empty()) {
+        return Err(AssertionFailure {
+            path: "HEAD body".into(),
+            expected: Some("empty".into()),
+            actual: head.body.as_ref().map(|b| shown.value(format!("{} bytes", b.len()))),
+            message: "Expected HEAD response without a body".into(),
+            location: None,
+            minor: false,
+        });
+    }
+
+    Ok(())
+}
+
+/// Methods probed by `ONLY_METHODS`. OPTIONS is left out because it is
+/// commonly answered by CORS middleware regardless of the endpoint.
+const PROBED_METHODS: [&str; 6] = ["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE"];
+
+/// Methods `ONLY_METHODS` expects to be rejected; HEAD is implied by GET
+fn disallowed_methods(allowed: &[String]) -> Vec<&'static str> {
+    PROBED_METHODS
+        .into_iter()
+        .filter(|method| {
+            let implied = *method == "HEAD" && allowed.iter().any(|m| m == "GET");
+            !implied && !allowed.iter().any(|m| m == method)
+        })
+        .collect()
+}
+
+fn check_only_methods(
+    responses: &[HttpResponse],
+    allowed: &[String],
+    shown: Shown,
+) -> Result<(), AssertionFailure> {
+    for (method, response) in disallowed_methods(allowed).into_iter().zip(responses) {
+        if response.status != 405 || response.header("allow").is_none() {
+            let allow = response
+                .header("allow")
+                .map(|value| format!(", Allow: {}", value))
+                .unwrap_or_default();
+
+            return Err(AssertionFailure {
+                path: method.to_string(),
+                expected: Some("405 with Allow header".into()),
+                actual: Some(shown.value(format!("{}{}", response.status, allow))),
+                message: format!(
+                    "Expected {} to be rejected (only {} allowed)",
+                    method,
+                    allowed.join(", ")
+                ),
+                location: None,
+                minor: false,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn check_rate_limit(
+    responses: &[HttpResponse],
+    headers: &[String],
+    recovers: bool,
+    shown: Shown,
+) -> Result<(), AssertionFailure> {
+    let Some(index) = responses.iter().position(|r| r.status == 429) else {
+        return Err(AssertionFailure {
+            path: "status".into(),
+            expected: Some("429".into()),
+            actual: Some(shown.value(format!("no 429 after {} requests", responses.len()))),
+            message: "Expected the endpoint to start rate limiting".into(),
+            location: None,
+            minor: false,
+        });
+    };
+    let limited = &responses[index];
+
+    let required = std::iter::once("Retry-After").chain(headers.iter().map(String::as_str));
+    for name in required {
+        if limited.header(name).is_none() {
+            return Err(AssertionFailure {
+                path: format!("headers.{}", name),
+                expected: Some("present".into()),
+                actual: None,
+                message: format!(
+                    "Expected 429 response (request {}) to include '{}'",
+                    index + 1,
+                    name
+                ),
+                location: None,
+                minor: false,
+            });
+        }
+    }
+
+    if recovers {
+        let recovered = responses.get(index + 1).map(|r| r.status);
+        if recovered.is_none() || recovered == Some(429) {
+            return Err(AssertionFailure {
+                path: "status".into(),
+                expected: Some("not 429 after Retry-After".into()),
+                actual: recovered.map(|status| shown.value(status)),
+                message: "Expected the endpoint to recover after waiting Retry-After".into(),
+                location: None,
+                minor: false,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn check_encoded(
+    response: &HttpResponse,
+    encoding: Option<&str>,
+    shown: Shown,
+) -> Result<(), AssertionFailure> {
+    let label = encoding
+        .map(str::to_ascii_lowercase)
+        .or_else(|| response.charset())
+        .unwrap_or_else(|| "utf-8".to_string());
+
+    let failure = |actual: Option<String>, message: String| AssertionFailure {
+        path: "body".into(),
+        expected: Some(format!("valid {}", label)),
+        actual: actual.map(|actual| shown.value(actual)),
+        message,
+        location: None,
+        minor: false,
+    };
+
+    let Some(decoder) = encoding_rs::Encoding::for_label(label.as_bytes()) else {
+        return Err(failure(None, format!("Unknown encoding '{}'", label)));
+    };
+
+    if decoder == encoding_rs::UTF_8 {
+        if let Err(error) = std::str::from_utf8(&response.bytes) {
+            return Err(failure(
+                Some(format!("invalid byte at offset {}", error.valid_up_to())),
+                "Expected body to be valid UTF-8".into(),
+            ));
+        }
+    } else if decoder
+        .decode_without_bom_handling_and_without_replacement(&response.bytes)
+        .is_none()
+    {
+        return Err(failure(
+            Some("malformed byte sequence".into()),
+            format!("Expected body to be valid {}", decoder.name()),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Whether the items `source` returned for `key` include one whose `field` matches
+fn check_receives(
+    snapshot: &HttpResponse,
+    source: &str,
+    key: &str,
+    field: &str,
+    matcher: &TextMatch,
+    expected: &str,
+    within: Duration,
+) -> Result<(), AssertionFailure> {
+    let items = snapshot.decoded_body().unwrap_or_default();
+    let items = items.as_array().map(Vec::as_slice).unwrap_or_default();
+
+    let found = items.iter().any(|item| {
+        item.get(field)
+            .and_then(|value| value.as_str())
+            .is_some_and(|actual| matcher.holds(actual, expected))
+    });
+    if found {
+        return Ok(());
+    }
+
+    Err(AssertionFailure {
+        path: format!("{} {}", source, key),
+        expected: Some(format!("{} {} \"{}\"", field, matcher, expected)),
+        actual: Some(format!("{} item(s)", items.len())),
+        message: format!(
+            "{} '{}' received nothing with {} {} '{}' within {:?}",
+            source, key, field, matcher, expected, within
+        ),
+        location: None,
+        minor: false,
+    })
+}
+
+fn check_query(
+    snapshot: &HttpResponse,
+    query: &str,
+    op: &Operator,
+    expected: &Value,
+    shown: Shown,
+) -> Result<(), AssertionFailure> {
+    let failure = |actual: Option<String>, message: String| AssertionFailure {
+        path: "DB".to_string(),
+        expected: Some(expected.to_string()),
+        actual,
+        message,
+        location: None,
+        minor: false,
+    };
+
+    let rows = snapshot.decoded_body().unwrap_or_default();
+    let Some(row) = rows.get(0).and_then(serde_json::Value::as_object) else {
+        return Err(failure(None, format!("Query returned no rows: {}", query)));
+    };
+    let mut columns = row.values();
+    let (Some(column), None) = (columns.next(), columns.next()) else {
+        return Err(failure(
+            None,
+            format!("Query must select a single column, got {}: {}", row.len(), query),
+        ));
+    };
+
+    let actual = stored_value(column);
+    if compare(op, &actual, expected) {
+        return Ok(());
+    }
+
+    let actual = shown.value(actual);
+    Err(failure(
+        Some(actual.clone()),
+        format!("Expected query result {:?} {}, got {}", op, expected, actual),
+    ))
+}
+
+fn check_key_value(
+    snapshot: &HttpResponse,
+    key: &str,
+    expected: Option<&(Operator, Value)>,
+    shown: Shown,
+) -> Result<(), AssertionFailure> {
+    let items = snapshot.decoded_body().unwrap_or_default();
+    let Some(item) = items.get(0) else {
+        return Err(AssertionFailure {
+            path: format!("REDIS {}", key),
+            expected: Some("exists".to_string()),
+            actual: None,
+            message: format!("Key '{}' does not exist", key),
+            location: None,
+            minor: false,
+        });
+    };
+    let Some((op, value)) = expected else {
+        return Ok(());
+    };
+
+    let actual = stored_value(&item["value"]);
+    if compare(op, &actual, value) {
+        return Ok(());
+    }
+
+    let actual = shown.value(actual);
+    Err(AssertionFailure {
+        path: format!("REDIS {}", key),
+        expected: Some(value.to_string()),
+        actual: Some(actual.clone()),
+        message: format!("Expected key '{}' {:?} {}, got {}", key, op, value, actual),
+        location: None,
+        minor: false,
+    })
+}
+
+/// A value read from a store, as compared by assertions
+fn stored_value(value: &serde_json::Value) -> Value {
+    match value {
+        serde_json::Value::String(text) => Value::String(text.clone()),
+        serde_json::Value::Bool(flag) => Value::Bool(*flag),
+        serde_json::Value::Number(number) => match (number.as_i64(), number.as_f64()) {
+            (Some(number), _) => Value::Number(number),
+            (None, Some(number)) => Value::Float(number),
+            _ => Value::String(number.to_string()),
+        },
+        other => Value::String(other.to_string()),
+    }
+}
+
+fn article(kind: JsonType) -> &'static str {
+    match kind {
+        JsonType::Array | JsonType::Object => "an",
+        _ => "a",
+    }
+}
+
+/// Replace a value with its length and a short SHA-256 fingerprint, so two
+/// failures can still be compared without revealing the underlying data.
+/// Both are measured on the value itself rather than its display form, which
+/// quotes strings.
+fn redact(actual: &str) -> String {
+    let value = actual
+        .strip_prefix('"')
+        .and_then(|inner| inner.strip_suffix('"'))
+        .unwrap_or(actual);
+    let digest = Sha256::digest(value.as_bytes());
+    let fingerprint: String = digest.iter().take(6).map(|b| format!("{:02x}", b)).collect();
+
+    format!("<redacted len={} sha256={}>", value.chars().count(), fingerprint)
+}
+
+/// How failures show values read from responses: as they are, or redacted
+/// for `SENSITIVE` assertions and the other assertions of their tests
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shown {
+    Plain,
+    Redacted,
+}
+
+impl Shown {
+    fn value(self, actual: impl fmt::Display) -> String {
+        match self {
+            Shown::Plain => actual.to_string(),
+            Shown::Redacted => redact(&actual.to_string()),
+        }
+    }
+}
+
+/// Failure of an assertion that got no response to check
+fn no_response(path: &str) -> AssertionFailure {
+    AssertionFailure {
+        path: path.into(),
+        expected: None,
+        actual: None,
+        message: "No response was received to check".into(),
+        location: None,
+        minor: false,
+    }
+}
+
+fn minor(mut failure: AssertionFailure) -> AssertionFailure {
+    failure.minor = true;
+    failure
+}
+
+/// One failure for an `OR` none of whose operands held, listing why each
+/// did not
+fn either_failure(failures: Vec<AssertionFailure>) -> AssertionFailure {
+    let mut paths: Vec<String> = Vec::new();
+    for failure in &failures {
+        if !paths.contains(&failure.path) {
+            paths.push(failure.path.clone());
+        }
+    }
+    let messages: Vec<&str> = failures.iter().map(|f| f.message.as_str()).collect();
+    AssertionFailure {
+        path: paths.join(", "),
+        expected: None,
+        actual: None,
+        message: format!("None of the alternatives held: {}", messages.join(" OR ")),
+        location: None,
+        minor: false,
+    }
 }
 
 fn compare(op: &Operator, actual: &Value, expected: &Value) -> bool {
@@ -121,18 +1323,377 @@ fn compare(op: &Operator, actual: &Value, expected: &Value) -> bool {
         (Operator::Eq, a, b) => a == b,
         (Operator::Ne, a, b) => a != b,
 
-        (Operator::Gt, Value::Number(a), Value::Number(b)) => a > b,
-        (Operator::Lt, Value::Number(a), Value::Number(b)) => a < b,
-        (Operator::Gte, Value::Number(a), Value::Number(b)) => a >= b,
-        (Operator::Lte, Value::Number(a), Value::Number(b)) => a <= b,
+        (Operator::Gt, a, b) => a.numeric_cmp(b).is_some_and(Ordering::is_gt),
+        (Operator::Lt, a, b) => a.numeric_cmp(b).is_some_and(Ordering::is_lt),
+        (Operator::Gte, a, b) => a.numeric_cmp(b).is_some_and(Ordering::is_ge),
+        (Operator::Lte, a, b) => a.numeric_cmp(b).is_some_and(Ordering::is_le),
+
+        (Operator::StartsWith, Value::String(a), Value::String(b)) => a.starts_with(b.as_str()),
+        (Operator::EndsWith, Value::String(a), Value::String(b)) => a.ends_with(b.as_str()),
 
         _ => false,
     }
 }
 
 impl Assertion {
+    /// Whether failure output for this assertion must hide response values,
+    /// because it or one of its operands is `SENSITIVE`
+    pub fn is_sensitive(&self) -> bool {
+        match self {
+            Assertion::Sensitive { .. } => true,
+            Assertion::Minor { assertion }
+            | Assertion::Callback { assertion, .. }
+            | Assertion::PollUntil { assertion, .. } => assertion.is_sensitive(),
+            Assertion::And(operands) | Assertion::Or(operands) => {
+                operands.iter().any(Assertion::is_sensitive)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether a failure of this assertion leaves the exit code alone
+    pub fn is_minor(&self) -> bool {
+        matches!(self, Assertion::Minor { .. })
+    }
+
+    /// Also leave `paths` out of `EQUALS FILE` comparisons, as the run's
+    /// normalization leaves them out of responses
+    pub fn ignore_paths(&mut self, paths: &[String]) {
+        match self {
+            Assertion::EqualsFile { ignoring, .. } => {
+                for path in paths {
+                    if !ignoring.contains(path) {
+                        ignoring.push(path.clone());
+                    }
+                }
+            }
+            Assertion::Sensitive { assertion }
+            | Assertion::Minor { assertion }
+            | Assertion::Callback { assertion, .. }
+            | Assertion::PollUntil { assertion, .. } => assertion.ignore_paths(paths),
+            Assertion::And(operands) | Assertion::Or(operands) => {
+                for operand in operands {
+                    operand.ignore_paths(paths);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Make fixture paths relative to `base_dir` (the directory of the .ax file)
+    pub fn resolve_paths(&mut self, base_dir: &Path) {
+        match self {
+            Assertion::EqualsFile { file, .. } if file.is_relative() => {
+                *file = base_dir.join(&*file);
+            }
+            Assertion::Sensitive { assertion }
+            | Assertion::Minor { assertion }
+            | Assertion::Callback { assertion, .. }
+            | Assertion::PollUntil { assertion, .. } => assertion.resolve_paths(base_dir),
+            Assertion::And(operands) | Assertion::Or(operands) => {
+                for operand in operands {
+                    operand.resolve_paths(base_dir);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The fixture files this assertion compares against, every operand's
+    /// for `AND`/`OR`
+    pub fn fixture_files(&self) -> Vec<&Path> {
+        match self {
+            Assertion::EqualsFile { file, .. } => vec![file.as_path()],
+            Assertion::Sensitive { assertion }
+            | Assertion::Minor { assertion }
+            | Assertion::Callback { assertion, .. }
+            | Assertion::PollUntil { assertion, .. } => assertion.fixture_files(),
+            Assertion::And(operands) | Assertion::Or(operands) => {
+                operands.iter().flat_map(Assertion::fixture_files).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Port and path to listen on before the request is sent, for callbacks
+    pub fn callback_target(&self) -> Option<(u16, &str)> {
+        match self {
+            Assertion::Callback { port, path, .. } => Some((*port, path)),
+            Assertion::Sensitive { assertion } | Assertion::Minor { assertion } => {
+                assertion.callback_target()
+            }
+            _ => None,
+        }
+    }
+
+    /// How the responses this assertion is checked against are gathered
+    pub fn sampling(&self, base: &HttpRequest) -> Sampling {
+        match self {
+            Assertion::Poll {
+                count, interval, ..
+            } => Sampling::Poll {
+                count: *count,
+                interval: *interval,
+            },
+            Assertion::PollUntil {
+                interval, timeout, ..
+            } => Sampling::Until {
+                interval: *interval,
+                timeout: *timeout,
+            },
+            Assertion::Negotiates { .. }
+            | Assertion::HeadMatchesGet
+            | Assertion::OnlyMethods { .. } => Sampling::Probes(self.probe_requests(base)),
+            Assertion::RateLimit {
+                max,
+                interval,
+                recovers,
+                ..
+            } => Sampling::UntilRateLimited {
+                max: *max,
+                interval: *interval,
+                recover: *recovers,
+            },
+            Assertion::Callback { within, .. } => Sampling::Callback { within: *within },
+            Assertion::ExpectContinue { within } => Sampling::ExpectContinue { within: *within },
+            Assertion::Receives {
+                source,
+                key,
+                within,
+                ..
+            } => Sampling::External {
+                source: source.clone(),
+                target: None,
+                key: key.clone(),
+                within: *within,
+            },
+            Assertion::Query {
+                url,
+                query,
+                within,
+                ..
+            } => Sampling::External {
+                source: "DB".to_string(),
+                target: Some(url.clone()),
+                key: query.clone(),
+                within: within.unwrap_or_default(),
+            },
+            Assertion::KeyValue {
+                url, key, within, ..
+            } => Sampling::External {
+                source: "REDIS".to_string(),
+                target: Some(url.clone()),
+                key: key.clone(),
+                within: within.unwrap_or_default(),
+            },
+            Assertion::Sensitive { assertion } | Assertion::Minor { assertion } => {
+                assertion.sampling(base)
+            }
+            _ => Sampling::Single,
+        }
+    }
+
+    /// Extra requests, derived from the test's own request, whose responses
+    /// are passed to [`Assertion::check_series`] in the same order
+    pub fn probe_requests(&self, base: &HttpRequest) -> Vec<HttpRequest> {
+        match self {
+            Assertion::Negotiates { variants } => variants
+                .iter()
+                .map(|variant| {
+                    let mut request = base.clone();
+                    request.headers.retain(|key, _| !key.eq_ignore_ascii_case("accept"));
+                    request
+                        .headers
+                        .insert("Accept".to_string(), variant.accept.clone());
+                    request
+                })
+                .collect(),
+            Assertion::HeadMatchesGet => ["GET", "HEAD"]
+                .into_iter()
+                .map(|method| {
+                    let mut request = base.clone();
+                    request.method = method.to_string();
+                    request.body = None;
+                    request
+                })
+                .collect(),
+            Assertion::OnlyMethods { allowed } => disallowed_methods(allowed)
+                .into_iter()
+                .map(|method| {
+                    let mut request = base.clone();
+                    request.method = method.to_string();
+                    request
+                })
+                .collect(),
+            Assertion::Sensitive { assertion } | Assertion::Minor { assertion } => {
+                assertion.probe_requests(base)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Check against a series of responses (polls or probes). Other
+    /// assertions only look at the first response.
+    pub fn check_series(&self, responses: &[HttpResponse]) -> Result<(), AssertionFailure> {
+        self.check_series_shown(responses, Shown::Plain)
+    }
+
+    /// [`Assertion::check_series`], with response values in failures as `shown`
+    pub fn check_series_shown(
+        &self,
+        responses: &[HttpResponse],
+        shown: Shown,
+    ) -> Result<(), AssertionFailure> {
+        match self {
+            Assertion::Poll { path, order, .. } => check_poll(responses, path, order, shown),
+            Assertion::PollUntil {
+                timeout, assertion, ..
+            } => check_poll_until(responses, *timeout, assertion, shown),
+            Assertion::Negotiates { variants } => check_negotiation(responses, variants, shown),
+            Assertion::HeadMatchesGet => check_head_matches_get(responses, shown),
+            Assertion::OnlyMethods { allowed } => check_only_methods(responses, allowed, shown),
+            Assertion::RateLimit {
+                headers, recovers, ..
+            } => check_rate_limit(responses, headers, *recovers, shown),
+            Assertion::Sensitive { assertion } => {
+                assertion.check_series_shown(responses, Shown::Redacted)
+            }
+            Assertion::Minor { assertion } => {
+                assertion.check_series_shown(responses, shown).map_err(minor)
+            }
+            _ => match responses.first() {
+                Some(response) => self.check_shown(response, shown),
+                None => Err(no_response("response")),
+            },
+        }
+    }
+
     pub fn check(&self, response: &HttpResponse) -> Result<(), AssertionFailure> {
+        self.check_shown(response, Shown::Plain)
+    }
+
+    /// [`Assertion::check`], with response values in failures as `shown`
+    pub fn check_shown(&self, response: &HttpResponse, shown: Shown) -> Result<(), AssertionFailure> {
         match self {
+            Assertion::Poll { path, order, .. } => {
+                check_poll(std::slice::from_ref(response), path, order, shown)?;
+            }
+            Assertion::Negotiates { variants } => {
+                check_negotiation(std::slice::from_ref(response), variants, shown)?;
+            }
+            Assertion::HeadMatchesGet => {
+                check_head_matches_get(std::slice::from_ref(response), shown)?;
+            }
+            Assertion::OnlyMethods { allowed } => {
+                check_only_methods(std::slice::from_ref(response), allowed, shown)?;
+            }
+            Assertion::RateLimit {
+                headers, recovers, ..
+            } => {
+                check_rate_limit(std::slice::from_ref(response), headers, *recovers, shown)?;
+            }
+            Assertion::EqualsFile {
+                path,
+                file,
+                ignoring,
+            } => {
+                check_equals_file(response, path, file, ignoring, shown)?;
+            }
+            Assertion::EqualsJson { path, expected } => {
+                let Some(actual) = resolve_json(response, path) else {
+                    return Err(AssertionFailure {
+                        path: path.clone(),
+                        expected: Some(expected.to_string()),
+                        actual: None,
+                        message: format!("Path '{}' not found", path),
+                        location: None,
+                        minor: false,
+                    });
+                };
+                check_json_equal(path, expected, &actual, "the expected JSON", shown)?;
+            }
+            // Checked while sampling: the server answered in time
+            Assertion::ExpectContinue { .. } => {}
+            Assertion::Encoded { encoding } => {
+                check_encoded(response, encoding.as_deref(), shown)?;
+            }
+            Assertion::Receives {
+                source,
+                key,
+                field,
+                matcher,
+                expected,
+                within,
+            } => {
+                check_receives(response, source, key, field, matcher, expected, *within)?;
+            }
+            Assertion::Query {
+                query, op, value, ..
+            } => {
+                check_query(response, query, op, value, shown)?;
+            }
+            Assertion::KeyValue { key, expected, .. } => {
+                check_key_value(response, key, expected.as_ref(), shown)?;
+            }
+
+            Assertion::Sensitive { assertion } => {
+                return assertion.check_shown(response, Shown::Redacted);
+            }
+            Assertion::Minor { assertion } => {
+                return assertion.check_shown(response, shown).map_err(minor);
+            }
+            Assertion::PollUntil { assertion, .. } => return assertion.check_shown(response, shown),
+            Assertion::Callback { assertion, .. } => {
+                return assertion.check_shown(response, shown).map_err(|mut failure| {
+                    failure.message = format!("Callback: {}", failure.message);
+                    failure
+                });
+            }
+            Assertion::And(operands) => {
+                for operand in operands {
+                    operand.check_shown(response, shown)?;
+                }
+            }
+            Assertion::Or(operands) => {
+                let mut failures = Vec::new();
+                for operand in operands {
+                    match operand.check_shown(response, shown) {
+                        Ok(()) => return Ok(()),
+                        Err(failure) => failures.push(failure),
+                    }
+                }
+                return Err(either_failure(failures));
+            }
+
+            Assertion::Binary { path, op, value } if is_wildcard(path) => {
+                check_each(response, path, op, value, false, shown)?;
+            }
+            Assertion::Any { path, op, value } => {
+                check_each(response, path, op, value, true, shown)?;
+            }
+            Assertion::Length { path, op, value } => {
+                let Some(actual) = resolve_length(response, path) else {
+                    return Err(AssertionFailure {
+                        path: path.clone(),
+                        expected: Some(value.to_string()),
+                        actual: None,
+                        message: format!("Path '{}' has no length", path),
+                        location: None,
+                        minor: false,
+                    });
+                };
+                if !compare(op, &actual, value) {
+                    return Err(AssertionFailure {
+                        path: path.clone(),
+                        expected: Some(value.to_string()),
+                        actual: Some(shown.value(actual)),
+                        message: format!("Expected {} LENGTH {:?} {}", path, op, value),
+                        location: None,
+                        minor: false,
+                    });
+                }
+            }
+
             Assertion::Binary { path, op, value } => {
                 let actual = match resolve_path(response, path) {
                     Some(v) => v,
@@ -142,6 +1703,8 @@ impl Assertion {
                             expected: Some(value.to_string()),
                             actual: None,
                             message: format!("Path '{}' not found", path),
+                            location: None,
+                            minor: false,
                         });
                     }
                 };
@@ -149,13 +1712,66 @@ impl Assertion {
                 if !compare(op, &actual, value) {
                     return Err(AssertionFailure {
                         path: path.clone(),
-                        expected: Some(value.to_string()),
-                        actual: Some(actual.to_string()),
-                        message: format!("Expected {} {:?} {}", path, op, value),
+                        expected: Some(value.to_string()),
+                        actual: Some(shown.value(actual)),
+                        message: format!("Expected {} {:?} {}", path, op, value),
+                        location: None,
+                        minor: false,
+                    });
+                }
+            }
+
+            Assertion::Type { path, expected } => {
+                let Some(json) = resolve_json(response, path) else {
+                    return Err(AssertionFailure {
+                        path: path.clone(),
+                        expected: Some(expected.to_string()),
+                        actual: None,
+                        message: format!("Path '{}' not found", path),
+                        location: None,
+                        minor: false,
+                    });
+                };
+                let actual = JsonType::of(&json);
+                if actual != Some(*expected) {
+                    return Err(AssertionFailure {
+                        path: path.clone(),
+                        expected: Some(expected.to_string()),
+                        actual: Some(actual.map_or("null".into(), |actual| shown.value(actual))),
+                        message: format!(
+                            "Expected '{}' to be {} {}",
+                            path,
+                            article(*expected),
+                            expected
+                        ),
+                        location: None,
+                        minor: false,
                     });
                 }
             }
 
+            Assertion::Null { path, negated } => {
+                let expected = if *negated { "not null" } else { "null" };
+                let failure = |message: String, actual: Option<Value>| AssertionFailure {
+                    path: path.clone(),
+                    expected: Some(expected.into()),
+                    actual: actual.map(|actual| shown.value(actual)),
+                    message,
+                    location: None,
+                    minor: false,
+                };
+                match resolve_path(response, path) {
+                    None => return Err(failure(format!("Path '{}' not found", path), None)),
+                    Some(actual) if (actual == Value::Null) == *negated => {
+                        return Err(failure(
+                            format!("Expected '{}' to be {}", path, expected),
+                            Some(actual),
+                        ));
+                    }
+                    Some(_) => {}
+                }
+            }
+
             Assertion::Exists { path } => {
                 if resolve_path(response, path).is_none() {
                     return Err(AssertionFailure {
@@ -163,6 +1779,8 @@ impl Assertion {
                         expected: Some("exists".into()),
                         actual: None,
                         message: format!("Expected '{}' to exist", path),
+                        location: None,
+                        minor: false,
                     });
                 }
             }
@@ -176,6 +1794,8 @@ impl Assertion {
                             expected: Some("true".into()),
                             actual: None,
                             message: format!("Path '{}' not found", path),
+                            location: None,
+                            minor: false,
                         });
                     }
                 };
@@ -184,8 +1804,10 @@ impl Assertion {
                     return Err(AssertionFailure {
                         path: path.clone(),
                         expected: Some("true".into()),
-                        actual: Some(actual.to_string()),
+                        actual: Some(shown.value(actual)),
                         message: format!("Expected '{}' to be true", path),
+                        location: None,
+                        minor: false,
                     });
                 }
             }
@@ -199,6 +1821,8 @@ impl Assertion {
                             expected: Some(format!("{:?}", values)),
                             actual: None,
                             message: format!("Path '{}' not found", path),
+                            location: None,
+                            minor: false,
                         });
                     }
                 };
@@ -207,8 +1831,10 @@ impl Assertion {
                     return Err(AssertionFailure {
                         path: path.clone(),
                         expected: Some(format!("{:?}", values)),
-                        actual: Some(actual.to_string()),
+                        actual: Some(shown.value(actual)),
                         message: format!("Expected '{}' to be in list", path),
+                        location: None,
+                        minor: false,
                     });
                 }
             }
@@ -222,21 +1848,23 @@ impl Assertion {
                             expected: Some(format!("between {} and {}", min, max)),
                             actual: None,
                             message: format!("Path '{}' not found", path),
+                            location: None,
+                            minor: false,
                         });
                     }
                 };
 
-                match (&actual, min, max) {
-                    (Value::Number(a), Value::Number(lo), Value::Number(hi))
-                        if a >= lo && a <= hi => {}
-                    _ => {
-                        return Err(AssertionFailure {
-                            path: path.clone(),
-                            expected: Some(format!("between {} and {}", min, max)),
-                            actual: Some(actual.to_string()),
-                            message: format!("Value not in range"),
-                        });
-                    }
+                let above = actual.numeric_cmp(min).is_some_and(Ordering::is_ge);
+                let below = actual.numeric_cmp(max).is_some_and(Ordering::is_le);
+                if !(above && below) {
+                    return Err(AssertionFailure {
+                        path: path.clone(),
+                        expected: Some(format!("between {} and {}", min, max)),
+                        actual: Some(shown.value(actual)),
+                        message: "Value not in range".to_string(),
+                        location: None,
+                        minor: false,
+                    });
                 }
             }
         }
@@ -257,15 +1885,258 @@ mod tests {
             status,
             headers: HashMap::new(),
             body: body.map(|s| s.to_string()),
+            bytes: body.map(|s| s.as_bytes().to_vec()).unwrap_or_default(),
+            cookies: Vec::new(),
+            trailers: HashMap::new(),
         }
     }
 
+    #[test]
+    fn test_resolve_path_array_indexes() {
+        let body = r#"{"items": [{"id": 7}, {"id": 8, "tags": ["a", "b"]}], "matrix": [[1, 2], [3, 4]]}"#;
+        let response = create_response(200, Some(body));
+        assert_eq!(resolve_path(&response, "body.items[0].id"), Some(Value::Number(7)));
+        assert_eq!(
+            resolve_path(&response, "body.items[1].tags[1]"),
+            Some(Value::String("b".to_string()))
+        );
+        assert_eq!(resolve_path(&response, "body.matrix[1][0]"), Some(Value::Number(3)));
+        assert_eq!(resolve_path(&response, "body.items[2].id"), None);
+        assert_eq!(resolve_path(&response, "body.items[x].id"), None);
+        assert_eq!(resolve_path(&response, "body.items[0"), None);
+
+        let response = create_response(200, Some(r#"[{"name": "first"}]"#));
+        assert_eq!(
+            resolve_path(&response, "body[0].name"),
+            Some(Value::String("first".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_wildcard_checks_every_element() {
+        let body = r#"{"items": [{"status": "active", "role": "user"}, {"status": "active", "role": "admin"}]}"#;
+        let response = create_response(200, Some(body));
+        let binary = |path: &str, value: &str| Assertion::Binary {
+            path: path.to_string(),
+            op: Operator::Eq,
+            value: Value::String(value.to_string()),
+        };
+        let any = |path: &str, value: &str| Assertion::Any {
+            path: path.to_string(),
+            op: Operator::Eq,
+            value: Value::String(value.to_string()),
+        };
+
+        assert!(binary("body.items[*].status", "active").check(&response).is_ok());
+        let failure = binary("body.items[*].role", "user").check(&response).unwrap_err();
+        assert!(failure.message.contains("element 1"));
+        assert_eq!(failure.actual.as_deref(), Some("\"admin\""));
+
+        assert!(any("body.items[*].role", "admin").check(&response).is_ok());
+        assert!(any("body.items[*].role", "owner").check(&response).is_err());
+        assert!(binary("body.users[*].role", "user").check(&response).is_err());
+
+        let failure = binary("body.items[*].role", "owner").check(&response).unwrap_err();
+        assert!(failure.message.contains("elements 0, 1 do not match"));
+        assert_eq!(failure.actual.as_deref(), Some(r#"["user", "admin"]"#));
+    }
+
+    #[test]
+    fn test_and_or_short_circuit_and_combine_failures() {
+        let response = create_response(200, Some(r#"{"ok": false}"#));
+        let status = |code: i64| Assertion::Binary {
+            path: "status".to_string(),
+            op: Operator::Eq,
+            value: Value::Number(code),
+        };
+        let ok = Assertion::Binary {
+            path: "body.ok".to_string(),
+            op: Operator::Eq,
+            value: Value::Bool(true),
+        };
+
+        assert!(Assertion::Or(vec![status(201), status(200)]).check(&response).is_ok());
+        let failure = Assertion::And(vec![status(500), ok]).check(&response).unwrap_err();
+        assert_eq!(failure.path, "status");
+
+        let failure = Assertion::Or(vec![status(201), status(204)])
+            .check(&response)
+            .unwrap_err();
+        assert_eq!(failure.path, "status");
+        assert_eq!(failure.message.matches("Expected status").count(), 2);
+        assert!(failure.message.contains(" OR "));
+    }
+
+    #[test]
+    fn test_fixture_files_of_every_operand() {
+        let equals = |file: &str| Assertion::EqualsFile {
+            path: "body".to_string(),
+            file: file.into(),
+            ignoring: Vec::new(),
+        };
+        let assertion = Assertion::Minor {
+            assertion: Box::new(Assertion::Or(vec![
+                equals("v1.json"),
+                Assertion::And(vec![equals("v2.json"), equals("v2-extra.json")]),
+            ])),
+        };
+        assert_eq!(
+            assertion.fixture_files(),
+            vec![
+                Path::new("v1.json"),
+                Path::new("v2.json"),
+                Path::new("v2-extra.json")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_minor_failures_are_marked() {
+        let response = create_response(500, None);
+        let status = Assertion::Binary {
+            path: "status".to_string(),
+            op: Operator::Eq,
+            value: Value::Number(200),
+        };
+        assert!(!status.check(&response).unwrap_err().minor);
+
+        let minor = Assertion::Minor {
+            assertion: Box::new(status),
+        };
+        assert!(minor.check(&response).unwrap_err().minor);
+        assert!(minor.check(&create_response(200, None)).is_ok());
+    }
+
+    #[test]
+    fn test_size_counts_body_bytes() {
+        let response = create_response(200, Some(r#"{"name": "Zoë"}"#));
+        assert_eq!(resolve_path(&response, "size"), Some(Value::Number(16)));
+
+        let small = Assertion::Binary {
+            path: "size".to_string(),
+            op: Operator::Lt,
+            value: Value::Number(10240),
+        };
+        assert!(small.check(&response).is_ok());
+        assert_eq!(resolve_path(&create_response(204, None), "size"), Some(Value::Number(0)));
+    }
+
+    #[test]
+    fn test_length_of_arrays_objects_and_strings() {
+        let body = r#"{"items": [1, 2, 3], "user": {"id": 1, "name": "Zoë"}}"#;
+        let response = create_response(200, Some(body));
+        assert_eq!(resolve_length(&response, "body.items"), Some(Value::Number(3)));
+        assert_eq!(resolve_length(&response, "body.user"), Some(Value::Number(2)));
+        assert_eq!(resolve_length(&response, "body.user.name"), Some(Value::Number(3)));
+        assert_eq!(resolve_length(&response, "body"), Some(Value::Number(2)));
+        assert_eq!(resolve_length(&response, "body.user.id"), None);
+        assert_eq!(resolve_length(&response, "body.missing"), None);
+
+        let length = |op, value| Assertion::Length {
+            path: "body.items".into(),
+            op,
+            value: Value::Number(value),
+        };
+        assert!(length(Operator::Eq, 3).check(&response).is_ok());
+        let failure = length(Operator::Gt, 5).check(&response).unwrap_err();
+        assert_eq!(failure.actual.as_deref(), Some("3"));
+
+        let response = create_response(200, Some("plain text"));
+        assert_eq!(resolve_length(&response, "body"), Some(Value::Number(10)));
+    }
+
+    #[test]
+    fn test_type_assertions() {
+        let body = r#"{"tags": ["a"], "user": {"id": 1}, "price": 9.5, "active": true, "name": "x", "gone": null}"#;
+        let response = create_response(200, Some(body));
+        let is = |path: &str, expected| {
+            Assertion::Type {
+                path: path.into(),
+                expected,
+            }
+            .check(&response)
+        };
+
+        assert!(is("body.tags", JsonType::Array).is_ok());
+        assert!(is("body.user", JsonType::Object).is_ok());
+        assert!(is("body.price", JsonType::Number).is_ok());
+        assert!(is("body.active", JsonType::Boolean).is_ok());
+        assert!(is("body.name", JsonType::String).is_ok());
+        assert!(is("status", JsonType::Number).is_ok());
+        assert!(is("body", JsonType::Object).is_ok());
+
+        let failure = is("body.tags", JsonType::Object).unwrap_err();
+        assert_eq!(failure.message, "Expected 'body.tags' to be an object");
+        assert_eq!(failure.actual.as_deref(), Some("array"));
+        let failure = is("body.gone", JsonType::String).unwrap_err();
+        assert_eq!(failure.actual.as_deref(), Some("null"));
+        assert!(is("body.missing", JsonType::String).is_err());
+    }
+
+    #[test]
+    fn test_null_is_not_missing() {
+        let response = create_response(200, Some(r#"{"deleted_at": null, "name": "Ada"}"#));
+        assert_eq!(resolve_path(&response, "body.deleted_at"), Some(Value::Null));
+        assert_eq!(resolve_path(&response, "body.missing"), None);
+
+        let null = |path: &str, negated| Assertion::Null {
+            path: path.into(),
+            negated,
+        };
+        assert!(null("body.deleted_at", false).check(&response).is_ok());
+        assert!(null("body.name", true).check(&response).is_ok());
+        let failure = null("body.name", false).check(&response).unwrap_err();
+        assert_eq!(failure.actual.as_deref(), Some("\"Ada\""));
+        assert!(null("body.deleted_at", true).check(&response).is_err());
+        let failure = null("body.missing", false).check(&response).unwrap_err();
+        assert_eq!(failure.message, "Path 'body.missing' not found");
+    }
+
+    #[test]
+    fn test_resolve_path_trailers() {
+        let mut response = create_response(200, Some("{}"));
+        response.trailers.insert("grpc-status".to_string(), "0".to_string());
+        assert_eq!(
+            resolve_path(&response, "trailers.Grpc-Status"),
+            Some(Value::String("0".to_string()))
+        );
+        assert_eq!(resolve_path(&response, "trailers.grpc-message"), None);
+        assert_eq!(resolve_path(&response, "headers.grpc-status"), None);
+    }
+
     #[test]
     fn test_resolve_path_status() {
         let response = create_response(200, None);
         assert_eq!(resolve_path(&response, "status"), Some(Value::Number(200)));
     }
 
+    #[test]
+    fn test_resolve_path_headers() {
+        let mut response = create_response(200, None);
+        response
+            .headers
+            .insert("content-type".to_string(), "application/json".to_string());
+        assert_eq!(
+            resolve_path(&response, "headers.Content-Type"),
+            Some(Value::String("application/json".to_string()))
+        );
+        assert_eq!(resolve_path(&response, "headers.ETag"), None);
+    }
+
+    #[test]
+    fn test_resolve_path_cookies() {
+        let mut response = create_response(200, None);
+        response.cookies = vec![
+            ("session".to_string(), "old".to_string()),
+            ("session".to_string(), "abc123".to_string()),
+        ];
+        assert_eq!(
+            resolve_path(&response, "cookies.session"),
+            Some(Value::String("abc123".to_string()))
+        );
+        assert_eq!(resolve_path(&response, "cookies.theme"), None);
+    }
+
     #[test]
     fn test_resolve_path_body() {
         let response = create_response(200, Some("hello world"));
@@ -334,6 +2205,23 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_compare_floats_and_negatives() {
+        assert!(compare(&Operator::Eq, &Value::Number(1), &Value::Float(1.0)));
+        assert!(compare(&Operator::Lt, &Value::Number(-3), &Value::Number(-2)));
+        assert!(compare(&Operator::Gte, &Value::Float(2.5), &Value::Number(2)));
+        assert!(!compare(&Operator::Gt, &Value::String("3".into()), &Value::Number(2)));
+
+        let response = create_response(200, Some(r#"{"delta": -4.5}"#));
+        let between = |min, max| Assertion::Between {
+            path: "body.delta".to_string(),
+            min,
+            max,
+        };
+        assert!(between(Value::Number(-10), Value::Number(10)).check(&response).is_ok());
+        assert!(between(Value::Float(-4.0), Value::Number(0)).check(&response).is_err());
+    }
+
     #[test]
     fn test_compare_ne() {
         assert!(!compare(
@@ -371,6 +2259,18 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_compare_prefix_suffix() {
+        let url = Value::String("https://example.com/users/7".to_string());
+        let text = |s: &str| Value::String(s.to_string());
+        assert!(compare(&Operator::StartsWith, &url, &text("https://")));
+        assert!(!compare(&Operator::StartsWith, &url, &text("http://")));
+        assert!(compare(&Operator::EndsWith, &url, &text("/7")));
+        assert!(!compare(&Operator::EndsWith, &url, &text("/8")));
+        // Only text has a prefix
+        assert!(!compare(&Operator::StartsWith, &Value::Number(404), &text("4")));
+    }
+
     #[test]
     fn test_assertion_binary_pass() {
         let assertion = Assertion::Binary {
@@ -490,6 +2390,473 @@ mod tests {
         assert!(assertion.check(&response).is_ok());
     }
 
+    #[test]
+    fn test_assertion_sensitive_redacts_values() {
+        let assertion = Assertion::Sensitive {
+            assertion: Box::new(Assertion::Binary {
+                path: "body.ssn".to_string(),
+                op: Operator::Eq,
+                value: Value::String("000-00-0000".to_string()),
+            }),
+        };
+        let response = create_response(200, Some(r#"{"ssn": "123-45-6789"}"#));
+        let err = assertion.check(&response).unwrap_err();
+
+        assert_eq!(err.path, "body.ssn");
+        assert!(!err.actual.as_ref().unwrap().contains("123-45-6789"));
+        assert!(err.actual.as_ref().unwrap().starts_with("<redacted len=11 "));
+        assert_eq!(err.expected, Some("\"000-00-0000\"".to_string()));
+        assert_eq!(redact("\"123-45-6789\""), redact("123-45-6789"));
+        assert!(redact("12345").starts_with("<redacted len=5 "));
+    }
+
+    #[test]
+    fn test_assertion_sensitive_redacts_messages() {
+        let file = write_fixture("sensitive", r#"{"ssn": "000"}"#);
+        let equals_file = || Assertion::EqualsFile {
+            path: "body".to_string(),
+            file: file.clone(),
+            ignoring: Vec::new(),
+        };
+        let response = create_response(200, Some(r#"{"ssn": "123-45-6789"}"#));
+
+        let plain = equals_file().check(&response).unwrap_err();
+        assert!(plain.message.contains("got \"123-45-6789\""));
+
+        let sensitive = Assertion::Sensitive {
+            assertion: Box::new(equals_file()),
+        };
+        let either = Assertion::Or(vec![
+            Assertion::Exists {
+                path: "body.name".to_string(),
+            },
+            Assertion::Sensitive {
+                assertion: Box::new(equals_file()),
+            },
+        ]);
+        for assertion in [&sensitive, &either] {
+            assert!(assertion.is_sensitive());
+            let err = assertion.check(&response).unwrap_err();
+            assert!(!err.to_string().contains("123-45-6789"), "{}", err);
+            assert!(err.message.contains("body.ssn: expected \"000\", got <redacted len=11 "));
+        }
+
+        // Other assertions of a sensitive test are checked redacted too
+        let err = equals_file().check_shown(&response, Shown::Redacted).unwrap_err();
+        assert!(!err.to_string().contains("123-45-6789"));
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn test_assertion_sensitive_pass() {
+        let assertion = Assertion::Sensitive {
+            assertion: Box::new(Assertion::Exists {
+                path: "body.ssn".to_string(),
+            }),
+        };
+        let response = create_response(200, Some(r#"{"ssn": "123-45-6789"}"#));
+        assert!(assertion.check(&response).is_ok());
+    }
+
+    fn write_fixture(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("axotly-{}-{}.json", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_assertion_equals_file_ignoring() {
+        let file = write_fixture("equals", r#"{"name": "alice", "id": 1}"#);
+        let assertion = Assertion::EqualsFile {
+            path: "body".to_string(),
+            file: file.clone(),
+            ignoring: vec!["body.id".to_string(), "body.updated_at".to_string()],
+        };
+        let response = create_response(
+            200,
+            Some(r#"{"updated_at": "2024-01-01", "id": 7, "name": "alice"}"#),
+        );
+        assert!(assertion.check(&response).is_ok());
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn test_assertion_equals_file_decodes_binary_bodies() {
+        let file = write_fixture("equals-msgpack", r#"{"device": "sensor-1", "ok": true}"#);
+        let assertion = Assertion::EqualsFile {
+            path: "body".to_string(),
+            file: file.clone(),
+            ignoring: Vec::new(),
+        };
+        let mut response = create_response(200, None);
+        response.bytes = crate::domain::encode::Encode::MsgPack
+            .encode(r#"{"ok": true, "device": "sensor-1"}"#)
+            .unwrap();
+        response
+            .headers
+            .insert("Content-Type".to_string(), "application/msgpack".to_string());
+        assert!(assertion.check(&response).is_ok());
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn test_assertion_equals_file_sub_path_fail() {
+        let file = write_fixture("equals-sub", r#"{"name": "bob", "id": 1}"#);
+        let assertion = Assertion::EqualsFile {
+            path: "body.user".to_string(),
+            file: file.clone(),
+            ignoring: vec!["body.user.id".to_string()],
+        };
+        let response = create_response(200, Some(r#"{"user": {"id": 7, "name": "alice"}}"#));
+        let err = assertion.check(&response).unwrap_err();
+        assert_eq!(err.expected, Some(r#"{"name":"bob"}"#.to_string()));
+        assert_eq!(err.actual, Some(r#"{"name":"alice"}"#.to_string()));
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn test_assertion_equals_json() {
+        let assertion = Assertion::EqualsJson {
+            path: "body.user".to_string(),
+            expected: serde_json::json!({"name": "bob", "roles": ["admin"], "id": 7}),
+        };
+        let response = create_response(
+            200,
+            Some(r#"{"user": {"id": 7, "roles": ["admin"], "name": "bob"}}"#),
+        );
+        assert!(assertion.check(&response).is_ok());
+
+        let response = create_response(
+            200,
+            Some(r#"{"user": {"id": 7, "roles": ["admin", "ops"], "name": "alice", "age": 30}}"#),
+        );
+        let err = assertion.check(&response).unwrap_err();
+        assert_eq!(
+            err.message,
+            "Expected 'body.user' to equal the expected JSON:\n  \
+             body.user.name: expected \"bob\", got \"alice\"\n  \
+             body.user.roles[1]: unexpected \"ops\"\n  \
+             body.user.age: unexpected 30"
+        );
+    }
+
+    #[test]
+    fn test_assertion_poll_non_decreasing() {
+        let assertion = Assertion::Poll {
+            count: 3,
+            interval: None,
+            path: "body.version".to_string(),
+            order: PollOrder::NonDecreasing,
+        };
+        let responses = vec![
+            create_response(200, Some(r#"{"version": 1}"#)),
+            create_response(200, Some(r#"{"version": 1}"#)),
+            create_response(200, Some(r#"{"version": 2}"#)),
+        ];
+        assert!(assertion.check_series(&responses).is_ok());
+    }
+
+    #[test]
+    fn test_assertion_poll_compares_floats() {
+        let assertion = Assertion::Poll {
+            count: 3,
+            interval: None,
+            path: "body.updated_at".to_string(),
+            order: PollOrder::Increasing,
+        };
+        let responses = vec![
+            create_response(200, Some(r#"{"updated_at": 1.5}"#)),
+            create_response(200, Some(r#"{"updated_at": 2}"#)),
+            create_response(200, Some(r#"{"updated_at": 2.25}"#)),
+        ];
+        assert!(assertion.check_series(&responses).is_ok());
+
+        let reversed = [responses[1].clone(), responses[0].clone()];
+        let err = assertion.check_series(&reversed).unwrap_err();
+        assert_eq!(err.actual, Some("[2, 1.5]".to_string()));
+    }
+
+    #[test]
+    fn test_assertion_poll_reports_broken_order() {
+        let assertion = Assertion::Poll {
+            count: 3,
+            interval: None,
+            path: "body.version".to_string(),
+            order: PollOrder::Increasing,
+        };
+        let responses = vec![
+            create_response(200, Some(r#"{"version": 1}"#)),
+            create_response(200, Some(r#"{"version": 2}"#)),
+            create_response(200, Some(r#"{"version": 2}"#)),
+        ];
+        let err = assertion.check_series(&responses).unwrap_err();
+        assert_eq!(err.actual, Some("[1, 2, 2]".to_string()));
+        assert!(err.message.contains("poll 3"));
+    }
+
+    #[test]
+    fn test_assertion_poll_until_checks_last_response() {
+        let assertion = Assertion::PollUntil {
+            interval: Duration::from_secs(1),
+            timeout: Duration::from_secs(30),
+            assertion: Box::new(Assertion::Binary {
+                path: "body.status".to_string(),
+                op: Operator::Eq,
+                value: Value::String("done".to_string()),
+            }),
+        };
+        let pending = create_response(200, Some(r#"{"status": "pending"}"#));
+        let done = create_response(200, Some(r#"{"status": "done"}"#));
+
+        assert!(assertion
+            .check_series(&[pending.clone(), done.clone()])
+            .is_ok());
+        let err = assertion.check_series(&[done, pending.clone(), pending]).unwrap_err();
+        assert!(err.message.starts_with("Still failing after 3 attempt(s) over 30s"));
+        assert_eq!(err.actual, Some("\"pending\"".to_string()));
+
+        assert!(assertion.check_series(&[]).is_err());
+        let status = Assertion::Binary {
+            path: "status".to_string(),
+            op: Operator::Eq,
+            value: Value::Number(200),
+        };
+        assert!(status.check_series(&[]).is_err());
+    }
+
+    #[test]
+    fn test_assertion_negotiates() {
+        let assertion = Assertion::Negotiates {
+            variants: vec![
+                NegotiationVariant {
+                    accept: "application/json".to_string(),
+                    status: 200,
+                    content_type: Some("application/json".to_string()),
+                },
+                NegotiationVariant {
+                    accept: "image/png".to_string(),
+                    status: 406,
+                    content_type: None,
+                },
+            ],
+        };
+
+        let base = HttpRequest::new("GET".into(), url::Url::parse("https://a.test/").unwrap());
+        let probes = assertion.probe_requests(&base);
+        assert_eq!(probes.len(), 2);
+        assert_eq!(probes[1].headers.get("Accept"), Some(&"image/png".to_string()));
+
+        let mut json = create_response(200, Some("{}"));
+        json.headers
+            .insert("content-type".into(), "application/json; charset=utf-8".into());
+        let png = create_response(200, None);
+
+        let err = assertion.check_series(&[json, png]).unwrap_err();
+        assert_eq!(err.path, "Accept: image/png");
+        assert_eq!(err.actual, Some("status 200".to_string()));
+    }
+
+    #[test]
+    fn test_assertion_head_matches_get() {
+        let mut get = create_response(200, Some("hello"));
+        get.headers.insert("content-length".into(), "5".into());
+        get.headers.insert("etag".into(), "\"abc\"".into());
+
+        let mut head = create_response(200, Some(""));
+        head.headers.insert("content-length".into(), "5".into());
+        head.headers.insert("etag".into(), "\"abc\"".into());
+        assert!(Assertion::HeadMatchesGet
+            .check_series(&[get.clone(), head.clone()])
+            .is_ok());
+
+        head.headers.insert("etag".into(), "\"def\"".into());
+        let err = Assertion::HeadMatchesGet
+            .check_series(&[get, head])
+            .unwrap_err();
+        assert_eq!(err.path, "HEAD etag");
+    }
+
+    #[test]
+    fn test_disallowed_methods_implies_head_from_get() {
+        let allowed = vec!["GET".to_string(), "POST".to_string()];
+        assert_eq!(disallowed_methods(&allowed), vec!["PUT", "PATCH", "DELETE"]);
+    }
+
+    #[test]
+    fn test_assertion_only_methods() {
+        let assertion = Assertion::OnlyMethods {
+            allowed: vec!["GET".to_string(), "POST".to_string()],
+        };
+        let mut rejected = create_response(405, None);
+        rejected.headers.insert("allow".into(), "GET, POST".into());
+        let accepted = create_response(200, None);
+
+        let responses = [rejected.clone(), rejected.clone(), rejected.clone()];
+        assert!(assertion.check_series(&responses).is_ok());
+
+        let responses = [rejected.clone(), rejected, accepted];
+        let err = assertion.check_series(&responses).unwrap_err();
+        assert_eq!(err.path, "DELETE");
+        assert_eq!(err.actual, Some("200".to_string()));
+    }
+
+    #[test]
+    fn test_resolve_path_decodes_by_content_type() {
+        let mut response = create_response(200, Some("<user><name>Ada</name></user>"));
+        assert_eq!(resolve_path(&response, "body.user.name"), None);
+
+        response
+            .headers
+            .insert("Content-Type".into(), "application/xml".into());
+        assert_eq!(
+            resolve_path(&response, "body.user.name"),
+            Some(Value::String("Ada".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_resolve_path_charset() {
+        let mut response = create_response(200, None);
+        assert_eq!(resolve_path(&response, "charset"), None);
+
+        response.headers.insert(
+            "content-type".into(),
+            "text/html; Charset=\"UTF-8\"".into(),
+        );
+        assert_eq!(
+            resolve_path(&response, "charset"),
+            Some(Value::String("utf-8".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_assertion_encoded() {
+        let declared = Assertion::Encoded { encoding: None };
+        let mut response = create_response(200, Some("żółw"));
+        assert!(declared.check(&response).is_ok());
+
+        // "é" in Latin-1 is not valid UTF-8
+        response.bytes = vec![b'c', b'a', b'f', 0xE9];
+        let err = declared.check(&response).unwrap_err();
+        assert_eq!(err.actual, Some("invalid byte at offset 3".to_string()));
+
+        response
+            .headers
+            .insert("content-type".into(), "text/plain; charset=iso-8859-1".into());
+        assert!(declared.check(&response).is_ok());
+
+        let shift_jis = Assertion::Encoded {
+            encoding: Some("Shift_JIS".to_string()),
+        };
+        response.bytes = vec![0x82];
+        assert!(shift_jis.check(&response).is_err());
+
+        let unknown = Assertion::Encoded {
+            encoding: Some("klingon".to_string()),
+        };
+        assert!(unknown.check(&response).is_err());
+    }
+
+    #[test]
+    fn test_assertion_rate_limit() {
+        let assertion = Assertion::RateLimit {
+            max: 10,
+            interval: None,
+            headers: vec!["X-RateLimit-Limit".to_string()],
+            recovers: true,
+        };
+        let mut limited = create_response(429, None);
+        limited.headers.insert("retry-after".into(), "1".into());
+        limited.headers.insert("x-ratelimit-limit".into(), "2".into());
+        let ok = create_response(200, None);
+
+        let responses = [ok.clone(), ok.clone(), limited.clone(), ok.clone()];
+        assert!(assertion.check_series(&responses).is_ok());
+
+        let responses = [ok.clone(), limited.clone(), limited];
+        let err = assertion.check_series(&responses).unwrap_err();
+        assert_eq!(err.actual, Some("429".to_string()));
+
+        let err = assertion.check_series(&[ok.clone(), ok]).unwrap_err();
+        assert_eq!(err.actual, Some("no 429 after 2 requests".to_string()));
+    }
+
+    #[test]
+    fn test_assertion_receives() {
+        let assertion = Assertion::Receives {
+            source: "MAILBOX".to_string(),
+            key: "user@example.com".to_string(),
+            field: "subject".to_string(),
+            matcher: TextMatch::Contains,
+            expected: "Verify".to_string(),
+            within: Duration::from_secs(20),
+        };
+
+        let inbox = create_response(200, Some(r#"[{"subject": "Welcome"}]"#));
+        let err = assertion.check(&inbox).unwrap_err();
+        assert_eq!(
+            err.message,
+            "MAILBOX 'user@example.com' received nothing with subject containing 'Verify' within 20s"
+        );
+
+        let inbox = create_response(
+            200,
+            Some(r#"[{"subject": "Welcome"}, {"subject": "Verify your email"}]"#),
+        );
+        assert!(assertion.check(&inbox).is_ok());
+    }
+
+    #[test]
+    fn test_assertion_query() {
+        let assertion = Assertion::Query {
+            url: "postgres://localhost/app".to_string(),
+            query: "select count(*) from users".to_string(),
+            op: Operator::Eq,
+            value: Value::Number(1),
+            within: None,
+        };
+
+        assert!(assertion.check(&create_response(200, Some(r#"[{"count": 1}]"#))).is_ok());
+
+        let err = assertion
+            .check(&create_response(200, Some(r#"[{"count": 0}]"#)))
+            .unwrap_err();
+        assert_eq!(err.actual.as_deref(), Some("0"));
+
+        let err = assertion
+            .check(&create_response(200, Some(r#"[{"id": 1, "email": "x"}]"#)))
+            .unwrap_err();
+        assert!(err.message.starts_with("Query must select a single column"));
+        assert!(assertion.check(&create_response(200, Some("[]"))).is_err());
+    }
+
+    #[test]
+    fn test_assertion_key_value() {
+        let exists = Assertion::KeyValue {
+            url: "redis://localhost".to_string(),
+            key: "session:7".to_string(),
+            expected: None,
+            within: None,
+        };
+        let stored = create_response(
+            200,
+            Some(r#"[{"key": "session:7", "type": "string", "value": "pro"}]"#),
+        );
+
+        assert!(exists.check(&stored).is_ok());
+        let err = exists.check(&create_response(200, Some("[]"))).unwrap_err();
+        assert_eq!(err.message, "Key 'session:7' does not exist");
+
+        let equals = Assertion::KeyValue {
+            url: "redis://localhost".to_string(),
+            key: "session:7".to_string(),
+            expected: Some((Operator::Eq, Value::String("free".to_string()))),
+            within: None,
+        };
+        let err = equals.check(&stored).unwrap_err();
+        assert_eq!(err.actual.as_deref(), Some("\"pro\""));
+    }
+
     #[test]
     fn test_assertion_between_fail() {
         let assertion = Assertion::Between {
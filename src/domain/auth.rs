@@ -0,0 +1,156 @@
+//! `AUTH` directives.
+//!
+//! `AUTH BASIC user pass` and `AUTH BEARER token` add the matching
+//! `Authorization` header to every request of a test, steps included. At the
+//! top of a file the directive is the default for the tests below it; a test
+//! overrides it with its own `AUTH`, or drops it with `AUTH NONE`. A request
+//! that writes its own `Authorization` header keeps it.
+
+use base64::Engine;
+
+use super::http_request::{HttpRequest, RequestTemplate};
+use crate::template;
+
+const HEADER: &str = "Authorization";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Auth {
+    Basic {
+        user: String,
+        password: String,
+    },
+    /// Token text, possibly a placeholder captured by an earlier test
+    Bearer(String),
+    None,
+}
+
+impl Auth {
+    /// Basic credentials; placeholders in them, e.g. `{{secret.pw}}`, are
+    /// encoded once filled in at run time
+    pub fn basic(user: String, password: String) -> Self {
+        Auth::Basic { user, password }
+    }
+
+    /// `Authorization` header value, if any
+    pub fn header(&self) -> Option<String> {
+        match self {
+            Auth::Basic { user, password } => {
+                let credentials = base64::engine::general_purpose::STANDARD
+                    .encode(format!("{}:{}", user, password));
+                Some(format!("Basic {}", credentials))
+            }
+            Auth::Bearer(token) => Some(format!("Bearer {}", token)),
+            Auth::None => None,
+        }
+    }
+
+    /// Whether Basic credentials wait for run-time values
+    fn is_templated(&self) -> bool {
+        match self {
+            Auth::Basic { user, password } => {
+                !template::placeholders(user).is_empty()
+                    || !template::placeholders(password).is_empty()
+            }
+            _ => false,
+        }
+    }
+
+    /// Add the header to `request` unless it already has one
+    pub fn apply(&self, request: &mut HttpRequest) {
+        let Some(value) = self.header() else {
+            return;
+        };
+        if request
+            .headers
+            .keys()
+            .any(|key| key.eq_ignore_ascii_case(HEADER))
+        {
+            return;
+        }
+
+        // A captured token is filled in at run time, like any header, and
+        // Basic credentials are encoded after that
+        if !template::placeholders(&value).is_empty() || self.is_templated() {
+            let url = request.url.to_string();
+            let deferred = request.deferred.get_or_insert_with(|| RequestTemplate {
+                url,
+                query: Vec::new(),
+                headers: Vec::new(),
+                basic: None,
+                body: None,
+                encode: None,
+            });
+            match self {
+                Auth::Basic { user, password } => {
+                    deferred.basic = Some((user.clone(), password.clone()))
+                }
+                _ => deferred.headers.push((HEADER.to_string(), value.clone())),
+            }
+        }
+        request.headers.insert(HEADER.to_string(), value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use url::Url;
+
+    fn request() -> HttpRequest {
+        HttpRequest {
+            method: "GET".into(),
+            url: Url::parse("https://example.com/me").unwrap(),
+            headers: HashMap::new(),
+            body: None,
+            decode: None,
+            deferred: None,
+            share: false,
+        }
+    }
+
+    #[test]
+    fn basic_is_base64_encoded() {
+        let mut request = request();
+        Auth::basic("alice".into(), "s3cret".into())
+            .apply(&mut request);
+        assert_eq!(request.headers[HEADER], "Basic YWxpY2U6czNjcmV0");
+        assert!(request.deferred.is_none());
+    }
+
+    #[test]
+    fn basic_with_secret_is_encoded_at_send_time() {
+        let mut request = request();
+        Auth::basic("alice".into(), "{{password}}".into())
+            .apply(&mut request);
+
+        let deferred = request.deferred.clone().unwrap();
+        assert_eq!(deferred.placeholders(), vec!["password"]);
+        let variables = template::Variables::from([("password".to_string(), "s3cret".to_string())]);
+        deferred.apply(&mut request, &variables).unwrap();
+        assert_eq!(request.headers[HEADER], "Basic YWxpY2U6czNjcmV0");
+    }
+
+    #[test]
+    fn captured_bearer_is_deferred() {
+        let mut request = request();
+        Auth::Bearer("{{token}}".into()).apply(&mut request);
+
+        let deferred = request.deferred.clone().unwrap();
+        assert_eq!(deferred.placeholders(), vec!["token"]);
+        let variables = template::Variables::from([("token".to_string(), "abc".to_string())]);
+        deferred.apply(&mut request, &variables).unwrap();
+        assert_eq!(request.headers[HEADER], "Bearer abc");
+    }
+
+    #[test]
+    fn explicit_header_wins() {
+        let mut request = request();
+        request
+            .headers
+            .insert("authorization".into(), "Token xyz".into());
+        Auth::Bearer("abc".into()).apply(&mut request);
+        assert_eq!(request.headers.len(), 1);
+        assert_eq!(request.headers["authorization"], "Token xyz");
+    }
+}
@@ -0,0 +1,82 @@
+//! Client-side chaos injection.
+//!
+//! [`Chaos`] describes adverse conditions applied to every request of a run:
+//! an artificial delay before sending, a percentage of requests dropped
+//! before they leave the client, and a percentage of requests sent twice.
+//! Each test rolls its own [`ChaosPlan`], and everything that was injected is
+//! recorded on the test so renderers can label it clearly.
+
+use rand::Rng;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Default)]
+pub struct Chaos {
+    pub delay: Option<Duration>,
+    pub drop_percent: u8,
+    pub duplicate_percent: u8,
+}
+
+/// What chaos injection decided for a single request
+#[derive(Debug, Default, PartialEq)]
+pub struct ChaosPlan {
+    pub delay: Option<Duration>,
+    pub drop: bool,
+    pub duplicate: bool,
+}
+
+impl Chaos {
+    pub fn is_enabled(&self) -> bool {
+        self.delay.is_some() || self.drop_percent > 0 || self.duplicate_percent > 0
+    }
+
+    /// Short summary of the active settings, e.g. `delay 200ms, drop 10%`
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(delay) = self.delay {
+            parts.push(format!("delay {:?}", delay));
+        }
+        if self.drop_percent > 0 {
+            parts.push(format!("drop {}%", self.drop_percent));
+        }
+        if self.duplicate_percent > 0 {
+            parts.push(format!("duplicate {}%", self.duplicate_percent));
+        }
+        parts.join(", ")
+    }
+
+    /// Decide which conditions apply to the next request
+    pub fn roll(&self) -> ChaosPlan {
+        if !self.is_enabled() {
+            return ChaosPlan::default();
+        }
+
+        let mut rng = rand::thread_rng();
+        ChaosPlan {
+            delay: self.delay,
+            drop: rng.gen_range(0..100) < self.drop_percent,
+            duplicate: rng.gen_range(0..100) < self.duplicate_percent,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roll_disabled_is_noop() {
+        assert_eq!(Chaos::default().roll(), ChaosPlan::default());
+    }
+
+    #[test]
+    fn roll_always_drops_at_full_percentage() {
+        let chaos = Chaos {
+            delay: None,
+            drop_percent: 100,
+            duplicate_percent: 0,
+        };
+        let plan = chaos.roll();
+        assert!(plan.drop);
+        assert!(!plan.duplicate);
+    }
+}
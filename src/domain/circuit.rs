@@ -0,0 +1,110 @@
+//! Per-host circuit breaker for transport-level failures.
+//!
+//! Once a host fails at the transport level (connection refused, timeout,
+//! DNS, TLS, ...) `threshold` times in a row, its circuit opens and stays open
+//! for the rest of the run: remaining tests against that host fail fast
+//! instead of each waiting out the full timeout. Any successful exchange
+//! resets the host's failure count while the circuit is still closed.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use url::Url;
+
+#[derive(Debug, Default)]
+pub struct CircuitBreaker {
+    /// Consecutive failures that open a circuit; `None` disables the breaker
+    threshold: Option<u32>,
+    failures: Mutex<HashMap<String, u32>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: Option<u32>) -> Self {
+        Self {
+            threshold,
+            failures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// `host:port` key used to track a URL's host
+    pub fn host_key(url: &Url) -> String {
+        format!(
+            "{}:{}",
+            url.host_str().unwrap_or_default(),
+            url.port_or_known_default().unwrap_or_default()
+        )
+    }
+
+    /// Whether requests to `url`'s host should fail fast
+    pub fn is_open(&self, url: &Url) -> bool {
+        let Some(threshold) = self.threshold else {
+            return false;
+        };
+        let failures = self.failures.lock().unwrap();
+        failures
+            .get(&Self::host_key(url))
+            .is_some_and(|&n| n >= threshold)
+    }
+
+    pub fn record_failure(&self, url: &Url) {
+        if self.threshold.is_none() {
+            return;
+        }
+        *self
+            .failures
+            .lock()
+            .unwrap()
+            .entry(Self::host_key(url))
+            .or_insert(0) += 1;
+    }
+
+    pub fn record_success(&self, url: &Url) {
+        if self.threshold.is_none() || self.is_open(url) {
+            return;
+        }
+        self.failures.lock().unwrap().remove(&Self::host_key(url));
+    }
+
+    /// Failure message for tests skipped because `url`'s circuit is open
+    pub fn open_message(&self, url: &Url) -> String {
+        format!(
+            "Circuit open for {} after {} consecutive transport failures",
+            Self::host_key(url),
+            self.threshold.unwrap_or_default()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new(Some(2));
+        let api = Url::parse("https://api.example.com/users").unwrap();
+        let other = Url::parse("http://api.example.com/users").unwrap();
+
+        breaker.record_failure(&api);
+        breaker.record_success(&api);
+        breaker.record_failure(&api);
+        assert!(!breaker.is_open(&api));
+
+        breaker.record_failure(&api);
+        assert!(breaker.is_open(&api));
+        assert!(!breaker.is_open(&other));
+
+        // An open circuit stays open for the rest of the run
+        breaker.record_success(&api);
+        assert!(breaker.is_open(&api));
+    }
+
+    #[test]
+    fn disabled_breaker_never_opens() {
+        let breaker = CircuitBreaker::default();
+        let api = Url::parse("https://api.example.com").unwrap();
+        for _ in 0..10 {
+            breaker.record_failure(&api);
+        }
+        assert!(!breaker.is_open(&api));
+    }
+}
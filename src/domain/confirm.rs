@@ -0,0 +1,97 @@
+//! Interactive approval of mutating requests (`--confirm`).
+//!
+//! Before a POST, PUT, PATCH or DELETE goes out, the request is printed as
+//! it will be sent (placeholders filled in, signed) and the user answers
+//! `y` to send it, `a` to send it and every later one, or anything else to
+//! hold it back; the test is then reported as not run. This covers every
+//! request a test sends, including the extra ones its assertions send
+//! (probes, polls, rate-limit bursts) and resends. GET, HEAD and
+//! OPTIONS requests are approved without asking. Prompts of concurrent tests
+//! are asked one at a time, and a closed stdin declines.
+
+use owo_colors::OwoColorize;
+use std::io::{BufRead, Write};
+use std::sync::{Arc, Mutex};
+
+use super::http_request::HttpRequest;
+use crate::artifacts::request_text;
+
+#[derive(Debug, Default)]
+pub struct Confirmation {
+    /// Held while a prompt is open; true once the user approved everything
+    approve_all: Arc<Mutex<bool>>,
+}
+
+/// Methods that are approved without asking
+fn is_safe(method: &str) -> bool {
+    matches!(
+        method.to_ascii_uppercase().as_str(),
+        "GET" | "HEAD" | "OPTIONS"
+    )
+}
+
+/// Whether an answer approves the request, and every later one
+fn parse_answer(answer: &str) -> (bool, bool) {
+    match answer.trim().to_ascii_lowercase().as_str() {
+        "y" | "yes" => (true, false),
+        "a" | "all" => (true, true),
+        _ => (false, false),
+    }
+}
+
+impl Confirmation {
+    /// Ask whether `request` may be sent
+    pub async fn approve(&self, request: &HttpRequest) -> bool {
+        if is_safe(&request.method) {
+            return true;
+        }
+
+        let approve_all = Arc::clone(&self.approve_all);
+        let text = request_text(request);
+        tokio::task::spawn_blocking(move || {
+            let mut approve_all = approve_all.lock().unwrap();
+            if *approve_all {
+                return true;
+            }
+
+            println!(
+                "\n{}\n{}",
+                "About to send:".yellow().bold(),
+                text.trim_end()
+            );
+            print!("Send it? [y]es / [n]o / [a]ll: ");
+            let _ = std::io::stdout().flush();
+
+            let mut answer = String::new();
+            if std::io::stdin().lock().read_line(&mut answer).is_err() {
+                return false;
+            }
+            let (approved, all) = parse_answer(&answer);
+            *approve_all = all;
+            approved
+        })
+        .await
+        .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_mutating_methods_ask() {
+        assert!(is_safe("GET"));
+        assert!(is_safe("options"));
+        assert!(!is_safe("DELETE"));
+        assert!(!is_safe("POST"));
+    }
+
+    #[test]
+    fn answers() {
+        assert_eq!(parse_answer("y\n"), (true, false));
+        assert_eq!(parse_answer("ALL"), (true, true));
+        assert_eq!(parse_answer("\n"), (false, false));
+        assert_eq!(parse_answer(""), (false, false));
+    }
+}
@@ -0,0 +1,52 @@
+use super::chaos::Chaos;
+use super::circuit::CircuitBreaker;
+use super::confirm::Confirmation;
+use super::share::SharedResponses;
+use super::signing::RequestSigner;
+use super::source::{self, ExternalSource};
+use super::token::TokenRefresher;
+use super::webhook::Webhooks;
+use crate::template::Variables;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::time::Instant;
+
+/// Settings and shared state available to every test while it runs
+#[derive(Debug, Default)]
+pub struct ExecutionContext {
+    pub chaos: Chaos,
+    /// Tests still running at this instant are cancelled and marked not run
+    pub deadline: Option<Instant>,
+    /// Per-host transport failure tracking shared by all tests of the run
+    pub circuits: CircuitBreaker,
+    /// Values stored by `CAPTURE`, filled into later tests' placeholders
+    pub captured: Mutex<Variables>,
+    /// Signs each request right before it is sent (`[signing]` in the config)
+    pub signer: Option<Arc<dyn RequestSigner>>,
+    /// Keeps a captured token fresh (`[token_refresh]` in the config)
+    pub refresher: Option<Arc<TokenRefresher>>,
+    /// Asks before each mutating request is sent (`--confirm`)
+    pub confirm: Option<Confirmation>,
+    /// Listeners for `LISTEN` callback assertions
+    pub webhooks: Webhooks,
+    /// Responses of `SHARE RESPONSE` requests sent so far
+    pub shared: SharedResponses,
+    /// External sources by assertion keyword, e.g. `MAILBOX`
+    pub sources: HashMap<String, Arc<dyn ExternalSource>>,
+}
+
+impl ExecutionContext {
+    /// Source behind an assertion keyword: opened for `target` when the test
+    /// names one (a DB URL), else the one configured for the run
+    pub fn source(&self, name: &str, target: Option<&str>) -> Result<Arc<dyn ExternalSource>> {
+        match target {
+            Some(target) => source::connect(name, target),
+            None => self
+                .sources
+                .get(name)
+                .cloned()
+                .with_context(|| format!("No {} source configured", name)),
+        }
+    }
+}
@@ -0,0 +1,368 @@
+//! Response body decoders.
+//!
+//! Assertions on `body.<path>` work on a JSON value. A [`BodyDecoder`] turns
+//! the raw response bytes into that value, so the same paths work for every
+//! supported wire format:
+//!
+//! - `json`, `yaml`, `msgpack`, `cbor`
+//! - `xml` — elements become objects, attributes `@name` keys, text next to
+//!   attributes or children `#text`, and repeated elements arrays
+//! - `protobuf` — needs a descriptor set and message name:
+//!   `DECODE protobuf "api.desc" "shop.Order"`
+//!
+//! A test picks a decoder with `DECODE <name>`; otherwise one is chosen from
+//! the response Content-Type via [`Decode::for_content_type`], falling back
+//! to JSON.
+
+use anyhow::{bail, Context, Result};
+use prost_reflect::MessageDescriptor;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+/// Turns a raw response body into a JSON value for path lookups
+pub trait BodyDecoder {
+    fn decode(&self, body: &[u8]) -> Result<Value>;
+}
+
+/// Decoder selected for a test, by `DECODE` or by content type
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decode {
+    Json,
+    Xml,
+    Yaml,
+    MsgPack,
+    Cbor,
+    Protobuf {
+        descriptor: PathBuf,
+        message: String,
+    },
+}
+
+/// Media types handled by each argument-free decoder
+const REGISTRY: &[(&[&str], Decode)] = &[
+    (&["application/json", "+json"], Decode::Json),
+    (&["application/xml", "text/xml", "+xml"], Decode::Xml),
+    (
+        &[
+            "application/yaml",
+            "application/x-yaml",
+            "text/yaml",
+            "+yaml",
+        ],
+        Decode::Yaml,
+    ),
+    (
+        &[
+            "application/msgpack",
+            "application/x-msgpack",
+            "application/vnd.msgpack",
+        ],
+        Decode::MsgPack,
+    ),
+    (&["application/cbor", "+cbor"], Decode::Cbor),
+];
+
+impl Decode {
+    /// Decoder named in `DECODE <name> [args...]`
+    pub fn from_name(name: &str, args: Vec<String>) -> Result<Self> {
+        let decode = match name.to_ascii_lowercase().as_str() {
+            "json" => Decode::Json,
+            "xml" => Decode::Xml,
+            "yaml" => Decode::Yaml,
+            "msgpack" => Decode::MsgPack,
+            "cbor" => Decode::Cbor,
+            "protobuf" | "proto" => {
+                let [descriptor, message] = <[String; 2]>::try_from(args).map_err(|_| {
+                    anyhow::anyhow!(r#"DECODE protobuf needs a descriptor set and a message: DECODE protobuf "api.desc" "pkg.Message""#)
+                })?;
+                return Ok(Decode::Protobuf {
+                    descriptor: descriptor.into(),
+                    message,
+                });
+            }
+            other => bail!(
+                "Unknown decoder '{}': use json, xml, yaml, msgpack, cbor, or protobuf",
+                other
+            ),
+        };
+
+        if !args.is_empty() {
+            bail!("DECODE {} takes no arguments", name);
+        }
+        Ok(decode)
+    }
+
+    /// Decoder registered for a Content-Type header value, if any
+    pub fn for_content_type(content_type: &str) -> Option<Self> {
+        let media_type = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+
+        REGISTRY
+            .iter()
+            .find(|(types, _)| {
+                types.iter().any(|t| match t.strip_prefix('+') {
+                    Some(suffix) => media_type.ends_with(&format!("+{}", suffix)),
+                    None => media_type == *t,
+                })
+            })
+            .map(|(_, decode)| decode.clone())
+    }
+
+    /// Resolve file arguments relative to `base_dir`
+    pub fn resolve_paths(&mut self, base_dir: &Path) {
+        if let Decode::Protobuf { descriptor, .. } = self {
+            if descriptor.is_relative() {
+                *descriptor = base_dir.join(&*descriptor);
+            }
+        }
+    }
+
+    pub fn decode(&self, body: &[u8]) -> Result<Value> {
+        self.decoder().decode(body)
+    }
+
+    fn decoder(&self) -> Box<dyn BodyDecoder + '_> {
+        match self {
+            Decode::Json => Box::new(JsonDecoder),
+            Decode::Xml => Box::new(XmlDecoder),
+            Decode::Yaml => Box::new(YamlDecoder),
+            Decode::MsgPack => Box::new(MsgPackDecoder),
+            Decode::Cbor => Box::new(CborDecoder),
+            Decode::Protobuf {
+                descriptor,
+                message,
+            } => Box::new(ProtobufDecoder {
+                descriptor,
+                message,
+            }),
+        }
+    }
+}
+
+struct JsonDecoder;
+
+impl BodyDecoder for JsonDecoder {
+    fn decode(&self, body: &[u8]) -> Result<Value> {
+        Ok(serde_json::from_slice(body)?)
+    }
+}
+
+struct YamlDecoder;
+
+impl BodyDecoder for YamlDecoder {
+    fn decode(&self, body: &[u8]) -> Result<Value> {
+        Ok(serde_yaml::from_slice(body)?)
+    }
+}
+
+struct MsgPackDecoder;
+
+impl BodyDecoder for MsgPackDecoder {
+    fn decode(&self, body: &[u8]) -> Result<Value> {
+        Ok(rmp_serde::from_slice(body)?)
+    }
+}
+
+struct CborDecoder;
+
+impl BodyDecoder for CborDecoder {
+    fn decode(&self, body: &[u8]) -> Result<Value> {
+        Ok(ciborium::from_reader(body)?)
+    }
+}
+
+struct ProtobufDecoder<'a> {
+    descriptor: &'a Path,
+    message: &'a str,
+}
+
+impl BodyDecoder for ProtobufDecoder<'_> {
+    fn decode(&self, body: &[u8]) -> Result<Value> {
+        let message = load_message(self.descriptor, self.message)?;
+        let decoded = prost_reflect::DynamicMessage::decode(message, body)?;
+        Ok(serde_json::to_value(&decoded)?)
+    }
+}
+
+/// Look up `message` in the descriptor set file at `descriptor`
+pub(crate) fn load_message(descriptor: &Path, message: &str) -> Result<MessageDescriptor> {
+    let descriptors = std::fs::read(descriptor)
+        .with_context(|| format!("Failed to read descriptor set {}", descriptor.display()))?;
+    let pool = prost_reflect::DescriptorPool::decode(descriptors.as_slice())
+        .with_context(|| format!("Invalid descriptor set {}", descriptor.display()))?;
+    pool.get_message_by_name(message)
+        .with_context(|| format!("Message '{}' not found in {}", message, descriptor.display()))
+}
+
+struct XmlDecoder;
+
+/// An element being built: its name, attributes and children, and text
+type OpenElement = (String, Map<String, Value>, String);
+
+impl BodyDecoder for XmlDecoder {
+    fn decode(&self, body: &[u8]) -> Result<Value> {
+        let mut reader = Reader::from_reader(body);
+        reader.config_mut().trim_text(true);
+
+        let mut stack: Vec<OpenElement> = vec![(String::new(), Map::new(), String::new())];
+        let mut buf = Vec::new();
+
+        loop {
+            match reader.read_event_into(&mut buf)? {
+                Event::Start(start) => stack.push(open_element(&start)?),
+                Event::Empty(start) => {
+                    let (name, fields, text) = open_element(&start)?;
+                    add_child(stack.last_mut().unwrap(), name, close_element(fields, text));
+                }
+                Event::Text(text) => stack.last_mut().unwrap().2.push_str(&text.unescape()?),
+                Event::CData(data) => stack
+                    .last_mut()
+                    .unwrap()
+                    .2
+                    .push_str(&String::from_utf8_lossy(&data)),
+                Event::End(_) => {
+                    let (name, fields, text) = stack.pop().unwrap();
+                    let parent = stack.last_mut().context("Unbalanced XML end tag")?;
+                    add_child(parent, name, close_element(fields, text));
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+
+        if stack.len() != 1 {
+            bail!("Unclosed XML element '{}'", stack.last().unwrap().0);
+        }
+        let (_, document, _) = stack.pop().unwrap();
+        Ok(Value::Object(document))
+    }
+}
+
+fn open_element(start: &BytesStart) -> Result<OpenElement> {
+    let name = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+
+    let mut fields = Map::new();
+    for attribute in start.attributes() {
+        let attribute = attribute?;
+        fields.insert(
+            format!("@{}", String::from_utf8_lossy(attribute.key.as_ref())),
+            Value::String(attribute.unescape_value()?.into_owned()),
+        );
+    }
+
+    Ok((name, fields, String::new()))
+}
+
+fn close_element(mut fields: Map<String, Value>, text: String) -> Value {
+    if fields.is_empty() {
+        return Value::String(text);
+    }
+    if !text.is_empty() {
+        fields.insert("#text".into(), Value::String(text));
+    }
+    Value::Object(fields)
+}
+
+/// Attach a child element, turning repeated names into arrays
+fn add_child(parent: &mut OpenElement, name: String, value: Value) {
+    match parent.1.get_mut(&name) {
+        Some(Value::Array(items)) => items.push(value),
+        Some(existing) => {
+            let first = existing.take();
+            *existing = Value::Array(vec![first, value]);
+        }
+        None => {
+            parent.1.insert(name, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn decoder_for_content_type() {
+        assert_eq!(
+            Decode::for_content_type("application/json; charset=utf-8"),
+            Some(Decode::Json)
+        );
+        assert_eq!(
+            Decode::for_content_type("application/problem+json"),
+            Some(Decode::Json)
+        );
+        assert_eq!(Decode::for_content_type("text/xml"), Some(Decode::Xml));
+        assert_eq!(
+            Decode::for_content_type("application/x-msgpack"),
+            Some(Decode::MsgPack)
+        );
+        assert_eq!(Decode::for_content_type("text/plain"), None);
+    }
+
+    #[test]
+    fn decoder_from_name() {
+        assert_eq!(Decode::from_name("YAML", Vec::new()).unwrap(), Decode::Yaml);
+        assert_eq!(
+            Decode::from_name("protobuf", vec!["api.desc".into(), "shop.Order".into()]).unwrap(),
+            Decode::Protobuf {
+                descriptor: "api.desc".into(),
+                message: "shop.Order".into(),
+            }
+        );
+        assert!(Decode::from_name("protobuf", Vec::new()).is_err());
+        assert!(Decode::from_name("proto", vec!["a.desc".into(), "a.B".into()]).is_ok());
+        assert!(Decode::from_name("json", vec!["x".into()]).is_err());
+        assert!(Decode::from_name("bson", Vec::new()).is_err());
+    }
+
+    #[test]
+    fn decode_xml() {
+        let body = br#"<?xml version="1.0"?>
+            <order id="7">
+              <item sku="a">Pen</item>
+              <item sku="b">Ink</item>
+              <total>3</total>
+              <gift/>
+            </order>"#;
+
+        assert_eq!(
+            Decode::Xml.decode(body).unwrap(),
+            json!({
+                "order": {
+                    "@id": "7",
+                    "item": [
+                        { "@sku": "a", "#text": "Pen" },
+                        { "@sku": "b", "#text": "Ink" }
+                    ],
+                    "total": "3",
+                    "gift": ""
+                }
+            })
+        );
+        assert!(Decode::Xml.decode(b"<order><item></order>").is_err());
+    }
+
+    #[test]
+    fn decode_yaml_and_msgpack() {
+        assert_eq!(
+            Decode::Yaml
+                .decode(b"user:\n  name: Ada\n  age: 36\n")
+                .unwrap(),
+            json!({ "user": { "name": "Ada", "age": 36 } })
+        );
+
+        let packed = rmp_serde::to_vec_named(&json!({ "count": 3 })).unwrap();
+        assert_eq!(
+            Decode::MsgPack.decode(&packed).unwrap(),
+            json!({ "count": 3 })
+        );
+    }
+}
@@ -0,0 +1,276 @@
+//! Request body encoders.
+//!
+//! `BODY MSGPACK` and `BODY CBOR` blocks are written as JSON and encoded to
+//! the binary format before sending, so binary APIs are tested with the same
+//! readable bodies as JSON ones. Responses in these formats are decoded for
+//! assertions by [`Decode`](super::decode::Decode).
+//!
+//! `BODY JSON` blocks are checked while parsing and sent as JSON, so a
+//! malformed payload fails before any request goes out. `BODY MINIFY` sends
+//! the JSON without whitespace, keys in the order written, and `BODY
+//! CANONICAL` with its keys sorted, for servers that sign or hash bodies.
+//! Bodies in any of these formats may hold `//` and `/* */` comments, which
+//! are never sent.
+//!
+//! `BODY PROTO ./schemas/api.desc my.pkg.CreateUserRequest` encodes the JSON
+//! as that protobuf message, using a descriptor set (`protoc
+//! --descriptor_set_out`) resolved relative to the `.ax` file.
+
+use anyhow::{Context, Result};
+use prost_reflect::prost::Message;
+use prost_reflect::DynamicMessage;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use super::decode::load_message;
+use super::http_request::Body;
+use super::normalize::sort_keys;
+use crate::template::{self, Variables};
+
+/// Format a JSON-written request body is sent as
+#[derive(Debug, Clone, PartialEq)]
+pub enum Encode {
+    /// Validated JSON, sent as is
+    Json,
+    /// JSON sent without whitespace or comments, keys in written order
+    Minified,
+    /// JSON sent without whitespace or comments, keys sorted
+    Canonical,
+    MsgPack,
+    Cbor,
+    Protobuf {
+        descriptor: PathBuf,
+        message: String,
+    },
+}
+
+impl Encode {
+    /// Encoder named after `BODY`, e.g. `BODY MSGPACK`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "JSON" => Some(Encode::Json),
+            "MINIFY" => Some(Encode::Minified),
+            "CANONICAL" => Some(Encode::Canonical),
+            "MSGPACK" => Some(Encode::MsgPack),
+            "CBOR" => Some(Encode::Cbor),
+            _ => None,
+        }
+    }
+
+    /// Content-Type sent unless the request sets its own
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Encode::Json | Encode::Minified | Encode::Canonical => "application/json",
+            Encode::MsgPack => "application/msgpack",
+            Encode::Cbor => "application/cbor",
+            Encode::Protobuf { .. } => "application/x-protobuf",
+        }
+    }
+
+    /// Whether encoding reads files, so it must wait for
+    /// [`resolve_paths`](Self::resolve_paths)
+    pub fn needs_files(&self) -> bool {
+        matches!(self, Encode::Protobuf { .. })
+    }
+
+    /// Resolve file arguments relative to `base_dir`
+    pub fn resolve_paths(&mut self, base_dir: &Path) {
+        if let Encode::Protobuf { descriptor, .. } = self {
+            if descriptor.is_relative() {
+                *descriptor = base_dir.join(&*descriptor);
+            }
+        }
+    }
+
+    /// Check that a body whose placeholders are filled in later will be
+    /// valid JSON, whatever they turn into (each is tried as `0`)
+    pub fn check_template(&self, text: &str) -> Result<()> {
+        let stand_ins: Variables = template::placeholders(text)
+            .into_iter()
+            .map(|expr| (expr.to_string(), "0".to_string()))
+            .collect();
+        serde_json::from_str::<Value>(&minify(&template::substitute(text, &stand_ins)))
+            .context("Encoded bodies must be written as JSON")?;
+        Ok(())
+    }
+
+    /// The request body for a JSON text body
+    pub fn body(&self, text: &str) -> Result<Body> {
+        Ok(match self {
+            Encode::Json => Body::Json(
+                serde_json::from_str(&minify(text)).context("BODY JSON is not valid JSON")?,
+            ),
+            Encode::Minified | Encode::Canonical => {
+                Body::Text(String::from_utf8(self.encode(text)?)?)
+            }
+            _ => Body::Bytes(self.encode(text)?),
+        })
+    }
+
+    /// Encode a JSON text body
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>> {
+        let minified = minify(text);
+        let mut value: Value =
+            serde_json::from_str(&minified).context("Encoded bodies must be written as JSON")?;
+        if *self == Encode::Canonical {
+            sort_keys(&mut value);
+        }
+
+        let bytes = match self {
+            Encode::Json | Encode::Canonical => serde_json::to_vec(&value)?,
+            Encode::Minified => minified.into_bytes(),
+            Encode::MsgPack => rmp_serde::to_vec_named(&value)?,
+            Encode::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(&value, &mut bytes)?;
+                bytes
+            }
+            Encode::Protobuf {
+                descriptor,
+                message,
+            } => {
+                let message = load_message(descriptor, message)?;
+                DynamicMessage::deserialize(message, value)
+                    .context("Body does not match the protobuf message")?
+                    .encode_to_vec()
+            }
+        };
+        Ok(bytes)
+    }
+}
+
+/// Check that a body sent as written is valid JSON once its placeholders are
+/// filled in (each is tried as `0`); comments are not allowed here
+pub fn check_json(text: &str) -> Result<()> {
+    let stand_ins: Variables = template::placeholders(text)
+        .into_iter()
+        .map(|expr| (expr.to_string(), "0".to_string()))
+        .collect();
+    serde_json::from_str::<Value>(&template::substitute(text, &stand_ins))?;
+    Ok(())
+}
+
+/// `text` without whitespace and comments outside of strings
+pub fn minify(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            match c {
+                '\\' => output.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                output.push(c);
+            }
+            ('/', Some('/')) => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            (c, _) if c.is_whitespace() => {}
+            (c, _) => output.push(c),
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::decode::Decode;
+
+    #[test]
+    fn encode_round_trips_through_decode() {
+        let text = r#"{"device": "sensor-1", "readings": [1, 2, 3], "ok": true}"#;
+        let expected: Value = serde_json::from_str(text).unwrap();
+
+        let packed = Encode::MsgPack.encode(text).unwrap();
+        assert_eq!(Decode::MsgPack.decode(&packed).unwrap(), expected);
+
+        let cbor = Encode::Cbor.encode(text).unwrap();
+        assert_eq!(Decode::Cbor.decode(&cbor).unwrap(), expected);
+    }
+
+    #[test]
+    fn json_body_is_validated() {
+        let Body::Json(value) = Encode::Json.body(r#"{"name": "Ada"}"#).unwrap() else {
+            panic!("expected a JSON body");
+        };
+        assert_eq!(value["name"], "Ada");
+        assert!(Encode::Json.body(r#"{"name": "Ada",}"#).is_err());
+
+        assert!(Encode::Json
+            .check_template(r#"{"id": {{id}}, "token": "{{ token }}"}"#)
+            .is_ok());
+        assert!(Encode::Json.check_template(r#"{"id": {{id}}"#).is_err());
+    }
+
+    #[test]
+    fn minify_and_canonicalize() {
+        let text = r#"{
+            // who signs up
+            "name": "Ada Lovelace", /* dropped, "quotes" too */
+            "id": 1,
+            "note": "a // b \" /* c */"
+        }"#;
+        assert_eq!(
+            minify(text),
+            r#"{"name":"Ada Lovelace","id":1,"note":"a // b \" /* c */"}"#
+        );
+        let Body::Text(canonical) = Encode::Canonical.body(text).unwrap() else {
+            panic!("expected a text body");
+        };
+        assert_eq!(
+            canonical,
+            r#"{"id":1,"name":"Ada Lovelace","note":"a // b \" /* c */"}"#
+        );
+        assert!(Encode::Json.body(text).is_ok());
+        assert!(Encode::Minified.body(r#"{"id": 1,}"#).is_err());
+
+        assert!(check_json(r#"{"id": {{id}}}"#).is_ok());
+        assert!(check_json(text).is_err());
+    }
+
+    #[test]
+    fn encode_requires_json() {
+        assert!(Encode::Cbor.encode("not json").is_err());
+        assert_eq!(Encode::from_name("cbor"), Some(Encode::Cbor));
+        assert_eq!(Encode::from_name("xml"), None);
+        assert_eq!(Encode::MsgPack.encode("1").unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn protobuf_descriptor_resolves_relative_to_suite() {
+        let mut encode = Encode::Protobuf {
+            descriptor: "schemas/api.desc".into(),
+            message: "shop.Order".into(),
+        };
+        assert!(encode.needs_files());
+        encode.resolve_paths(Path::new("tests"));
+        assert_eq!(
+            encode,
+            Encode::Protobuf {
+                descriptor: "tests/schemas/api.desc".into(),
+                message: "shop.Order".into(),
+            }
+        );
+        assert!(encode.encode("{}").is_err());
+    }
+}
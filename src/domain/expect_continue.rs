@@ -0,0 +1,320 @@
+//! `Expect: 100-continue` uploads (`ASSERT EXPECT_CONTINUE`).
+//!
+//! A client about to upload a large body can send its headers with
+//! `Expect: 100-continue` and wait for an interim `100 Continue` before the
+//! body, so a server that will reject the upload (too large, unauthorized)
+//! says so before any of it is sent. The server honors this when it answers
+//! before the body: with `100 Continue`, or with a final status. Staying
+//! silent until the client gives up waiting means it ignores the header.
+//!
+//! reqwest neither waits for nor reports interim responses, so the probe
+//! speaks HTTP/1.1 itself over a plain TCP connection: only `http://` URLs
+//! are supported. Bodies of 1 MiB or more report their upload progress on
+//! stderr.
+
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+use super::http_request::{Body, HttpRequest, HttpResponse};
+
+/// How long the server gets to answer the headers when `WITHIN` is not set
+pub const DEFAULT_WAIT: Duration = Duration::from_secs(1);
+
+/// How long the final response may take once the body is sent
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Bodies from this size on report their upload progress
+const PROGRESS_MIN: usize = 1024 * 1024;
+
+/// Bytes written at a time
+const CHUNK: usize = 64 * 1024;
+
+/// What the server did between the headers and the body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interim {
+    /// Sent `100 Continue`, then took the body
+    Continued,
+    /// Sent its final response without waiting for the body
+    Answered,
+    /// Said nothing within the wait, so the body was sent anyway
+    Ignored,
+}
+
+impl Interim {
+    pub fn honored(self) -> bool {
+        self != Interim::Ignored
+    }
+}
+
+/// Body of `request` as sent, and the Content-Type it implies
+fn body_bytes(request: &HttpRequest) -> Result<(Vec<u8>, Option<&'static str>)> {
+    Ok(match &request.body {
+        None => (Vec::new(), None),
+        Some(Body::Text(text)) => (text.clone().into_bytes(), None),
+        Some(Body::Json(value)) => (serde_json::to_vec(value)?, Some("application/json")),
+        Some(Body::Bytes(bytes)) => (bytes.clone(), None),
+        Some(Body::Multipart(_)) => bail!("EXPECT_CONTINUE does not support MULTIPART bodies"),
+        Some(Body::Stream(_)) => bail!("EXPECT_CONTINUE does not support BODY STREAM"),
+    })
+}
+
+/// Request line and headers announcing a body of `length` bytes
+fn head(request: &HttpRequest, length: usize, content_type: Option<&str>) -> String {
+    let url = &request.url;
+    let mut target = url.path().to_string();
+    if let Some(query) = url.query() {
+        target = format!("{}?{}", target, query);
+    }
+    let host = match url.port() {
+        Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+
+    let mut head = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\n",
+        request.method, target, host
+    );
+    for (name, value) in &request.headers {
+        let managed = [
+            "host",
+            "content-length",
+            "expect",
+            "connection",
+            "transfer-encoding",
+        ];
+        if !managed.contains(&name.to_ascii_lowercase().as_str()) {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+    }
+    if let Some(content_type) = content_type {
+        if !request
+            .headers
+            .keys()
+            .any(|name| name.eq_ignore_ascii_case("content-type"))
+        {
+            head.push_str(&format!("Content-Type: {}\r\n", content_type));
+        }
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nExpect: 100-continue\r\nConnection: close\r\n\r\n",
+        length
+    ));
+    head
+}
+
+/// Status and headers of the next response on `reader`
+async fn read_head<R: AsyncRead + Unpin>(
+    reader: &mut BufReader<R>,
+) -> Result<(u16, HashMap<String, String>)> {
+    let mut line = String::new();
+    reader.read_line(&mut line).await?;
+    let status = line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .with_context(|| format!("Malformed status line '{}'", line.trim_end()))?;
+
+    let mut headers = HashMap::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            break;
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.insert(name.trim().to_string(), value.trim().to_string());
+        }
+    }
+    Ok((status, headers))
+}
+
+fn header<'a>(headers: &'a HashMap<String, String>, name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Body of the response whose `headers` were just read
+async fn read_body<R: AsyncRead + Unpin>(
+    reader: &mut BufReader<R>,
+    headers: &HashMap<String, String>,
+) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    if header(headers, "transfer-encoding").is_some_and(|value| value.contains("chunked")) {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            reader.read_line(&mut line).await?;
+            let size = line.trim_end().split(';').next().unwrap_or_default();
+            let size = usize::from_str_radix(size, 16)
+                .with_context(|| format!("Malformed chunk size '{}'", size))?;
+            if size == 0 {
+                break;
+            }
+            let start = bytes.len();
+            bytes.resize(start + size, 0);
+            reader.read_exact(&mut bytes[start..]).await?;
+            // CRLF after the chunk
+            line.clear();
+            reader.read_line(&mut line).await?;
+        }
+    } else if let Some(length) = header(headers, "content-length") {
+        bytes.resize(length.parse().context("Malformed Content-Length")?, 0);
+        reader.read_exact(&mut bytes).await?;
+    } else {
+        reader.read_to_end(&mut bytes).await?;
+    }
+    Ok(bytes)
+}
+
+fn mebibytes(bytes: usize) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+/// Send `request` announcing its body with `Expect: 100-continue`, giving
+/// the server `wait` to answer before the body goes out anyway
+pub async fn send(request: HttpRequest, wait: Duration) -> Result<(HttpResponse, Interim)> {
+    if request.url.scheme() != "http" {
+        bail!(
+            "EXPECT_CONTINUE needs an http:// URL, not {}",
+            request.url.scheme()
+        );
+    }
+    let start = Instant::now();
+    let (body, content_type) = body_bytes(&request)?;
+    let address = (
+        request.url.host_str().unwrap_or_default().to_string(),
+        request.url.port_or_known_default().unwrap_or(80),
+    );
+
+    let stream = TcpStream::connect(address)
+        .await
+        .with_context(|| format!("Cannot connect to {}", request.url))?;
+    let (read, mut write) = stream.into_split();
+    let mut reader = BufReader::new(read);
+    write
+        .write_all(head(&request, body.len(), content_type).as_bytes())
+        .await?;
+
+    let mut interim = Interim::Ignored;
+    let mut answer = None;
+    if let Ok(first) = tokio::time::timeout(wait, read_head(&mut reader)).await {
+        let (status, headers) = first?;
+        if status == 100 {
+            interim = Interim::Continued;
+        } else if status >= 200 {
+            interim = Interim::Answered;
+            answer = Some((status, headers));
+        }
+    }
+
+    if answer.is_none() {
+        let report = body.len() >= PROGRESS_MIN;
+        let mut reported = 0;
+        for (index, chunk) in body.chunks(CHUNK).enumerate() {
+            write.write_all(chunk).await?;
+            let sent = (index * CHUNK + chunk.len()) * 100 / body.len();
+            if report && sent / 25 > reported / 25 {
+                eprintln!(
+                    "↑ {} {}: {}% of {}",
+                    request.method,
+                    request.url,
+                    sent,
+                    mebibytes(body.len())
+                );
+                reported = sent;
+            }
+        }
+    }
+
+    let (status, headers, bytes) = tokio::time::timeout(RESPONSE_TIMEOUT, async {
+        let (mut status, mut headers) = match answer {
+            Some(answer) => answer,
+            None => read_head(&mut reader).await?,
+        };
+        // Interim responses still to come, e.g. a late 100 Continue
+        while (100..200).contains(&status) {
+            (status, headers) = read_head(&mut reader).await?;
+        }
+        let bytes = read_body(&mut reader, &headers).await?;
+        anyhow::Ok((status, headers, bytes))
+    })
+    .await
+    .context("Timed out waiting for the response")??;
+
+    let mut response = HttpResponse {
+        request: Some(request),
+        duration: start.elapsed(),
+        status,
+        headers,
+        body: None,
+        bytes,
+        cookies: Vec::new(),
+        trailers: HashMap::new(),
+    };
+    response.body = Some(response.decode_body());
+    Ok((response, interim))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+    use url::Url;
+
+    /// A server answering the headers with `interim`, if any, then `201`
+    async fn server(interim: Option<&'static str>) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (read, mut write) = stream.into_split();
+            let mut reader = BufReader::new(read);
+            let mut line = String::new();
+            while reader.read_line(&mut line).await.unwrap() > 2 {
+                line.clear();
+            }
+            if let Some(interim) = interim {
+                write.write_all(interim.as_bytes()).await.unwrap();
+            }
+            let mut body = [0; 4];
+            reader.read_exact(&mut body).await.unwrap();
+            write
+                .write_all(b"HTTP/1.1 201 Created\r\nContent-Length: 2\r\n\r\nok")
+                .await
+                .unwrap();
+        });
+        port
+    }
+
+    fn upload(port: u16) -> HttpRequest {
+        let url = Url::parse(&format!("http://127.0.0.1:{}/upload", port)).unwrap();
+        HttpRequest::new("POST".into(), url).body(Some(Body::Text("data".into())))
+    }
+
+    #[tokio::test]
+    async fn continue_is_honored() {
+        let port = server(Some("HTTP/1.1 100 Continue\r\n\r\n")).await;
+        let (response, interim) = send(upload(port), DEFAULT_WAIT).await.unwrap();
+        assert_eq!(interim, Interim::Continued);
+        assert_eq!(response.status, 201);
+        assert_eq!(response.body.as_deref(), Some("ok"));
+    }
+
+    #[tokio::test]
+    async fn silence_is_ignored() {
+        let port = server(None).await;
+        let (response, interim) = send(upload(port), Duration::from_millis(100))
+            .await
+            .unwrap();
+        assert_eq!(interim, Interim::Ignored);
+        assert_eq!(response.status, 201);
+    }
+}
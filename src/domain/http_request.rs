@@ -1,8 +1,69 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
 use url::Url;
-use anyhow::Result;
+use anyhow::{Context, Result};
+use http_body_util::BodyExt;
+use reqwest::multipart::{Form, Part};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use reqwest::{Client, Method as ReqwestMethod, Response};
 
+use super::auth::Auth;
+use super::decode::Decode;
+use super::encode::Encode;
+use super::normalize::Normalization;
+use crate::template::{self, Variables};
+
+/// Addresses pinned into the shared client, by host
+type Pinned = BTreeMap<String, Vec<SocketAddr>>;
+
+/// Client shared by every request so connections and DNS results are
+/// reused, with the addresses pinned into it so far
+static CLIENT: RwLock<Option<(Pinned, Client)>> = RwLock::new(None);
+
+fn client() -> Client {
+    if let Some((_, client)) = &*CLIENT.read().unwrap() {
+        return client.clone();
+    }
+    let mut shared = CLIENT.write().unwrap();
+    shared.get_or_insert_with(|| (Pinned::new(), Client::new())).1.clone()
+}
+
+/// Header names sent first, in this order (`--impersonate`)
+static HEADER_ORDER: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Send these headers first, in this order, then the rest by name; must run
+/// before the first request is sent
+pub fn set_header_order(names: Vec<String>) -> Result<()> {
+    HEADER_ORDER
+        .set(names)
+        .map_err(|_| anyhow::anyhow!("Header order already set"))
+}
+
+/// Pin pre-resolved addresses for `hosts` into the shared client. Hosts
+/// pinned by an earlier run in the process (`--api-versions`) keep their
+/// addresses, and the client is only rebuilt when a host is new.
+pub fn pin_resolved_hosts(hosts: &[(String, Vec<SocketAddr>)]) -> Result<()> {
+    let mut shared = CLIENT.write().unwrap();
+    let mut pinned = shared.as_ref().map(|(pinned, _)| pinned.clone()).unwrap_or_default();
+    let known = pinned.len();
+    for (host, addrs) in hosts {
+        pinned.entry(host.clone()).or_insert_with(|| addrs.clone());
+    }
+    if shared.is_some() && pinned.len() == known {
+        return Ok(());
+    }
+
+    let mut builder = Client::builder();
+    for (host, addrs) in &pinned {
+        builder = builder.resolve_to_addrs(host, addrs);
+    }
+    *shared = Some((pinned, builder.build()?));
+    Ok(())
+}
+
 /// HTTP request domain object
 #[derive(Debug, Clone)]
 pub struct HttpRequest {
@@ -10,6 +71,76 @@ pub struct HttpRequest {
     pub url: Url,
     pub headers: HashMap<String, String>,
     pub body: Option<Body>,
+    /// How to decode the response body (`DECODE`); by content type if unset
+    pub decode: Option<Decode>,
+    /// Placeholders left for run time, e.g. values captured by earlier tests
+    pub deferred: Option<RequestTemplate>,
+    /// Sent once for all identical requests marked `SHARE RESPONSE`
+    pub share: bool,
+}
+
+/// Request text whose placeholders could not be filled in while parsing
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestTemplate {
+    pub url: String,
+    /// `QUERY` parameters appended to the URL
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    /// `AUTH BASIC` user and password, encoded into `Authorization` once
+    /// rendered
+    pub basic: Option<(String, String)>,
+    pub body: Option<String>,
+    /// Binary format the rendered body is encoded to
+    pub encode: Option<Encode>,
+}
+
+impl RequestTemplate {
+    /// Expressions of the remaining placeholders, e.g. captured variable names
+    pub fn placeholders(&self) -> Vec<&str> {
+        let texts = std::iter::once(self.url.as_str())
+            .chain(self.query.iter().map(|(_, value)| value.as_str()))
+            .chain(self.headers.iter().map(|(_, value)| value.as_str()))
+            .chain(self.basic.iter().flat_map(|(user, password)| [user.as_str(), password.as_str()]))
+            .chain(self.body.as_deref());
+        texts.flat_map(template::placeholders).collect()
+    }
+
+    /// Render with `variables` and update `request` accordingly; fails on a
+    /// placeholder still unresolved, e.g. a variable nothing defines
+    pub fn apply(&self, request: &mut HttpRequest, variables: &Variables) -> Result<()> {
+        let url = template::render_request(&self.url, variables)?;
+        request.url = Url::parse(&url).with_context(|| format!("Invalid URL: {}", url))?;
+        let query = self
+            .query
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), template::render_request(value, variables)?)))
+            .collect::<Result<Vec<_>>>()?;
+        append_query(&mut request.url, &query);
+
+        for (key, value) in &self.headers {
+            request
+                .headers
+                .insert(key.clone(), template::render_request(value, variables)?);
+        }
+        if let Some((user, password)) = &self.basic {
+            let auth = Auth::Basic {
+                user: template::render_request(user, variables)?,
+                password: template::render_request(password, variables)?,
+            };
+            request
+                .headers
+                .extend(auth.header().map(|value| ("Authorization".to_string(), value)));
+        }
+        if let Some(body) = &self.body {
+            let text = template::render_request(body, variables)?;
+            request.body = Some(match &self.encode {
+                Some(encode) => encode.body(&text)?,
+                None => Body::Text(text),
+            });
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -19,6 +150,13 @@ pub struct HttpResponse {
     pub status: u16,
     pub headers: HashMap<String, String>,
     pub body: Option<String>,
+    /// Body exactly as received, before charset decoding
+    pub bytes: Vec<u8>,
+    /// Cookies from every Set-Cookie header, as name/value pairs
+    pub cookies: Vec<(String, String)>,
+    /// Trailer fields sent after the body, in a chunked HTTP/1.1 or an
+    /// HTTP/2 response (e.g. `grpc-status`)
+    pub trailers: HashMap<String, String>,
 }
 
 
@@ -27,6 +165,226 @@ pub struct HttpResponse {
 pub enum Body {
     Text(String),
     Json(serde_json::Value),
+    /// Already-encoded binary body (e.g. `BODY MSGPACK`)
+    Bytes(Vec<u8>),
+    /// `multipart/form-data` parts, in order (`MULTIPART`)
+    Multipart(Vec<FormPart>),
+    /// Produced while it is sent, never held in memory (`BODY STREAM`)
+    Stream(BodyStream),
+}
+
+/// Source of a `BODY STREAM` body
+#[derive(Debug, Clone, PartialEq)]
+pub enum BodyStream {
+    /// `BODY STREAM ./big.bin`: read from the file as it is sent
+    File(PathBuf),
+    /// `BODY STREAM ZEROS 500MB`: that many zero bytes
+    Zeros(u64),
+    /// `BODY STREAM RANDOM 500MB`: that many random bytes
+    Random(u64),
+}
+
+/// Bytes generated at a time for `ZEROS` and `RANDOM`
+const STREAM_CHUNK: u64 = 64 * 1024;
+
+impl BodyStream {
+    /// Size in bytes, sent as Content-Length
+    pub fn size(&self) -> Result<u64> {
+        match self {
+            BodyStream::File(path) => Ok(std::fs::metadata(path)
+                .with_context(|| format!("Failed to read upload {}", path.display()))?
+                .len()),
+            BodyStream::Zeros(size) | BodyStream::Random(size) => Ok(*size),
+        }
+    }
+
+    async fn into_reqwest(self) -> Result<reqwest::Body> {
+        let (size, random) = match self {
+            BodyStream::File(path) => {
+                let file = tokio::fs::File::open(&path)
+                    .await
+                    .with_context(|| format!("Failed to read upload {}", path.display()))?;
+                return Ok(reqwest::Body::from(file));
+            }
+            BodyStream::Zeros(size) => (size, false),
+            BodyStream::Random(size) => (size, true),
+        };
+
+        let mut rng = StdRng::from_entropy();
+        let chunks = (0..size.div_ceil(STREAM_CHUNK)).map(move |index| {
+            let mut chunk = vec![0; STREAM_CHUNK.min(size - index * STREAM_CHUNK) as usize];
+            if random {
+                rng.fill(&mut chunk[..]);
+            }
+            Ok::<_, std::io::Error>(chunk)
+        });
+        Ok(reqwest::Body::wrap_stream(futures_util::stream::iter(chunks)))
+    }
+}
+
+/// `./big.bin`, or `500.0 MiB of zeros`
+impl std::fmt::Display for BodyStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mebibytes = |size: u64| size as f64 / (1024.0 * 1024.0);
+        match self {
+            BodyStream::File(path) => write!(f, "{}", path.display()),
+            BodyStream::Zeros(size) => write!(f, "{:.1} MiB of zeros", mebibytes(*size)),
+            BodyStream::Random(size) => write!(f, "{:.1} MiB of random bytes", mebibytes(*size)),
+        }
+    }
+}
+
+/// One part of a `MULTIPART` body
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormPart {
+    /// `name = value`
+    Text { name: String, value: String },
+    /// `name file=./path`, read when the request is sent
+    File { name: String, path: PathBuf },
+}
+
+impl FormPart {
+    fn into_reqwest(self) -> Result<Part> {
+        Ok(match self {
+            FormPart::Text { value, .. } => Part::text(value),
+            FormPart::File { path, .. } => {
+                let bytes = std::fs::read(&path)
+                    .with_context(|| format!("Failed to read upload {}", path.display()))?;
+                let file_name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                Part::bytes(bytes)
+                    .file_name(file_name)
+                    .mime_str(mime_type(&path))?
+            }
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            FormPart::Text { name, .. } | FormPart::File { name, .. } => name,
+        }
+    }
+}
+
+/// Add `params` to the query string of `url`, percent-encoded
+pub fn append_query(url: &mut Url, params: &[(String, String)]) {
+    // An empty serializer would still leave a bare `?`
+    if params.is_empty() {
+        return;
+    }
+    url.query_pairs_mut().extend_pairs(params);
+}
+
+/// Content type of an uploaded file, by extension
+fn mime_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "csv" => "text/csv",
+        "txt" => "text/plain",
+        "xml" => "application/xml",
+        "zip" => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+impl HttpResponse {
+    /// Case-insensitive header lookup
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Case-insensitive trailer lookup
+    pub fn trailer(&self, name: &str) -> Option<&str> {
+        self.trailers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Bytes in the body as received (still compressed when the server
+    /// compressed it), whatever Content-Length claimed
+    pub fn size(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Body as a JSON value for path lookups, decoded with the request's
+    /// `DECODE`, else by Content-Type, else parsed as JSON text
+    pub fn decoded_body(&self) -> Option<serde_json::Value> {
+        let decode = self
+            .request
+            .as_ref()
+            .and_then(|request| request.decode.clone())
+            .or_else(|| Decode::for_content_type(self.header("content-type")?));
+
+        match decode {
+            Some(decode) => decode.decode(&self.bytes).ok(),
+            None => serde_json::from_str(self.body.as_ref()?).ok(),
+        }
+    }
+
+    /// Lowercased `charset` parameter of the Content-Type header
+    pub fn charset(&self) -> Option<String> {
+        self.header("content-type")?
+            .split(';')
+            .skip(1)
+            .filter_map(|param| param.split_once('='))
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case("charset"))
+            .map(|(_, value)| value.trim().trim_matches('"').to_ascii_lowercase())
+    }
+
+    /// Delay requested by a `Retry-After` header, given in seconds or as an
+    /// HTTP date
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        let value = self.header("retry-after")?.trim();
+
+        if let Ok(seconds) = value.parse::<u64>() {
+            return Some(std::time::Duration::from_secs(seconds));
+        }
+
+        let at = httpdate::parse_http_date(value).ok()?;
+        Some(
+            at.duration_since(std::time::SystemTime::now())
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Decode the raw body using the declared charset (UTF-8 by default),
+    /// replacing malformed sequences
+    pub(crate) fn decode_body(&self) -> String {
+        let encoding = self
+            .charset()
+            .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()))
+            .unwrap_or(encoding_rs::UTF_8);
+        let (text, _, _) = encoding.decode(&self.bytes);
+        text.into_owned()
+    }
+
+    /// Canonicalize a JSON body in place; non-JSON bodies are left untouched
+    pub fn normalize(&mut self, normalization: &Normalization) {
+        if normalization.is_noop() {
+            return;
+        }
+
+        if let Some(normalized) = self.body.as_deref().and_then(|b| normalization.apply_to_body(b)) {
+            self.body = Some(normalized);
+        }
+    }
 }
 
 impl HttpRequest {
@@ -36,6 +394,9 @@ impl HttpRequest {
             url,
             headers: HashMap::new(),
             body: None,
+            decode: None,
+            deferred: None,
+            share: false,
         }
     }
 
@@ -44,8 +405,15 @@ impl HttpRequest {
         self
     }
 
+    /// Set a header unless the request already has it (case-insensitive)
+    pub fn default_header(&mut self, name: &str, value: &str) {
+        if !self.headers.keys().any(|key| key.eq_ignore_ascii_case(name)) {
+            self.headers.insert(name.to_string(), value.to_string());
+        }
+    }
+
     pub async fn call_request(&self) -> Result<Response> {
-        let client = Client::new();
+        let client = client();
 
         // Method mapping (string → reqwest)
         let method = match self.method.as_str() {
@@ -62,7 +430,17 @@ impl HttpRequest {
         let mut req = client.request(method, self.url.as_str());
 
         // Headers
-        for (key, value) in &self.headers {
+        let mut headers: Vec<_> = self.headers.iter().collect();
+        if let Some(order) = HEADER_ORDER.get() {
+            let rank = |name: &str| {
+                order
+                    .iter()
+                    .position(|first| first.eq_ignore_ascii_case(name))
+                    .unwrap_or(order.len())
+            };
+            headers.sort_by_key(|(name, _)| (rank(name), name.to_ascii_lowercase()));
+        }
+        for (key, value) in headers {
             req = req.header(key, value);
         }
 
@@ -73,9 +451,28 @@ impl HttpRequest {
                     req = req.body(text.clone());
                 }
                 Body::Json(value) => {
-                    req = req
-                        .header("Content-Type", "application/json")
-                        .json(value);
+                    // Sets Content-Type unless a header above already did,
+                    // so it is never sent twice
+                    req = req.json(value);
+                }
+                Body::Bytes(bytes) => {
+                    req = req.body(bytes.clone());
+                }
+                Body::Multipart(parts) => {
+                    let mut form = Form::new();
+                    for part in parts.clone() {
+                        let name = part.name().to_string();
+                        form = form.part(name, part.into_reqwest()?);
+                    }
+                    // Sets Content-Type with the boundary
+                    req = req.multipart(form);
+                }
+                Body::Stream(stream) => {
+                    // Otherwise the body would go out chunked
+                    if !self.headers.keys().any(|key| key.eq_ignore_ascii_case("content-length")) {
+                        req = req.header("Content-Length", stream.size()?);
+                    }
+                    req = req.body(stream.clone().into_reqwest().await?);
                 }
             }
         }
@@ -95,16 +492,59 @@ impl HttpRequest {
             .iter()
             .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
             .collect::<HashMap<String, String>>();
-        let body = response.text().await?;
+        let cookies = response
+            .headers()
+            .get_all(reqwest::header::SET_COOKIE)
+            .iter()
+            .filter_map(|value| parse_set_cookie(value.to_str().ok()?))
+            .collect();
+        // Collected frame by frame: trailers arrive after the last data frame
+        let collected = reqwest::Body::from(response).collect().await?;
+        let trailers = collected
+            .trailers()
+            .map(|trailers| {
+                trailers
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let bytes = collected.to_bytes().to_vec();
 
         let duration = start.elapsed();
 
-        Ok(HttpResponse {
+        let mut response = HttpResponse {
             request: Some(self),
             duration,
             status,
             headers,
-            body: Some(body),
-        })
+            body: None,
+            bytes,
+            cookies,
+            trailers,
+        };
+        response.body = Some(response.decode_body());
+
+        Ok(response)
+    }
+}
+
+/// Name and value of a Set-Cookie header; a cookie expired with
+/// `Max-Age=0` gets an empty value
+fn parse_set_cookie(header: &str) -> Option<(String, String)> {
+    let mut parts = header.split(';');
+    let (name, value) = parts.next()?.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
     }
+
+    let expired = parts.any(|attribute| {
+        attribute
+            .trim()
+            .split_once('=')
+            .is_some_and(|(key, value)| key.eq_ignore_ascii_case("max-age") && value.trim() == "0")
+    });
+    let value = if expired { "" } else { value.trim() };
+    Some((name.to_string(), value.to_string()))
 }
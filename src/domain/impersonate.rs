@@ -0,0 +1,95 @@
+//! Client impersonation profiles (`--impersonate`).
+//!
+//! Bot detection and WAF rules look at which headers a client sends and in
+//! what order. A [`Profile`] adds the User-Agent, Accept and related headers
+//! a real client sends to every request that does not set them, and makes
+//! requests list their headers in that client's order.
+//!
+//! `Accept-Encoding` is left out: responses must stay readable by the
+//! assertions.
+
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Profile {
+    /// Desktop Chrome on Windows, as when navigating to a page
+    Chrome,
+    /// The curl command-line client
+    Curl,
+    /// Safari on an iPhone
+    Mobile,
+}
+
+const CHROME: &[(&str, &str)] = &[
+    (
+        "sec-ch-ua",
+        "\"Chromium\";v=\"124\", \"Google Chrome\";v=\"124\", \"Not-A.Brand\";v=\"99\"",
+    ),
+    ("sec-ch-ua-mobile", "?0"),
+    ("sec-ch-ua-platform", "\"Windows\""),
+    ("Upgrade-Insecure-Requests", "1"),
+    (
+        "User-Agent",
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36",
+    ),
+    (
+        "Accept",
+        "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8",
+    ),
+    ("Sec-Fetch-Site", "none"),
+    ("Sec-Fetch-Mode", "navigate"),
+    ("Sec-Fetch-User", "?1"),
+    ("Sec-Fetch-Dest", "document"),
+    ("Accept-Language", "en-US,en;q=0.9"),
+];
+
+const CURL: &[(&str, &str)] = &[("User-Agent", "curl/8.5.0"), ("Accept", "*/*")];
+
+const MOBILE: &[(&str, &str)] = &[
+    (
+        "Accept",
+        "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8",
+    ),
+    ("Sec-Fetch-Site", "none"),
+    ("Accept-Language", "en-US,en;q=0.9"),
+    ("Sec-Fetch-Mode", "navigate"),
+    (
+        "User-Agent",
+        "Mozilla/5.0 (iPhone; CPU iPhone OS 17_4 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Mobile/15E148 Safari/604.1",
+    ),
+    ("Sec-Fetch-Dest", "document"),
+];
+
+impl Profile {
+    /// Headers the client sends, in the order it sends them
+    pub fn headers(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Profile::Chrome => CHROME,
+            Profile::Curl => CURL,
+            Profile::Mobile => MOBILE,
+        }
+    }
+
+    /// Header names in the client's order; others follow them
+    pub fn header_order(self) -> Vec<String> {
+        self.headers()
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_profile_sets_user_agent_and_accept() {
+        for profile in Profile::value_variants() {
+            let names = profile.header_order();
+            assert!(names.iter().any(|name| name == "User-Agent"));
+            assert!(names.iter().any(|name| name == "Accept"));
+            assert!(!names.iter().any(|name| name == "Accept-Encoding"));
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! Kafka source for `KAFKA "<topic>" RECEIVES ...` assertions (behind the
+//! `kafka` feature).
+//!
+//! Every partition of the topic is read from where the previous fetch
+//! stopped, up to its end; the first time, from the first record timestamped
+//! at or after the test's request. Records are kept while a test that sent
+//! its request earlier may still look at them, and only those timestamped at
+//! or after the test's request are returned.
+
+use chrono::{DateTime, Utc};
+use rskafka::client::partition::{OffsetAt, PartitionClient, UnknownTopicHandling};
+use rskafka::client::{Client, ClientBuilder};
+use rskafka::record::RecordAndOffset;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use tokio::sync::{Mutex, OnceCell};
+
+use super::source::{ExternalSource, Fetch};
+
+/// Largest batch requested per partition and round trip
+const MAX_FETCH_BYTES: i32 = 4 * 1024 * 1024;
+
+/// How long the broker may wait for records before answering a fetch
+const MAX_WAIT_MS: i32 = 100;
+
+#[derive(Debug, Default)]
+struct Topic {
+    /// Next offset to read, by partition
+    offsets: HashMap<i32, i64>,
+    /// Records read so far, with their timestamps
+    records: Vec<(DateTime<Utc>, Value)>,
+    /// Time from which `records` holds every record of the topic
+    from: Option<DateTime<Utc>>,
+}
+
+pub struct Kafka {
+    brokers: Vec<String>,
+    client: OnceCell<Client>,
+    topics: Mutex<HashMap<String, Topic>>,
+    /// When the requests of the tests watching each topic were sent
+    watchers: std::sync::Mutex<HashMap<String, Vec<DateTime<Utc>>>>,
+}
+
+impl std::fmt::Debug for Kafka {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Kafka")
+            .field("brokers", &self.brokers)
+            .finish()
+    }
+}
+
+impl Kafka {
+    pub fn new(brokers: Vec<String>) -> Self {
+        Self {
+            brokers,
+            client: OnceCell::new(),
+            topics: Mutex::new(HashMap::new()),
+            watchers: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The earliest request of a test still watching `name`
+    fn oldest_watched(&self, name: &str) -> Option<DateTime<Utc>> {
+        let watchers = self.watchers.lock().unwrap();
+        watchers.get(name)?.iter().min().copied()
+    }
+
+    async fn client(&self) -> anyhow::Result<&Client> {
+        self.client
+            .get_or_try_init(|| ClientBuilder::new(self.brokers.clone()).build())
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Read the records added to `name` since the last call, or from
+    /// `topic.from` on for partitions not read yet
+    async fn read_new(&self, name: &str, topic: &mut Topic) -> anyhow::Result<()> {
+        let client = self.client().await?;
+        let partitions = client
+            .list_topics()
+            .await?
+            .into_iter()
+            .find(|t| t.name == name)
+            .map(|t| t.partitions)
+            .ok_or_else(|| anyhow::anyhow!("Kafka topic '{}' not found", name))?;
+
+        for partition in partitions {
+            let partition_client = client
+                .partition_client(name.to_string(), partition, UnknownTopicHandling::Error)
+                .await?;
+            let mut offset = match (topic.offsets.get(&partition), topic.from) {
+                (Some(offset), _) => *offset,
+                (None, Some(from)) => first_offset(&partition_client, from).await?,
+                (None, None) => partition_client.get_offset(OffsetAt::Latest).await?,
+            };
+
+            loop {
+                let (records, high_watermark) = partition_client
+                    .fetch_records(offset, 1..MAX_FETCH_BYTES, MAX_WAIT_MS)
+                    .await?;
+                let Some(last) = records.last() else {
+                    break;
+                };
+                offset = last.offset + 1;
+                topic.records.extend(records.into_iter().map(|r| record(partition, r)));
+                if offset >= high_watermark {
+                    break;
+                }
+            }
+            topic.offsets.insert(partition, offset);
+        }
+
+        Ok(())
+    }
+}
+
+/// Offset of the first record of the partition timestamped at or after
+/// `from`, or its end when there is none yet
+async fn first_offset(partition: &PartitionClient, from: DateTime<Utc>) -> anyhow::Result<i64> {
+    let offset = partition.get_offset(OffsetAt::Timestamp(from)).await?;
+    if offset < 0 {
+        return Ok(partition.get_offset(OffsetAt::Latest).await?);
+    }
+    Ok(offset)
+}
+
+/// `record` of `partition` with its timestamp, as the JSON assertions see
+fn record(partition: i32, record: RecordAndOffset) -> (DateTime<Utc>, Value) {
+    let text = |bytes: Option<Vec<u8>>| bytes.map(|b| String::from_utf8_lossy(&b).into_owned());
+    let headers: Map<String, Value> = record
+        .record
+        .headers
+        .into_iter()
+        .map(|(k, v)| (k, Value::String(String::from_utf8_lossy(&v).into_owned())))
+        .collect();
+    (
+        record.record.timestamp,
+        json!({
+            "key": text(record.record.key),
+            "body": text(record.record.value),
+            "headers": headers,
+            "partition": partition,
+            "offset": record.offset,
+        }),
+    )
+}
+
+impl ExternalSource for Kafka {
+    fn fetch<'a>(&'a self, key: &'a str, since: DateTime<Utc>) -> Fetch<'a> {
+        Box::pin(async move {
+            let oldest = self.oldest_watched(key).map_or(since, |oldest| oldest.min(since));
+            let mut topics = self.topics.lock().await;
+            let topic = topics.entry(key.to_string()).or_default();
+            // Records from `since` on were dropped or never read: seek again
+            if topic.from.is_none_or(|from| since < from) {
+                *topic = Topic {
+                    from: Some(oldest),
+                    ..Topic::default()
+                };
+            }
+            self.read_new(key, topic).await?;
+
+            // No test watching the topic looks further back than `oldest`
+            topic.records.retain(|(timestamp, _)| *timestamp >= oldest);
+            topic.from = topic.from.max(Some(oldest));
+
+            Ok(topic
+                .records
+                .iter()
+                .filter(|(timestamp, _)| *timestamp >= since)
+                .map(|(_, record)| record.clone())
+                .collect())
+        })
+    }
+
+    fn watch(&self, key: &str, since: DateTime<Utc>) {
+        let mut watchers = self.watchers.lock().unwrap();
+        watchers.entry(key.to_string()).or_default().push(since);
+    }
+
+    fn release(&self, key: &str, since: DateTime<Utc>) {
+        let mut watchers = self.watchers.lock().unwrap();
+        if let Some(sinces) = watchers.get_mut(key) {
+            if let Some(index) = sinces.iter().position(|s| *s == since) {
+                sinces.swap_remove(index);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::source::Watch;
+
+    #[test]
+    fn oldest_watched_follows_watches() {
+        let kafka = Kafka::new(Vec::new());
+        let at = |secs| DateTime::from_timestamp(secs, 0).unwrap();
+
+        let first = Watch::new(&kafka, "orders", at(10));
+        let second = Watch::new(&kafka, "orders", at(20));
+        assert_eq!(kafka.oldest_watched("orders"), Some(at(10)));
+        assert_eq!(kafka.oldest_watched("payments"), None);
+
+        drop(first);
+        assert_eq!(kafka.oldest_watched("orders"), Some(at(20)));
+        drop(second);
+        assert_eq!(kafka.oldest_watched("orders"), None);
+    }
+}
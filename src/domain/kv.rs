@@ -0,0 +1,50 @@
+//! Redis source for `REDIS` key assertions (behind the `redis` feature).
+//!
+//! ```text
+//! EXPECT REDIS "redis://localhost:6379" KEY "session:{{user_id}}" EXISTS
+//! EXPECT REDIS "redis://localhost:6379" KEY "user:{{user_id}}:plan" == "pro" WITHIN 2s
+//! ```
+//!
+//! A fetch lists the key as a single item, `{"key", "type", "value"}`, or
+//! nothing when it does not exist. Only string keys have a value; it keeps
+//! its JSON type when it parses as JSON, like DB columns.
+
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+use super::source::{text_value, ExternalSource, Fetch};
+
+#[derive(Debug, Clone)]
+pub struct Redis {
+    /// Connection URL, e.g. `redis://localhost:6379/0`
+    pub url: String,
+}
+
+impl ExternalSource for Redis {
+    fn fetch<'a>(&'a self, key: &'a str, _since: DateTime<Utc>) -> Fetch<'a> {
+        Box::pin(async move {
+            let client = redis::Client::open(self.url.as_str())?;
+            let mut connection = client.get_multiplexed_async_connection().await?;
+
+            let kind: String = redis::cmd("TYPE")
+                .arg(key)
+                .query_async(&mut connection)
+                .await?;
+            if kind == "none" {
+                return Ok(Vec::new());
+            }
+
+            let value = if kind == "string" {
+                let text: Option<String> = redis::cmd("GET")
+                    .arg(key)
+                    .query_async(&mut connection)
+                    .await?;
+                text_value(text.as_deref())
+            } else {
+                Value::Null
+            };
+
+            Ok(vec![json!({ "key": key, "type": kind, "value": value })])
+        })
+    }
+}
@@ -0,0 +1,67 @@
+//! Where tests and assertions are written.
+//!
+//! The parser records the lines of each `TEST` block and of each assertion
+//! in it; the runner adds the file once it knows it. Failures carry the
+//! location of the assertion that failed, so reports and editors can link
+//! straight to it as `path/to/file.ax:12`.
+
+use std::fmt;
+use std::path::PathBuf;
+
+/// Lines `start..=end` (1-based) of an `.ax` file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Location {
+    /// Empty until the runner sets it: the parser only sees the contents
+    pub file: PathBuf,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Location {
+    /// Lines `start..=end` of a file not known yet
+    pub fn lines(start: usize, end: usize) -> Self {
+        Location {
+            file: PathBuf::new(),
+            start,
+            end,
+        }
+    }
+
+    /// Just `line` of the same file
+    pub fn line(&self, line: usize) -> Self {
+        Location {
+            file: self.file.clone(),
+            start: line,
+            end: line,
+        }
+    }
+}
+
+/// `file.ax:12`, or `file.ax:12-20` for several lines
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.file.as_os_str().is_empty() {
+            write!(f, "line {}", self.start)?;
+        } else {
+            write!(f, "{}:{}", self.file.display(), self.start)?;
+        }
+        if self.end > self.start {
+            write!(f, "-{}", self.end)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_names_file_and_lines() {
+        let mut block = Location::lines(12, 20);
+        assert_eq!(block.to_string(), "line 12-20");
+        block.file = PathBuf::from("api/users.ax");
+        assert_eq!(block.to_string(), "api/users.ax:12-20");
+        assert_eq!(block.line(15).to_string(), "api/users.ax:15");
+    }
+}
@@ -1,8 +1,37 @@
 pub mod http_request;
 pub mod test_case;
 pub mod assertion;
+pub mod auth;
 pub mod renderer;
+pub mod normalize;
+pub mod chaos;
+pub mod circuit;
+pub mod confirm;
+pub mod context;
+pub mod decode;
+pub mod encode;
+pub mod expect_continue;
+pub mod impersonate;
+pub mod location;
+pub mod share;
+pub mod signing;
+pub mod source;
+pub mod token;
+#[cfg(feature = "db")]
+pub mod database;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "amqp")]
+pub mod amqp;
+#[cfg(feature = "redis")]
+pub mod kv;
+pub mod webhook;
 
-pub use assertion::{Assertion, AssertionFailure};
+pub use assertion::{Assertion, AssertionFailure, Sampling};
 pub use test_case::{TestCase, TestResult};
 pub use renderer::Renderer;
+pub use normalize::Normalization;
+pub use chaos::Chaos;
+pub use circuit::CircuitBreaker;
+pub use context::ExecutionContext;
+pub use location::Location;
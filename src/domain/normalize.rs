@@ -0,0 +1,171 @@
+//! JSON response normalization.
+//!
+//! Responses often contain volatile fields (timestamps, generated ids) and
+//! unordered objects that make otherwise identical outputs differ from run to
+//! run. A [`Normalization`] canonicalizes a JSON body by optionally sorting
+//! object keys and removing configured ignore paths, so printed responses and
+//! body comparisons stay stable. Keys keep the order the server sent them in
+//! unless sorted.
+//!
+//! Ignore paths use the same dotted syntax as assertions, e.g.
+//! `body.updated_at`, `body.meta.request_id`, `body.items[0].id`, or
+//! `body.items[*].id` for every element of an array.
+
+use serde_json::{Map, Value};
+
+use super::assertion::{body_path, segments, Segment};
+
+#[derive(Debug, Clone, Default)]
+pub struct Normalization {
+    pub sort_keys: bool,
+    pub ignore_paths: Vec<String>,
+}
+
+impl Normalization {
+    pub fn new(sort_keys: bool, ignore_paths: Vec<String>) -> Self {
+        Self {
+            sort_keys,
+            ignore_paths,
+        }
+    }
+
+    /// Whether applying this normalization would leave bodies untouched
+    pub fn is_noop(&self) -> bool {
+        !self.sort_keys && self.ignore_paths.is_empty()
+    }
+
+    /// Normalize a parsed JSON body in place
+    pub fn apply(&self, json: &mut Value) {
+        for path in &self.ignore_paths {
+            remove_path(json, path);
+        }
+
+        if self.sort_keys {
+            sort_keys(json);
+        }
+    }
+
+    /// Normalize a raw body, returning `None` when it is not JSON
+    pub fn apply_to_body(&self, body: &str) -> Option<String> {
+        let mut json: Value = serde_json::from_str(body).ok()?;
+        self.apply(&mut json);
+        serde_json::to_string_pretty(&json).ok()
+    }
+}
+
+/// Remove the values at a `body.` path, if present
+fn remove_path(json: &mut Value, path: &str) {
+    if let Some(segments) = body_path(path).and_then(segments) {
+        remove_at(json, &segments);
+    }
+}
+
+fn remove_at(json: &mut Value, segments: &[Segment]) {
+    let Some((first, rest)) = segments.split_first() else {
+        return;
+    };
+
+    if rest.is_empty() {
+        match (json, first) {
+            (Value::Object(map), Segment::Key(key)) => {
+                map.shift_remove(*key);
+            }
+            (Value::Array(items), Segment::Index(index)) if *index < items.len() => {
+                items.remove(*index);
+            }
+            (Value::Array(items), Segment::Each) => items.clear(),
+            _ => {}
+        }
+        return;
+    }
+
+    match first {
+        Segment::Key(key) => {
+            if let Some(next) = json.get_mut(*key) {
+                remove_at(next, rest);
+            }
+        }
+        Segment::Index(index) => {
+            if let Some(next) = json.get_mut(*index) {
+                remove_at(next, rest);
+            }
+        }
+        Segment::Each => {
+            if let Value::Array(items) = json {
+                for item in items {
+                    remove_at(item, rest);
+                }
+            }
+        }
+    }
+}
+
+/// Sort the keys of every object in `json`, however deeply nested
+pub(crate) fn sort_keys(json: &mut Value) {
+    match json {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = std::mem::take(map).into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+            let mut sorted = Map::new();
+            for (key, mut value) in entries {
+                sort_keys(&mut value);
+                sorted.insert(key, value);
+            }
+            *map = sorted;
+        }
+        Value::Array(items) => items.iter_mut().for_each(sort_keys),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn apply_removes_ignore_paths() {
+        let normalization = Normalization::new(
+            false,
+            vec!["body.updated_at".to_string(), "body.meta.id".to_string()],
+        );
+        let mut body = json!({"name": "a", "updated_at": "now", "meta": {"id": 1, "v": 2}});
+        normalization.apply(&mut body);
+        assert_eq!(body, json!({"name": "a", "meta": {"v": 2}}));
+    }
+
+    #[test]
+    fn apply_removes_array_paths() {
+        let normalization = Normalization::new(
+            false,
+            vec!["body.items[*].id".to_string(), "body.pages[0]".to_string()],
+        );
+        let mut body = json!({"items": [{"id": 1, "n": "a"}, {"id": 2}], "pages": [1, 2]});
+        normalization.apply(&mut body);
+        assert_eq!(body, json!({"items": [{"n": "a"}, {}], "pages": [2]}));
+    }
+
+    #[test]
+    fn apply_to_body_keeps_key_order_unless_sorting() {
+        let body = r#"{"b": 1, "updated_at": "now", "a": 2}"#;
+        let ignoring = Normalization::new(false, vec!["body.updated_at".to_string()]);
+        assert_eq!(
+            ignoring.apply_to_body(body).unwrap(),
+            "{\n  \"b\": 1,\n  \"a\": 2\n}"
+        );
+    }
+
+    #[test]
+    fn apply_to_body_sorts_keys() {
+        let normalization = Normalization::new(true, vec![]);
+        let body = normalization.apply_to_body(r#"{"b": 1, "a": {"d": 2, "c": 3}}"#).unwrap();
+        assert_eq!(body, "{\n  \"a\": {\n    \"c\": 3,\n    \"d\": 2\n  },\n  \"b\": 1\n}");
+    }
+
+    #[test]
+    fn apply_to_body_ignores_non_json() {
+        let normalization = Normalization::new(true, vec![]);
+        assert_eq!(normalization.apply_to_body("plain text"), None);
+    }
+}
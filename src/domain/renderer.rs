@@ -1,16 +1,29 @@
 use crate::domain::TestCase;
 use owo_colors::OwoColorize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 
 pub trait Renderer {
-    fn start(&self, total: usize) {
+    /// `total` is unknown when files are streamed (`--stream`)
+    fn start(&self, total: Option<usize>) {
         println!("\n{}", "Axotly — API tests".bold());
-        println!("Running {} tests...", total);
+        match total {
+            Some(total) => println!("Running {} tests...", total),
+            None => println!("Running tests..."),
+        }
     }
 
     fn test(&self, test: &TestCase, file: Option<&PathBuf>);
 
+    /// A test block of `file` left out of the run because it does not parse
+    fn skipped_block(&self, _file: &Path, _error: &anyhow::Error) {}
+
     fn summary(&self, tests: &[TestCase], total_duration: &Duration );
+
+    /// Whether machine-readable output goes to stdout, so the run's other
+    /// messages (banners, file headers, warnings) must go to stderr
+    fn owns_stdout(&self) -> bool {
+        false
+    }
 }
@@ -0,0 +1,121 @@
+//! Responses shared by identical read-only requests (`SHARE RESPONSE`).
+//!
+//! Tests marked `SHARE RESPONSE` whose requests are identical (same method,
+//! URL, headers and body once placeholders are filled in) send a single
+//! physical request between them: the first one sends it, the others wait
+//! for and assert against its response, or its error. This spares fragile
+//! shared environments a burst of duplicate reads. Since the response is sent
+//! only once, sharing tests cannot RETRY, REPEAT or POLL.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+
+use super::http_request::{HttpRequest, HttpResponse};
+
+/// Outcome of a shared request; errors are kept as text to be cloned
+type Shared = Arc<OnceCell<Result<HttpResponse, String>>>;
+
+/// Responses of the shared requests sent so far in a run, by fingerprint
+#[derive(Debug, Default)]
+pub struct SharedResponses {
+    responses: Mutex<HashMap<String, Shared>>,
+}
+
+impl SharedResponses {
+    /// The response to `request`, sent unless an identical one already was
+    pub async fn send(&self, request: &HttpRequest) -> Result<HttpResponse> {
+        let shared = Arc::clone(
+            self.responses
+                .lock()
+                .unwrap()
+                .entry(fingerprint(request))
+                .or_default(),
+        );
+        let outcome = shared
+            .get_or_init(|| async {
+                request
+                    .clone()
+                    .send()
+                    .await
+                    .map_err(|error| format!("{:#}", error))
+            })
+            .await;
+        outcome.clone().map_err(anyhow::Error::msg)
+    }
+}
+
+/// Method, URL, headers (sorted) and body of `request`
+fn fingerprint(request: &HttpRequest) -> String {
+    let mut headers: Vec<_> = request
+        .headers
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name.to_ascii_lowercase(), value))
+        .collect();
+    headers.sort();
+    format!(
+        "{} {}\n{}\n\n{:?}",
+        request.method,
+        request.url,
+        headers.join("\n"),
+        request.body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use url::Url;
+
+    #[test]
+    fn fingerprint_ignores_header_order_and_case() {
+        let url = Url::parse("https://example.com/users").unwrap();
+        let mut first = HttpRequest::new("GET".into(), url.clone());
+        first
+            .headers
+            .insert("Accept".into(), "application/json".into());
+        first.headers.insert("X-Tenant".into(), "a".into());
+        let mut second = HttpRequest::new("GET".into(), url);
+        second.headers.insert("x-tenant".into(), "a".into());
+        second
+            .headers
+            .insert("accept".into(), "application/json".into());
+        assert_eq!(fingerprint(&first), fingerprint(&second));
+
+        second.headers.insert("x-tenant".into(), "b".into());
+        assert_ne!(fingerprint(&first), fingerprint(&second));
+    }
+
+    #[tokio::test]
+    async fn identical_requests_are_sent_once() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&hits);
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                counted.fetch_add(1, Ordering::SeqCst);
+                let mut head = [0; 1024];
+                let _ = stream.read(&mut head).await;
+                let _ = stream
+                    .write_all(
+                        b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+                    )
+                    .await;
+            }
+        });
+
+        let url = Url::parse(&format!("http://127.0.0.1:{}/catalog", port)).unwrap();
+        let request = HttpRequest::new("GET".into(), url);
+        let shared = SharedResponses::default();
+        let (first, second) = tokio::join!(shared.send(&request), shared.send(&request));
+        assert_eq!(first.unwrap().body.as_deref(), Some("ok"));
+        assert_eq!(second.unwrap().status, 200);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+}
@@ -1,6 +1,29 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
-use super::http_request::{HttpRequest, HttpResponse};
-use crate::domain::{Assertion, AssertionFailure};
+use tokio::sync::oneshot;
+use super::context::ExecutionContext;
+use super::http_request::{Body, BodyStream, FormPart, HttpRequest, HttpResponse};
+use super::decode::Decode;
+use super::encode::Encode;
+use super::expect_continue;
+use super::source;
+use crate::domain::assertion::{resolve_path, Shown, Value};
+use crate::domain::{Assertion, AssertionFailure, Location, Sampling};
+use crate::template::{self, Variables};
+
+/// Upper bound on how long a rate-limit check waits for Retry-After
+const MAX_RECOVERY_WAIT: Duration = Duration::from_secs(60);
+
+/// Path of the failure reported for a request held back at the `--confirm`
+/// prompt; the test is then marked not run
+const DECLINED: &str = "confirm";
+
+/// Upper bound on how long `RETRY` waits for Retry-After before an attempt
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Pause between snapshots of an external source (e.g. `MAILBOX`)
+const SOURCE_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
 /// Result of executing a test case
 #[derive(Debug)]
@@ -12,6 +35,26 @@ pub enum TestResult {
         duration: Duration,
         errors: Vec<AssertionFailure>,
     },
+    /// Cancelled before completing (e.g. run timeout)
+    NotRun {
+        reason: String,
+    },
+    /// Marked `SKIP` in the file; never sent
+    Skipped {
+        reason: Option<String>,
+    },
+}
+
+impl TestResult {
+    /// Failed on `MINOR` assertions only: reported, but the run still passes
+    pub fn is_minor_failure(&self) -> bool {
+        match self {
+            TestResult::Failed { errors, .. } => {
+                !errors.is_empty() && errors.iter().all(|error| error.minor)
+            }
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -21,39 +64,690 @@ pub struct TestCase {
     pub response: Option<HttpResponse>,
     pub assertions: Vec<Assertion>,
     pub result: Option<TestResult>,
+    /// Where `axotly fixtures pull` stores this test's response body
+    pub fixture: Option<PathBuf>,
+    /// Notes about how the test was executed (e.g. injected chaos)
+    pub annotations: Vec<String>,
+    /// Response values stored for later tests (`CAPTURE name = path`)
+    pub captures: Vec<Capture>,
+    /// Follow-up requests of a multi-step test, sent in order after `request`
+    pub steps: Vec<Step>,
+    /// Set by `WAIT` after `request`: pause before checking its expectations
+    pub settle: Option<Duration>,
+    /// Labels for grouping and filtering (`TAGS smoke, regression`)
+    pub tags: Vec<String>,
+    /// Set by `SKIP`, with its optional reason: parsed but never run
+    pub skip: Option<Option<String>>,
+    /// Set by `ONLY`: when any test has it, only those tests run
+    pub only: bool,
+    /// Set by `REPEAT n`: how many times the whole test runs (at least 1)
+    pub repeat: usize,
+    /// Outcome of each run of a repeated test, in order
+    pub iterations: Vec<Iteration>,
+    /// Set by `RETRY n`: re-attempt a failing run up to `n` more times
+    pub retry: Option<Retry>,
+    /// Attempts made across all runs; above `repeat` when retries happened
+    pub attempts: usize,
+    /// Extra requests and waits across all runs
+    pub pacing: Pacing,
+    /// Dataset this test is a row of (`WITH DATA`)
+    pub dataset: Option<PathBuf>,
+    /// When the test started and stopped running; unset until it runs
+    pub span: Option<(std::time::Instant, std::time::Instant)>,
+    /// Lines of the `TEST` block, and its file once the runner sets it
+    pub location: Option<Location>,
+    /// Line of each of `assertions`, in order; empty when not parsed
+    pub assertion_lines: Vec<usize>,
+    /// `--check` warning codes silenced by `ALLOW W001, W002`
+    pub allow: Vec<String>,
+}
+
+/// What a test spent beyond sending its requests once: responses sampled
+/// again (polls, rate-limit probes) and time slept between them, between
+/// attempts, and for Retry-After
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Pacing {
+    pub polls: usize,
+    pub waited: Duration,
+}
+
+impl Pacing {
+    fn add(&mut self, other: Pacing) {
+        self.polls += other.polls;
+        self.waited += other.waited;
+    }
+}
+
+/// One run of a test marked `REPEAT`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Iteration {
+    pub passed: bool,
+    pub duration: Duration,
+}
+
+/// `RETRY n [DELAY d]`: how often and how patiently a failing run is retried.
+/// A 429 or 503 answered with `Retry-After` stretches the delay to what the
+/// server asked for, up to a minute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Retry {
+    pub retries: usize,
+    pub delay: Option<Duration>,
+}
+
+/// One follow-up request of a multi-step test, with its own expectations
+#[derive(Debug)]
+pub struct Step {
+    pub request: HttpRequest,
+    pub response: Option<HttpResponse>,
+    pub assertions: Vec<Assertion>,
+    /// Line of each of `assertions`, in order; empty when not parsed
+    pub assertion_lines: Vec<usize>,
+    pub captures: Vec<Capture>,
+    /// Set by `WAIT` before `request`: pause before sending it
+    pub delay: Option<Duration>,
+    /// Set by `WAIT` after `request`: pause before checking its expectations
+    pub settle: Option<Duration>,
+}
+
+/// `CAPTURE name = path`: store the value at `path` as `{{name}}`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capture {
+    pub name: String,
+    pub path: String,
 }
 
 impl TestCase {
-    pub async fn run(mut self) -> TestCase {
+    /// Resolve file references (fixtures, data files, descriptors, uploads)
+    /// relative to `base_dir`
+    pub fn resolve_paths(&mut self, base_dir: &Path) {
+        let step_assertions = self.steps.iter_mut().flat_map(|step| step.assertions.iter_mut());
+        for assertion in self.assertions.iter_mut().chain(step_assertions) {
+            assertion.resolve_paths(base_dir);
+        }
+
+        let step_requests = self.steps.iter_mut().map(|step| &mut step.request);
+        for request in std::iter::once(&mut self.request).chain(step_requests) {
+            if let Some(decode) = &mut request.decode {
+                decode.resolve_paths(base_dir);
+            }
+            if let Some(encode) = request.deferred.as_mut().and_then(|d| d.encode.as_mut()) {
+                encode.resolve_paths(base_dir);
+            }
+            if let Some(Body::Multipart(parts)) = &mut request.body {
+                for part in parts {
+                    if let FormPart::File { path, .. } = part {
+                        if path.is_relative() {
+                            *path = base_dir.join(&*path);
+                        }
+                    }
+                }
+            }
+            if let Some(Body::Stream(BodyStream::File(path))) = &mut request.body {
+                if path.is_relative() {
+                    *path = base_dir.join(&*path);
+                }
+            }
+        }
+
+        for path in [&mut self.fixture, &mut self.dataset].into_iter().flatten() {
+            if path.is_relative() {
+                *path = base_dir.join(&*path);
+            }
+        }
+    }
+
+    /// Record that the test is written in `file`
+    pub fn locate(&mut self, file: &Path) {
+        if let Some(location) = &mut self.location {
+            location.file = file.to_path_buf();
+        }
+    }
+
+    /// Retries, polls and waits the test went through, for renderers to
+    /// show next to its duration, e.g. `["3 attempts", "waited 2s"]`
+    pub fn execution_notes(&self) -> Vec<String> {
+        let mut notes = Vec::new();
+        if self.attempts > self.repeat.max(1) {
+            notes.push(format!("{} attempts", self.attempts));
+        }
+        match self.pacing.polls {
+            0 => {}
+            1 => notes.push("1 poll".to_string()),
+            polls => notes.push(format!("{} polls", polls)),
+        }
+        if !self.pacing.waited.is_zero() {
+            notes.push(format!("waited {:?}", self.pacing.waited));
+        }
+        notes
+    }
+
+    /// Move the recorded lines `by` lines down, for a test parsed from a
+    /// part of its file
+    pub fn offset_lines(&mut self, by: usize) {
+        self.map_lines(|line| line + by);
+    }
+
+    /// Replace each recorded line with `map(line)`
+    pub fn map_lines(&mut self, map: impl Fn(usize) -> usize) {
+        if let Some(location) = &mut self.location {
+            location.start = map(location.start);
+            location.end = map(location.end);
+        }
+        let step_lines = self.steps.iter_mut().flat_map(|step| step.assertion_lines.iter_mut());
+        for line in self.assertion_lines.iter_mut().chain(step_lines) {
+            *line = map(*line);
+        }
+    }
+
+    /// Files besides its `.ax` file the test reads when it runs: its dataset,
+    /// `EQUALS FILE` fixtures, protobuf descriptors and uploads
+    pub fn inputs(&self) -> Vec<&Path> {
+        let mut inputs: Vec<&Path> = self.dataset.iter().map(PathBuf::as_path).collect();
+
+        let step_assertions = self.steps.iter().flat_map(|step| step.assertions.iter());
+        inputs.extend(
+            self.assertions
+                .iter()
+                .chain(step_assertions)
+                .flat_map(Assertion::fixture_files),
+        );
+
+        for request in self.requests() {
+            if let Some(Decode::Protobuf { descriptor, .. }) = &request.decode {
+                inputs.push(descriptor);
+            }
+            let encode = request.deferred.as_ref().and_then(|d| d.encode.as_ref());
+            if let Some(Encode::Protobuf { descriptor, .. }) = encode {
+                inputs.push(descriptor);
+            }
+            if let Some(Body::Multipart(parts)) = &request.body {
+                inputs.extend(parts.iter().filter_map(|part| match part {
+                    FormPart::File { path, .. } => Some(path.as_path()),
+                    FormPart::Text { .. } => None,
+                }));
+            }
+            if let Some(Body::Stream(BodyStream::File(path))) = &request.body {
+                inputs.push(path);
+            }
+        }
+
+        inputs
+    }
+
+    /// Whether any assertion of the test or its steps is `SENSITIVE`. Then
+    /// no failure of the test shows response values as they are, and
+    /// renderers leave its responses out.
+    pub fn is_sensitive(&self) -> bool {
+        let step_assertions = self.steps.iter().flat_map(|step| step.assertions.iter());
+        self.assertions
+            .iter()
+            .chain(step_assertions)
+            .any(Assertion::is_sensitive)
+    }
+
+    /// Whether the test is tagged `tag` (case-insensitive)
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Every request of the test, in the order they are sent
+    pub fn requests(&self) -> impl Iterator<Item = &HttpRequest> {
+        std::iter::once(&self.request).chain(self.steps.iter().map(|step| &step.request))
+    }
+
+    /// Every capture of the test, including those of its steps
+    pub fn all_captures(&self) -> impl Iterator<Item = &Capture> {
+        self.captures
+            .iter()
+            .chain(self.steps.iter().flat_map(|step| step.captures.iter()))
+    }
+
+    /// Collect `count` responses to the request, starting with `first`
+    async fn poll(
+        request: &Resend<'_>,
+        first: &HttpResponse,
+        count: usize,
+        interval: Option<Duration>,
+        pacing: &mut Pacing,
+        context: &ExecutionContext,
+    ) -> Result<Vec<HttpResponse>, AssertionFailure> {
+        let mut responses = vec![first.clone()];
+
+        while responses.len() < count {
+            if let Some(interval) = interval {
+                tokio::time::sleep(interval).await;
+                pacing.waited += interval;
+            }
+            pacing.polls += 1;
+
+            let number = responses.len() + 1;
+            let response = send(&request()?, context, |error| {
+                format!("Poll {} failed: {}", number, error)
+            })
+            .await?;
+            responses.push(response);
+        }
+
+        Ok(responses)
+    }
+
+    /// Send the request every `interval`, starting with `first`, until
+    /// `assertion` holds on the latest response or `timeout` would pass
+    async fn poll_until(
+        request: &Resend<'_>,
+        first: &HttpResponse,
+        assertion: &Assertion,
+        interval: Duration,
+        timeout: Duration,
+        pacing: &mut Pacing,
+        context: &ExecutionContext,
+    ) -> Result<Vec<HttpResponse>, AssertionFailure> {
         let start = std::time::Instant::now();
+        let mut responses = vec![first.clone()];
 
-        let response = match self.request.clone().send().await {
-            Ok(res) => res,
-            Err(error) => {
-                self.result = Some(TestResult::Failed {
-                    duration: start.elapsed(),
-                    errors: vec![AssertionFailure {
-                        path: "request".into(),
-                        expected: None,
-                        actual: None,
-                        message: error.to_string(),
-                    }],
-                });
-                return self;
+        while assertion.check(responses.last().unwrap()).is_err()
+            && start.elapsed() + interval <= timeout
+        {
+            tokio::time::sleep(interval).await;
+            pacing.waited += interval;
+            pacing.polls += 1;
+            responses.push(Self::send_sample(&request()?, responses.len() + 1, context).await?);
+        }
+
+        Ok(responses)
+    }
+
+    /// Send the request until the server answers 429 (at most `max` times).
+    /// With `recover`, wait for Retry-After (capped) and send once more.
+    async fn until_rate_limited(
+        request: &Resend<'_>,
+        max: usize,
+        interval: Option<Duration>,
+        recover: bool,
+        pacing: &mut Pacing,
+        context: &ExecutionContext,
+    ) -> Result<Vec<HttpResponse>, AssertionFailure> {
+        let mut responses: Vec<HttpResponse> = Vec::new();
+
+        while responses.len() < max {
+            if let (Some(interval), false) = (interval, responses.is_empty()) {
+                tokio::time::sleep(interval).await;
+                pacing.waited += interval;
+            }
+
+            let response = Self::send_sample(&request()?, responses.len() + 1, context).await?;
+            let limited = response.status == 429;
+            let wait = response.retry_after();
+            responses.push(response);
+
+            if limited {
+                if recover {
+                    let wait = wait.unwrap_or(Duration::from_secs(1)).min(MAX_RECOVERY_WAIT);
+                    tokio::time::sleep(wait).await;
+                    pacing.waited += wait;
+                    let number = responses.len() + 1;
+                    responses.push(Self::send_sample(&request()?, number, context).await?);
+                }
+                break;
             }
+        }
+
+        // The first response is the test's own request
+        pacing.polls += responses.len().saturating_sub(1);
+        Ok(responses)
+    }
+
+    /// Send the request once more, numbering failures by `attempt`
+    async fn send_sample(
+        request: &HttpRequest,
+        attempt: usize,
+        context: &ExecutionContext,
+    ) -> Result<HttpResponse, AssertionFailure> {
+        send(request, context, |error| format!("Request {} failed: {}", attempt, error)).await
+    }
+
+    /// Send `request` with `Expect: 100-continue`; fails unless the server
+    /// answers before the body
+    async fn expect_continue(
+        request: &HttpRequest,
+        within: Duration,
+        context: &ExecutionContext,
+    ) -> Result<Vec<HttpResponse>, AssertionFailure> {
+        let failure = |message: String| AssertionFailure {
+            path: "expect-continue".into(),
+            expected: Some("100 Continue or a final status before the body".into()),
+            actual: None,
+            message,
+            location: None,
+            minor: false,
+        };
+
+        approve(request, context).await?;
+        let (response, interim) = expect_continue::send(request.clone(), within)
+            .await
+            .map_err(|error| failure(format!("{:#}", error)))?;
+        if !interim.honored() {
+            return Err(failure(format!(
+                "Server did not answer Expect: 100-continue within {:?}",
+                within
+            )));
+        }
+        Ok(vec![response])
+    }
+
+    /// Wait for the callback a listener was registered for
+    async fn receive_callback(
+        callback: Option<oneshot::Receiver<HttpResponse>>,
+        within: Duration,
+    ) -> Result<Vec<HttpResponse>, AssertionFailure> {
+        let received = match callback {
+            Some(callback) => tokio::time::timeout(within, callback).await.ok().and_then(Result::ok),
+            None => None,
+        };
+
+        received.map(|callback| vec![callback]).ok_or_else(|| AssertionFailure {
+            path: "callback".into(),
+            expected: None,
+            actual: None,
+            message: format!("No callback received within {:?}", within),
+            location: None,
+            minor: false,
+        })
+    }
+
+    /// Snapshot an external source every second until `assertion` holds or
+    /// `within` has passed, returning the last snapshot
+    async fn watch_source(
+        assertion: &Assertion,
+        context: &ExecutionContext,
+        name: &str,
+        target: Option<&str>,
+        key: &str,
+        since: chrono::DateTime<chrono::Utc>,
+        within: Duration,
+    ) -> Result<Vec<HttpResponse>, AssertionFailure> {
+        let failure = |error: anyhow::Error| AssertionFailure {
+            path: name.to_string(),
+            expected: None,
+            actual: None,
+            message: format!("{} failed: {:#}", name, error),
+            location: None,
+            minor: false,
         };
+        let source = context.source(name, target).map_err(failure)?;
+        let _watch = source::Watch::new(&*source, key, since);
+
+        let start = std::time::Instant::now();
+        loop {
+            let items = source.fetch(key, since).await.map_err(failure)?;
+
+            let snapshot = source::snapshot(items, start.elapsed());
+            if assertion.check(&snapshot).is_ok() || start.elapsed() >= within {
+                return Ok(vec![snapshot]);
+            }
+            tokio::time::sleep(SOURCE_POLL_INTERVAL.min(within)).await;
+        }
+    }
+
+    /// Send the extra requests an assertion asked for, in order
+    async fn send_probes(
+        probes: Vec<HttpRequest>,
+        context: &ExecutionContext,
+    ) -> Result<Vec<HttpResponse>, AssertionFailure> {
+        let mut responses = Vec::with_capacity(probes.len());
+
+        for probe in probes {
+            let response =
+                send(&probe, context, |error| format!("Probe request failed: {}", error)).await?;
+            responses.push(response);
+        }
 
-        self.response = Some(response.clone());
+        Ok(responses)
+    }
+
+    /// Execute the test, or mark it not run if the context's deadline passes
+    /// first
+    pub async fn run(mut self, context: &ExecutionContext) -> TestCase {
+        if let Some(reason) = &self.skip {
+            self.result = Some(TestResult::Skipped {
+                reason: reason.clone(),
+            });
+            return self;
+        }
+
+        let started = std::time::Instant::now();
+        match context.deadline {
+            Some(deadline) => {
+                if tokio::time::timeout_at(deadline, self.execute(context)).await.is_err() {
+                    self.result = Some(TestResult::NotRun {
+                        reason: "Run timeout reached".into(),
+                    });
+                }
+            }
+            None => self.execute(context).await,
+        }
+        self.span = Some((started, std::time::Instant::now()));
+
+        self
+    }
+
+    /// Run the test `repeat` times; it fails if any iteration does, with the
+    /// errors of every failing iteration and the response of the first one
+    async fn execute(&mut self, context: &ExecutionContext) {
+        if self.repeat <= 1 {
+            return self.attempt(context).await;
+        }
 
+        let start = std::time::Instant::now();
         let mut errors = Vec::new();
+        let mut failed_response = None;
+
+        for number in 1..=self.repeat {
+            self.attempt(context).await;
+            match self.result.take() {
+                Some(TestResult::Passed { duration }) => self.iterations.push(Iteration {
+                    passed: true,
+                    duration,
+                }),
+                Some(TestResult::Failed {
+                    duration,
+                    errors: failures,
+                }) => {
+                    self.iterations.push(Iteration {
+                        passed: false,
+                        duration,
+                    });
+                    for mut failure in failures {
+                        failure.message =
+                            format!("Iteration {}/{}: {}", number, self.repeat, failure.message);
+                        errors.push(failure);
+                    }
+                    if failed_response.is_none() {
+                        failed_response = self.response.take();
+                    }
+                }
+                _ => {}
+            }
+        }
 
-        for assertion in &self.assertions {
-            if let Err(err) = assertion.check(&response) {
-                errors.push(err);
+        let passed = self.iterations.iter().filter(|i| i.passed).count();
+        self.annotations
+            .push(format!("{}/{} iterations passed", passed, self.repeat));
+        if failed_response.is_some() {
+            self.response = failed_response;
+        }
+
+        self.result = Some(if errors.is_empty() {
+            TestResult::Passed {
+                duration: start.elapsed(),
             }
+        } else {
+            TestResult::Failed {
+                duration: start.elapsed(),
+                errors,
+            }
+        });
+    }
+
+    /// Run the test once, retrying while it fails as `RETRY` allows. Only the
+    /// last attempt's outcome counts.
+    async fn attempt(&mut self, context: &ExecutionContext) {
+        let allowed = 1 + self.retry.as_ref().map_or(0, |retry| retry.retries);
+
+        for number in 1..=allowed {
+            self.attempts += 1;
+            // A retried flow may stop earlier than the attempt before it
+            for step in &mut self.steps {
+                step.response = None;
+            }
+            self.execute_once(context).await;
+
+            let failed = matches!(self.result, Some(TestResult::Failed { .. }));
+            if !failed || number == allowed {
+                return;
+            }
+
+            let delay = self.retry.as_ref().and_then(|retry| retry.delay);
+            let asked = self.retry_after().map(|asked| asked.min(MAX_RETRY_AFTER));
+            let Some(wait) = delay.max(asked) else {
+                continue;
+            };
+            // Waiting past the run timeout would only get the test cancelled;
+            // report this attempt's failure instead
+            if context
+                .deadline
+                .is_some_and(|deadline| tokio::time::Instant::now() + wait >= deadline)
+            {
+                self.annotations.push(format!(
+                    "not retried: waiting {:?} would pass the run timeout",
+                    wait
+                ));
+                return;
+            }
+            tokio::time::sleep(wait).await;
+            self.pacing.waited += wait;
         }
+    }
+
+    /// Delay a rate-limited or unavailable (429/503) last response asked for
+    fn retry_after(&self) -> Option<Duration> {
+        let last = self
+            .steps
+            .iter()
+            .rev()
+            .find_map(|step| step.response.as_ref())
+            .or(self.response.as_ref())?;
+        if !matches!(last.status, 429 | 503) {
+            return None;
+        }
+        last.retry_after()
+    }
+
+    async fn execute_once(&mut self, context: &ExecutionContext) {
+        let start = std::time::Instant::now();
+
+        let chaos = context.chaos.roll();
+        if let Some(delay) = chaos.delay {
+            tokio::time::sleep(delay).await;
+            self.annotations.push(format!("chaos: delayed {:?}", delay));
+        }
+        if chaos.drop {
+            self.annotations.push("chaos: dropped".into());
+            self.result = Some(TestResult::Failed {
+                duration: start.elapsed(),
+                errors: vec![AssertionFailure {
+                    path: "request".into(),
+                    expected: None,
+                    actual: None,
+                    message: "Request dropped by chaos injection".into(),
+                    location: self.location.clone().map(Box::new),
+                    minor: false,
+                }],
+            });
+            return;
+        }
+        if chaos.duplicate {
+            self.annotations.push("chaos: duplicated".into());
+        }
+
+        let shown = if self.is_sensitive() {
+            Shown::Redacted
+        } else {
+            Shown::Plain
+        };
+        let mut cookies = Cookies::new();
+        let mut errors = match exchange(
+            &self.request,
+            &self.assertions,
+            &self.assertion_lines,
+            self.location.as_ref(),
+            &self.captures,
+            self.settle,
+            shown,
+            chaos.duplicate,
+            &mut cookies,
+            context,
+        )
+        .await
+        {
+            Ok((response, errors, pacing)) => {
+                self.response = Some(response);
+                self.pacing.add(pacing);
+                errors
+            }
+            Err(error) => vec![error],
+        };
 
-        if errors.is_empty() {
+        // Later steps build on earlier ones, so the flow stops at the first
+        // failing step
+        for (index, step) in self.steps.iter_mut().enumerate() {
+            if !errors.is_empty() {
+                break;
+            }
+
+            let number = index + 2;
+            if let Some(delay) = step.delay {
+                tokio::time::sleep(delay).await;
+                self.pacing.waited += delay;
+            }
+            match exchange(
+                &step.request,
+                &step.assertions,
+                &step.assertion_lines,
+                self.location.as_ref(),
+                &step.captures,
+                step.settle,
+                shown,
+                false,
+                &mut cookies,
+                context,
+            )
+            .await
+            {
+                Ok((response, step_errors, pacing)) => {
+                    step.response = Some(response);
+                    self.pacing.add(pacing);
+                    errors = step_errors;
+                }
+                Err(error) => errors = vec![error],
+            }
+            for error in &mut errors {
+                error.message = format!("Step {}: {}", number, error.message);
+            }
+        }
+
+        // Failures not tied to an assertion point at the whole block
+        for error in &mut errors {
+            if error.location.is_none() {
+                error.location = self.location.clone().map(Box::new);
+            }
+        }
+
+        if let Some(declined) = errors.iter().find(|error| error.path == DECLINED) {
+            self.result = Some(TestResult::NotRun {
+                reason: declined.message.clone(),
+            });
+        } else if errors.is_empty() {
             self.result = Some(TestResult::Passed {
                 duration: start.elapsed(),
             });
@@ -63,7 +757,449 @@ impl TestCase {
                 errors,
             });
         }
+    }
+}
 
-        self
+/// Cookies set during a multi-step test, sent with its later requests
+type Cookies = BTreeMap<String, String>;
+
+/// Builds a request again for each extra send of it (polls, rate-limit
+/// bursts), with new values for its `uuid()` and `randomInt()`
+type Resend<'a> = dyn Fn() -> Result<HttpRequest, AssertionFailure> + Sync + 'a;
+
+/// Failure of a request that could not be sent, or got no response
+fn request_failure(message: String) -> AssertionFailure {
+    AssertionFailure {
+        path: "request".into(),
+        expected: None,
+        actual: None,
+        message,
+        location: None,
+        minor: false,
+    }
+}
+
+/// `parsed` as it goes out: placeholders filled in from `captured`, cookies
+/// from earlier steps attached, then signed. Each call gives placeholders
+/// such as `uuid()` new values.
+fn prepare(
+    parsed: &HttpRequest,
+    captured: &Variables,
+    cookies: &Cookies,
+    context: &ExecutionContext,
+) -> Result<HttpRequest, AssertionFailure> {
+    let mut request = parsed.clone();
+    if let Some(deferred) = request.deferred.take() {
+        deferred
+            .apply(&mut request, captured)
+            .map_err(|error| request_failure(format!("{:#}", error)))?;
+    }
+
+    send_cookies(&mut request, cookies);
+
+    if let Some(signer) = &context.signer {
+        signer
+            .sign(&mut request)
+            .map_err(|error| request_failure(format!("Signing failed: {:#}", error)))?;
+    }
+    Ok(request)
+}
+
+/// Set the Cookie header to the cookies written in the test merged with
+/// those set by earlier steps, the latter winning for the same name
+fn send_cookies(request: &mut HttpRequest, cookies: &Cookies) {
+    if cookies.is_empty() {
+        return;
+    }
+    let written = request
+        .headers
+        .keys()
+        .find(|key| key.eq_ignore_ascii_case("Cookie"))
+        .cloned();
+    let mut pairs: Vec<String> = written
+        .as_ref()
+        .and_then(|key| request.headers.remove(key))
+        .unwrap_or_default()
+        .split(';')
+        .map(str::trim)
+        .filter(|pair| {
+            let name = pair.split('=').next().unwrap_or_default();
+            !pair.is_empty() && !cookies.contains_key(name.trim())
+        })
+        .map(str::to_string)
+        .collect();
+    pairs.extend(cookies.iter().map(|(name, value)| format!("{}={}", name, value)));
+    request
+        .headers
+        .insert(written.unwrap_or_else(|| "Cookie".to_string()), pairs.join("; "));
+}
+
+/// Ask at the `--confirm` prompt whether `request` may go out. Every request
+/// a test sends is approved here first, including polls, probes and resends.
+async fn approve(request: &HttpRequest, context: &ExecutionContext) -> Result<(), AssertionFailure> {
+    match &context.confirm {
+        Some(confirm) if !confirm.approve(request).await => Err(AssertionFailure {
+            path: DECLINED.into(),
+            expected: None,
+            actual: None,
+            message: "Declined at the confirmation prompt".into(),
+            location: None,
+            minor: false,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Send `request` once it is approved, describing a send that got no
+/// response with `failed(error)`
+async fn send(
+    request: &HttpRequest,
+    context: &ExecutionContext,
+    failed: impl FnOnce(anyhow::Error) -> String,
+) -> Result<HttpResponse, AssertionFailure> {
+    approve(request, context).await?;
+    request
+        .clone()
+        .send()
+        .await
+        .map_err(|error| request_failure(failed(error)))
+}
+
+/// Send one request of a test and check it: fill in captured placeholders,
+/// attach cookies from earlier steps, then run captures and assertions, each
+/// failure located at its assertion's line of `location`, along with the
+/// polls and waits the assertions took. With `settle`, the checks start only
+/// that long after the response; failures show response values as `shown`.
+/// With `duplicate`, the request is sent twice as it is about to go out and
+/// only the second response is checked. `Err` means no response was received.
+#[allow(clippy::too_many_arguments)]
+async fn exchange(
+    parsed: &HttpRequest,
+    assertions: &[Assertion],
+    lines: &[usize],
+    location: Option<&Location>,
+    captures: &[Capture],
+    settle: Option<Duration>,
+    shown: Shown,
+    duplicate: bool,
+    cookies: &mut Cookies,
+    context: &ExecutionContext,
+) -> Result<(HttpResponse, Vec<AssertionFailure>, Pacing), AssertionFailure> {
+    // Only requests using the refreshed token wait for it
+    let refresher = context.refresher.as_ref().filter(|refresher| {
+        parsed
+            .deferred
+            .as_ref()
+            .is_some_and(|deferred| deferred.placeholders().contains(&refresher.variable()))
+    });
+    if let Some(refresher) = refresher {
+        refresher
+            .ensure(&context.captured)
+            .await
+            .map_err(|error| request_failure(format!("{:#}", error)))?;
+    }
+
+    // Every attempt starts over from the parsed request, so a REPEAT or RETRY
+    // picks up the latest captures and cookies
+    let mut captured = context.captured.lock().unwrap().clone();
+    let token = refresher.and_then(|refresher| captured.get(refresher.variable()).cloned());
+    let mut request = prepare(parsed, &captured, cookies, context)?;
+
+    approve(&request, context).await?;
+
+    // Listeners must be up before the request can trigger a callback
+    let mut callbacks = Vec::with_capacity(assertions.len());
+    for assertion in assertions {
+        callbacks.push(match assertion.callback_target() {
+            Some((port, path)) => Some(
+                context
+                    .webhooks
+                    .expect(port, path)
+                    .await
+                    .map_err(|error| request_failure(format!("{:#}", error)))?,
+            ),
+            None => None,
+        });
+    }
+
+    let url = request.url.clone();
+    if context.circuits.is_open(&url) {
+        return Err(request_failure(context.circuits.open_message(&url)));
+    }
+
+    if duplicate {
+        // The duplicate's outcome is irrelevant; only the original is asserted on
+        let _ = request.clone().send().await;
+    }
+
+    let mut sent_at = chrono::Utc::now();
+    let sent = if request.share {
+        context.shared.send(&request).await
+    } else {
+        request.clone().send().await
+    };
+    let mut response = match sent {
+        Ok(res) => {
+            context.circuits.record_success(&url);
+            res
+        }
+        Err(error) => {
+            context.circuits.record_failure(&url);
+            return Err(request_failure(error.to_string()));
+        }
+    };
+
+    // Refused with an expired token: send once more with a new one
+    if let (401, Some(refresher)) = (response.status, refresher) {
+        refresher
+            .refresh(&context.captured, token.as_deref())
+            .await
+            .map_err(|error| request_failure(format!("{:#}", error)))?;
+        captured = context.captured.lock().unwrap().clone();
+        request = prepare(parsed, &captured, cookies, context)?;
+        sent_at = chrono::Utc::now();
+        response = send(&request, context, |error| error.to_string()).await?;
+    }
+
+    // Polls and bursts send the request again as it went out, but with new
+    // generated values
+    let sent_cookies = cookies.clone();
+    let resend = || prepare(parsed, &captured, &sent_cookies, context);
+
+    for (name, value) in &response.cookies {
+        if value.is_empty() {
+            cookies.remove(name);
+        } else {
+            cookies.insert(name.clone(), value.clone());
+        }
+    }
+
+    let mut pacing = Pacing::default();
+    if let Some(settle) = settle {
+        tokio::time::sleep(settle).await;
+        pacing.waited += settle;
+    }
+
+    let mut errors = Vec::new();
+
+    // Captured first, so assertion keys can use values from this response
+    for capture in captures {
+        match resolve_path(&response, &capture.path) {
+            Some(value) => {
+                let value = match value {
+                    Value::String(text) => text,
+                    other => other.to_string(),
+                };
+                context.captured.lock().unwrap().insert(capture.name.clone(), value);
+            }
+            None => errors.push(AssertionFailure {
+                path: capture.path.clone(),
+                expected: Some("exists".into()),
+                actual: None,
+                message: format!(
+                    "Cannot capture '{}': path '{}' not found",
+                    capture.name, capture.path
+                ),
+                location: None,
+                minor: false,
+            }),
+        }
+    }
+
+    for (index, (assertion, callback)) in assertions.iter().zip(callbacks).enumerate() {
+        let sampled = match assertion.sampling(&request) {
+            Sampling::Single => Ok(vec![response.clone()]),
+            Sampling::Poll { count, interval } => {
+                TestCase::poll(&resend, &response, count, interval, &mut pacing, context).await
+            }
+            Sampling::Probes(probes) => TestCase::send_probes(probes, context).await,
+            Sampling::UntilRateLimited {
+                max,
+                interval,
+                recover,
+            } => {
+                TestCase::until_rate_limited(
+                    &resend, max, interval, recover, &mut pacing, context,
+                )
+                .await
+            }
+            Sampling::Until { interval, timeout } => {
+                let polled = TestCase::poll_until(
+                    &resend, &response, assertion, interval, timeout, &mut pacing, context,
+                )
+                .await;
+                // Later assertions check the response the polling ended on
+                if let Some(last) = polled.as_ref().ok().and_then(|responses| responses.last()) {
+                    response = last.clone();
+                }
+                polled
+            }
+            Sampling::Callback { within } => TestCase::receive_callback(callback, within).await,
+            Sampling::ExpectContinue { within } => {
+                TestCase::expect_continue(&request, within, context).await
+            }
+            Sampling::External {
+                source,
+                target,
+                key,
+                within,
+            } => {
+                let captured = context.captured.lock().unwrap().clone();
+                match template::render_with(&key, &captured) {
+                    Ok(key) => {
+                        let target = target.as_deref();
+                        TestCase::watch_source(
+                            assertion, context, &source, target, &key, sent_at, within,
+                        )
+                        .await
+                    }
+                    Err(error) => Err(AssertionFailure {
+                        path: source.clone(),
+                        expected: None,
+                        actual: None,
+                        message: format!("{:#}", error),
+                        location: None,
+                        minor: false,
+                    }),
+                }
+            }
+        };
+
+        let outcome = sampled.and_then(|responses| assertion.check_series_shown(&responses, shown));
+
+        if let Err(mut err) = outcome {
+            // A MINOR assertion that could not even be sampled stays minor
+            err.minor = assertion.is_minor();
+            err.location = location.map(|block| {
+                Box::new(match lines.get(index) {
+                    Some(line) => block.line(*line),
+                    None => block.clone(),
+                })
+            });
+            errors.push(err);
+        }
+    }
+
+    Ok((response, errors, pacing))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::chaos::Chaos;
+    use crate::parser::AxParser;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn execution_notes_show_retries_polls_and_waits() {
+        let input = "TEST Create user\nPOST https://example.com/users\n\nEND\n";
+        let mut test = AxParser::parse_file(input).unwrap().remove(0);
+        assert!(test.execution_notes().is_empty());
+
+        test.attempts = 3;
+        test.pacing = Pacing {
+            polls: 1,
+            waited: Duration::from_millis(1500),
+        };
+        assert_eq!(
+            test.execution_notes(),
+            vec!["3 attempts", "1 poll", "waited 1.5s"]
+        );
+
+        // Runs of a REPEAT are not retries
+        test.repeat = 3;
+        assert_eq!(test.execution_notes(), vec!["1 poll", "waited 1.5s"]);
+    }
+
+    #[test]
+    fn send_cookies_replaces_stale_values() {
+        let input = "TEST Profile\nGET https://example.com/me\nCookie: consent=yes; session=old\n\nEND\n";
+        let parsed = AxParser::parse_file(input).unwrap().remove(0).request;
+        let mut cookies = Cookies::new();
+        cookies.insert("session".into(), "first".into());
+
+        let mut request = parsed.clone();
+        send_cookies(&mut request, &cookies);
+        assert_eq!(request.headers["Cookie"], "consent=yes; session=first");
+
+        // A later attempt starts from the parsed request, not the last one sent
+        cookies.insert("session".into(), "second".into());
+        let mut request = parsed.clone();
+        send_cookies(&mut request, &cookies);
+        assert_eq!(request.headers["Cookie"], "consent=yes; session=second");
+    }
+
+    /// Port of a local server answering 200 to everything, and the request
+    /// line of each request it got
+    async fn record_requests() -> (u16, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&lines);
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut head = [0; 1024];
+                let read = stream.read(&mut head).await.unwrap_or(0);
+                let head = String::from_utf8_lossy(&head[..read]).to_string();
+                seen.lock().unwrap().push(head.lines().next().unwrap_or_default().to_string());
+                let _ = stream
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                    .await;
+            }
+        });
+        (port, lines)
+    }
+
+    #[tokio::test]
+    async fn chaos_duplicate_is_sent_as_rendered() {
+        let (port, paths) = record_requests().await;
+        let input = format!(
+            "TEST Next\nGET http://127.0.0.1:{}/next/{{{{id}}}}\n\nEXPECT status == 200\nEND\n",
+            port
+        );
+        let test = AxParser::parse_file(&input).unwrap().remove(0);
+        let context = ExecutionContext {
+            chaos: Chaos {
+                duplicate_percent: 100,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        context.captured.lock().unwrap().insert("id".into(), "7".into());
+
+        let test = test.run(&context).await;
+        assert!(matches!(test.result, Some(TestResult::Passed { .. })));
+        assert_eq!(
+            *paths.lock().unwrap(),
+            ["GET /next/7 HTTP/1.1", "GET /next/7 HTTP/1.1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn repeats_send_new_generated_values() {
+        let sent = |directives: &'static str| async move {
+            let (port, paths) = record_requests().await;
+            let input = format!(
+                "TEST Create\n{}POST http://127.0.0.1:{}/items/{{{{uuid()}}}}\n\nEXPECT status == 200\nEND\n",
+                directives, port
+            );
+            let test = AxParser::parse_file(&input).unwrap().remove(0);
+            let test = test.run(&ExecutionContext::default()).await;
+            assert!(matches!(test.result, Some(TestResult::Passed { .. })));
+            let sent = paths.lock().unwrap().clone();
+            sent
+        };
+
+        let mut paths = sent("REPEAT 3\n").await;
+        paths.dedup();
+        assert_eq!(paths.len(), 3);
+
+        // A SEED makes every iteration send the same values
+        let mut paths = sent("REPEAT 3\nSEED 42\n").await;
+        paths.dedup();
+        assert_eq!(paths.len(), 1);
     }
 }
@@ -0,0 +1,151 @@
+//! Access tokens refreshed during the run (`[token_refresh]` in the config).
+//!
+//! Long runs outlive short-lived tokens: without a refresh, the tail of the
+//! run fails with 401s. The refresher owns one captured variable (e.g.
+//! `token`, used as `AUTH BEARER {{token}}`). It fetches the token before
+//! the first request that needs it, again when the token is a JWT whose
+//! `exp` claim is about to pass, and again when a request using it gets a
+//! `401`, which is then sent once more with the new token.
+//!
+//! The variable must not be defined by the environment: placeholders known
+//! while parsing are filled in once and cannot be refreshed.
+//!
+//! Configured by `[token_refresh]` (see [`crate::config`]).
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use url::Url;
+
+use super::assertion::{resolve_path, Value};
+use super::http_request::{Body, HttpRequest};
+use crate::template::{self, Variables};
+
+/// Refreshes one token for all the tests of a run
+#[derive(Debug, Default)]
+pub struct TokenRefresher {
+    /// Captured variable holding the token
+    pub variable: String,
+    pub method: String,
+    /// Templates may use `{{secret.x}}` and captured values
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+    /// Where the token is in the response, e.g. `body.access_token`
+    pub path: String,
+    /// Refresh a JWT this long before it expires
+    pub margin: Duration,
+    /// Held while refreshing, so concurrent tests wait for a single refresh
+    pub refreshing: tokio::sync::Mutex<()>,
+}
+
+impl TokenRefresher {
+    pub fn variable(&self) -> &str {
+        &self.variable
+    }
+
+    /// Make sure `captured` holds a token that is not about to expire
+    pub async fn ensure(&self, captured: &std::sync::Mutex<Variables>) -> Result<()> {
+        let current = captured.lock().unwrap().get(self.variable()).cloned();
+        match current {
+            Some(token) if !expires_within(&token, self.margin) => Ok(()),
+            stale => self.refresh(captured, stale.as_deref()).await,
+        }
+    }
+
+    /// Replace `stale` (the token a request was refused with) in `captured`,
+    /// unless another test already did
+    pub async fn refresh(
+        &self,
+        captured: &std::sync::Mutex<Variables>,
+        stale: Option<&str>,
+    ) -> Result<()> {
+        let _refreshing = self.refreshing.lock().await;
+        let variables = captured.lock().unwrap().clone();
+        if variables.get(self.variable()).map(String::as_str) != stale {
+            return Ok(());
+        }
+
+        let token = self
+            .fetch(&variables)
+            .await
+            .with_context(|| format!("Failed to refresh token '{}'", self.variable()))?;
+        captured
+            .lock()
+            .unwrap()
+            .insert(self.variable().to_string(), token);
+        Ok(())
+    }
+
+    async fn fetch(&self, variables: &Variables) -> Result<String> {
+        let url = template::render_with(&self.url, variables)?;
+        let mut request = HttpRequest::new(
+            self.method.clone(),
+            Url::parse(&url).with_context(|| format!("Invalid URL: {}", url))?,
+        );
+        for (name, value) in &self.headers {
+            request
+                .headers
+                .insert(name.clone(), template::render_with(value, variables)?);
+        }
+        if let Some(body) = &self.body {
+            request.body = Some(Body::Text(template::render_with(body, variables)?));
+        }
+
+        let response = request.send().await?;
+        if !(200..300).contains(&response.status) {
+            anyhow::bail!("{} {} returned {}", self.method, url, response.status);
+        }
+        match resolve_path(&response, &self.path) {
+            Some(Value::String(token)) => Ok(token),
+            Some(other) => Ok(other.to_string()),
+            None => anyhow::bail!("No token at '{}' in the response", self.path),
+        }
+    }
+}
+
+/// Whether `token` is a JWT whose `exp` claim passes within `margin`; opaque
+/// tokens are only refreshed on 401
+fn expires_within(token: &str, margin: Duration) -> bool {
+    let Some(exp) = expiry(token) else {
+        return false;
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    exp <= (now + margin).as_secs()
+}
+
+/// `exp` claim of a JWT, in seconds since the epoch
+fn expiry(token: &str) -> Option<u64> {
+    let payload = token.split('.').nth(1)?;
+    let claims = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .ok()?;
+    let claims: serde_json::Value = serde_json::from_slice(&claims).ok()?;
+    claims.get("exp")?.as_u64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jwt(exp: u64) -> String {
+        let encode = |json: &str| base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(json);
+        format!(
+            "{}.{}.signature",
+            encode(r#"{"alg":"HS256"}"#),
+            encode(&format!(r#"{{"sub":"ci","exp":{}}}"#, exp))
+        )
+    }
+
+    #[test]
+    fn expiry_comes_from_exp_claim() {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let margin = Duration::from_secs(30);
+        assert_eq!(expiry(&jwt(1_700_000_000)), Some(1_700_000_000));
+        assert!(expires_within(&jwt(now + 10), margin));
+        assert!(!expires_within(&jwt(now + 3600), margin));
+        assert!(!expires_within("opaque-token", margin));
+    }
+}
@@ -0,0 +1,178 @@
+//! Local listeners for webhook callbacks.
+//!
+//! `LISTEN :9099 /callback EXPECT body.event == "user.created" WITHIN 30s`
+//! registers interest in the next request to `/callback` on port 9099 before
+//! the test's request is sent, then checks that callback as if it were a
+//! response: its headers and body are available to the usual paths.
+//!
+//! There is one listener per port for the whole run, started on first use.
+//! Each callback goes to the test that has been waiting longest on its path,
+//! so concurrent tests can share a port. Callbacks nobody waits for get 404.
+
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+use super::http_request::HttpResponse;
+
+/// Larger callback bodies are rejected
+const MAX_CALLBACK_BODY: usize = 16 * 1024 * 1024;
+
+/// Tests waiting on a port, oldest first, with the path they expect
+type Waiters = Mutex<Vec<(String, oneshot::Sender<HttpResponse>)>>;
+
+/// Callback listeners shared by every test of the run
+#[derive(Debug, Default)]
+pub struct Webhooks {
+    ports: tokio::sync::Mutex<HashMap<u16, Arc<Waiters>>>,
+}
+
+impl Webhooks {
+    /// Wait for the next request to `path` on `port`, listening if needed
+    pub async fn expect(&self, port: u16, path: &str) -> Result<oneshot::Receiver<HttpResponse>> {
+        let mut ports = self.ports.lock().await;
+
+        let waiters = match ports.get(&port) {
+            Some(waiters) => Arc::clone(waiters),
+            None => {
+                let listener = TcpListener::bind(("0.0.0.0", port))
+                    .await
+                    .with_context(|| format!("Cannot listen on port {}", port))?;
+                let waiters = Arc::new(Waiters::default());
+                tokio::spawn(serve(listener, Arc::clone(&waiters)));
+                ports.insert(port, Arc::clone(&waiters));
+                waiters
+            }
+        };
+
+        let (sender, receiver) = oneshot::channel();
+        waiters.lock().unwrap().push((path.to_string(), sender));
+        Ok(receiver)
+    }
+}
+
+async fn serve(listener: TcpListener, waiters: Arc<Waiters>) {
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let waiters = Arc::clone(&waiters);
+        tokio::spawn(async move {
+            // A malformed callback only concerns the sender
+            let _ = handle(stream, &waiters).await;
+        });
+    }
+}
+
+/// Read one HTTP/1.1 request and hand it to the first matching waiter
+async fn handle(stream: TcpStream, waiters: &Waiters) -> Result<()> {
+    let start = Instant::now();
+    let mut reader = BufReader::new(stream);
+
+    let mut line = String::new();
+    reader.read_line(&mut line).await?;
+    let mut parts = line.split_whitespace();
+    let (Some(_method), Some(target)) = (parts.next(), parts.next()) else {
+        bail!("Malformed request line");
+    };
+    let path = target.split('?').next().unwrap_or(target).to_string();
+
+    let mut headers = HashMap::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            break;
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.insert(name.trim().to_string(), value.trim().to_string());
+        }
+    }
+
+    let length = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    if length > MAX_CALLBACK_BODY {
+        bail!("Callback body too large: {} bytes", length);
+    }
+    let mut bytes = vec![0; length];
+    reader.read_exact(&mut bytes).await?;
+
+    let waiter = {
+        let mut waiters = waiters.lock().unwrap();
+        waiters.retain(|(_, sender)| !sender.is_closed());
+        waiters
+            .iter()
+            .position(|(expected, _)| *expected == path)
+            .map(|index| waiters.remove(index).1)
+    };
+
+    let status = if waiter.is_some() {
+        "200 OK"
+    } else {
+        "404 Not Found"
+    };
+    let reply = format!(
+        "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        status
+    );
+    reader.get_mut().write_all(reply.as_bytes()).await?;
+
+    if let Some(waiter) = waiter {
+        let _ = waiter.send(HttpResponse {
+            request: None,
+            duration: start.elapsed(),
+            status: 200,
+            headers,
+            body: Some(String::from_utf8_lossy(&bytes).into_owned()),
+            bytes,
+            cookies: Vec::new(),
+            trailers: HashMap::new(),
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn delivers_callback_to_waiting_test() {
+        let webhooks = Webhooks::default();
+        let port = {
+            // Find a free port, then release it for the listener
+            let probe = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            probe.local_addr().unwrap().port()
+        };
+
+        let callback = webhooks.expect(port, "/callback").await.unwrap();
+
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let body = r#"{"event":"user.created"}"#;
+        let request = format!(
+            "POST /callback?attempt=1 HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).await.unwrap();
+        assert!(reply.starts_with("HTTP/1.1 200 OK"));
+
+        let callback = callback.await.unwrap();
+        assert_eq!(callback.body.as_deref(), Some(body));
+        assert_eq!(callback.header("content-type"), Some("application/json"));
+    }
+}
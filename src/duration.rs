@@ -0,0 +1,48 @@
+//! Human-friendly duration literals such as `250ms`, `2s`, `10m`, or `1h`.
+//!
+//! Used both by CLI options and by time-based directives in `.ax` files so
+//! every duration in axotly is written the same way.
+
+use anyhow::{bail, Result};
+use std::time::Duration;
+
+/// Parse `<integer><unit>` where unit is one of `ms`, `s`, `m`, `h`
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (amount, unit) = input.split_at(split);
+
+    let Ok(amount) = amount.parse::<u64>() else {
+        bail!("Invalid duration '{}': expected e.g. 500ms, 2s, 10m", input);
+    };
+
+    Ok(match unit {
+        "ms" => Duration::from_millis(amount),
+        "s" => Duration::from_secs(amount),
+        "m" => Duration::from_secs(amount * 60),
+        "h" => Duration::from_secs(amount * 3600),
+        _ => bail!("Invalid duration unit in '{}': use ms, s, m, or h", input),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_units() {
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("2s").unwrap(), Duration::from_secs(2));
+        assert_eq!(parse_duration("10m").unwrap(), Duration::from_secs(600));
+        assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn parse_duration_rejects_invalid() {
+        assert!(parse_duration("10").is_err());
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("3d").is_err());
+    }
+}
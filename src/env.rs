@@ -0,0 +1,109 @@
+//! Per-environment variable files.
+//!
+//! `--env dev` loads `axotly.dev.env` and makes its variables available to
+//! `{{name}}` placeholders, so one suite can target several deployments.
+//! The file is looked up next to the suite (the folder being run, or the
+//! folder of the `.ax` file) and then in the working directory.
+//!
+//! The format is dotenv-like:
+//!
+//! ```text
+//! # comments and blank lines are ignored
+//! base_url=https://staging.example.com
+//! user = "qa@example.com"
+//! ```
+//!
+//! `{{env}}` is the environment's name unless the file sets `env`. `VAR`
+//! lines inside an `.ax` file override environment values.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::template::Variables;
+
+/// File name for environment `name`, e.g. `axotly.dev.env`
+pub fn file_name(name: &str) -> String {
+    format!("axotly.{}.env", name)
+}
+
+/// Find and load the variables of environment `name` for the suite at `suite`
+pub fn load(name: &str, suite: &Path) -> Result<Variables> {
+    let suite_dir = if suite.is_dir() {
+        suite
+    } else {
+        suite.parent().unwrap_or_else(|| Path::new("."))
+    };
+
+    let candidates = [
+        suite_dir.join(file_name(name)),
+        PathBuf::from(file_name(name)),
+    ];
+    let Some(path) = candidates.iter().find(|path| path.is_file()) else {
+        bail!(
+            "Environment '{}' not found: expected {} next to the suite or in the working directory",
+            name,
+            file_name(name)
+        );
+    };
+
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut variables =
+        parse(&text).with_context(|| format!("Invalid environment file {}", path.display()))?;
+    variables
+        .entry("env".to_string())
+        .or_insert_with(|| name.to_string());
+    Ok(variables)
+}
+
+/// Parse `KEY=value` lines
+pub fn parse(text: &str) -> Result<Variables> {
+    let mut variables = Variables::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            bail!("Line {}: expected KEY=value, got '{}'", index + 1, line);
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            bail!("Line {}: missing variable name", index + 1);
+        }
+
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+        variables.insert(key.to_string(), value.to_string());
+    }
+
+    Ok(variables)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_env_file() {
+        let variables = parse(
+            "# staging\n\nbase_url=https://staging.example.com\n user = \"qa@example.com\" \n",
+        )
+        .unwrap();
+
+        assert_eq!(variables.len(), 2);
+        assert_eq!(variables["base_url"], "https://staging.example.com");
+        assert_eq!(variables["user"], "qa@example.com");
+    }
+
+    #[test]
+    fn parse_env_file_rejects_invalid_lines() {
+        assert!(parse("just text").is_err());
+        assert!(parse("=value").is_err());
+    }
+}
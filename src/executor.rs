@@ -10,8 +10,8 @@
 //! running.
 //!
 //! The executor is intentionally stateless. It receives all required input
-//! (the test cases and concurrency limit) and returns the executed test cases
-//! with their results populated.
+//! (the test cases, concurrency limit, and shared [`ExecutionContext`]) and
+//! returns the executed test cases with their results populated.
 //!
 //! ## Design goals
 //!
@@ -25,42 +25,173 @@
 //! 1. A semaphore is created with `max_concurrency` permits.
 //! 2. Each [`TestCase`] is spawned as an async task.
 //! 3. Before running, the task acquires a semaphore permit.
-//! 4. The test is executed via [`TestCase::run`].
-//! 5. Completed test cases are collected and returned.
+//! 4. The test is executed via [`TestCase::run`] with the shared context.
+//! 5. Completed test cases are collected and returned in input order.
+//!
+//! [`Executor::stream_tests`] runs the same way but reports each test on a
+//! channel as it starts and finishes ([`TestEvent`]), for live front ends
+//! such as the TUI.
 //!
 //! Failed or panicked tasks are ignored and not included in the results.
+//!
+//! ## Ordering
+//!
+//! A test whose request uses a `{{name}}` captured by an earlier test in the
+//! same batch (`CAPTURE name = ...`) waits for that test to finish before
+//! taking a permit; independent tests still run concurrently.
 
 
+use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::Semaphore;
-use crate::domain::TestCase;
+use tokio::sync::{mpsc, watch, Semaphore};
+use tokio::task::JoinSet;
+use crate::domain::{ExecutionContext, TestCase};
+
+/// Progress of a streamed run, by position of the test in its batch
+#[derive(Debug)]
+pub enum TestEvent {
+    /// The test got a permit and is being sent
+    Started(usize),
+    /// The test completed, with its result populated
+    Finished(usize, Box<TestCase>),
+}
 
 pub struct Executor;
 
 impl Executor {
-    pub async fn run_tests(test_cases: Vec<TestCase>, max_concurrency: usize) -> Vec<TestCase> {
+    pub async fn run_tests(
+        test_cases: Vec<TestCase>,
+        max_concurrency: usize,
+        context: Arc<ExecutionContext>,
+    ) -> Vec<TestCase> {
+        let mut tasks = Self::spawn_tests(test_cases, max_concurrency, context, None);
+
+        let mut results = Vec::new();
+        while let Some(joined) = tasks.join_next().await {
+            if let Ok(finished) = joined {
+                results.push(finished);
+            }
+        }
+
+        // Keep the order of the input
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, test_case)| test_case).collect()
+    }
+
+    /// Run `test_cases` like [`Executor::run_tests`], reporting each start
+    /// and completion on `events` as it happens instead of collecting results
+    pub async fn stream_tests(
+        test_cases: Vec<TestCase>,
+        max_concurrency: usize,
+        context: Arc<ExecutionContext>,
+        events: mpsc::UnboundedSender<TestEvent>,
+    ) {
+        let mut tasks =
+            Self::spawn_tests(test_cases, max_concurrency, context, Some(events.clone()));
+
+        while let Some(joined) = tasks.join_next().await {
+            if let Ok((index, test_case)) = joined {
+                let _ = events.send(TestEvent::Finished(index, Box::new(test_case)));
+            }
+        }
+    }
+
+    /// Spawn one task per test, each yielding the test with its position
+    fn spawn_tests(
+        test_cases: Vec<TestCase>,
+        max_concurrency: usize,
+        context: Arc<ExecutionContext>,
+        events: Option<mpsc::UnboundedSender<TestEvent>>,
+    ) -> JoinSet<(usize, TestCase)> {
         let semaphore = Arc::new(Semaphore::new(max_concurrency));
-        let mut handles = Vec::new();
+        let mut tasks = JoinSet::new();
+        let dependencies = Self::dependencies(&test_cases);
+        let mut finished: Vec<watch::Receiver<bool>> = Vec::new();
 
-        for test_case in test_cases {
+        for (index, (test_case, depends_on)) in
+            test_cases.into_iter().zip(dependencies).enumerate()
+        {
             let sem = Arc::clone(&semaphore);
-            
-            let handle = tokio::spawn(async move {
+            let context = Arc::clone(&context);
+            let events = events.clone();
+            let (done, done_rx) = watch::channel(false);
+            let waits: Vec<watch::Receiver<bool>> =
+                depends_on.iter().map(|&i| finished[i].clone()).collect();
+            finished.push(done_rx);
+
+            tasks.spawn(async move {
+                for mut wait in waits {
+                    // A dropped sender means the provider panicked; run anyway
+                    let _ = wait.wait_for(|done| *done).await;
+                }
+
                 let _permit = sem.acquire().await.expect("Semaphore closed");
-                let result = test_case.run().await;
-                result
+                if let Some(events) = &events {
+                    let _ = events.send(TestEvent::Started(index));
+                }
+                let result = test_case.run(&context).await;
+                let _ = done.send(true);
+                (index, result)
             });
-            
-            handles.push(handle);
         }
 
-        let mut results = Vec::new();
-        for handle in handles {
-            if let Ok(test_case) = handle.await {
-                results.push(test_case);
+        tasks
+    }
+
+    /// For each test, the earlier tests capturing a variable its request uses
+    pub(crate) fn dependencies<'a>(
+        test_cases: impl IntoIterator<Item = &'a TestCase>,
+    ) -> Vec<Vec<usize>> {
+        let mut providers: HashMap<&str, usize> = HashMap::new();
+        let mut dependencies = Vec::new();
+
+        for (index, test) in test_cases.into_iter().enumerate() {
+            let mut depends_on: Vec<usize> = test
+                .requests()
+                .flat_map(|request| request.deferred.iter())
+                .flat_map(|deferred| deferred.placeholders())
+                .filter_map(|name| providers.get(name).copied())
+                .collect();
+            depends_on.sort_unstable();
+            depends_on.dedup();
+            dependencies.push(depends_on);
+
+            for capture in test.all_captures() {
+                providers.insert(capture.name.as_str(), index);
             }
         }
 
-        results
+        dependencies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::AxParser;
+
+    #[test]
+    fn tests_wait_for_the_captures_they_use() {
+        let input = r#"TEST Login
+POST https://example.com/login
+
+CAPTURE token = body.token
+END
+
+TEST Health
+GET https://example.com/health
+
+END
+
+TEST Profile
+GET https://example.com/me
+Authorization: Bearer {{token}}
+
+END
+"#
+        .to_string();
+
+        let tests = AxParser::parse_file(&input).unwrap();
+        assert_eq!(Executor::dependencies(&tests), vec![vec![], vec![], vec![0]]);
     }
 }
@@ -0,0 +1,153 @@
+//! Exit codes of the `axotly` binary.
+//!
+//! CI scripts branch on these, so they are stable:
+//!
+//! | Code | Meaning                                                        |
+//! |------|----------------------------------------------------------------|
+//! | 0    | Every test passed (or the single request got a response)       |
+//! | 1    | Some test failed a critical assertion, or did not run          |
+//! | 2    | A `.ax` file or the command line is invalid, or a test block   |
+//! |      | did not parse and was left out of the run                      |
+//! | 3    | The configuration or environment could not be loaded           |
+//! | 4    | The network is unavailable: no test got a response, or a host  |
+//! |      | could not be resolved                                          |
+//! | 124  | `--run-timeout` cancelled the run                              |
+//! | 130  | Interrupted with Ctrl-C                                        |
+//!
+//! Errors are classified by the [`ErrorKind`] attached to them where they
+//! arise (see [`Kind`]); any other error exits with 1. The kind only picks
+//! the code: the error still shows its own message.
+
+use std::fmt;
+
+use crate::runner::RunOutcome;
+
+pub const SUCCESS: i32 = 0;
+pub const TEST_FAILURES: i32 = 1;
+pub const INVALID: i32 = 2;
+pub const CONFIG: i32 = 3;
+pub const NETWORK_UNAVAILABLE: i32 = 4;
+/// Same as `timeout(1)`
+pub const RUN_TIMEOUT: i32 = 124;
+/// 128 + SIGINT, as shells report it
+pub const INTERRUPTED: i32 = 130;
+
+/// Shown at the end of `--help`
+pub const HELP: &str = "\
+Exit codes:
+  0    all tests passed
+  1    test failures
+  2    invalid .ax file or arguments
+  3    configuration error
+  4    network unavailable
+  124  run timeout
+  130  interrupted";
+
+/// Cause of an error, for its exit code: `.kind(ErrorKind::Config)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Invalid,
+    Config,
+    Network,
+}
+
+impl ErrorKind {
+    /// `error`, classified as this kind
+    pub fn wrap(self, error: impl Into<anyhow::Error>) -> anyhow::Error {
+        anyhow::Error::new(Classified {
+            kind: self,
+            error: error.into(),
+        })
+    }
+}
+
+/// An error with its [`ErrorKind`]; it shows as the error it wraps
+#[derive(Debug)]
+struct Classified {
+    kind: ErrorKind,
+    error: anyhow::Error,
+}
+
+impl fmt::Display for Classified {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl std::error::Error for Classified {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
+/// Classify the error of a result, like `.context(...)` describes it
+pub trait Kind<T> {
+    fn kind(self, kind: ErrorKind) -> anyhow::Result<T>;
+}
+
+impl<T, E: Into<anyhow::Error>> Kind<T> for Result<T, E> {
+    fn kind(self, kind: ErrorKind) -> anyhow::Result<T> {
+        self.map_err(|error| kind.wrap(error))
+    }
+}
+
+/// Exit code of a run that finished
+pub fn for_outcome(outcome: RunOutcome) -> i32 {
+    match outcome {
+        RunOutcome::Completed => SUCCESS,
+        RunOutcome::Failed => TEST_FAILURES,
+        RunOutcome::Unreachable => NETWORK_UNAVAILABLE,
+        RunOutcome::TimedOut => RUN_TIMEOUT,
+        RunOutcome::Invalid => INVALID,
+    }
+}
+
+/// Exit code of an error, by the first [`ErrorKind`] attached to it
+pub fn for_error(error: &anyhow::Error) -> i32 {
+    match error.downcast_ref::<Classified>().map(|classified| classified.kind) {
+        Some(ErrorKind::Invalid) => INVALID,
+        Some(ErrorKind::Config) => CONFIG,
+        Some(ErrorKind::Network) => NETWORK_UNAVAILABLE,
+        None => TEST_FAILURES,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Context};
+
+    #[test]
+    fn errors_map_to_their_kind() {
+        let error = Err::<(), _>(anyhow!("missing ="))
+            .kind(ErrorKind::Invalid)
+            .context("Failed to parse users.ax")
+            .unwrap_err();
+        assert_eq!(for_error(&error), INVALID);
+
+        let error = Err::<(), _>(anyhow!("no such file"))
+            .kind(ErrorKind::Config)
+            .unwrap_err();
+        assert_eq!(for_error(&error), CONFIG);
+
+        assert_eq!(for_error(&anyhow!("unexpected")), TEST_FAILURES);
+    }
+
+    #[test]
+    fn kind_does_not_replace_the_message() {
+        let error = Err::<(), _>(anyhow!("HTTP client already initialized"))
+            .context("Failed to pre-resolve hosts")
+            .kind(ErrorKind::Network)
+            .unwrap_err();
+        assert_eq!(
+            format!("{:?}", error).lines().take(4).collect::<Vec<_>>(),
+            [
+                "Failed to pre-resolve hosts",
+                "",
+                "Caused by:",
+                "    HTTP client already initialized"
+            ]
+        );
+        assert_eq!(for_error(&error), NETWORK_UNAVAILABLE);
+    }
+}
@@ -0,0 +1,207 @@
+//! Fixture file management.
+//!
+//! Tests marked with a `FIXTURE` directive describe GET requests whose
+//! response bodies are worth keeping on disk, e.g. to compare against later
+//! with `EXPECT body EQUALS FILE "..."` while offline.
+//!
+//! - [`pull`] executes every fixture-marked GET test and writes the response
+//!   body to the test's fixture path (JSON bodies are pretty-printed), unless
+//!   the response is not a 2xx.
+//! - [`clean`] removes files inside `fixtures/` folders next to discovered
+//!   `.ax` files that no test references anymore, either through `FIXTURE`
+//!   or as one of its inputs (an `EQUALS FILE` assertion of the test or of
+//!   its steps, a dataset, an upload...).
+
+use anyhow::{bail, Context, Result};
+use owo_colors::OwoColorize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::domain::{Normalization, TestResult};
+use crate::runner::{RunOptions, Runner};
+use crate::template::Variables;
+
+/// Name of the folder, next to each `.ax` file, that [`clean`] manages
+const FIXTURES_DIR: &str = "fixtures";
+
+/// Fetch and store the body of every fixture-marked test under `path`. Each
+/// request is sent the way a run sends it, with `options`' environment,
+/// default headers and signer; a response outside 2xx is not saved.
+pub async fn pull(path: &Path, options: &RunOptions) -> Result<()> {
+    let variables = Runner::variables(path, options)?;
+    let context = Runner::context(options);
+    let mut saved = 0;
+    let mut refused = 0;
+
+    for (_, mut tests) in Runner::discover(path, &variables)? {
+        Runner::apply_defaults(&mut tests, options);
+
+        for mut test in tests {
+            let Some(fixture) = test.fixture.clone() else {
+                continue;
+            };
+            let name = test.name.clone().unwrap_or_else(|| "<unnamed>".into());
+
+            if test.request.method != "GET" {
+                println!(
+                    "{} {} skipped: FIXTURE only applies to GET requests",
+                    "!".yellow().bold(),
+                    name.bold()
+                );
+                continue;
+            }
+
+            // Only the test's own request makes the fixture
+            test.steps.clear();
+            let test = test.run(&context).await;
+            let Some(mut response) = test.response else {
+                let reason = match &test.result {
+                    Some(TestResult::Failed { errors, .. }) => errors
+                        .first()
+                        .map_or("no response".into(), |error| error.message.clone()),
+                    Some(TestResult::NotRun { reason }) => reason.clone(),
+                    Some(TestResult::Skipped { .. }) => "marked SKIP".into(),
+                    _ => "no response".into(),
+                };
+                println!("{} {} not saved: {}", "✗".red().bold(), name.bold(), reason);
+                refused += 1;
+                continue;
+            };
+            if !(200..300).contains(&response.status) {
+                println!(
+                    "{} {} not saved: status {}",
+                    "✗".red().bold(),
+                    name.bold(),
+                    response.status
+                );
+                refused += 1;
+                continue;
+            }
+            response.normalize(&Normalization::new(true, Vec::new()));
+
+            if let Some(parent) = fixture.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            std::fs::write(&fixture, response.body.unwrap_or_default())
+                .with_context(|| format!("Failed to write fixture {}", fixture.display()))?;
+
+            println!(
+                "{} {} → {}",
+                "✓".green().bold(),
+                name.bold(),
+                fixture.display().dimmed()
+            );
+            saved += 1;
+        }
+    }
+
+    println!("\nSaved {} fixture(s)", saved);
+    if refused > 0 {
+        bail!("{} fixture(s) not saved", refused);
+    }
+    Ok(())
+}
+
+/// Remove fixture files that no test under `path` references
+pub fn clean(path: &Path, dry_run: bool) -> Result<()> {
+    let mut referenced = HashSet::new();
+    let mut fixture_dirs = HashSet::new();
+
+    for (file, tests) in Runner::discover(path, &Variables::new())? {
+        let base_dir = file.parent().unwrap_or_else(|| Path::new("."));
+        fixture_dirs.insert(base_dir.join(FIXTURES_DIR));
+
+        for test in &tests {
+            let files = test.fixture.as_deref().into_iter().chain(test.inputs());
+
+            referenced.extend(files.filter_map(|f| f.canonicalize().ok()));
+        }
+    }
+
+    let mut removed = 0;
+    for dir in fixture_dirs.iter().filter(|d| d.is_dir()) {
+        for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+            let entry_path = entry.path();
+            if !entry_path.is_file() || is_referenced(&referenced, entry_path) {
+                continue;
+            }
+
+            if !dry_run {
+                std::fs::remove_file(entry_path)
+                    .with_context(|| format!("Failed to remove {}", entry_path.display()))?;
+            }
+            println!("{} {}", "-".red(), entry_path.display());
+            removed += 1;
+        }
+    }
+
+    if dry_run {
+        println!("\n{} unused fixture(s) would be removed", removed);
+    } else {
+        println!("\nRemoved {} unused fixture(s)", removed);
+    }
+
+    Ok(())
+}
+
+fn is_referenced(referenced: &HashSet<PathBuf>, path: &Path) -> bool {
+    path.canonicalize()
+        .map(|p| referenced.contains(&p))
+        .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn pull_sends_like_a_run_and_keeps_only_2xx() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let heads = Arc::clone(&seen);
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut head = [0; 1024];
+                let read = stream.read(&mut head).await.unwrap_or_default();
+                let head = String::from_utf8_lossy(&head[..read]).to_ascii_lowercase();
+                let response: &[u8] = if head.starts_with("get /users ") {
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 13\r\nConnection: close\r\n\r\n{\"b\":1,\"a\":2}"
+                } else {
+                    b"HTTP/1.1 500 Internal Server Error\r\nContent-Length: 4\r\nConnection: close\r\n\r\noops"
+                };
+                heads.lock().unwrap().push(head);
+                let _ = stream.write_all(response).await;
+            }
+        });
+
+        let dir = std::env::temp_dir().join(format!("axotly-fixtures-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("users.ax");
+        let tests = format!(
+            "TEST Users\nFIXTURE\nGET http://127.0.0.1:{port}/users\n\nEXPECT status == 200\nEND\n\n\
+             TEST Broken\nFIXTURE\nGET http://127.0.0.1:{port}/broken\n\nEXPECT status == 200\nEND\n"
+        );
+        std::fs::write(&file, tests).unwrap();
+
+        let options = RunOptions {
+            default_headers: vec![("X-Team".into(), "qa".into())],
+            ..RunOptions::default()
+        };
+        let result = pull(&file, &options).await;
+        let users = std::fs::read_to_string(dir.join("fixtures/users.json"));
+        let broken = dir.join("fixtures/broken.json").exists();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(result.is_err());
+        assert!(users.unwrap().contains("\"a\": 2"));
+        assert!(!broken);
+        assert!(seen.lock().unwrap().iter().all(|head| head.contains("x-team: qa\r\n")));
+    }
+}
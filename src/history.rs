@@ -0,0 +1,278 @@
+//! Outcomes of past runs, for `--order priority`.
+//!
+//! Every file run appends each test's outcome to `.axotly/history.json`
+//! under the working directory, keeping the last [`KEPT`] per test.
+//! `--order priority` then runs first the tests most likely to fail: those
+//! failing recently (recent runs weigh more) and those new or changed since
+//! the last commit, so CI fails fast while the long tail runs afterward.
+//!
+//! Tests capturing a value keep running before the tests using it: a
+//! provider is moved up with its most urgent dependent, across files too.
+//! Tests are identified like in [`crate::last_run`], by file and name.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::domain::test_case::{TestCase, TestResult};
+use crate::executor::Executor;
+
+/// Where the history is recorded, relative to the working directory
+pub const PATH: &str = ".axotly/history.json";
+
+/// Outcomes kept per test
+const KEPT: usize = 20;
+
+/// How much less each older run weighs than the next one
+const DECAY: f64 = 0.8;
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct History {
+    pub tests: Vec<TestHistory>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestHistory {
+    pub file: PathBuf,
+    pub name: Option<String>,
+    /// Whether each recorded run passed, oldest first
+    pub passed: Vec<bool>,
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+impl History {
+    /// Append the outcome of `test` from `file`; tests that did not get to
+    /// run leave no trace
+    pub fn record(&mut self, file: &Path, test: &TestCase) {
+        let passed = match test.result {
+            Some(TestResult::Passed { .. }) => true,
+            Some(TestResult::Failed { .. }) => false,
+            _ => return,
+        };
+        let file = canonical(file);
+        let index = match self.find(&file, &test.name) {
+            Some(index) => index,
+            None => {
+                self.tests.push(TestHistory {
+                    file,
+                    name: test.name.clone(),
+                    passed: Vec::new(),
+                });
+                self.tests.len() - 1
+            }
+        };
+
+        let outcomes = &mut self.tests[index].passed;
+        outcomes.push(passed);
+        if outcomes.len() > KEPT {
+            outcomes.drain(..outcomes.len() - KEPT);
+        }
+    }
+
+    fn find(&self, file: &Path, name: &Option<String>) -> Option<usize> {
+        self.tests
+            .iter()
+            .position(|test| test.file == file && test.name == *name)
+    }
+
+    /// How likely `test` is to fail: its recent failure rate, plus one when
+    /// it is new or reads a file in `changed`
+    pub fn priority(&self, file: &Path, test: &TestCase, changed: &HashSet<PathBuf>) -> f64 {
+        let file = canonical(file);
+        let changed = changed.contains(&file)
+            || test
+                .inputs()
+                .into_iter()
+                .any(|input| changed.contains(&canonical(input)));
+
+        let Some(index) = self.find(&file, &test.name) else {
+            return 1.0;
+        };
+        let (mut failed, mut total, mut weight) = (0.0, 0.0, 1.0);
+        for passed in self.tests[index].passed.iter().rev() {
+            if !passed {
+                failed += weight;
+            }
+            total += weight;
+            weight *= DECAY;
+        }
+        let rate = if total > 0.0 { failed / total } else { 1.0 };
+        rate + if changed { 1.0 } else { 0.0 }
+    }
+
+    /// Reorder files and their tests by priority, most urgent first, keeping
+    /// each capturing test before the tests using its value
+    pub fn prioritize(
+        &self,
+        all_tests: &mut Vec<(PathBuf, Vec<TestCase>)>,
+        changed: &HashSet<PathBuf>,
+    ) {
+        // Flat positions, so captures are followed across files
+        let mut owners = Vec::new();
+        let mut scores = Vec::new();
+        for (file_index, (file, tests)) in all_tests.iter().enumerate() {
+            for test in tests {
+                owners.push(file_index);
+                scores.push(self.priority(file, test, changed));
+            }
+        }
+        let dependencies =
+            Executor::dependencies(all_tests.iter().flat_map(|(_, tests)| tests.iter()));
+
+        // Providers always come earlier, so one backward pass lifts chains
+        let mut file_scores = vec![f64::MIN; all_tests.len()];
+        let mut file_dependencies = vec![Vec::new(); all_tests.len()];
+        for index in (0..scores.len()).rev() {
+            for &provider in &dependencies[index] {
+                scores[provider] = scores[provider].max(scores[index]);
+                if owners[provider] != owners[index] {
+                    file_dependencies[owners[provider]].push(owners[index]);
+                }
+            }
+        }
+        for (index, score) in scores.iter().enumerate() {
+            file_scores[owners[index]] = file_scores[owners[index]].max(*score);
+        }
+        for file_index in (0..all_tests.len()).rev() {
+            for &dependent in &file_dependencies[file_index] {
+                file_scores[file_index] = file_scores[file_index].max(file_scores[dependent]);
+            }
+        }
+
+        // Stable sorts: equal scores keep their written order
+        let mut offset = 0;
+        let mut files: Vec<(f64, (PathBuf, Vec<TestCase>))> = Vec::new();
+        for (file_index, (file, tests)) in std::mem::take(all_tests).into_iter().enumerate() {
+            let mut ranked: Vec<(f64, TestCase)> = scores[offset..offset + tests.len()]
+                .iter()
+                .copied()
+                .zip(tests)
+                .collect();
+            offset += ranked.len();
+            ranked.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+            let tests = ranked.into_iter().map(|(_, test)| test).collect();
+            files.push((file_scores[file_index], (file, tests)));
+        }
+        files.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        *all_tests = files.into_iter().map(|(_, file)| file).collect();
+    }
+
+    /// The recorded history, or an empty one before the first run
+    pub fn load() -> Result<Self> {
+        match std::fs::read_to_string(PATH) {
+            Ok(text) => serde_json::from_str(&text).with_context(|| format!("Invalid {}", PATH)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error).with_context(|| format!("Failed to read {}", PATH)),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Path::new(PATH);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", PATH))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::AxParser;
+    use std::time::Duration;
+
+    const INPUT: &str = r#"TEST Login
+POST https://example.com/login
+
+CAPTURE token = body.token
+END
+
+TEST Health
+GET https://example.com/health
+
+END
+
+TEST Profile
+GET https://example.com/me
+Authorization: Bearer {{token}}
+
+END
+"#;
+
+    fn names(all_tests: &[(PathBuf, Vec<TestCase>)]) -> Vec<&str> {
+        all_tests
+            .iter()
+            .flat_map(|(_, tests)| tests.iter().map(|test| test.name.as_deref().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn recent_failures_weigh_more() {
+        let mut tests = AxParser::parse_file(INPUT).unwrap();
+        let mut history = History::default();
+        let file = Path::new("api.ax");
+        let mut run = |test: &mut TestCase, passed: bool| {
+            test.result = Some(if passed {
+                TestResult::Passed {
+                    duration: Duration::ZERO,
+                }
+            } else {
+                TestResult::Failed {
+                    duration: Duration::ZERO,
+                    errors: Vec::new(),
+                }
+            });
+            history.record(file, test);
+        };
+        run(&mut tests[0], false);
+        run(&mut tests[0], true);
+        run(&mut tests[1], true);
+        run(&mut tests[1], false);
+
+        let none = HashSet::new();
+        let login = history.priority(file, &tests[0], &none);
+        let health = history.priority(file, &tests[1], &none);
+        assert!(health > login && login > 0.0);
+        assert_eq!(history.priority(file, &tests[2], &none), 1.0);
+        let changed = HashSet::from([canonical(file)]);
+        assert_eq!(history.priority(file, &tests[0], &changed), login + 1.0);
+    }
+
+    #[test]
+    fn providers_move_up_with_their_dependents() {
+        let past = |file: &str, name: &str, passed: bool| TestHistory {
+            file: canonical(Path::new(file)),
+            name: Some(name.to_string()),
+            passed: vec![passed],
+        };
+        let history = History {
+            tests: vec![
+                past("other.ax", "Ok", true),
+                past("api.ax", "Login", true),
+                past("api.ax", "Health", true),
+                past("api.ax", "Profile", false),
+            ],
+        };
+        let other = "TEST Ok\nGET https://example.com/ok\n\nEND\n".to_string();
+        let mut all_tests = vec![
+            (
+                PathBuf::from("other.ax"),
+                AxParser::parse_file(&other).unwrap(),
+            ),
+            (
+                PathBuf::from("api.ax"),
+                AxParser::parse_file(INPUT).unwrap(),
+            ),
+        ];
+
+        history.prioritize(&mut all_tests, &HashSet::new());
+        // Login comes along as the provider of the failing Profile
+        assert_eq!(names(&all_tests), vec!["Login", "Profile", "Health", "Ok"]);
+    }
+}
@@ -0,0 +1,123 @@
+//! Failures of the previous run, for `--rerun-failed`.
+//!
+//! Every file run records the tests that failed or were not run in
+//! `.axotly/last-run.json` under the working directory. `--rerun-failed`
+//! then keeps only those tests, so the fix-verify loop on a big suite does
+//! not pay for the tests that already pass.
+//!
+//! Tests are identified by their file and name; files are compared by
+//! canonical path, so `tests/a.ax` and `./tests/a.ax` match.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::domain::test_case::{TestCase, TestResult};
+
+/// Where the last run is recorded, relative to the working directory
+pub const PATH: &str = ".axotly/last-run.json";
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LastRun {
+    pub failed: Vec<FailedTest>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedTest {
+    pub file: PathBuf,
+    pub name: Option<String>,
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+impl LastRun {
+    /// Record `test` from `file` if it did not pass
+    pub fn record(&mut self, file: &Path, test: &TestCase) {
+        if matches!(
+            test.result,
+            Some(TestResult::Failed { .. } | TestResult::NotRun { .. })
+        ) {
+            self.failed.push(FailedTest {
+                file: canonical(file),
+                name: test.name.clone(),
+            });
+        }
+    }
+
+    /// Keep only the recorded tests, dropping files left empty
+    pub fn retain(&self, all_tests: &mut Vec<(PathBuf, Vec<TestCase>)>) {
+        for (file, tests) in all_tests.iter_mut() {
+            let file = canonical(file);
+            tests.retain(|test| {
+                self.failed
+                    .iter()
+                    .any(|failed| failed.file == file && failed.name == test.name)
+            });
+        }
+        all_tests.retain(|(_, tests)| !tests.is_empty());
+    }
+
+    pub fn load() -> Result<Self> {
+        let text = std::fs::read_to_string(PATH)
+            .with_context(|| format!("No previous run recorded in {}", PATH))?;
+        serde_json::from_str(&text).with_context(|| format!("Invalid {}", PATH))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Path::new(PATH);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", PATH))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::AxParser;
+    use std::time::Duration;
+
+    #[test]
+    fn keeps_only_recorded_failures() {
+        let input = r#"TEST Login
+GET https://example.com/login
+
+END
+
+TEST Health
+GET https://example.com/health
+
+END
+"#
+        .to_string();
+        let mut tests = AxParser::parse_file(&input).unwrap();
+        tests[0].result = Some(TestResult::Failed {
+            duration: Duration::ZERO,
+            errors: Vec::new(),
+        });
+        tests[1].result = Some(TestResult::Passed {
+            duration: Duration::ZERO,
+        });
+
+        let mut last_run = LastRun::default();
+        for test in &tests {
+            last_run.record(Path::new("api.ax"), test);
+        }
+        assert_eq!(last_run.failed.len(), 1);
+
+        let fresh = AxParser::parse_file(&input).unwrap();
+        let mut all_tests = vec![
+            (PathBuf::from("api.ax"), fresh),
+            (PathBuf::from("other.ax"), AxParser::parse_file(&input).unwrap()),
+        ];
+        last_run.retain(&mut all_tests);
+        assert_eq!(all_tests.len(), 1);
+        assert_eq!(all_tests[0].1.len(), 1);
+        assert_eq!(all_tests[0].1[0].name.as_deref(), Some("Login"));
+    }
+}
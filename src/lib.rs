@@ -0,0 +1,31 @@
+//! Library half of axotly: everything but the command-line entry point in
+//! `main.rs`, so other targets, such as the parser fuzz targets in `fuzz/`,
+//! can build on the same modules.
+
+pub mod api_versions;
+pub mod artifacts;
+pub mod baseline;
+pub mod changes;
+pub mod cli;
+pub mod commands;
+pub mod config;
+pub mod dataset;
+pub mod dns;
+pub mod env;
+pub mod exit;
+pub mod domain;
+pub mod duration;
+pub mod parser;
+pub mod proxy;
+pub mod executor;
+pub mod fixtures;
+pub mod history;
+pub mod last_run;
+pub mod lint;
+pub mod renderers;
+pub mod runner;
+pub mod secrets;
+pub mod shell_env;
+pub mod size;
+pub mod template;
+pub mod tui;
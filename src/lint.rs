@@ -0,0 +1,232 @@
+//! Static checks of `.ax` files (`--check`).
+//!
+//! Parses the suite without sending anything and reports tests likely to do
+//! harm or prove nothing, each warning with a code:
+//!
+//! - `W001`: a POST, PUT, PATCH or DELETE sent to a host matching one of the
+//!   `prod_hosts` patterns of `[check]` in the config (`*` matches any run of
+//!   characters, e.g. `*.prod.example.com`)
+//! - `W002`: a test without any assertion, which passes whatever the
+//!   response
+//! - `W003`: a request placeholder naming a variable that no `VAR`,
+//!   `CAPTURE` or `[token_refresh]` defines, most likely a typo; sending it
+//!   fails the test
+//!
+//! A test silences codes it knows about with `ALLOW W001, W002` between its
+//! `TEST` line and its request.
+
+use anyhow::Result;
+use owo_colors::OwoColorize;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use crate::domain::{Location, TestCase};
+use crate::runner::Runner;
+use crate::template::{self, Variables};
+
+pub const MUTATING_PROD: &str = "W001";
+pub const NO_ASSERTIONS: &str = "W002";
+pub const UNDEFINED_VARIABLE: &str = "W003";
+
+#[derive(Debug, PartialEq)]
+pub struct Warning {
+    pub code: &'static str,
+    pub location: Option<Location>,
+    pub message: String,
+}
+
+/// Whether `host` matches `pattern`, where `*` matches any run of characters
+fn matches_host(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    let host = host.to_ascii_lowercase();
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = host.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Warnings about `test`, minus those it `ALLOW`s; `provided` names the
+/// variables set while the suite runs (captures, the refreshed token)
+pub fn check(test: &TestCase, prod_hosts: &[String], provided: &BTreeSet<&str>) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    let name = test.name.as_deref().unwrap_or("<unnamed>");
+
+    for request in test.requests() {
+        let mutating = matches!(request.method.as_str(), "POST" | "PUT" | "PATCH" | "DELETE");
+        let host = request.url.host_str().unwrap_or_default();
+        if mutating && prod_hosts.iter().any(|pattern| matches_host(pattern, host)) {
+            warnings.push(Warning {
+                code: MUTATING_PROD,
+                location: test.location.clone(),
+                message: format!(
+                    "'{}' sends {} to {}, a production-like host",
+                    name, request.method, host
+                ),
+            });
+        }
+
+        let placeholders = request.deferred.iter().flat_map(|deferred| deferred.placeholders());
+        let mut undefined: Vec<&str> = placeholders
+            .filter(|expr| template::is_variable(expr) && !provided.contains(expr))
+            .collect();
+        undefined.dedup();
+        for variable in undefined {
+            warnings.push(Warning {
+                code: UNDEFINED_VARIABLE,
+                location: test.location.clone(),
+                message: format!(
+                    "'{}' uses {{{{{}}}}}, which no VAR, CAPTURE or token refresh defines",
+                    name, variable
+                ),
+            });
+        }
+    }
+
+    let asserts =
+        !test.assertions.is_empty() || test.steps.iter().any(|step| !step.assertions.is_empty());
+    if !asserts {
+        warnings.push(Warning {
+            code: NO_ASSERTIONS,
+            location: test.location.clone(),
+            message: format!(
+                "'{}' has no assertions and passes whatever the response",
+                name
+            ),
+        });
+    }
+
+    warnings.retain(|warning| !test.allow.iter().any(|code| code == warning.code));
+    warnings
+}
+
+/// Check every test under `path` and print the warnings; returns how many
+/// there were. `refreshed` is the variable `[token_refresh]` keeps set.
+pub fn run(
+    path: &Path,
+    variables: &Variables,
+    prod_hosts: &[String],
+    refreshed: Option<&str>,
+) -> Result<usize> {
+    let files = Runner::discover(path, variables)?;
+    let captures = files
+        .iter()
+        .flat_map(|(_, tests)| tests.iter().flat_map(TestCase::all_captures));
+    let provided: BTreeSet<&str> = captures
+        .map(|capture| capture.name.as_str())
+        .chain(refreshed)
+        .collect();
+
+    let mut count = 0;
+    for (_, tests) in &files {
+        for test in tests {
+            for warning in check(test, prod_hosts, &provided) {
+                count += 1;
+                let location = warning
+                    .location
+                    .map(|location| location.to_string())
+                    .unwrap_or_default();
+                println!(
+                    "{} {} {}",
+                    location.dimmed(),
+                    warning.code.yellow().bold(),
+                    warning.message
+                );
+            }
+        }
+    }
+
+    if count == 0 {
+        println!("{} No warnings", "✓".green().bold());
+    } else {
+        println!("\n{} {} warning(s)", "!".yellow().bold(), count);
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::AxParser;
+
+    #[test]
+    fn host_patterns() {
+        assert!(matches_host("*.prod.example.com", "api.prod.example.com"));
+        assert!(matches_host("api.example.com", "API.example.com"));
+        assert!(matches_host("prod-*-db.*", "prod-eu-db.internal"));
+        assert!(!matches_host("*.prod.example.com", "api.staging.example.com"));
+        assert!(!matches_host("api.example.com", "api.example.com.evil"));
+    }
+
+    #[test]
+    fn flags_mutations_on_prod_and_tests_without_assertions() {
+        let input = r#"TEST Delete user
+DELETE https://api.prod.example.com/users/1
+
+EXPECT status == 204
+END
+
+TEST Ping
+GET https://api.prod.example.com/ping
+
+END
+
+TEST Reset
+ALLOW W001, W002
+POST https://api.prod.example.com/reset
+
+END
+"#;
+        let tests = AxParser::parse_file(input).unwrap();
+        let prod = vec!["*.prod.example.com".to_string()];
+        let codes = |test: &TestCase| -> Vec<&str> {
+            check(test, &prod, &BTreeSet::new())
+                .iter()
+                .map(|warning| warning.code)
+                .collect()
+        };
+
+        assert_eq!(codes(&tests[0]), vec![MUTATING_PROD]);
+        assert_eq!(codes(&tests[1]), vec![NO_ASSERTIONS]);
+        assert!(codes(&tests[2]).is_empty());
+        assert!(check(&tests[0], &[], &BTreeSet::new()).is_empty());
+    }
+
+    #[test]
+    fn flags_variables_nothing_defines() {
+        let input = r#"TEST Login
+POST https://example.com/login
+
+CAPTURE session = body.session
+EXPECT status == 200
+END
+
+TEST Orders
+GET https://example.com/orders/{{tpyo}}?page={{uuid()}}
+Authorization: Bearer {{token}}
+Cookie: session={{session}}
+X-Key: {{secret.api}}
+
+EXPECT status == 200
+END
+"#;
+        let tests = AxParser::parse_file(input).unwrap();
+        let provided = BTreeSet::from(["session", "token"]);
+        let warnings = check(&tests[1], &[], &provided);
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, UNDEFINED_VARIABLE);
+        assert!(warnings[0].message.contains("{{tpyo}}"));
+    }
+}
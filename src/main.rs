@@ -23,6 +23,8 @@
 //! Output formatting is delegated to a pluggable [`Renderer`] implementation,
 //! selected at runtime via CLI options. This allows the same execution pipeline
 //! to support multiple output styles (human-readable, diff-based, etc.).
+//! Several renderers can be active at once, each writing to stdout or to its
+//! own file, combined behind a [`CompositeRenderer`].
 //!
 //! ## 2. Single request mode
 //!
@@ -33,45 +35,71 @@
 //! - The request is executed immediately
 //!
 //! This mode is useful for quick inspection, debugging, or exploratory calls.
+//!
+//! ## Subcommands
+//!
+//! Auxiliary tasks such as `axotly auth login` are dispatched to the
+//! [`commands`] module before either execution mode is considered, as is
+//! `axotly tui`, the interactive dashboard (see [`tui`]).
+//!
+//! ## Exit codes
+//!
+//! The process exits with a code telling CI scripts why a run ended: test
+//! failures, invalid files, configuration errors, an unreachable network,
+//! a timeout or an interrupt each have their own (see [`exit`]).
 
 
-mod cli;
-mod domain;
-mod parser;
-mod executor;
-mod renderers;
-mod runner;
-
-use anyhow::Result;
-use cli::{Cli, RendererKind};
+use axotly::{
+    api_versions, cli, commands, config, domain, exit, lint, proxy, renderers, runner, shell_env,
+    template,
+};
+use anyhow::{Context, Result};
+use cli::{Cli, Command, RendererKind, RendererSpec};
+use config::Config;
 use clap::Parser;
 use domain::{
-    http_request::{Body, HttpRequest, HttpResponse},
-    Renderer,
+    http_request::{self, Body, HttpRequest, HttpResponse},
+    Chaos, Normalization, Renderer,
 };
+use renderers::composite::CompositeRenderer;
+use renderers::csv::CsvRenderer;
 use renderers::human::HumanRenderer;
 use renderers::diff::DiffRenderer;
+use renderers::html::HtmlRenderer;
+use renderers::junit::JunitRenderer;
+use renderers::markdown::MarkdownRenderer;
+use renderers::ndjson::NdjsonRenderer;
+use renderers::output::Output;
 use renderers::response::ResponseRenderer;
-use runner::Runner;
+use renderers::rotate::Rotation;
+use renderers::slo::Slo;
+use renderers::template::TemplateRenderer;
+use std::path::Path;
+use exit::{ErrorKind, Kind};
+use runner::{RunOptions, RunOutcome, Runner};
+use template::Variables;
 use url::Url;
 
 async fn handle_file_request(
     path: String,
-    max_concurrency: usize,
+    options: &RunOptions,
     renderer: &dyn Renderer,
-    show_response: bool,
-) -> Result<()> {
-    Runner::run_path(path, max_concurrency, renderer, show_response).await?;
-    Ok(())
+) -> Result<RunOutcome> {
+    Runner::run_path(path, options, renderer).await
 }
 
-async fn handle_single_request(args: &Cli) -> Result<()> {
+async fn handle_single_request(
+    args: &Cli,
+    config: &Config,
+    default_headers: &[(String, String)],
+) -> Result<()> {
     let url = args.url.clone().unwrap_or_else(|| {
         "http://httpbin.org/get".to_string()
     });
 
     if args.json.is_some() && args.body.is_some() {
-        anyhow::bail!("Cannot use both --body and --json options together.");
+        return Err(anyhow::anyhow!("Cannot use both --body and --json options together."))
+            .kind(ErrorKind::Invalid);
     }
 
     let mut body_content: Option<Body> = None;
@@ -79,7 +107,8 @@ async fn handle_single_request(args: &Cli) -> Result<()> {
     if args.json.is_some() {
         let json_str = args.json.clone().unwrap();
         let json_value: serde_json::Value = serde_json::from_str(&json_str)
-            .map_err(|e| anyhow::anyhow!("Invalid JSON body: {}", e))?;
+            .map_err(|e| anyhow::anyhow!("Invalid JSON body: {}", e))
+            .kind(ErrorKind::Invalid)?;
         body_content = Some(Body::Json(json_value));
     }
 
@@ -87,31 +116,215 @@ async fn handle_single_request(args: &Cli) -> Result<()> {
         body_content = Some(Body::Text(args.body.clone().unwrap()));
     }
 
-    let request = HttpRequest::new(args.method.clone(), Url::parse(&url)?)
-        .body(body_content);
+    let url = Url::parse(&url)
+        .with_context(|| format!("Invalid URL: {}", url))
+        .kind(ErrorKind::Invalid)?;
+    let mut request = HttpRequest::new(args.method.clone(), url).body(body_content);
+    for (name, value) in default_headers {
+        request.default_header(name, value);
+    }
+    if let Some(signer) = config.signer().kind(ErrorKind::Config)? {
+        signer.sign(&mut request)?;
+    }
 
-    let response: HttpResponse = request.send().await?;
-    ResponseRenderer::print_response(&response);
+    let response: HttpResponse = match request.send().await {
+        Ok(response) => response,
+        Err(error) if is_unreachable(&error) => return Err(ErrorKind::Network.wrap(error)),
+        Err(error) => return Err(error),
+    };
+    if args.emit_env {
+        print!("{}", shell_env::exports(&response, &args.capture)?);
+    } else {
+        ResponseRenderer::print_response(&response);
+    }
 
     Ok(())
 }
 
+/// Whether sending failed because the host could not be reached at all
+fn is_unreachable(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|error| error.is_connect() || error.is_timeout())
+}
+
+/// Headers from the config, then those of the `--impersonate` profile, whose
+/// header order is applied to every request
+fn default_headers(args: &Cli, config: &Config) -> Result<Vec<(String, String)>> {
+    let mut headers = config.default_headers().kind(ErrorKind::Config)?;
+    if let Some(profile) = args.impersonate {
+        headers.extend(
+            profile
+                .headers()
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string())),
+        );
+        http_request::set_header_order(profile.header_order())?;
+    }
+    Ok(headers)
+}
+
+fn open_output(path: &Path) -> Result<Output> {
+    Output::file(path).with_context(|| format!("Failed to create {}", path.display()))
+}
+
+fn build_renderer(
+    spec: &RendererSpec,
+    template: Option<&Path>,
+    full_diff: bool,
+    slos: &[Slo],
+) -> Result<Box<dyn Renderer>> {
+    let out = match (&spec.command, &spec.path) {
+        (Some(command), _) => Output::command(command)
+            .with_context(|| format!("Failed to start renderer command '{}'", command))?,
+        (None, Some(path)) => open_output(path)?,
+        (None, None) => Output::stdout(),
+    };
+
+    let renderer: Box<dyn Renderer> = match spec.kind {
+        RendererKind::Human => {
+            Box::new(HumanRenderer::new(out, full_diff).with_slos(slos.to_vec()))
+        }
+        RendererKind::Diff => Box::new(DiffRenderer::new(out, full_diff)),
+        RendererKind::Junit => Box::new(JunitRenderer::new(out)),
+        RendererKind::Ndjson => Box::new(NdjsonRenderer::new(out)),
+        RendererKind::Markdown => Box::new(MarkdownRenderer::new(out, full_diff)),
+        RendererKind::Csv => Box::new(CsvRenderer::new(out)),
+        RendererKind::Template => {
+            let template = template.context("The template renderer requires --template <PATH>")?;
+            Box::new(TemplateRenderer::new(out, template)?)
+        }
+    };
+
+    Ok(renderer)
+}
+
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
     let args = Cli::parse();
-    
-    let renderer: Box<dyn Renderer> = match args.renderer {
-        RendererKind::Human => Box::new(HumanRenderer::new()),
-        RendererKind::Diff => Box::new(DiffRenderer::new()),
+
+    // Dropping the run on Ctrl-C flushes and closes the renderer outputs
+    let code = tokio::select! {
+        result = run(args) => result.unwrap_or_else(|error| {
+            eprintln!("Error: {:?}", error);
+            exit::for_error(&error)
+        }),
+        _ = tokio::signal::ctrl_c() => {
+            eprintln!("\nInterrupted");
+            exit::INTERRUPTED
+        }
     };
+    std::process::exit(code);
+}
 
+/// Run what the arguments ask for; returns the exit code (see [`exit`])
+async fn run(args: Cli) -> Result<i32> {
+    if args.no_hyperlinks {
+        renderers::hyperlink::disable();
+    }
+
+    if let Some(command) = args.command {
+        let result = match command {
+            Command::Auth { action } => commands::auth::run(action),
+            Command::Fixtures { action } => {
+                let config = Config::load(args.config.as_deref()).kind(ErrorKind::Config)?;
+                commands::fixtures::run(action, &config).await
+            }
+            Command::Proxy { port, out } => proxy::serve(port, out.into()).await,
+            Command::Migrate { file, check } => commands::migrate::run(file, check),
+            Command::Inventory { file, format } => commands::inventory::run(file, format),
+            Command::SelfUpdate { check } => commands::self_update::run(check).await,
+            Command::Tui {
+                file,
+                env,
+                concurrently,
+            } => {
+                let config = Config::load(args.config.as_deref()).kind(ErrorKind::Config)?;
+                commands::tui::run(file, env, concurrently, &config).await
+            }
+        };
+        return result.map(|()| exit::SUCCESS);
+    }
+
+    let config = Config::load(args.config.as_deref()).kind(ErrorKind::Config)?;
+
+    if let (true, Some(path)) = (args.check, &args.file) {
+        let path = Path::new(path);
+        let options = RunOptions {
+            env: args.env.clone(),
+            ..Default::default()
+        };
+        let variables = Runner::variables(path, &options)?;
+        let refreshed = config.token_refresh.as_ref().map(|refresh| refresh.variable.as_str());
+        let warnings = lint::run(path, &variables, &config.check.prod_hosts, refreshed)?;
+        return Ok(if warnings == 0 {
+            exit::SUCCESS
+        } else {
+            exit::TEST_FAILURES
+        });
+    }
+
+    let slos = config.slos().kind(ErrorKind::Config)?;
+    let mut renderers = Vec::new();
+    for spec in &args.renderer {
+        renderers.push(build_renderer(spec, args.template.as_deref(), args.full_diff, &slos)?);
+    }
+    if let Some(path) = &args.report_html {
+        let html = HtmlRenderer::new(open_output(path)?).with_slos(slos.clone());
+        renderers.push(Box::new(html));
+    }
+    if let Some(path) = &args.log {
+        let rotation = Rotation {
+            max_bytes: Some(args.log_max_size),
+            daily: args.log_daily,
+            keep: args.log_keep,
+        };
+        let out = Output::rotating(path, rotation)
+            .with_context(|| format!("Failed to open log {}", path.display()))?;
+        renderers.push(Box::new(NdjsonRenderer::new(out)));
+    }
+    let renderer = CompositeRenderer::new(renderers);
+
+    let default_headers = default_headers(&args, &config)?;
     if let Some(path) = args.file {
-        handle_file_request(path, args.concurrently, renderer.as_ref(), args.show_response).await?;
+        let options = RunOptions {
+            max_concurrency: args.concurrently,
+            show_response: args.show_response,
+            normalization: Normalization::new(args.sort_keys, args.ignore_path),
+            chaos: Chaos {
+                delay: args.chaos_delay,
+                drop_percent: args.chaos_drop,
+                duplicate_percent: args.chaos_duplicate,
+            },
+            default_headers,
+            run_timeout: args.run_timeout,
+            circuit_threshold: args.circuit_breaker,
+            preresolve: !args.no_preresolve,
+            env: args.env.clone(),
+            signer: config.signer().kind(ErrorKind::Config)?,
+            refresher: config.refresher().kind(ErrorKind::Config)?,
+            sources: config.sources().kind(ErrorKind::Config)?,
+            changed_since: args.changed_since.clone(),
+            artifacts: args.artifacts.clone(),
+            rerun_failed: args.rerun_failed,
+            confirm: args.confirm,
+            timeline: args.timeline,
+            stream: args.stream,
+            order: args.order.unwrap_or_default(),
+            overrides: Variables::new(),
+            baseline: args.baseline.clone(),
+        };
+        let outcome = if args.api_versions.is_empty() {
+            handle_file_request(path, &options, &renderer).await?
+        } else {
+            let path = Path::new(&path);
+            api_versions::run(path, &options, &config, &args.api_versions, &renderer).await?
+        };
+        Ok(exit::for_outcome(outcome))
     } else {
         // Single request mode
-        handle_single_request(&args).await?;
+        handle_single_request(&args, &config, &default_headers).await?;
+        Ok(exit::SUCCESS)
     }
-
-    Ok(())
 }
 
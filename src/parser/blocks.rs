@@ -0,0 +1,629 @@
+//! Block-by-block parsing of `.ax` files.
+//!
+//! [`AxParser::parse_file_in`] builds the parse tree of the whole file before
+//! the first test exists, which for files with thousands of tests means a
+//! large tree in memory and nothing to run until the end (`cargo bench
+//! --bench parse_memory` measures it). [`Blocks`] instead splits the source
+//! at its top-level lines and parses each `TEST ... END` block on its own,
+//! yielding its tests before reading further. `VAR` and `AUTH` lines, and
+//! `VARS` blocks, apply to the blocks below them, as in a full parse.
+//!
+//! A block ends at the first `END` line that is not inside a `BODY`, which is
+//! how the grammar reads it too. Lines between blocks starting with `#` are
+//! comments ([`strip_comments`]).
+//!
+//! The same split lets a file with a malformed block still run:
+//! [`AxParser::parse_file_recovering`] leaves the block out with a diagnostic
+//! naming its line, and parses the rest.
+
+use anyhow::{anyhow, bail, Context, Result};
+use pest::error::LineColLocation;
+use pest::Parser;
+use std::path::Path;
+
+use super::parser::{
+    expand_test_block, original_line, parse_auth, parse_variable, AxParser, Rule,
+};
+use super::syntax;
+use crate::domain::auth::Auth;
+use crate::domain::TestCase;
+use crate::template::{self, Variables};
+
+/// First word of a line, e.g. `TEST` for `TEST Create user`
+fn keyword(line: &str) -> &str {
+    line.split_whitespace().next().unwrap_or_default()
+}
+
+/// Whether the `EXPECT` line `line` compares with inline JSON (`== JSON`)
+fn inline_json(line: &str) -> bool {
+    line.split_once("==")
+        .is_some_and(|(_, rest)| rest.trim_start().starts_with("JSON"))
+}
+
+/// Whether `line` closes inline JSON: it ends with `END`, possibly followed
+/// by the assertion's modifiers (`END SENSITIVE MINOR`)
+fn closes_json(line: &str) -> bool {
+    let mut rest = line.trim_end();
+    for modifiers in [&["CRITICAL", "MINOR"][..], &["SENSITIVE"]] {
+        if let Some(modifier) = modifiers.iter().find(|modifier| rest.ends_with(*modifier)) {
+            rest = rest[..rest.len() - modifier.len()].trim_end();
+        }
+    }
+    rest.ends_with("END")
+}
+
+/// Byte length of the `TEST` block at the start of `text`, through its `END`
+/// line
+fn block_len(text: &str) -> Option<usize> {
+    let mut in_body = false;
+    let mut in_json = false;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        offset += line.len();
+        let trimmed = line.trim();
+        if in_body {
+            in_body = !trimmed.contains("BODYEND");
+        } else if in_json {
+            in_json = !closes_json(trimmed);
+        } else if keyword(trimmed) == "EXPECT" && inline_json(trimmed) {
+            // `EXPECT body == JSON` spans lines up to its own `END`
+            in_json = !closes_json(trimmed);
+        } else if keyword(trimmed) == "BODY" {
+            // `BODY STREAM` is a single line
+            in_body = trimmed.split_whitespace().nth(1) != Some("STREAM");
+        } else if trimmed == "END" {
+            return Some(offset);
+        }
+    }
+    None
+}
+
+/// Byte length of the `VARS` block at the start of `text`, through its `END`
+/// line
+fn vars_len(text: &str) -> Option<usize> {
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        offset += line.len();
+        if line.trim() == "END" {
+            return Some(offset);
+        }
+    }
+    None
+}
+
+/// `file` with its comment lines blanked: the lines between blocks whose
+/// first non-blank character is `#`. Line numbers stay the same, and a
+/// `${NAME}` in a comment is neither substituted nor required.
+pub fn strip_comments(file: &str) -> String {
+    let mut output = String::with_capacity(file.len());
+    let mut rest = file;
+    while let Some(line) = rest.split_inclusive('\n').next() {
+        let len = match keyword(line) {
+            "TEST" => block_len(rest),
+            "VARS" => vars_len(rest),
+            _ => None,
+        }
+        .unwrap_or(line.len());
+        if line.trim_start().starts_with('#') {
+            output.push_str(&line[line.trim_end().len()..]);
+        } else {
+            output.push_str(&rest[..len]);
+        }
+        rest = &rest[len..];
+    }
+    output
+}
+
+/// `error`, from parsing text that starts at line `start` of the file, as
+/// a one-line diagnostic with the file's line number (mapped by `origins`)
+fn diagnostic(error: pest::error::Error<Rule>, start: usize, origins: &[usize]) -> anyhow::Error {
+    let (line, column) = match error.line_col {
+        LineColLocation::Pos(at) | LineColLocation::Span(at, _) => at,
+    };
+    anyhow!(
+        "Line {}, column {}: {} in '{}'",
+        original_line(origins, start + line - 1),
+        column,
+        error.variant.message(),
+        error.line().trim()
+    )
+}
+
+/// Tests of a `.ax` file, one block at a time; see the module docs
+pub struct Blocks<'a> {
+    /// The whole file once upgraded and its conditionals applied; only the
+    /// parse is split by block
+    source: String,
+    /// Line of the file as written for each line of `source`
+    origins: Vec<usize>,
+    offset: usize,
+    line: usize,
+    variables: Variables,
+    auth: Option<Auth>,
+    base_dir: &'a Path,
+    done: bool,
+}
+
+impl AxParser {
+    /// Parse `file`, stored in `base_dir`, block by block
+    pub fn blocks<'a>(file: &str, variables: &Variables, base_dir: &'a Path) -> Result<Blocks<'a>> {
+        let source = strip_comments(&syntax::upgrade(file)?);
+        let (source, origins) = template::conditionals(&template::environment(&source), variables)?;
+        Ok(Blocks {
+            source,
+            origins,
+            offset: 0,
+            line: 1,
+            variables: variables.clone(),
+            auth: None,
+            base_dir,
+            done: false,
+        })
+    }
+
+    /// Parse `file` like [`AxParser::parse_file_in`], but leave out the
+    /// blocks and lines that do not parse instead of failing. Returns the
+    /// tests, and why each block was left out.
+    pub fn parse_file_recovering(
+        file: &str,
+        variables: &Variables,
+        base_dir: &Path,
+    ) -> Result<(Vec<TestCase>, Vec<anyhow::Error>)> {
+        let error = match Self::parse_file_in(file, variables, base_dir) {
+            Ok(tests) => return Ok((tests, Vec::new())),
+            Err(error) => error,
+        };
+
+        let mut tests = Vec::new();
+        let mut skipped = Vec::new();
+        for block in Self::blocks(file, variables, base_dir)? {
+            match block {
+                Ok(block) => tests.extend(block),
+                Err(error) => skipped.push(error),
+            }
+        }
+        // Nothing to point at block by block: the file as a whole is wrong
+        if skipped.is_empty() {
+            return Err(error);
+        }
+        Ok((tests, skipped))
+    }
+}
+
+impl Blocks<'_> {
+    /// Parse the top-level item at the current offset: `None` for lines that
+    /// only update state, the tests of a block otherwise
+    fn step(&mut self) -> Result<Option<Vec<TestCase>>> {
+        let rest = &self.source[self.offset..];
+        let line = rest.split_inclusive('\n').next().unwrap_or(rest);
+        let start = self.line;
+
+        let len = match keyword(line) {
+            "" | "SYNTAX" => line.len(),
+            "VAR" | "AUTH" => {
+                let text = format!("{}\n", line.trim_end());
+                let rule = if keyword(line) == "VAR" {
+                    Rule::variable
+                } else {
+                    Rule::auth
+                };
+                let pair = AxParser::parse(rule, &text)
+                    .map_err(|e| diagnostic(e, start, &self.origins))?
+                    .next()
+                    .unwrap();
+                if rule == Rule::variable {
+                    let (name, value) = parse_variable(pair, &self.variables)?;
+                    self.variables.insert(name, value);
+                } else {
+                    self.auth = Some(parse_auth(pair, &self.variables)?);
+                }
+                line.len()
+            }
+            "VARS" => {
+                let len = vars_len(rest).with_context(|| {
+                    format!(
+                        "Line {}: VARS block without END",
+                        original_line(&self.origins, start)
+                    )
+                })?;
+                let pair = AxParser::parse(Rule::vars, &rest[..len])
+                    .map_err(|e| diagnostic(e, start, &self.origins))?
+                    .next()
+                    .unwrap();
+                for entry in pair.into_inner() {
+                    let (name, value) = parse_variable(entry, &self.variables)?;
+                    self.variables.insert(name, value);
+                }
+                len
+            }
+            "TEST" => {
+                let written = original_line(&self.origins, start);
+                let len = block_len(rest)
+                    .with_context(|| format!("Line {}: TEST block without END", written))?;
+                let pair = AxParser::parse(Rule::test_block, &rest[..len])
+                    .map_err(|e| diagnostic(e, start, &self.origins))?
+                    .next()
+                    .unwrap();
+                let mut tests =
+                    expand_test_block(pair, &self.variables, self.auth.as_ref(), self.base_dir)
+                        .with_context(|| format!("Block at line {}", written))?;
+                for test in &mut tests {
+                    test.offset_lines(start - 1);
+                    test.map_lines(|line| original_line(&self.origins, line));
+                }
+                self.advance(len);
+                return Ok(Some(tests));
+            }
+            other => bail!(
+                "Line {}: expected VAR, VARS, AUTH or TEST, found '{}'",
+                original_line(&self.origins, start),
+                other
+            ),
+        };
+        self.advance(len);
+        Ok(None)
+    }
+
+    /// Move past the item at the current offset, which failed to parse: the
+    /// whole block for a `TEST`, the line otherwise
+    fn skip(&mut self) {
+        let rest = &self.source[self.offset..];
+        let line = rest.split_inclusive('\n').next().unwrap_or(rest);
+        let len = match keyword(line) {
+            "TEST" => block_len(rest),
+            "VARS" => vars_len(rest),
+            _ => Some(line.len()),
+        };
+        match len {
+            Some(len) => self.advance(len),
+            // A block without END runs to the end of the file
+            None => self.done = true,
+        }
+    }
+
+    fn advance(&mut self, len: usize) {
+        let end = self.offset + len;
+        self.line += self.source[self.offset..end].matches('\n').count();
+        self.offset = end;
+    }
+}
+
+impl Iterator for Blocks<'_> {
+    type Item = Result<Vec<TestCase>>;
+
+    /// Tests of the next block, or why it does not parse; the blocks after
+    /// a failed one still follow
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done && self.offset < self.source.len() {
+            match self.step() {
+                Ok(Some(tests)) => return Some(Ok(tests)),
+                Ok(None) => {}
+                Err(error) => {
+                    self.skip();
+                    return Some(Err(error));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Assertion;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    const FILE: &str = "\
+VAR host = example.com
+
+TEST Create
+POST https://{{host}}/users
+
+BODY
+{\"note\": \"line with END\"}
+END
+BODYEND
+
+EXPECT status == 201
+END
+
+AUTH BEARER abc
+TEST List
+GET https://{{host}}/users
+
+EXPECT status == 200
+END
+";
+
+    #[test]
+    fn comments_between_blocks_are_blanked() {
+        let input = "# token was ${AXOTLY_UNSET_OLD_TOKEN}\n\
+TEST Notes
+POST https://example.com/notes
+
+BODY
+# heading ${AXOTLY_UNSET_OLD_TOKEN}
+BODYEND
+
+EXPECT status == 201
+END
+  # done
+";
+        let stripped = strip_comments(input);
+        assert_eq!(stripped.lines().count(), input.lines().count());
+        assert!(stripped.starts_with("\nTEST Notes\n"));
+        assert!(stripped.contains("# heading"));
+        assert!(stripped.ends_with("END\n\n"));
+        assert_eq!(
+            template::missing_environment(&strip_comments("# ${AXOTLY_UNSET_OLD_TOKEN}\n")),
+            Vec::<String>::new()
+        );
+
+        let tests = AxParser::parse_file(&input.replace("${AXOTLY_UNSET_OLD_TOKEN}", "x")).unwrap();
+        assert_eq!(tests[0].location.as_ref().unwrap().start, 2);
+    }
+
+    #[test]
+    fn blocks_match_a_full_parse() {
+        let tests: Vec<TestCase> = AxParser::blocks(FILE, &Variables::new(), Path::new(""))
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect();
+        let full = AxParser::parse_file(FILE).unwrap();
+
+        assert_eq!(tests.len(), full.len());
+        for (block, whole) in tests.iter().zip(&full) {
+            assert_eq!(block.name, whole.name);
+            assert_eq!(block.request.url, whole.request.url);
+            assert_eq!(block.request.headers, whole.request.headers);
+            assert_eq!(block.assertions, whole.assertions);
+            assert_eq!(block.location, whole.location);
+            assert_eq!(block.assertion_lines, whole.assertion_lines);
+        }
+    }
+
+    #[test]
+    fn vars_block_applies_below() {
+        let input = "\
+VARS
+  host = example.com
+  base = \"https://{{host}}/v2\"
+
+  user_id = 42
+END
+
+TEST User
+GET {{base}}/users/{{user_id}}
+
+EXPECT status == 200
+END
+";
+        let blocks: Vec<TestCase> = AxParser::blocks(input, &Variables::new(), Path::new(""))
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect();
+        let full = AxParser::parse_file(input).unwrap();
+
+        assert_eq!(full[0].request.url.as_str(), "https://example.com/v2/users/42");
+        assert_eq!(blocks[0].request.url, full[0].request.url);
+        assert_eq!(blocks[0].location, full[0].location);
+
+        let unclosed = "VARS\nhost = example.com\n";
+        assert!(AxParser::blocks(unclosed, &Variables::new(), Path::new(""))
+            .unwrap()
+            .all(|block| block.is_err()));
+    }
+
+    #[test]
+    fn inline_json_spans_lines() {
+        let input = "\
+TEST User
+GET https://example.com/user
+
+EXPECT body == JSON
+{
+  \"name\": \"alice\",
+  \"tags\": [\"END\"]
+}
+END
+EXPECT body.kind == \"JSON\"
+END
+
+TEST Other
+GET https://example.com/other
+
+EXPECT body == JSON {\"ok\": true} END
+END
+";
+        let tests: Vec<TestCase> = AxParser::blocks(input, &Variables::new(), Path::new(""))
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect();
+        assert_eq!(tests.len(), 2);
+        assert_eq!(tests[0].assertions.len(), 2);
+        assert_eq!(
+            tests[1].assertions[0],
+            Assertion::EqualsJson {
+                path: "body".to_string(),
+                expected: serde_json::json!({"ok": true}),
+            }
+        );
+    }
+
+    #[test]
+    fn inline_json_end_takes_modifiers() {
+        let input = "\
+TEST Secret
+GET https://example.com/user
+
+EXPECT body == JSON {\"ssn\": \"000\"} END SENSITIVE
+EXPECT body == JSON
+{\"ok\": true}
+END SENSITIVE MINOR
+END
+
+TEST Other
+GET https://example.com/other
+
+EXPECT status == 200
+END
+";
+        let tests: Vec<TestCase> = AxParser::blocks(input, &Variables::new(), Path::new(""))
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect();
+        let full = AxParser::parse_file(input).unwrap();
+
+        assert_eq!(tests.len(), 2);
+        assert_eq!(tests[0].assertions, full[0].assertions);
+        assert!(matches!(tests[0].assertions[0], Assertion::Sensitive { .. }));
+        assert!(tests[0].assertions[1].is_minor());
+    }
+
+    #[test]
+    fn conditional_lines_keep_their_numbers() {
+        let input = "\
+TEST One
+GET https://example.com/1
+{{#if debug}}
+X-Debug: 1
+{{/if}}
+
+END
+
+TEST Two
+GET https://example.com/2
+
+EXPECT status == 200
+END
+";
+        let tests: Vec<TestCase> = AxParser::blocks(input, &Variables::new(), Path::new(""))
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect();
+        assert!(tests[0].request.headers.is_empty());
+        assert_eq!(tests[1].location.as_ref().unwrap().start, 9);
+        assert_eq!(tests[1].assertion_lines, vec![12]);
+
+        let full = AxParser::parse_file(input).unwrap();
+        assert_eq!(full[1].location, tests[1].location);
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let input = "TEST One\nGET https://example.com\n\nEND\nnonsense\n";
+        let mut blocks = AxParser::blocks(input, &Variables::new(), Path::new("")).unwrap();
+        assert!(blocks.next().unwrap().is_ok());
+        let error = blocks.next().unwrap().unwrap_err();
+        assert!(error.to_string().starts_with("Line 5:"), "{}", error);
+        assert!(blocks.next().is_none());
+    }
+
+    #[test]
+    fn malformed_blocks_are_left_out() {
+        let input = "\
+TEST One
+GET https://example.com/1
+
+END
+
+TEST Two
+GET https://example.com/2
+
+EXPECT status === 200
+END
+
+TEST Three
+GET https://example.com/3
+
+END
+";
+        let (tests, skipped) =
+            AxParser::parse_file_recovering(input, &Variables::new(), Path::new("")).unwrap();
+        let names: Vec<_> = tests
+            .iter()
+            .filter_map(|test| test.name.as_deref())
+            .collect();
+        assert_eq!(names, ["One", "Three"]);
+        assert_eq!(skipped.len(), 1);
+        assert!(
+            skipped[0].to_string().starts_with("Line 9,"),
+            "{}",
+            skipped[0]
+        );
+
+        let (tests, skipped) =
+            AxParser::parse_file_recovering(FILE, &Variables::new(), Path::new("")).unwrap();
+        assert_eq!((tests.len(), skipped.len()), (2, 0));
+    }
+
+    /// Parses mutations of the example files and checks that nothing panics.
+    /// Set `AXOTLY_FUZZ_ITERATIONS` to run longer than the default.
+    #[test]
+    fn fuzz_malformed_input() {
+        const TOKENS: &[&str] = &[
+            "TEST", "END", "BODY", "BODYEND", "EXPECT", "VAR", "AUTH", "GET", "QUERY", "{{", "}}",
+            "\"", "==", "\u{feff}", "é", "",
+        ];
+        let iterations: usize = std::env::var("AXOTLY_FUZZ_ITERATIONS")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(500);
+
+        let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+        let mut corpus = vec![FILE.to_string()];
+        for entry in std::fs::read_dir(&examples).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "ax") {
+                corpus.push(std::fs::read_to_string(path).unwrap());
+            }
+        }
+
+        let mut rng = StdRng::seed_from_u64(2776);
+        for _ in 0..iterations {
+            let seed = &corpus[rng.gen_range(0..corpus.len())];
+            let mut lines: Vec<String> = seed.lines().map(str::to_string).collect();
+            for _ in 0..rng.gen_range(1..4) {
+                if lines.is_empty() {
+                    break;
+                }
+                let at = rng.gen_range(0..lines.len());
+                match rng.gen_range(0..4) {
+                    0 => {
+                        lines.remove(at);
+                    }
+                    1 => {
+                        let line = lines[at].clone();
+                        lines.insert(at, line);
+                    }
+                    2 => lines[at] = TOKENS[rng.gen_range(0..TOKENS.len())].to_string(),
+                    _ => {
+                        let token = TOKENS[rng.gen_range(0..TOKENS.len())];
+                        lines[at].push(' ');
+                        lines[at].push_str(token);
+                    }
+                }
+            }
+            let input = lines.join("\n") + "\n";
+
+            let _ = AxParser::parse_file_recovering(&input, &Variables::new(), &examples);
+        }
+    }
+}
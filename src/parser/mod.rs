@@ -1,3 +1,6 @@
-
+pub mod blocks;
+#[allow(clippy::module_inception)]
 pub mod parser;
+pub mod syntax;
+pub mod tree;
 pub use parser::AxParser;
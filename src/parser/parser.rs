@@ -3,12 +3,30 @@ use anyhow::{Context, Result};
 use pest::iterators::Pair;
 use pest::Parser;
 use pest_derive::Parser;
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use url::Url;
 
-use crate::domain::assertion::{Operator, Value};
-use crate::domain::http_request::{Body, HttpRequest};
-use crate::domain::{Assertion, TestCase};
+use crate::domain::auth::Auth;
+use crate::domain::assertion::{
+    is_wildcard, JsonType, NegotiationVariant, Operator, PollOrder, TextMatch, Value,
+};
+use crate::duration::parse_duration;
+use crate::domain::decode::Decode;
+use crate::domain::expect_continue;
+use crate::domain::encode::{self, Encode};
+use crate::domain::http_request::{
+    append_query, Body, BodyStream, FormPart, HttpRequest, RequestTemplate,
+};
+use crate::size::parse_size;
+use crate::domain::test_case::{Capture, Pacing, Retry, Step};
+use crate::dataset;
+use super::blocks::strip_comments;
+use super::syntax;
+use crate::domain::{Assertion, Location, TestCase};
+use crate::template::{self, Variables};
 
 #[derive(Parser)]
 #[grammar = "src/parser/grammar.pest"]
@@ -16,7 +34,27 @@ pub struct AxParser;
 
 impl AxParser {
     /// Parse a full .ax file from its contents
-    pub fn parse_file(file: &String) -> Result<Vec<TestCase>> {
+    pub fn parse_file(file: &str) -> Result<Vec<TestCase>> {
+        Self::parse_file_with(file, &Variables::new())
+    }
+
+    /// Parse a full .ax file, with `variables` available to its placeholders.
+    /// `VAR` lines and `VARS` blocks add to (or override) them for the rest of
+    /// the file.
+    pub fn parse_file_with(file: &str, variables: &Variables) -> Result<Vec<TestCase>> {
+        Self::parse_file_in(file, variables, Path::new(""))
+    }
+
+    /// Parse a .ax file stored in `base_dir`, which `WITH DATA` paths are
+    /// relative to
+    pub fn parse_file_in(
+        file: &str,
+        variables: &Variables,
+        base_dir: &Path,
+    ) -> Result<Vec<TestCase>> {
+        let file = strip_comments(&syntax::upgrade(file)?);
+        let (file, origins) = template::conditionals(&template::environment(&file), variables)?;
+
         // Parse the file content using Pest
         let mut pairs = AxParser::parse(Rule::file, file.as_str())
             .map_err(|e| anyhow::anyhow!("Failed to parse input: {}", e))?;
@@ -26,26 +64,78 @@ impl AxParser {
             .next()
             .ok_or_else(|| anyhow::anyhow!("Empty .ax file"))?;
 
-        // Now parse each test_block inside the file
+        // Now parse each variable and test_block inside the file, in order
+        let mut variables = variables.clone();
+        let mut auth: Option<Auth> = None;
         let mut tests = Vec::new();
         for inner in file_pair.into_inner() {
-            if inner.as_rule() == Rule::test_block {
-                let test_case = parse_test_block(inner)?;
-                tests.push(test_case);
+            match inner.as_rule() {
+                Rule::variable => {
+                    let (name, value) = parse_variable(inner, &variables)?;
+                    variables.insert(name, value);
+                }
+                Rule::vars => {
+                    for entry in inner.into_inner() {
+                        let (name, value) = parse_variable(entry, &variables)?;
+                        variables.insert(name, value);
+                    }
+                }
+                Rule::auth => auth = Some(parse_auth(inner, &variables)?),
+                Rule::test_block => {
+                    tests.extend(expand_test_block(inner, &variables, auth.as_ref(), base_dir)?);
+                }
+                _ => {}
             }
         }
+        for test in &mut tests {
+            test.map_lines(|line| original_line(&origins, line));
+        }
 
         Ok(tests)
     }
 }
 
-pub fn parse_http_request(pair: Pair<Rule>) -> Result<HttpRequest> {
+/// Line of the file as written for `line` of the file once its `{{#if}}`
+/// blocks are resolved (see [`template::conditionals`])
+pub(super) fn original_line(origins: &[usize], line: usize) -> usize {
+    line.checked_sub(1)
+        .and_then(|index| origins.get(index))
+        .copied()
+        .unwrap_or(line)
+}
+
+/// Parse `VAR name = value`, or a `name = value` line of `VARS`; the value
+/// may be quoted and may use earlier variables
+pub(super) fn parse_variable(pair: Pair<Rule>, variables: &Variables) -> Result<(String, String)> {
+    let mut inner = pair.into_inner();
+    let name = inner.next().unwrap().as_str().to_string();
+    let raw = inner.next().unwrap().as_str().trim();
+    let raw = raw
+        .strip_prefix('"')
+        .and_then(|r| r.strip_suffix('"'))
+        .unwrap_or(raw);
+
+    let value = template::prerender(raw, variables)
+        .with_context(|| format!("Failed to render variable '{}'", name))?;
+    Ok((name, value))
+}
+
+pub fn parse_http_request(pair: Pair<Rule>, variables: &Variables) -> Result<HttpRequest> {
     debug_assert_eq!(pair.as_rule(), Rule::request);
 
     let mut method: Option<String> = None;
     let mut url: Option<Url> = None;
     let mut headers: HashMap<String, String> = HashMap::new();
     let mut body: Option<Body> = None;
+    let mut cookies: Vec<String> = Vec::new();
+    let mut raw = RequestTemplate {
+        url: String::new(),
+        query: Vec::new(),
+        headers: Vec::new(),
+        basic: None,
+        body: None,
+        encode: None,
+    };
 
     for inner in pair.into_inner() {
         match inner.as_rule() {
@@ -53,25 +143,76 @@ pub fn parse_http_request(pair: Pair<Rule>) -> Result<HttpRequest> {
                 method = Some(inner.as_str().to_string());
             }
             Rule::url => {
-                let url_str = inner.as_str();
-                url =
-                    Some(Url::parse(url_str).with_context(|| format!("Invalid URL: {}", url_str))?);
+                let url_str = template::prerender_request(inner.as_str(), variables)?;
+                url = Some(
+                    Url::parse(&url_str).with_context(|| format!("Invalid URL: {}", url_str))?,
+                );
+                raw.url = url_str;
+            }
+            Rule::query => {
+                for param in inner.into_inner() {
+                    let mut param_inner = param.into_inner();
+                    let name = param_inner.next().unwrap().as_str().to_string();
+                    let value = param_inner.next().map_or("", |value| value.as_str().trim());
+                    raw.query
+                        .push((name, template::prerender_request(value, variables)?));
+                }
+                if let Some(url) = &mut url {
+                    append_query(url, &raw.query);
+                }
+            }
+            Rule::cookies => {
+                for cookie in inner.into_inner() {
+                    let mut cookie_inner = cookie.into_inner();
+                    let name = cookie_inner.next().unwrap().as_str();
+                    let value = cookie_inner.next().map_or("", |value| value.as_str().trim());
+                    cookies.push(format!("{}={}", name, template::prerender_request(value, variables)?));
+                }
             }
             Rule::headers => {
                 for header_pair in inner.into_inner() {
                     debug_assert_eq!(header_pair.as_rule(), Rule::header);
                     let mut header_inner = header_pair.into_inner();
                     let key = header_inner.next().unwrap().as_str().to_string();
-                    let value = header_inner.next().unwrap().as_str().to_string();
+                    let value = template::prerender_request(header_inner.next().unwrap().as_str(), variables)?;
+                    raw.headers.push((key.clone(), value.clone()));
                     headers.insert(key, value);
                 }
             }
 
+            Rule::multipart => body = Some(parse_multipart(inner, variables)?),
             Rule::body => {
                 for body_inner in inner.into_inner() {
-                    if body_inner.as_rule() == Rule::body_content {
-                        let text = body_inner.as_str().to_string();
-                        body = Some(Body::Text(text));
+                    match body_inner.as_rule() {
+                        Rule::body_format => raw.encode = Some(parse_body_format(body_inner)?),
+                        Rule::body_stream => {
+                            body = Some(Body::Stream(parse_body_stream(body_inner, variables)?))
+                        }
+                        Rule::body_content => {
+                            let text = template::prerender_request(body_inner.as_str(), variables)?;
+                            raw.body = Some(text.clone());
+                            body = match &raw.encode {
+                                // Encoding waits for run-time placeholders, or
+                                // for file paths to be resolved; the JSON is
+                                // still checked now
+                                Some(encode)
+                                    if encode.needs_files()
+                                        || !template::placeholders(&text).is_empty() =>
+                                {
+                                    encode.check_template(&text)?;
+                                    None
+                                }
+                                Some(encode) => Some(encode.body(&text)?),
+                                None if declares_json(&headers) => {
+                                    encode::check_json(&text).context(
+                                        "BODY is not valid JSON, though its Content-Type is",
+                                    )?;
+                                    Some(Body::Text(text))
+                                }
+                                None => Some(Body::Text(text)),
+                            };
+                        }
+                        _ => {}
                     }
                 }
             }
@@ -79,36 +220,424 @@ pub fn parse_http_request(pair: Pair<Rule>) -> Result<HttpRequest> {
         }
     }
 
+    if !cookies.is_empty() {
+        set_cookies(&mut headers, &mut raw, &cookies);
+    }
+
+    if let Some(encode) = &raw.encode {
+        if !headers.keys().any(|key| key.eq_ignore_ascii_case("content-type")) {
+            headers.insert("Content-Type".to_string(), encode.content_type().to_string());
+        }
+    }
+
+    // Keep the text around when placeholders remain, e.g. {{token}} captured
+    // by an earlier test
+    let needs_files = raw.encode.as_ref().is_some_and(Encode::needs_files);
+    let deferred = if raw.placeholders().is_empty() && !needs_files {
+        None
+    } else {
+        Some(raw)
+    };
+
     Ok(HttpRequest {
         method: method.unwrap_or_default(),
         url: url.context("HTTP request missing URL")?,
         headers,
         body,
+        decode: None,
+        deferred,
+        share: false,
+    })
+}
+
+/// Whether `headers` send a JSON Content-Type (`application/json`, or a
+/// `+json` type such as `application/merge-patch+json`)
+fn declares_json(headers: &HashMap<String, String>) -> bool {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-type"))
+        .and_then(|(_, value)| value.split(';').next())
+        .map(|media| media.trim().to_ascii_lowercase())
+        .is_some_and(|media| media == "application/json" || media.ends_with("+json"))
+}
+
+/// Add `COOKIES` to the request's Cookie header, after any written by hand
+fn set_cookies(
+    headers: &mut HashMap<String, String>,
+    raw: &mut RequestTemplate,
+    cookies: &[String],
+) {
+    let key = headers
+        .keys()
+        .find(|key| key.eq_ignore_ascii_case("cookie"))
+        .cloned()
+        .unwrap_or_else(|| "Cookie".to_string());
+    let value = std::iter::once(headers.get(&key).cloned().unwrap_or_default())
+        .filter(|existing| !existing.is_empty())
+        .chain(cookies.iter().cloned())
+        .collect::<Vec<_>>()
+        .join("; ");
+
+    raw.headers.retain(|(name, _)| *name != key);
+    raw.headers.push((key.clone(), value.clone()));
+    headers.insert(key, value);
+}
+
+/// `AUTH BASIC user pass`, `AUTH BEARER token` or `AUTH NONE`
+pub(super) fn parse_auth(pair: Pair<Rule>, variables: &Variables) -> Result<Auth> {
+    let kind = pair.into_inner().next().unwrap();
+    let rule = kind.as_rule();
+    let args = kind
+        .into_inner()
+        .map(|arg| template::prerender(arg.as_str(), variables))
+        .collect::<Result<Vec<_>>>()?;
+    let mut args = args.into_iter();
+
+    match rule {
+        Rule::auth_basic => Ok(Auth::basic(args.next().unwrap(), args.next().unwrap())),
+        Rule::auth_bearer => Ok(Auth::Bearer(args.next().unwrap())),
+        _ => Ok(Auth::None),
+    }
+}
+
+/// Parts of a `MULTIPART` block; upload paths are resolved later, with the
+/// test's other files
+fn parse_multipart(pair: Pair<Rule>, variables: &Variables) -> Result<Body> {
+    let mut parts = Vec::new();
+
+    for part in pair.into_inner() {
+        let mut inner = part.into_inner();
+        let name = inner.next().unwrap().as_str().to_string();
+        let value = inner.next().unwrap();
+        let text = template::prerender(value.as_str().trim(), variables)?;
+        if !template::placeholders(&text).is_empty() {
+            bail!("MULTIPART part '{}' uses a value only known at run time", name);
+        }
+
+        parts.push(match value.as_rule() {
+            Rule::part_file => FormPart::File {
+                name,
+                path: text.trim_start_matches("file=").trim().into(),
+            },
+            _ => FormPart::Text { name, value: text },
+        });
+    }
+
+    Ok(Body::Multipart(parts))
+}
+
+/// `<file>`, or `ZEROS <size>` / `RANDOM <size>` after `BODY STREAM`
+fn parse_body_stream(pair: Pair<Rule>, variables: &Variables) -> Result<BodyStream> {
+    let mut inner = pair.into_inner();
+    let first = inner.next().unwrap();
+    if first.as_rule() == Rule::stream_file {
+        return Ok(BodyStream::File(template::prerender(first.as_str(), variables)?.into()));
+    }
+
+    let size = parse_size(inner.next().unwrap().as_str())?;
+    Ok(match first.as_str() {
+        "ZEROS" => BodyStream::Zeros(size),
+        _ => BodyStream::Random(size),
     })
 }
 
+/// `MSGPACK`, `CBOR`, or `PROTO <descriptor set> <message>` after `BODY`
+fn parse_body_format(pair: Pair<Rule>) -> Result<Encode> {
+    if let Some(proto) = pair.clone().into_inner().next() {
+        let mut args = proto.into_inner();
+        return Ok(Encode::Protobuf {
+            descriptor: args.next().unwrap().as_str().into(),
+            message: args.next().unwrap().as_str().to_string(),
+        });
+    }
+
+    Encode::from_name(pair.as_str()).context("Unknown body format")
+}
+
 pub fn parse_assertion(pair: Pair<Rule>) -> Result<Assertion> {
     debug_assert_eq!(pair.as_rule(), Rule::expect_expr);
 
-    let inner = pair.into_inner().next().unwrap();
+    parse_operation(pair.into_inner().next().unwrap())
+}
+
+/// `AND`/`OR` operands, each a condition in parentheses or an operation. A
+/// single operand stands for itself
+fn parse_condition(pair: Pair<Rule>) -> Result<Assertion> {
+    let rule = pair.as_rule();
+    let mut operands = pair
+        .into_inner()
+        .map(|operand| match operand.as_rule() {
+            Rule::condition | Rule::conjunction => parse_condition(operand),
+            _ => parse_operation(operand),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(match rule {
+        _ if operands.len() == 1 => operands.pop().unwrap(),
+        Rule::conjunction => Assertion::And(operands),
+        _ => Assertion::Or(operands),
+    })
+}
 
+fn parse_operation(inner: Pair<Rule>) -> Result<Assertion> {
     match inner.as_rule() {
+        Rule::db_op => parse_db_op(inner),
+        Rule::redis_op => parse_redis_op(inner),
+        Rule::receives_op => parse_receives_op(inner),
+        Rule::poll_op => parse_poll_op(inner),
+        Rule::encoded_op => Ok(Assertion::Encoded {
+            encoding: inner.into_inner().next().map(unquote),
+        }),
+        Rule::negotiates_op => parse_negotiates_op(inner),
+        Rule::length_op => parse_length_op(inner),
         Rule::binary_op => parse_binary_op(inner),
         Rule::in_op => parse_in_op(inner),
+        Rule::equals_file_op => parse_equals_file_op(inner),
+        Rule::equals_json_op => {
+            let mut inner = inner.into_inner();
+            let path = inner.next().unwrap().as_str().to_string();
+            let json = inner.next().unwrap().as_str().trim();
+            let expected = serde_json::from_str(json)
+                .with_context(|| format!("Invalid JSON after '{} == JSON'", path))?;
+            Ok(Assertion::EqualsJson { path, expected })
+        }
         Rule::between_op => parse_between_op(inner),
+        Rule::null_op => {
+            let mut inner = inner.into_inner();
+            Ok(Assertion::Null {
+                path: inner.next().unwrap().as_str().to_string(),
+                negated: inner.next().is_some(),
+            })
+        }
+        Rule::type_op => {
+            let mut inner = inner.into_inner();
+            let path = inner.next().unwrap().as_str().to_string();
+            let expected = match inner.next().unwrap().as_str() {
+                "STRING" => JsonType::String,
+                "NUMBER" => JsonType::Number,
+                "BOOLEAN" => JsonType::Boolean,
+                "ARRAY" => JsonType::Array,
+                _ => JsonType::Object,
+            };
+            Ok(Assertion::Type { path, expected })
+        }
         Rule::exists_op => parse_exists_op(inner),
         Rule::unary_path => parse_unary_path(inner),
         _ => bail!("Unsupported assertion type: {:?}", inner.as_rule()),
     }
 }
 
-fn parse_binary_op(pair: Pair<Rule>) -> Result<Assertion> {
+/// Parse a single `EXPECT` line, applying trailing modifiers such as `SENSITIVE`
+pub fn parse_expect(pair: Pair<Rule>) -> Result<Assertion> {
+    debug_assert_eq!(pair.as_rule(), Rule::expect);
+
+    let mut inner = pair.into_inner();
+    let expr = inner.next().unwrap();
+    let mut assertion = match expr.as_rule() {
+        Rule::condition => parse_condition(expr)?,
+        _ => parse_assertion(expr)?,
+    };
+
+    for modifier in inner {
+        match (modifier.as_rule(), modifier.as_str()) {
+            (Rule::sensitive, _) => {
+                assertion = Assertion::Sensitive {
+                    assertion: Box::new(assertion),
+                };
+            }
+            (Rule::severity, "MINOR") => {
+                assertion = Assertion::Minor {
+                    assertion: Box::new(assertion),
+                };
+            }
+            _ => {}
+        }
+    }
+
+    Ok(assertion)
+}
+
+fn parse_db_op(pair: Pair<Rule>) -> Result<Assertion> {
+    let mut inner = pair.into_inner();
+
+    let url = unquote(inner.next().unwrap());
+    let query = unquote(inner.next().unwrap());
+    let op = parse_operator(inner.next().unwrap())?;
+    let value = parse_value(inner.next().unwrap())?;
+    let within = inner
+        .next()
+        .map(|duration| parse_duration(duration.as_str()))
+        .transpose()?;
+
+    Ok(Assertion::Query {
+        url,
+        query,
+        op,
+        value,
+        within,
+    })
+}
+
+fn parse_redis_op(pair: Pair<Rule>) -> Result<Assertion> {
+    let mut inner = pair.into_inner();
+
+    let url = unquote(inner.next().unwrap());
+    let key = unquote(inner.next().unwrap());
+    let mut check = inner.next().unwrap().into_inner();
+    let expected = match check.next() {
+        Some(op) => Some((parse_operator(op)?, parse_value(check.next().unwrap())?)),
+        None => None,
+    };
+    let within = inner
+        .next()
+        .map(|duration| parse_duration(duration.as_str()))
+        .transpose()?;
+
+    Ok(Assertion::KeyValue {
+        url,
+        key,
+        expected,
+        within,
+    })
+}
+
+fn parse_receives_op(pair: Pair<Rule>) -> Result<Assertion> {
+    let mut inner = pair.into_inner();
+
+    let source = inner.next().unwrap().as_str().to_string();
+    let key = unquote(inner.next().unwrap());
+    let field = inner.next().unwrap().as_str().to_ascii_lowercase();
+    let matcher = match inner.next().unwrap().as_str() {
+        "CONTAINS" => TextMatch::Contains,
+        _ => TextMatch::Equals,
+    };
+    let expected = unquote(inner.next().unwrap());
+    let within = parse_duration(inner.next().unwrap().as_str())?;
+
+    Ok(Assertion::Receives {
+        source,
+        key,
+        field,
+        matcher,
+        expected,
+        within,
+    })
+}
+
+/// Parse a `LISTEN :<port> <path> EXPECT <expr> WITHIN <duration>` line
+fn parse_listen(pair: Pair<Rule>) -> Result<Assertion> {
+    let mut inner = pair.into_inner();
+
+    let port = inner.next().unwrap().as_str();
+    let port = port
+        .parse()
+        .with_context(|| format!("Invalid LISTEN port: {}", port))?;
+    let path = inner.next().unwrap().as_str().to_string();
+    let assertion = Box::new(parse_assertion(inner.next().unwrap())?);
+    let within = parse_duration(inner.next().unwrap().as_str())?;
+
+    Ok(Assertion::Callback {
+        port,
+        path,
+        within,
+        assertion,
+    })
+}
+
+/// Parse a `POLL EVERY <interval> UNTIL <condition> TIMEOUT <timeout>` line
+fn parse_poll_until(pair: Pair<Rule>) -> Result<Assertion> {
     let mut inner = pair.into_inner();
 
+    let interval = parse_duration(inner.next().unwrap().as_str())?;
+    let assertion = Box::new(parse_condition(inner.next().unwrap())?);
+    let timeout = parse_duration(inner.next().unwrap().as_str())?;
+    if interval.is_zero() || interval > timeout {
+        bail!(
+            "POLL EVERY {:?} must be positive and within its TIMEOUT {:?}",
+            interval,
+            timeout
+        );
+    }
+
+    Ok(Assertion::PollUntil {
+        interval,
+        timeout,
+        assertion,
+    })
+}
+
+/// Parse an `ASSERT <helper>` line
+pub fn parse_assert_helper(pair: Pair<Rule>) -> Result<Assertion> {
+    debug_assert_eq!(pair.as_rule(), Rule::assert_helper);
+
+    let helper = pair.into_inner().next().unwrap().into_inner().next().unwrap();
+    match helper.as_rule() {
+        Rule::head_matches_get => Ok(Assertion::HeadMatchesGet),
+        Rule::only_methods => Ok(Assertion::OnlyMethods {
+            allowed: helper.into_inner().map(|m| m.as_str().to_string()).collect(),
+        }),
+        Rule::rate_limit => parse_rate_limit(helper),
+        Rule::expect_continue => Ok(Assertion::ExpectContinue {
+            within: match helper.into_inner().next() {
+                Some(duration) => parse_duration(duration.as_str())?,
+                None => expect_continue::DEFAULT_WAIT,
+            },
+        }),
+        _ => bail!("Unsupported ASSERT helper: {:?}", helper.as_rule()),
+    }
+}
+
+fn parse_rate_limit(pair: Pair<Rule>) -> Result<Assertion> {
+    let mut max = 0;
+    let mut interval = None;
+    let mut headers = Vec::new();
+    let mut recovers = false;
+
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::number => max = inner.as_str().parse()?,
+            Rule::duration => interval = Some(parse_duration(inner.as_str())?),
+            Rule::rate_limit_headers => headers = inner.into_inner().map(unquote).collect(),
+            Rule::recovers => recovers = true,
+            _ => {}
+        }
+    }
+
+    if max == 0 {
+        bail!("RATE_LIMIT needs MAX of at least 1");
+    }
+
+    Ok(Assertion::RateLimit {
+        max,
+        interval,
+        headers,
+        recovers,
+    })
+}
+
+fn parse_binary_op(pair: Pair<Rule>) -> Result<Assertion> {
+    let mut inner = pair.into_inner().peekable();
+
+    let any = inner.next_if(|pair| pair.as_rule() == Rule::any).is_some();
+    // A wildcard path already checks every element
+    let all = inner.next_if(|pair| pair.as_rule() == Rule::all).is_some();
     let path = inner.next().unwrap().as_str().to_string();
+    if (any || all) && !is_wildcard(&path) {
+        bail!(
+            "{} needs a path with [*] to range over, e.g. body.items[*].price",
+            if any { "ANY" } else { "ALL" }
+        );
+    }
     let op = parse_operator(inner.next().unwrap())?;
     let val = parse_value(inner.next().unwrap())?;
 
+    if any {
+        return Ok(Assertion::Any {
+            path,
+            op,
+            value: val,
+        });
+    }
     Ok(Assertion::Binary {
         path,
         op,
@@ -116,6 +645,67 @@ fn parse_binary_op(pair: Pair<Rule>) -> Result<Assertion> {
     })
 }
 
+fn parse_length_op(pair: Pair<Rule>) -> Result<Assertion> {
+    let mut inner = pair.into_inner();
+
+    let path = inner.next().unwrap().as_str().to_string();
+    let op = parse_operator(inner.next().unwrap())?;
+    let value = parse_value(inner.next().unwrap())?;
+
+    Ok(Assertion::Length { path, op, value })
+}
+
+fn parse_poll_op(pair: Pair<Rule>) -> Result<Assertion> {
+    let mut count = 0;
+    let mut interval = None;
+    let mut path = String::new();
+    let mut order = PollOrder::NonDecreasing;
+
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::number => count = inner.as_str().parse()?,
+            Rule::duration => interval = Some(parse_duration(inner.as_str())?),
+            Rule::path => path = inner.as_str().to_string(),
+            Rule::poll_order => {
+                order = match inner.as_str() {
+                    "INCREASING" => PollOrder::Increasing,
+                    "NON_DECREASING" => PollOrder::NonDecreasing,
+                    "DECREASING" => PollOrder::Decreasing,
+                    "NON_INCREASING" => PollOrder::NonIncreasing,
+                    other => bail!("Unknown poll order {}", other),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if count < 2 {
+        bail!("POLL needs at least 2 polls to compare, got {}", count);
+    }
+
+    Ok(Assertion::Poll {
+        count,
+        interval,
+        path,
+        order,
+    })
+}
+
+fn parse_negotiates_op(pair: Pair<Rule>) -> Result<Assertion> {
+    let mut variants = Vec::new();
+
+    for negotiation in pair.into_inner() {
+        let mut inner = negotiation.into_inner();
+        variants.push(NegotiationVariant {
+            accept: unquote(inner.next().unwrap()),
+            status: inner.next().unwrap().as_str().parse()?,
+            content_type: inner.next().map(unquote),
+        });
+    }
+
+    Ok(Assertion::Negotiates { variants })
+}
+
 fn parse_operator(pair: Pair<Rule>) -> Result<Operator> {
     Ok(match pair.as_str() {
         "==" => Operator::Eq,
@@ -124,6 +714,8 @@ fn parse_operator(pair: Pair<Rule>) -> Result<Operator> {
         "<" => Operator::Lt,
         ">=" => Operator::Gte,
         "<=" => Operator::Lte,
+        "STARTSWITH" => Operator::StartsWith,
+        "ENDSWITH" => Operator::EndsWith,
         _ => bail!("Unknown operator {}", pair.as_str()),
     })
 }
@@ -153,6 +745,29 @@ fn parse_between_op(pair: Pair<Rule>) -> Result<Assertion> {
     Ok(Assertion::Between { path, min, max })
 }
 
+fn parse_equals_file_op(pair: Pair<Rule>) -> Result<Assertion> {
+    let mut inner = pair.into_inner();
+
+    let path = inner.next().unwrap().as_str().to_string();
+    let file = unquote(inner.next().unwrap()).into();
+    let ignoring = match inner.next() {
+        Some(list) => list.into_inner().map(unquote).collect(),
+        None => Vec::new(),
+    };
+
+    Ok(Assertion::EqualsFile {
+        path,
+        file,
+        ignoring,
+    })
+}
+
+/// Strip the surrounding quotes of a `quoted_string`
+fn unquote(pair: Pair<Rule>) -> String {
+    let s = pair.as_str();
+    s[1..s.len() - 1].to_string()
+}
+
 fn parse_exists_op(pair: Pair<Rule>) -> Result<Assertion> {
     let path = pair.into_inner().next().unwrap().as_str().to_string();
 
@@ -168,52 +783,363 @@ fn parse_unary_path(pair: Pair<Rule>) -> Result<Assertion> {
 fn parse_value(pair: Pair<Rule>) -> Result<Value> {
     match pair.as_rule() {
         Rule::value => parse_value(pair.into_inner().next().unwrap()),
-        Rule::quoted_string => {
-            let s = pair.as_str();
-            Ok(Value::String(s[1..s.len() - 1].to_string()))
+        Rule::quoted_string => Ok(Value::String(unquote(pair))),
+        Rule::literal_number if pair.as_str().contains('.') => {
+            Ok(Value::Float(pair.as_str().parse()?))
         }
-        Rule::number => Ok(Value::Number(pair.as_str().parse()?)),
+        Rule::literal_number => Ok(Value::Number(pair.as_str().parse()?)),
         Rule::boolean => Ok(Value::Bool(pair.as_str() == "true")),
+        Rule::null => Ok(Value::Null),
         _ => bail!("Invalid value rule"),
     }
 }
 
-pub fn parse_test_block(pair: Pair<Rule>) -> Result<TestCase> {
+/// `fixtures/<slugified test name>.json`, used by a bare `FIXTURE` directive
+fn default_fixture_path(name: Option<&str>) -> Result<PathBuf> {
+    let name = name.context("FIXTURE without a path requires a test name")?;
+
+    let mut slug = String::new();
+    for c in name.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    Ok(Path::new("fixtures").join(format!("{}.json", slug.trim_matches('-'))))
+}
+
+/// Parse a test block, once per row of its `WITH DATA` dataset if it has one,
+/// and once per combination of its `MATRIX` dimensions if it has any.
+/// Row and dimension values are substituted into the block's text before it
+/// is parsed, so they can appear anywhere, including literals in assertions.
+pub(super) fn expand_test_block(
+    pair: Pair<Rule>,
+    variables: &Variables,
+    auth: Option<&Auth>,
+    base_dir: &Path,
+) -> Result<Vec<TestCase>> {
+    let mut data = None;
+    let mut dimensions = Vec::new();
+    for directive in pair
+        .clone()
+        .into_inner()
+        .filter(|inner| inner.as_rule() == Rule::directive)
+        .filter_map(|directive| directive.into_inner().next())
+    {
+        match directive.as_rule() {
+            Rule::with_data => data = Some(directive),
+            Rule::matrix => dimensions.push(parse_matrix(directive)),
+            _ => {}
+        }
+    }
+    if data.is_none() && dimensions.is_empty() {
+        return Ok(vec![parse_test_block_with(pair, variables, auth)?]);
+    }
+
+    // Each variant: the labels naming it, and the values it substitutes
+    let mut variants: Vec<(Vec<String>, Variables)> = vec![(Vec::new(), Variables::new())];
+    let mut dataset_path = None;
+    if let Some(data) = data {
+        let path = data.into_inner().next().unwrap();
+        let path = match path.as_rule() {
+            Rule::quoted_string => unquote(path),
+            _ => path.as_str().to_string(),
+        };
+        let rows = dataset::load(&base_dir.join(&path))?;
+        variants = rows
+            .into_iter()
+            .enumerate()
+            .map(|(index, row)| (vec![format!("row {}", index + 1)], row))
+            .collect();
+        dataset_path = Some(path);
+    }
+    for (dimension, values) in &dimensions {
+        variants = variants
+            .into_iter()
+            .flat_map(|(labels, row)| {
+                values.iter().map(move |value| {
+                    let mut labels = labels.clone();
+                    labels.push(format!("{}={}", dimension, value));
+                    let mut row = row.clone();
+                    row.insert(dimension.clone(), value.clone());
+                    (labels, row)
+                })
+            })
+            .collect();
+    }
+
+    // Variants are parsed from the block's own text, so their lines count
+    // from its first line
+    let offset = line_of(&pair) - 1;
+    let mut tests = Vec::with_capacity(variants.len());
+    for (labels, row) in &variants {
+        let label = labels.join(", ");
+        let origin = match &dataset_path {
+            Some(path) => format!("[{}] of {}", label, path),
+            None => format!("[{}]", label),
+        };
+        let text = template::substitute(pair.as_str(), row);
+        let block = AxParser::parse(Rule::test_block, &text)
+            .map_err(|e| anyhow::anyhow!("{}: {}", origin, e))?
+            .next()
+            .unwrap();
+
+        let mut test = parse_test_block_with(block, variables, auth).context(origin)?;
+        test.offset_lines(offset);
+        test.dataset = dataset_path.as_ref().map(PathBuf::from);
+        test.name = Some(match test.name {
+            Some(name) => format!("{} [{}]", name.trim_end(), label),
+            None => format!("[{}]", label),
+        });
+        tests.push(test);
+    }
+
+    Ok(tests)
+}
+
+/// A `MATRIX name = a, b, c` dimension: its name and values
+fn parse_matrix(pair: Pair<Rule>) -> (String, Vec<String>) {
+    let mut inner = pair.into_inner();
+    let name = inner.next().unwrap().as_str().to_string();
+    let values = inner
+        .map(|value| match value.as_rule() {
+            Rule::quoted_string => unquote(value),
+            _ => value.as_str().to_string(),
+        })
+        .collect();
+    (name, values)
+}
+
+/// Parse a single `test_block` pair, without a file-level `AUTH`
+pub fn parse_test_block(pair: Pair<Rule>, variables: &Variables) -> Result<TestCase> {
+    parse_test_block_with(pair, variables, None)
+}
+
+/// Parse a test block, authenticating with the file's `AUTH` unless the
+/// test has its own
+fn parse_test_block_with(
+    pair: Pair<Rule>,
+    variables: &Variables,
+    file_auth: Option<&Auth>,
+) -> Result<TestCase> {
     debug_assert_eq!(pair.as_rule(), Rule::test_block);
+    let start = line_of(&pair);
+    let location = Location::lines(start, start + pair.as_str().trim_end().lines().count() - 1);
     let mut name: Option<String> = None;
     let mut request: Option<HttpRequest> = None;
     let mut assertions: Vec<Assertion> = Vec::new();
+    let mut assertion_lines: Vec<usize> = Vec::new();
+    let mut fixture: Option<Option<String>> = None;
+    let mut decode: Option<Decode> = None;
+    let mut captures: Vec<Capture> = Vec::new();
+    let mut steps: Vec<Step> = Vec::new();
+    let mut settle = None;
+    let mut tags: Vec<String> = Vec::new();
+    let mut skip: Option<Option<String>> = None;
+    let mut only = false;
+    let mut share = false;
+    let mut allow: Vec<String> = Vec::new();
+    let mut repeat = 1;
+    let mut retry: Option<Retry> = None;
+    let mut auth: Option<Auth> = None;
+    // `FREEZE TIME` and `SEED` only apply to this test's templates
+    let mut variables = Cow::Borrowed(variables);
 
     for inner in pair.into_inner() {
         match inner.as_rule() {
             Rule::test_name => {
                 name = Some(inner.as_str().to_string());
             }
+            Rule::directive => {
+                let directive = inner.into_inner().next().unwrap();
+                match directive.as_rule() {
+                    Rule::fixture => fixture = Some(directive.into_inner().next().map(unquote)),
+                    Rule::decode => {
+                        let mut args = directive.into_inner();
+                        let name = args.next().unwrap().as_str();
+                        decode = Some(Decode::from_name(name, args.map(unquote).collect())?);
+                    }
+                    Rule::skip => skip = Some(directive.into_inner().next().map(unquote)),
+                    Rule::only => only = true,
+                    Rule::share => share = true,
+                    Rule::allow => {
+                        allow.extend(directive.into_inner().map(|code| code.as_str().to_string()))
+                    }
+                    Rule::freeze => {
+                        let time = unquote(directive.into_inner().next().unwrap());
+                        let time = DateTime::parse_from_rfc3339(&time)
+                            .with_context(|| format!("Invalid FREEZE TIME '{}'", time))?;
+                        let now = time.with_timezone(&Utc).to_rfc3339_opts(SecondsFormat::Secs, true);
+                        variables.to_mut().insert("now".to_string(), now);
+                    }
+                    Rule::seed => {
+                        let seed = directive.into_inner().next().unwrap().as_str();
+                        variables.to_mut().insert("seed".to_string(), seed.to_string());
+                    }
+                    Rule::repeat => {
+                        repeat = directive.into_inner().next().unwrap().as_str().parse()?;
+                        if repeat == 0 {
+                            bail!("REPEAT needs at least 1 iteration");
+                        }
+                    }
+                    Rule::retry => {
+                        let mut args = directive.into_inner();
+                        retry = Some(Retry {
+                            retries: args.next().unwrap().as_str().parse()?,
+                            delay: args.next().map(|d| parse_duration(d.as_str())).transpose()?,
+                        });
+                    }
+                    Rule::auth => auth = Some(parse_auth(directive, &variables)?),
+                    Rule::tags => {
+                        tags.extend(directive.into_inner().map(|tag| tag.as_str().to_string()))
+                    }
+                    _ => {}
+                }
+            }
             Rule::request => {
-                request = Some(parse_http_request(inner)?);
+                request = Some(parse_http_request(inner, &variables)?);
             }
+            Rule::wait => settle = Some(parse_wait(inner)?),
             Rule::expects => {
-                for expect in inner.into_inner() {
-                    debug_assert_eq!(expect.as_rule(), Rule::expect);
-                    let expr = expect.into_inner().next().unwrap();
-                    assertions.push(parse_assertion(expr)?);
-                }
+                (assertions, assertion_lines, captures) = parse_expects(inner)?;
             }
+            Rule::step => steps.push(parse_step(inner, &variables)?),
             _ => {}
         }
     }
 
+    let fixture = match fixture {
+        Some(Some(path)) => Some(path.into()),
+        Some(None) => Some(default_fixture_path(name.as_deref())?),
+        None => None,
+    };
+
+    let mut request = request.context("Test block missing HTTP request")?;
+    if share {
+        if request.method != "GET" {
+            bail!("SHARE RESPONSE only applies to GET requests");
+        }
+        // A shared response is sent once, so it cannot be asked for again
+        let polls = assertions.iter().any(|assertion| {
+            matches!(assertion, Assertion::Poll { .. } | Assertion::PollUntil { .. })
+        });
+        if retry.is_some() || repeat > 1 || polls {
+            bail!("SHARE RESPONSE cannot be combined with RETRY, REPEAT or POLL");
+        }
+    }
+    request.share = share;
+    request.decode = decode.clone();
+    for step in &mut steps {
+        step.request.decode = decode.clone();
+    }
+    if let Some(auth) = auth.as_ref().or(file_auth) {
+        auth.apply(&mut request);
+        for step in &mut steps {
+            auth.apply(&mut step.request);
+        }
+    }
+
     let test_case = TestCase {
         name,
-        request: request.context("Test block missing HTTP request")?,
+        request,
         response: None,
-        assertions: assertions,
+        assertions,
         result: None,
+        fixture,
+        annotations: Vec::new(),
+        captures,
+        steps,
+        settle,
+        tags,
+        skip,
+        only,
+        repeat,
+        iterations: Vec::new(),
+        retry,
+        attempts: 0,
+        pacing: Pacing::default(),
+        dataset: None,
+        span: None,
+        location: Some(location),
+        assertion_lines,
+        allow,
     };
 
     Ok(test_case)
 }
 
+/// A further request of a test, with its `WAIT`s before and after
+fn parse_step(pair: Pair<Rule>, variables: &Variables) -> Result<Step> {
+    let mut request = None;
+    let (mut delay, mut settle) = (None, None);
+    let mut expects = (Vec::new(), Vec::new(), Vec::new());
+
+    for part in pair.into_inner() {
+        match part.as_rule() {
+            Rule::wait if request.is_none() => delay = Some(parse_wait(part)?),
+            Rule::wait => settle = Some(parse_wait(part)?),
+            Rule::request => request = Some(parse_http_request(part, variables)?),
+            Rule::expects => expects = parse_expects(part)?,
+            _ => {}
+        }
+    }
+
+    let (assertions, assertion_lines, captures) = expects;
+    Ok(Step {
+        request: request.context("Step missing HTTP request")?,
+        response: None,
+        assertions,
+        assertion_lines,
+        captures,
+        delay,
+        settle,
+    })
+}
+
+/// `WAIT 2s`: how long to pause
+fn parse_wait(pair: Pair<Rule>) -> Result<std::time::Duration> {
+    parse_duration(pair.into_inner().next().unwrap().as_str())
+}
+
+/// Line of `pair` in the parsed text
+fn line_of(pair: &Pair<Rule>) -> usize {
+    pair.as_span().start_pos().line_col().0
+}
+
+/// Assertions following a request with the line of each, and captures
+fn parse_expects(pair: Pair<Rule>) -> Result<(Vec<Assertion>, Vec<usize>, Vec<Capture>)> {
+    let mut assertions = Vec::new();
+    let mut lines = Vec::new();
+    let mut captures = Vec::new();
+
+    for expect in pair.into_inner() {
+        if matches!(
+            expect.as_rule(),
+            Rule::expect | Rule::assert_helper | Rule::listen | Rule::poll_until
+        ) {
+            lines.push(line_of(&expect));
+        }
+        match expect.as_rule() {
+            Rule::expect => assertions.push(parse_expect(expect)?),
+            Rule::assert_helper => assertions.push(parse_assert_helper(expect)?),
+            Rule::listen => assertions.push(parse_listen(expect)?),
+            Rule::poll_until => assertions.push(parse_poll_until(expect)?),
+            Rule::capture => {
+                let mut inner = expect.into_inner();
+                captures.push(Capture {
+                    name: inner.next().unwrap().as_str().to_string(),
+                    path: inner.next().unwrap().as_str().to_string(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    Ok((assertions, lines, captures))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -232,10 +1158,10 @@ mod tests {
 
         assert!(result.is_ok());
         let test_cases = result.unwrap();
-        assert_eq!(test_cases.len(), 1);
+        assert_eq!(test_cases.len(), 2);
 
-        let test_case = &test_cases[0];
-        assert_eq!(test_case.name, Some("POST create a resource".to_string()));
+        let test_case = &test_cases[1];
+        assert_eq!(test_case.name, Some("My second test".to_string()));
         assert_eq!(test_case.request.method, "POST");
     }
 
@@ -255,24 +1181,379 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_http_request() {
-        let input = r#"POST https://httpbin.org/post
-Content-Type: application/json
+    fn parse_file_with_variables() {
+        let input = r#"VAR host = api.example.com
+VAR base = "https://{{host}}/v1"
 
-BODY
-{"name": "test"}
-BODYEND"#;
-        let mut pairs = AxParser::parse(Rule::request, input).unwrap();
-        let request_pair = pairs.next().unwrap();
-        let http_request = parse_http_request(request_pair).unwrap();
-        assert_eq!(http_request.method, "POST");
-        assert_eq!(http_request.url.as_str(), "https://httpbin.org/post");
+TEST List users
+GET {{base}}/users
+X-Host: {{host}}
+
+EXPECT status == 200
+END
+"#
+        .to_string();
+
+        let defaults = Variables::from([("host".to_string(), "ignored.example.com".to_string())]);
+        let tests = AxParser::parse_file_with(&input, &defaults).unwrap();
+        assert_eq!(tests[0].request.url.as_str(), "https://api.example.com/v1/users");
         assert_eq!(
-            http_request.headers.get("Content-Type"),
-            Some(&"application/json".to_string())
+            tests[0].request.headers.get("X-Host"),
+            Some(&"api.example.com".to_string())
         );
-        match http_request.body {
-            Some(Body::Text(text)) => assert_eq!(text.trim(), r#"{"name": "test"}"#),
+    }
+
+    #[test]
+    fn parse_file_auth_defaults() {
+        let input = r#"VAR user = alice
+AUTH BASIC {{user}} s3cret
+
+TEST Profile
+GET https://example.com/me
+
+EXPECT status == 200
+END
+
+TEST Orders
+AUTH BEARER {{token}}
+GET https://example.com/orders
+
+EXPECT status == 200
+END
+
+TEST Health
+AUTH NONE
+GET https://example.com/health
+
+EXPECT status == 200
+END
+"#
+        .to_string();
+
+        let tests = AxParser::parse_file(&input).unwrap();
+        let authorization = |test: &TestCase| test.request.headers.get("Authorization").cloned();
+        assert_eq!(
+            authorization(&tests[0]),
+            Some("Basic YWxpY2U6czNjcmV0".to_string())
+        );
+        assert_eq!(authorization(&tests[1]), Some("Bearer {{token}}".to_string()));
+        assert!(tests[1].request.deferred.is_some());
+        assert_eq!(authorization(&tests[2]), None);
+    }
+
+    #[test]
+    fn parse_records_lines() {
+        let input = "VAR host = example.com
+
+TEST Create
+POST https://{{host}}/users
+
+EXPECT status == 201
+EXPECT body.id EXISTS
+END
+".to_string();
+        let tests = AxParser::parse_file(&input).unwrap();
+        assert_eq!(tests[0].location, Some(Location::lines(3, 8)));
+        assert_eq!(tests[0].assertion_lines, [6, 7]);
+    }
+
+    #[test]
+    fn parse_with_data_expands_rows() {
+        let examples_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+        let input = fs::read_to_string(examples_dir.join("data_driven.ax")).unwrap();
+
+        let tests = AxParser::parse_file_in(&input, &Variables::new(), &examples_dir).unwrap();
+        assert_eq!(tests.len(), 3);
+        assert_eq!(
+            tests[1].name.as_deref(),
+            Some("Create user grace@example.com [row 2]")
+        );
+        assert_eq!(
+            tests[1].assertions[2],
+            Assertion::Binary {
+                path: "body.json.role".to_string(),
+                op: Operator::Eq,
+                value: Value::String("editor".to_string()),
+            }
+        );
+
+        assert!(AxParser::parse_file_with(&input, &Variables::new()).is_err());
+    }
+
+    #[test]
+    fn parse_matrix_expands_combinations() {
+        let input = r#"TEST Greeting
+MATRIX lang = en, fr
+MATRIX version = "2023-10", "2024-04"
+GET https://example.com/greeting
+Accept-Language: {{lang}}
+Api-Version: {{version}}
+
+EXPECT headers.Content-Language == "{{lang}}"
+END
+"#;
+        let tests = AxParser::parse_file(input).unwrap();
+        let names: Vec<&str> = tests.iter().filter_map(|t| t.name.as_deref()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Greeting [lang=en, version=2023-10]",
+                "Greeting [lang=en, version=2024-04]",
+                "Greeting [lang=fr, version=2023-10]",
+                "Greeting [lang=fr, version=2024-04]",
+            ]
+        );
+        assert_eq!(tests[3].request.headers.get("Api-Version").unwrap(), "2024-04");
+        assert_eq!(
+            tests[2].assertions[0],
+            Assertion::Binary {
+                path: "headers.Content-Language".to_string(),
+                op: Operator::Eq,
+                value: Value::String("fr".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn matrix_tests_keep_their_lines_in_the_file() {
+        let input = r#"TEST Ping
+GET https://example.com/ping
+
+EXPECT status == 200
+END
+
+TEST Greeting
+MATRIX lang = en, fr
+GET https://example.com/greeting
+Accept-Language: {{lang}}
+
+EXPECT status == 200
+END
+"#;
+        let tests = AxParser::parse_file(input).unwrap();
+        assert_eq!(tests.len(), 3);
+        for test in &tests[1..] {
+            let location = test.location.as_ref().unwrap();
+            assert_eq!((location.start, location.end), (7, 13));
+            assert_eq!(test.assertion_lines, vec![12]);
+        }
+    }
+
+    #[test]
+    fn test_parse_encoded_body() {
+        let input = r#"POST https://example.com/telemetry
+
+BODY MSGPACK
+{"device": "sensor-1", "value": 3}
+BODYEND"#;
+        let mut pairs = AxParser::parse(Rule::request, input).unwrap();
+        let request = parse_http_request(pairs.next().unwrap(), &Variables::new()).unwrap();
+
+        assert_eq!(
+            request.headers.get("Content-Type"),
+            Some(&"application/msgpack".to_string())
+        );
+        let Some(Body::Bytes(bytes)) = &request.body else {
+            panic!("expected an encoded body, got {:?}", request.body);
+        };
+        assert_eq!(bytes, &Encode::MsgPack.encode(r#"{"device": "sensor-1", "value": 3}"#).unwrap());
+    }
+
+    #[test]
+    fn test_parse_multipart_body() {
+        let input = r#"POST https://example.com/uploads
+Authorization: Bearer abc
+
+MULTIPART
+title = Holiday {{year}}
+photo file=./images/beach.png
+MULTIPARTEND"#;
+        let variables = Variables::from([("year".to_string(), "2024".to_string())]);
+        let mut pairs = AxParser::parse(Rule::request, input).unwrap();
+        let request = parse_http_request(pairs.next().unwrap(), &variables).unwrap();
+
+        let Some(Body::Multipart(parts)) = &request.body else {
+            panic!("expected a multipart body, got {:?}", request.body);
+        };
+        assert_eq!(
+            parts,
+            &vec![
+                FormPart::Text {
+                    name: "title".into(),
+                    value: "Holiday 2024".into(),
+                },
+                FormPart::File {
+                    name: "photo".into(),
+                    path: "./images/beach.png".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_stream_body() {
+        let variables = Variables::from([("dir".to_string(), "fixtures".to_string())]);
+        let stream = |input: &str| {
+            let mut pairs = AxParser::parse(Rule::request, input).unwrap();
+            match parse_http_request(pairs.next().unwrap(), &variables).unwrap().body {
+                Some(Body::Stream(stream)) => stream,
+                other => panic!("expected a stream body, got {:?}", other),
+            }
+        };
+
+        assert_eq!(
+            stream("PUT https://example.com/upload\nBODY STREAM ./{{dir}}/big.bin\n"),
+            BodyStream::File("./fixtures/big.bin".into())
+        );
+        assert_eq!(
+            stream("POST https://example.com/upload\nBODY STREAM ZEROS 500MB\n"),
+            BodyStream::Zeros(500 * 1024 * 1024)
+        );
+        assert_eq!(
+            stream("POST https://example.com/upload\nBODY STREAM RANDOM 64\n"),
+            BodyStream::Random(64)
+        );
+    }
+
+    #[test]
+    fn test_parse_query_block() {
+        let input = r#"GET https://example.com/users?active=true
+Accept: application/json
+QUERY
+page = 2
+q = Ada & Grace
+token = {{token}}
+QUERYEND
+"#;
+        let mut pairs = AxParser::parse(Rule::request, input).unwrap();
+        let mut request = parse_http_request(pairs.next().unwrap(), &Variables::new()).unwrap();
+
+        let deferred = request.deferred.clone().unwrap();
+        assert_eq!(deferred.placeholders(), vec!["token"]);
+
+        let variables = Variables::from([("token".to_string(), "a/b".to_string())]);
+        deferred.apply(&mut request, &variables).unwrap();
+        assert_eq!(
+            request.url.as_str(),
+            "https://example.com/users?active=true&page=2&q=Ada+%26+Grace&token=a%2Fb"
+        );
+    }
+
+    #[test]
+    fn test_parse_cookies_block() {
+        let input = r#"GET https://example.com/cart
+Cookie: consent=yes
+COOKIES
+session = {{session_id}}
+theme = dark
+COOKIESEND
+"#;
+        let mut pairs = AxParser::parse(Rule::request, input).unwrap();
+        let request = parse_http_request(pairs.next().unwrap(), &Variables::new()).unwrap();
+
+        assert_eq!(
+            request.headers.get("Cookie"),
+            Some(&"consent=yes; session={{session_id}}; theme=dark".to_string())
+        );
+        let deferred = request.deferred.unwrap();
+        assert_eq!(deferred.placeholders(), vec!["session_id"]);
+        assert_eq!(deferred.headers.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_json_body() {
+        let input = r#"POST https://example.com/users
+
+BODY JSON
+{"name": "Ada", "admin": false}
+BODYEND"#;
+        let mut pairs = AxParser::parse(Rule::request, input).unwrap();
+        let request = parse_http_request(pairs.next().unwrap(), &Variables::new()).unwrap();
+
+        assert_eq!(
+            request.headers.get("Content-Type"),
+            Some(&"application/json".to_string())
+        );
+        let Some(Body::Json(json)) = &request.body else {
+            panic!("expected a JSON body, got {:?}", request.body);
+        };
+        assert_eq!(json["admin"], false);
+
+        let malformed = r#"POST https://example.com/users
+
+BODY JSON
+{"name": "Ada", "id": {{id}}
+BODYEND"#;
+        let mut pairs = AxParser::parse(Rule::request, malformed).unwrap();
+        assert!(parse_http_request(pairs.next().unwrap(), &Variables::new()).is_err());
+    }
+
+    #[test]
+    fn test_parse_proto_body() {
+        let input = r#"POST https://example.com/twirp/users.Users/Create
+
+BODY PROTO ./schemas/api.desc my.pkg.CreateUserRequest
+{"name": "Ada"}
+BODYEND"#;
+        let mut pairs = AxParser::parse(Rule::request, input).unwrap();
+        let request = parse_http_request(pairs.next().unwrap(), &Variables::new()).unwrap();
+
+        assert_eq!(
+            request.headers.get("Content-Type"),
+            Some(&"application/x-protobuf".to_string())
+        );
+        // Encoded at run time, once the descriptor path is resolved
+        assert!(request.body.is_none());
+        assert_eq!(
+            request.deferred.unwrap().encode,
+            Some(Encode::Protobuf {
+                descriptor: "./schemas/api.desc".into(),
+                message: "my.pkg.CreateUserRequest".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_json_bodies_checked_and_canonicalized() {
+        let parse = |input: &str| {
+            let mut pairs = AxParser::parse(Rule::request, input).unwrap();
+            parse_http_request(pairs.next().unwrap(), &Variables::new())
+        };
+
+        let request = parse(
+            "POST https://example.com/orders\n\nBODY CANONICAL\n{\"b\": 1, // note\n \"a\": [1, 2]}\nBODYEND",
+        )
+        .unwrap();
+        assert_eq!(request.headers["Content-Type"], "application/json");
+        match request.body {
+            Some(Body::Text(text)) => assert_eq!(text, r#"{"a":[1,2],"b":1}"#),
+            _ => panic!("Expected text body"),
+        }
+
+        let typo = "POST https://example.com/orders\nContent-Type: application/json\n\nBODY\n{\"id\": {{id}},}\nBODYEND";
+        assert!(parse(typo).is_err());
+        assert!(parse(&typo.replace("application/json", "text/plain")).is_ok());
+    }
+
+    #[test]
+    fn test_parse_http_request() {
+        let input = r#"POST https://httpbin.org/post
+Content-Type: application/json
+
+BODY
+{"name": "test"}
+BODYEND"#;
+        let mut pairs = AxParser::parse(Rule::request, input).unwrap();
+        let request_pair = pairs.next().unwrap();
+        let http_request = parse_http_request(request_pair, &Variables::new()).unwrap();
+        assert_eq!(http_request.method, "POST");
+        assert_eq!(http_request.url.as_str(), "https://httpbin.org/post");
+        assert_eq!(
+            http_request.headers.get("Content-Type"),
+            Some(&"application/json".to_string())
+        );
+        match http_request.body {
+            Some(Body::Text(text)) => assert_eq!(text.trim(), r#"{"name": "test"}"#),
             _ => panic!("Expected text body"),
         }
     }
@@ -293,6 +1574,47 @@ BODYEND"#;
         }
     }
 
+    #[test]
+    fn test_parse_header_assertion() {
+        let input = r#"headers.Content-Type == "application/json""#;
+        let mut pairs = AxParser::parse(Rule::expect_expr, input).unwrap();
+        assert_eq!(
+            parse_assertion(pairs.next().unwrap()).unwrap(),
+            Assertion::Binary {
+                path: "headers.Content-Type".to_string(),
+                op: Operator::Eq,
+                value: Value::String("application/json".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_indexed_path() {
+        let mut pairs = AxParser::parse(Rule::expect_expr, "body.items[0].id == 7").unwrap();
+        assert_eq!(
+            parse_assertion(pairs.next().unwrap()).unwrap(),
+            Assertion::Binary {
+                path: "body.items[0].id".to_string(),
+                op: Operator::Eq,
+                value: Value::Number(7),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_prefix_suffix_assertions() {
+        for (input, operator) in [
+            (r#"headers.Location STARTSWITH "/users/""#, Operator::StartsWith),
+            (r#"body.file ENDSWITH ".pdf""#, Operator::EndsWith),
+        ] {
+            let mut pairs = AxParser::parse(Rule::expect_expr, input).unwrap();
+            match parse_assertion(pairs.next().unwrap()).unwrap() {
+                Assertion::Binary { op, .. } => assert_eq!(op, operator),
+                other => panic!("Expected binary assertion, got {:?}", other),
+            }
+        }
+    }
+
     #[test]
     fn test_parse_binary_op() {
         let input = "status == 200";
@@ -307,6 +1629,131 @@ BODYEND"#;
             }
             _ => panic!("Expected binary assertion"),
         }
+
+        let input = r#"ANY body.items[*].role == "admin""#;
+        let mut pairs = AxParser::parse(Rule::binary_op, input).unwrap();
+        assert_eq!(
+            parse_binary_op(pairs.next().unwrap()).unwrap(),
+            Assertion::Any {
+                path: "body.items[*].role".into(),
+                op: Operator::Eq,
+                value: Value::String("admin".into()),
+            }
+        );
+
+        let mut pairs = AxParser::parse(Rule::binary_op, "ALL body.items[*].price > 0").unwrap();
+        assert_eq!(
+            parse_binary_op(pairs.next().unwrap()).unwrap(),
+            Assertion::Binary {
+                path: "body.items[*].price".into(),
+                op: Operator::Gt,
+                value: Value::Number(0),
+            }
+        );
+        let mut pairs = AxParser::parse(Rule::binary_op, "ALL body.price > 0").unwrap();
+        assert!(parse_binary_op(pairs.next().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_parse_compound_expect() {
+        let binary = |path: &str, value: Value| Assertion::Binary {
+            path: path.into(),
+            op: Operator::Eq,
+            value,
+        };
+        let input = "EXPECT (status == 200 OR status == 204) AND body.ok == true SENSITIVE\n";
+        let mut pairs = AxParser::parse(Rule::expect, input).unwrap();
+        assert_eq!(
+            parse_expect(pairs.next().unwrap()).unwrap(),
+            Assertion::Sensitive {
+                assertion: Box::new(Assertion::And(vec![
+                    Assertion::Or(vec![
+                        binary("status", Value::Number(200)),
+                        binary("status", Value::Number(204)),
+                    ]),
+                    binary("body.ok", Value::Bool(true)),
+                ])),
+            }
+        );
+
+        // AND binds tighter than OR, and BETWEEN keeps its own AND
+        let input = "EXPECT status == 201 OR body.age BETWEEN 18 AND 65 AND body.id EXISTS\n";
+        let mut pairs = AxParser::parse(Rule::expect, input).unwrap();
+        let Assertion::Or(operands) = parse_expect(pairs.next().unwrap()).unwrap() else {
+            panic!("Expected OR at the top");
+        };
+        assert_eq!(operands[0], binary("status", Value::Number(201)));
+        assert!(matches!(&operands[1], Assertion::And(inner) if inner.len() == 2));
+
+        let mut pairs = AxParser::parse(Rule::expect, "EXPECT body ENCODED\n").unwrap();
+        assert_eq!(
+            parse_expect(pairs.next().unwrap()).unwrap(),
+            Assertion::Encoded { encoding: None }
+        );
+        assert!(AxParser::parse(Rule::expect, "EXPECT status == 200 AND\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_severity() {
+        let input = "EXPECT headers.Cache-Control EXISTS SENSITIVE MINOR\n";
+        let mut pairs = AxParser::parse(Rule::expect, input).unwrap();
+        let assertion = parse_expect(pairs.next().unwrap()).unwrap();
+        assert!(assertion.is_minor());
+        assert!(assertion.is_sensitive());
+
+        let mut pairs = AxParser::parse(Rule::expect, "EXPECT status == 200 CRITICAL\n").unwrap();
+        assert!(!parse_expect(pairs.next().unwrap()).unwrap().is_minor());
+    }
+
+    #[test]
+    fn test_parse_length_op() {
+        let input = "EXPECT body.items LENGTH >= 5\n";
+        let mut pairs = AxParser::parse(Rule::expect, input).unwrap();
+        assert_eq!(
+            parse_expect(pairs.next().unwrap()).unwrap(),
+            Assertion::Length {
+                path: "body.items".into(),
+                op: Operator::Gte,
+                value: Value::Number(5),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_null_and_type_ops() {
+        let parse = |input: &str| {
+            let mut pairs = AxParser::parse(Rule::expect, input).unwrap();
+            parse_expect(pairs.next().unwrap()).unwrap()
+        };
+        assert_eq!(
+            parse("EXPECT body.deleted_at IS NULL\n"),
+            Assertion::Null {
+                path: "body.deleted_at".into(),
+                negated: false,
+            }
+        );
+        assert_eq!(
+            parse("EXPECT body.id IS NOT NULL\n"),
+            Assertion::Null {
+                path: "body.id".into(),
+                negated: true,
+            }
+        );
+        assert_eq!(
+            parse("EXPECT body.tags IS ARRAY\n"),
+            Assertion::Type {
+                path: "body.tags".into(),
+                expected: JsonType::Array,
+            }
+        );
+        assert_eq!(
+            parse("EXPECT body.parent == null\n"),
+            Assertion::Binary {
+                path: "body.parent".into(),
+                op: Operator::Eq,
+                value: Value::Null,
+            }
+        );
     }
 
     #[test]
@@ -349,6 +1796,100 @@ BODYEND"#;
         }
     }
 
+    #[test]
+    fn test_parse_negative_and_float_values() {
+        let input = "body.delta BETWEEN -10 AND 2.5";
+        let mut pairs = AxParser::parse(Rule::between_op, input).unwrap();
+        match parse_between_op(pairs.next().unwrap()).unwrap() {
+            Assertion::Between { min, max, .. } => {
+                assert_eq!(min, Value::Number(-10));
+                assert_eq!(max, Value::Float(2.5));
+            }
+            _ => panic!("Expected between assertion"),
+        }
+
+        let input = "body.offset IN [-1, 0, 1.5]";
+        let mut pairs = AxParser::parse(Rule::in_op, input).unwrap();
+        match parse_in_op(pairs.next().unwrap()).unwrap() {
+            Assertion::In { values, .. } => {
+                assert_eq!(values[0], Value::Number(-1));
+                assert_eq!(values[2], Value::Float(1.5));
+            }
+            _ => panic!("Expected in assertion"),
+        }
+    }
+
+    #[test]
+    fn test_parse_equals_file_op() {
+        let input = r#"body EQUALS FILE "./expected/user.json" IGNORING ["body.updated_at", "body.id"]"#;
+        let mut pairs = AxParser::parse(Rule::expect_expr, input).unwrap();
+        let assertion = parse_assertion(pairs.next().unwrap()).unwrap();
+        assert_eq!(
+            assertion,
+            Assertion::EqualsFile {
+                path: "body".to_string(),
+                file: "./expected/user.json".into(),
+                ignoring: vec!["body.updated_at".to_string(), "body.id".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_poll_op() {
+        let input = "POLL 5 EVERY 500ms body.version NON_DECREASING";
+        let mut pairs = AxParser::parse(Rule::expect_expr, input).unwrap();
+        let assertion = parse_assertion(pairs.next().unwrap()).unwrap();
+        assert_eq!(
+            assertion,
+            Assertion::Poll {
+                count: 5,
+                interval: Some(std::time::Duration::from_millis(500)),
+                path: "body.version".to_string(),
+                order: PollOrder::NonDecreasing,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_encoded_op() {
+        let mut pairs = AxParser::parse(Rule::expect_expr, "body ENCODED").unwrap();
+        let assertion = parse_assertion(pairs.next().unwrap()).unwrap();
+        assert_eq!(assertion, Assertion::Encoded { encoding: None });
+
+        let mut pairs = AxParser::parse(Rule::expect_expr, r#"body ENCODED AS "shift_jis""#).unwrap();
+        let assertion = parse_assertion(pairs.next().unwrap()).unwrap();
+        assert_eq!(
+            assertion,
+            Assertion::Encoded {
+                encoding: Some("shift_jis".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_negotiates_op() {
+        let input = r#"NEGOTIATES ["application/json" => 200 "application/json", "image/png" => 406]"#;
+        let mut pairs = AxParser::parse(Rule::expect_expr, input).unwrap();
+        let assertion = parse_assertion(pairs.next().unwrap()).unwrap();
+        assert_eq!(
+            assertion,
+            Assertion::Negotiates {
+                variants: vec![
+                    NegotiationVariant {
+                        accept: "application/json".to_string(),
+                        status: 200,
+                        content_type: Some("application/json".to_string()),
+                    },
+                    NegotiationVariant {
+                        accept: "image/png".to_string(),
+                        status: 406,
+                        content_type: None,
+                    },
+                ],
+            }
+        );
+    }
+
     #[test]
     fn test_parse_exists_op() {
         let input = "body.email EXISTS";
@@ -404,6 +1945,25 @@ BODYEND"#;
         assert_eq!(value, Value::Bool(true));
     }
 
+    #[test]
+    fn test_parse_expect_sensitive() {
+        let input = "EXPECT body.ssn EXISTS SENSITIVE\n";
+        let mut pairs = AxParser::parse(Rule::expect, input).unwrap();
+        let expect_pair = pairs.next().unwrap();
+        let assertion = parse_expect(expect_pair).unwrap();
+        match assertion {
+            Assertion::Sensitive { assertion } => {
+                assert_eq!(
+                    *assertion,
+                    Assertion::Exists {
+                        path: "body.ssn".to_string()
+                    }
+                );
+            }
+            _ => panic!("Expected sensitive assertion"),
+        }
+    }
+
     #[test]
     fn test_parse_test_block() {
         let input = r#"TEST POST create a resource
@@ -418,9 +1978,556 @@ EXPECT status == 200
 END"#;
         let mut pairs = AxParser::parse(Rule::test_block, input).unwrap();
         let block_pair = pairs.next().unwrap();
-        let test_case = parse_test_block(block_pair).unwrap();
+        let test_case = parse_test_block(block_pair, &Variables::new()).unwrap();
         assert_eq!(test_case.name, Some("POST create a resource".to_string()));
         assert_eq!(test_case.request.method, "POST");
         assert_eq!(test_case.assertions.len(), 1);
+        assert_eq!(test_case.fixture, None);
+    }
+
+    #[test]
+    fn test_parse_test_block_assert_helper() {
+        let input = r#"TEST HEAD mirrors GET
+GET https://httpbin.org/get
+
+EXPECT status == 200
+ASSERT HEAD_MATCHES_GET
+END"#;
+        let mut pairs = AxParser::parse(Rule::test_block, input).unwrap();
+        let test_case = parse_test_block(pairs.next().unwrap(), &Variables::new()).unwrap();
+        assert_eq!(test_case.assertions.len(), 2);
+        assert_eq!(test_case.assertions[1], Assertion::HeadMatchesGet);
+    }
+
+    #[test]
+    fn test_parse_assert_helper_only_methods() {
+        let input = "ASSERT ONLY_METHODS [GET, POST]
+";
+        let mut pairs = AxParser::parse(Rule::assert_helper, input).unwrap();
+        let assertion = parse_assert_helper(pairs.next().unwrap()).unwrap();
+        assert_eq!(
+            assertion,
+            Assertion::OnlyMethods {
+                allowed: vec!["GET".to_string(), "POST".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_assert_helper_expect_continue() {
+        for (input, within) in [
+            ("ASSERT EXPECT_CONTINUE\n", expect_continue::DEFAULT_WAIT),
+            ("ASSERT EXPECT_CONTINUE WITHIN 2s\n", std::time::Duration::from_secs(2)),
+        ] {
+            let mut pairs = AxParser::parse(Rule::assert_helper, input).unwrap();
+            assert_eq!(
+                parse_assert_helper(pairs.next().unwrap()).unwrap(),
+                Assertion::ExpectContinue { within }
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_assert_helper_rate_limit() {
+        let input = r#"ASSERT RATE_LIMIT MAX 50 EVERY 20ms HEADERS ["X-RateLimit-Limit", "X-RateLimit-Remaining"] RECOVERS
+"#;
+        let mut pairs = AxParser::parse(Rule::assert_helper, input).unwrap();
+        let assertion = parse_assert_helper(pairs.next().unwrap()).unwrap();
+        assert_eq!(
+            assertion,
+            Assertion::RateLimit {
+                max: 50,
+                interval: Some(std::time::Duration::from_millis(20)),
+                headers: vec![
+                    "X-RateLimit-Limit".to_string(),
+                    "X-RateLimit-Remaining".to_string()
+                ],
+                recovers: true,
+            }
+        );
+
+        let input = "ASSERT RATE_LIMIT MAX 10\n";
+        let mut pairs = AxParser::parse(Rule::assert_helper, input).unwrap();
+        let assertion = parse_assert_helper(pairs.next().unwrap()).unwrap();
+        assert_eq!(
+            assertion,
+            Assertion::RateLimit {
+                max: 10,
+                interval: None,
+                headers: Vec::new(),
+                recovers: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_test_block_decode() {
+        let input = r#"TEST Order as protobuf
+DECODE protobuf "api.desc" "shop.Order"
+GET https://example.com/orders/1
+
+EXPECT body.id == 1
+END"#;
+        let mut pairs = AxParser::parse(Rule::test_block, input).unwrap();
+        let test_case = parse_test_block(pairs.next().unwrap(), &Variables::new()).unwrap();
+        assert_eq!(
+            test_case.request.decode,
+            Some(Decode::Protobuf {
+                descriptor: PathBuf::from("api.desc"),
+                message: "shop.Order".to_string(),
+            })
+        );
+
+        let input = "TEST x\nDECODE bson\nGET https://example.com\n\nEND";
+        let mut pairs = AxParser::parse(Rule::test_block, input).unwrap();
+        assert!(parse_test_block(pairs.next().unwrap(), &Variables::new()).is_err());
+    }
+
+    #[test]
+    fn parse_capture_and_deferred_placeholders() {
+        let input = r#"TEST Login
+POST https://example.com/login
+
+EXPECT status == 200
+CAPTURE token = body.access_token
+END
+
+TEST Profile
+GET https://example.com/me?session={{token}}
+Authorization: Bearer {{token}}
+
+EXPECT status == 200
+END
+"#
+        .to_string();
+
+        let tests = AxParser::parse_file(&input).unwrap();
+        assert_eq!(
+            tests[0].captures,
+            vec![Capture {
+                name: "token".to_string(),
+                path: "body.access_token".to_string(),
+            }]
+        );
+        assert!(tests[0].request.deferred.is_none());
+
+        let deferred = tests[1].request.deferred.as_ref().unwrap();
+        assert_eq!(deferred.placeholders(), vec!["token", "token"]);
+    }
+
+    #[test]
+    fn parse_leaves_secrets_to_send_time() {
+        let input = "TEST Profile\n\
+                     GET https://example.com/me\n\
+                     Authorization: Bearer {{secret.staging}}\n\
+                     \n\
+                     END\n";
+
+        let tests = AxParser::parse_file(input).unwrap();
+        let request = &tests[0].request;
+        assert_eq!(request.headers["Authorization"], "Bearer {{secret.staging}}");
+        assert_eq!(
+            request.deferred.as_ref().unwrap().placeholders(),
+            vec!["secret.staging"]
+        );
+    }
+
+    #[test]
+    fn parse_mailbox_receives() {
+        let input = r#"TEST Signup sends verification mail
+POST https://example.com/users
+
+EXPECT MAILBOX "user@example.com" RECEIVES SUBJECT CONTAINS "Verify" WITHIN 20s
+END
+"#
+        .to_string();
+
+        let tests = AxParser::parse_file(&input).unwrap();
+        assert_eq!(
+            tests[0].assertions[0],
+            Assertion::Receives {
+                source: "MAILBOX".to_string(),
+                key: "user@example.com".to_string(),
+                field: "subject".to_string(),
+                matcher: TextMatch::Contains,
+                expected: "Verify".to_string(),
+                within: std::time::Duration::from_secs(20),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_queue_receives() {
+        let input = r#"EXPECT KAFKA "user-events" RECEIVES KEY == "42" WITHIN 10s
+"#;
+        let mut pairs = AxParser::parse(Rule::expect, input).unwrap();
+        assert_eq!(
+            parse_expect(pairs.next().unwrap()).unwrap(),
+            Assertion::Receives {
+                source: "KAFKA".to_string(),
+                key: "user-events".to_string(),
+                field: "key".to_string(),
+                matcher: TextMatch::Equals,
+                expected: "42".to_string(),
+                within: std::time::Duration::from_secs(10),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_db_query() {
+        let input = r#"EXPECT DB "postgres://localhost/app" QUERY "select count(*) from users where email = 'x'" == 1 WITHIN 5s
+"#;
+        let mut pairs = AxParser::parse(Rule::expect, input).unwrap();
+        assert_eq!(
+            parse_expect(pairs.next().unwrap()).unwrap(),
+            Assertion::Query {
+                url: "postgres://localhost/app".to_string(),
+                query: "select count(*) from users where email = 'x'".to_string(),
+                op: Operator::Eq,
+                value: Value::Number(1),
+                within: Some(std::time::Duration::from_secs(5)),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_redis_key() {
+        let input = r#"EXPECT REDIS "redis://localhost:6379" KEY "session:{{user_id}}" EXISTS
+"#;
+        let mut pairs = AxParser::parse(Rule::expect, input).unwrap();
+        assert_eq!(
+            parse_expect(pairs.next().unwrap()).unwrap(),
+            Assertion::KeyValue {
+                url: "redis://localhost:6379".to_string(),
+                key: "session:{{user_id}}".to_string(),
+                expected: None,
+                within: None,
+            }
+        );
+
+        let input = r#"EXPECT REDIS "redis://localhost:6379" KEY "plan:7" == "pro" WITHIN 2s
+"#;
+        let mut pairs = AxParser::parse(Rule::expect, input).unwrap();
+        assert_eq!(
+            parse_expect(pairs.next().unwrap()).unwrap(),
+            Assertion::KeyValue {
+                url: "redis://localhost:6379".to_string(),
+                key: "plan:7".to_string(),
+                expected: Some((Operator::Eq, Value::String("pro".to_string()))),
+                within: Some(std::time::Duration::from_secs(2)),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_listen_callback() {
+        let input = r#"TEST Signup sends webhook
+POST https://example.com/users
+
+EXPECT status == 201
+LISTEN :9099 /callback EXPECT body.event == "user.created" WITHIN 30s
+END
+"#
+        .to_string();
+
+        let tests = AxParser::parse_file(&input).unwrap();
+        assert_eq!(
+            tests[0].assertions[1],
+            Assertion::Callback {
+                port: 9099,
+                path: "/callback".to_string(),
+                within: std::time::Duration::from_secs(30),
+                assertion: Box::new(Assertion::Binary {
+                    path: "body.event".to_string(),
+                    op: Operator::Eq,
+                    value: Value::String("user.created".to_string()),
+                }),
+            }
+        );
+        assert_eq!(tests[0].assertions[1].callback_target(), Some((9099, "/callback")));
+    }
+
+    #[test]
+    fn parse_poll_until() {
+        let input = r#"TEST Export finishes
+POST https://example.com/exports
+
+POLL EVERY 1s UNTIL body.status == "done" OR body.status == "failed" TIMEOUT 30s
+EXPECT body.status == "done"
+END
+"#
+        .to_string();
+
+        let tests = AxParser::parse_file(&input).unwrap();
+        let status = |value: &str| Assertion::Binary {
+            path: "body.status".to_string(),
+            op: Operator::Eq,
+            value: Value::String(value.to_string()),
+        };
+        assert_eq!(
+            tests[0].assertions[0],
+            Assertion::PollUntil {
+                interval: std::time::Duration::from_secs(1),
+                timeout: std::time::Duration::from_secs(30),
+                assertion: Box::new(Assertion::Or(vec![status("done"), status("failed")])),
+            }
+        );
+        assert_eq!(tests[0].assertion_lines, vec![4, 5]);
+
+        let too_slow = input.replace("EVERY 1s", "EVERY 1m");
+        assert!(AxParser::parse_file(&too_slow).is_err());
+    }
+
+    #[test]
+    fn parse_multi_step_test() {
+        let input = r#"TEST Order lifecycle
+DECODE json
+POST https://example.com/orders
+BODY
+{"item": "pen"}
+BODYEND
+
+EXPECT status == 201
+CAPTURE order_id = body.id
+
+GET https://example.com/orders/{{order_id}}
+
+EXPECT body.item == "pen"
+
+DELETE https://example.com/orders/{{order_id}}
+
+EXPECT status == 204
+END
+"#
+        .to_string();
+
+        let tests = AxParser::parse_file(&input).unwrap();
+        assert_eq!(tests.len(), 1);
+
+        let test = &tests[0];
+        assert_eq!(test.request.method, "POST");
+        assert_eq!(test.assertions.len(), 1);
+        assert_eq!(test.captures.len(), 1);
+        assert_eq!(test.steps.len(), 2);
+        assert_eq!(test.steps[0].request.method, "GET");
+        assert_eq!(test.steps[0].assertions.len(), 1);
+        assert_eq!(test.steps[1].request.method, "DELETE");
+        assert_eq!(test.steps[1].request.decode, Some(Decode::Json));
+        assert_eq!(
+            test.steps[1].request.deferred.as_ref().unwrap().placeholders(),
+            vec!["order_id"]
+        );
+        assert_eq!(test.requests().count(), 3);
+    }
+
+    #[test]
+    fn parse_waits_between_requests() {
+        let input = r#"TEST Export finishes
+POST https://example.com/exports
+
+WAIT 500ms
+
+EXPECT status == 202
+CAPTURE id = body.id
+
+SLEEP 2s
+GET https://example.com/exports/{{id}}
+
+EXPECT body.state == "done"
+
+GET https://example.com/exports/{{id}}/file
+WAIT 1s
+EXPECT status == 200
+END
+"#
+        .to_string();
+
+        let test = AxParser::parse_file(&input).unwrap().remove(0);
+        let seconds = std::time::Duration::from_secs;
+        assert_eq!(test.settle, Some(std::time::Duration::from_millis(500)));
+        assert_eq!(test.assertions.len(), 1);
+        assert_eq!((test.steps[0].delay, test.steps[0].settle), (Some(seconds(2)), None));
+        assert_eq!((test.steps[1].delay, test.steps[1].settle), (None, Some(seconds(1))));
+        assert_eq!(test.steps[1].assertions.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_test_block_tags() {
+        let input = r#"TEST Health
+TAGS smoke, regression
+GET https://httpbin.org/get
+
+EXPECT status == 200
+END"#;
+        let mut pairs = AxParser::parse(Rule::test_block, input).unwrap();
+        let test_case = parse_test_block(pairs.next().unwrap(), &Variables::new()).unwrap();
+        assert_eq!(test_case.tags, vec!["smoke", "regression"]);
+        assert!(test_case.has_tag("Smoke"));
+        assert!(!test_case.has_tag("slow"));
+    }
+
+    #[test]
+    fn test_parse_test_block_skip() {
+        let input = r#"TEST Flaky export
+SKIP "waiting on #123"
+GET https://httpbin.org/get
+
+EXPECT status == 200
+END
+
+TEST Legacy endpoint
+SKIP
+GET https://httpbin.org/get
+
+END
+"#
+        .to_string();
+        let tests = AxParser::parse_file(&input).unwrap();
+        assert_eq!(tests[0].skip, Some(Some("waiting on #123".to_string())));
+        assert_eq!(tests[1].skip, Some(None));
+        assert!(!tests[0].only);
+    }
+
+    #[test]
+    fn test_parse_test_block_only() {
+        let input = r#"TEST Checkout
+ONLY
+TAGS smoke
+POST https://httpbin.org/post
+
+EXPECT status == 200
+END"#;
+        let mut pairs = AxParser::parse(Rule::test_block, input).unwrap();
+        let test_case = parse_test_block(pairs.next().unwrap(), &Variables::new()).unwrap();
+        assert!(test_case.only);
+        assert_eq!(test_case.tags, vec!["smoke"]);
+    }
+
+    #[test]
+    fn test_parse_share_response() {
+        let parse = |method: &str| {
+            let input = format!(
+                "TEST Catalog\nSHARE RESPONSE\n{} https://example.com/catalog\n\nEND",
+                method
+            );
+            let mut pairs = AxParser::parse(Rule::test_block, &input).unwrap();
+            parse_test_block(pairs.next().unwrap(), &Variables::new())
+        };
+        assert!(parse("GET").unwrap().request.share);
+        assert!(parse("POST").is_err());
+        assert!(parse("RETRY 2\nGET").is_err());
+        assert!(parse("REPEAT 3\nGET").is_err());
+    }
+
+    #[test]
+    fn test_parse_freeze_time_and_seed() {
+        let input = r#"TEST Snapshot
+FREEZE TIME "2024-01-01T02:00:00+02:00"
+SEED 7
+GET https://example.com/orders?since={{now - 1d}}
+X-Request-Id: {{uuid()}}
+
+END
+"#;
+        let parse = || {
+            let mut pairs = AxParser::parse(Rule::test_block, input).unwrap();
+            parse_test_block(pairs.next().unwrap(), &Variables::new()).unwrap()
+        };
+        let test_case = parse();
+        assert_eq!(
+            test_case.request.url.query(),
+            Some("since=2023-12-31T00:00:00Z")
+        );
+        assert_eq!(
+            test_case.request.headers["X-Request-Id"],
+            parse().request.headers["X-Request-Id"]
+        );
+
+        let input = "TEST Bad\nFREEZE TIME \"soon\"\nGET https://example.com\n\nEND";
+        let mut pairs = AxParser::parse(Rule::test_block, input).unwrap();
+        assert!(parse_test_block(pairs.next().unwrap(), &Variables::new()).is_err());
+    }
+
+    #[test]
+    fn test_parse_test_block_repeat() {
+        let input = r#"TEST Intermittent 500
+REPEAT 10
+GET https://httpbin.org/get
+
+EXPECT status == 200
+END
+
+TEST Never
+REPEAT 0
+GET https://httpbin.org/get
+
+END
+"#;
+        let mut pairs = AxParser::parse(Rule::file, input).unwrap().next().unwrap().into_inner();
+        let test_case = parse_test_block(pairs.next().unwrap(), &Variables::new()).unwrap();
+        assert_eq!(test_case.repeat, 10);
+
+        let err = parse_test_block(pairs.next().unwrap(), &Variables::new()).unwrap_err();
+        assert_eq!(err.to_string(), "REPEAT needs at least 1 iteration");
+    }
+
+    #[test]
+    fn test_parse_syntax_declaration() {
+        let input = "\nSYNTAX 1\n\nTEST Health\nGET https://httpbin.org/get\n\nEND\n".to_string();
+        assert_eq!(AxParser::parse_file(&input).unwrap().len(), 1);
+
+        let newer = "SYNTAX 99\n\nTEST Health\nGET https://httpbin.org/get\n\nEND\n".to_string();
+        assert!(AxParser::parse_file(&newer).is_err());
+    }
+
+    #[test]
+    fn test_parse_test_block_retry() {
+        let input = r#"TEST Eventually consistent
+RETRY 3 DELAY 500ms
+GET https://httpbin.org/get
+
+EXPECT status == 200
+END
+
+TEST Immediate
+RETRY 2
+GET https://httpbin.org/get
+
+END
+"#
+        .to_string();
+        let tests = AxParser::parse_file(&input).unwrap();
+        assert_eq!(
+            tests[0].retry,
+            Some(Retry {
+                retries: 3,
+                delay: Some(std::time::Duration::from_millis(500)),
+            })
+        );
+        assert_eq!(
+            tests[1].retry,
+            Some(Retry {
+                retries: 2,
+                delay: None,
+            })
+        );
+        assert_eq!(tests[1].repeat, 1);
+    }
+
+    #[test]
+    fn test_parse_test_block_fixture() {
+        let input = r#"TEST List all users
+FIXTURE
+GET https://httpbin.org/get
+
+EXPECT status == 200
+END"#;
+        let mut pairs = AxParser::parse(Rule::test_block, input).unwrap();
+        let test_case = parse_test_block(pairs.next().unwrap(), &Variables::new()).unwrap();
+        assert_eq!(
+            test_case.fixture,
+            Some(PathBuf::from("fixtures/list-all-users.json"))
+        );
     }
 }
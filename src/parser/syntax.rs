@@ -0,0 +1,234 @@
+//! Versions of the `.ax` grammar.
+//!
+//! A file may start with `SYNTAX <n>` to declare the grammar it is written
+//! for; files without it predate the declaration and are read as syntax 1.
+//! Files written for an older syntax are upgraded in memory by the
+//! [`MIGRATIONS`] that followed it before parsing (the runner warns when
+//! one changes a file), and `axotly migrate` rewrites them on disk with the
+//! same migrations, declaring the current version. A file declaring a
+//! syntax that no longer has what a migration replaced is refused if it
+//! uses it anyway, as is a file declaring a newer syntax than this build
+//! knows.
+//!
+//! Migrations edit the [`Node`] tree of a file, which is then serialized
+//! back, so the layout, `VAR` lines and placeholders of a file survive an
+//! upgrade.
+
+use anyhow::{bail, Context, Result};
+
+use super::parser::Rule;
+use super::tree::Node;
+
+/// Grammar version of this build
+pub const CURRENT: u32 = 1;
+
+/// A breaking grammar change, as a rewrite of files written for `from`
+pub struct Migration {
+    /// Files declaring this syntax or older need the rewrite
+    pub from: u32,
+    pub describe: &'static str,
+    /// Text in every file the rewrite changes; files without it are not
+    /// parsed for it
+    pub trigger: &'static str,
+    /// Rewrite the tree of a file, telling whether anything changed
+    pub apply: fn(&mut Node) -> bool,
+}
+
+/// Every breaking change so far, oldest first. A grammar change that would
+/// break existing files bumps [`CURRENT`] and adds its rewrite here.
+pub const MIGRATIONS: &[Migration] = &[];
+
+/// Version declared by a leading `SYNTAX <n>` line, if any
+pub fn declared(source: &str) -> Result<Option<u32>> {
+    let Some(first) = source.lines().map(str::trim).find(|line| !line.is_empty()) else {
+        return Ok(None);
+    };
+    let Some(version) = first.strip_prefix("SYNTAX") else {
+        return Ok(None);
+    };
+    let Ok(version) = version.trim().parse::<u32>() else {
+        bail!(
+            "Invalid syntax declaration '{}': expected e.g. SYNTAX {}",
+            first,
+            CURRENT
+        );
+    };
+    if version == 0 || version > CURRENT {
+        bail!(
+            "Written for syntax {}, but this axotly reads syntax 1 to {}; upgrade axotly",
+            version,
+            CURRENT
+        );
+    }
+    Ok(Some(version))
+}
+
+/// Whether `source` is written for an older syntax: it declares one, or a
+/// migration changes it
+pub fn outdated(source: &str) -> Result<bool> {
+    Ok(declared(source)?.is_some_and(|version| version < CURRENT)
+        || rewrite(source, MIGRATIONS)?.is_some())
+}
+
+/// `source` rewritten for the current syntax, for parsing. The declaration
+/// line is left as is.
+pub fn upgrade(source: &str) -> Result<String> {
+    Ok(match rewrite(source, MIGRATIONS)? {
+        Some((tree, _)) => tree.serialize(),
+        None => source.to_string(),
+    })
+}
+
+/// Tree of `source` with the migrations for its syntax applied, and what
+/// they changed; `None` when none changes it. A file that does not parse
+/// is left for the parser to report.
+fn rewrite(source: &str, migrations: &[Migration]) -> Result<Option<(Node, Vec<&'static str>)>> {
+    let version = declared(source)?.unwrap_or(1);
+    let migrations: Vec<&Migration> = migrations
+        .iter()
+        .filter(|migration| source.contains(migration.trigger))
+        .collect();
+    if migrations.is_empty() {
+        return Ok(None);
+    }
+    let Ok(mut tree) = Node::parse(source) else {
+        return Ok(None);
+    };
+    let applied = apply(&mut tree, version, migrations)?;
+    Ok((!applied.is_empty()).then_some((tree, applied)))
+}
+
+/// Run the migrations for files written for `version` over `tree`, and
+/// refuse what later syntaxes no longer have. Returns what changed.
+fn apply<'a>(
+    tree: &mut Node,
+    version: u32,
+    migrations: impl IntoIterator<Item = &'a Migration>,
+) -> Result<Vec<&'static str>> {
+    let mut applied = Vec::new();
+    for migration in migrations {
+        if migration.from >= version {
+            if (migration.apply)(tree) {
+                applied.push(migration.describe);
+            }
+        } else if (migration.apply)(&mut tree.clone()) {
+            bail!("{} in syntax {}", migration.describe, migration.from + 1);
+        }
+    }
+    Ok(applied)
+}
+
+/// `source` upgraded and declaring [`CURRENT`], or `None` when it already
+/// is. Also returns the migrations applied.
+pub fn migrate(source: &str) -> Result<Option<(String, Vec<&'static str>)>> {
+    migrate_with(source, MIGRATIONS, CURRENT)
+}
+
+fn migrate_with(
+    source: &str,
+    migrations: &[Migration],
+    current: u32,
+) -> Result<Option<(String, Vec<&'static str>)>> {
+    let version = declared(source)?;
+    let mut tree = Node::parse(source).context("Cannot read it as any syntax")?;
+    let applied = apply(&mut tree, version.unwrap_or(1), migrations)?;
+    if version == Some(current) {
+        return Ok(None);
+    }
+
+    let header = format!("SYNTAX {}\n", current);
+    let mut stamped = false;
+    tree.visit_mut(&mut |node| {
+        if node.rule == Rule::syntax {
+            node.set_text(&header);
+            stamped = true;
+        }
+    });
+    if !stamped {
+        tree.prepend(&format!("{}\n", header));
+    }
+    Ok(Some((tree.serialize(), applied)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST: &str = "TEST Health\nGET https://example.com/health\n\nEND\n";
+
+    const LEGACY: &str = r#"VAR base = https://example.com
+
+TEST Export
+PATCH {{base}}/exports
+
+EXPECT status == 202
+CAPTURE id = body.id
+
+GET {{base}}/exports/{{id}}
+
+EXPECT body.method == "PATCH"
+END
+"#;
+
+    /// A breaking change for the tests only: syntax 2 has no PATCH
+    const PATCH_TO_PUT: &[Migration] = &[Migration {
+        from: 1,
+        describe: "PATCH replaced by PUT",
+        trigger: "PATCH",
+        apply: |tree| {
+            let mut changed = false;
+            tree.visit_mut(&mut |node| {
+                if node.rule == Rule::method && node.serialize() == "PATCH" {
+                    node.set_text("PUT");
+                    changed = true;
+                }
+            });
+            changed
+        },
+    }];
+
+    #[test]
+    fn declared_versions() {
+        assert_eq!(declared(TEST).unwrap(), None);
+        assert_eq!(declared(&format!("\nSYNTAX 1\n{}", TEST)).unwrap(), Some(1));
+        assert!(declared("SYNTAX 99\n").is_err());
+        assert!(declared("SYNTAX two\n").is_err());
+        assert!(!outdated(TEST).unwrap());
+        assert!(!outdated(&format!("SYNTAX {}\n{}", CURRENT, TEST)).unwrap());
+    }
+
+    #[test]
+    fn rewrite_applies_migrations_to_the_tree() {
+        let (tree, applied) = rewrite(LEGACY, PATCH_TO_PUT).unwrap().unwrap();
+        assert_eq!(tree.serialize(), LEGACY.replacen("PATCH {{base}}", "PUT {{base}}", 1));
+        assert_eq!(applied, vec!["PATCH replaced by PUT"]);
+        assert!(rewrite(TEST, PATCH_TO_PUT).unwrap().is_none());
+        assert_eq!(upgrade(LEGACY).unwrap(), LEGACY);
+    }
+
+    #[test]
+    fn migrate_stamps_the_current_syntax() {
+        let (text, applied) = migrate(TEST).unwrap().unwrap();
+        assert_eq!(text, format!("SYNTAX {}\n\n{}", CURRENT, TEST));
+        assert!(applied.is_empty());
+        assert!(migrate(&text).unwrap().is_none());
+    }
+
+    #[test]
+    fn migrate_keeps_the_layout() {
+        let old = format!("\nSYNTAX 1\n\n{}", LEGACY);
+        let (text, applied) = migrate_with(&old, PATCH_TO_PUT, 2).unwrap().unwrap();
+        assert_eq!(
+            text,
+            format!("\nSYNTAX 2\n\n{}", LEGACY.replacen("PATCH {{base}}", "PUT {{base}}", 1))
+        );
+        assert_eq!(applied, vec!["PATCH replaced by PUT"]);
+    }
+
+    #[test]
+    fn later_syntaxes_refuse_what_was_replaced() {
+        let mut tree = Node::parse(LEGACY).unwrap();
+        let error = apply(&mut tree, 2, PATCH_TO_PUT).unwrap_err();
+        assert_eq!(error.to_string(), "PATCH replaced by PUT in syntax 2");
+    }
+}
@@ -0,0 +1,149 @@
+//! Syntax tree of a whole `.ax` file, for rewriting it.
+//!
+//! [`AxParser`] turns source into tests, rendering placeholders and dropping
+//! `VAR` lines and layout on the way, so tests cannot be written back out.
+//! A [`Node`] instead keeps every byte of the text it was parsed from: the
+//! text of a grammar rule is its children and the text between them
+//! (keywords, spaces, blank lines, `{{#if}}` lines). [`Node::serialize`]
+//! gives back the source as it was, with any edits made to the tree.
+
+use anyhow::Result;
+use pest::iterators::Pair;
+use pest::Parser;
+
+use super::parser::{AxParser, Rule};
+
+/// A grammar rule matched in a file, with the text it covers
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub rule: Rule,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Node(Node),
+}
+
+impl Node {
+    /// The tree of `source`, a whole file
+    pub fn parse(source: &str) -> Result<Node> {
+        let file = AxParser::parse(Rule::file, source)
+            .map_err(|e| anyhow::anyhow!("Failed to parse input: {}", e))?
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Empty .ax file"))?;
+        Ok(Node::from_pair(file))
+    }
+
+    fn from_pair(pair: Pair<Rule>) -> Node {
+        let rule = pair.as_rule();
+        let span = pair.as_span();
+        // Offsets are into the whole source; `text` starts at the span
+        let text = |from: usize, to: usize| {
+            span.as_str()[from - span.start()..to - span.start()].to_string()
+        };
+
+        let mut parts = Vec::new();
+        let mut offset = span.start();
+        for child in pair.into_inner() {
+            let (start, end) = (child.as_span().start(), child.as_span().end());
+            if start > offset {
+                parts.push(Part::Text(text(offset, start)));
+            }
+            parts.push(Part::Node(Node::from_pair(child)));
+            offset = end;
+        }
+        if span.end() > offset {
+            parts.push(Part::Text(text(offset, span.end())));
+        }
+        Node { rule, parts }
+    }
+
+    /// The source text of this node, edits included
+    pub fn serialize(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out);
+        out
+    }
+
+    fn write(&self, out: &mut String) {
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => out.push_str(text),
+                Part::Node(node) => node.write(out),
+            }
+        }
+    }
+
+    /// Replace the whole text of this node, children included
+    pub fn set_text(&mut self, text: &str) {
+        self.parts = vec![Part::Text(text.to_string())];
+    }
+
+    /// Insert `text` before everything else in this node
+    pub fn prepend(&mut self, text: &str) {
+        self.parts.insert(0, Part::Text(text.to_string()));
+    }
+
+    /// Call `visit` on this node and every node below it, parents first
+    pub fn visit_mut(&mut self, visit: &mut impl FnMut(&mut Node)) {
+        visit(self);
+        for part in &mut self.parts {
+            if let Part::Node(node) = part {
+                node.visit_mut(visit);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_examples_unchanged() {
+        for entry in std::fs::read_dir("examples").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|ext| ext != "ax") {
+                continue;
+            }
+            let source = std::fs::read_to_string(&path).unwrap();
+            let tree = Node::parse(&source).unwrap();
+            assert_eq!(tree.serialize(), source, "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn keeps_conditional_lines() {
+        let source = r#"VAR env = staging
+
+TEST Health
+{{#if env == "prod"}}
+GET https://example.com/health
+{{else}}
+GET https://staging.example.com/health
+{{/if}}
+
+EXPECT status == 200
+END
+"#;
+        let tree = Node::parse(source).unwrap();
+        assert_eq!(tree.serialize(), source);
+    }
+
+    #[test]
+    fn edits_show_in_the_output() {
+        let mut tree = Node::parse("TEST Health\nGET https://example.com/health\n\nEND\n").unwrap();
+        tree.visit_mut(&mut |node| {
+            if node.rule == Rule::method {
+                node.set_text("DELETE");
+            }
+        });
+        tree.prepend("SYNTAX 2\n\n");
+        assert_eq!(
+            tree.serialize(),
+            "SYNTAX 2\n\nTEST Health\nDELETE https://example.com/health\n\nEND\n"
+        );
+    }
+}
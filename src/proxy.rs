@@ -0,0 +1,392 @@
+//! Recording HTTP proxy.
+//!
+//! `axotly proxy` starts a local forward proxy. Every plain-HTTP request that
+//! passes through it is forwarded upstream unchanged and, once the response
+//! arrives, written out as a starter `.ax` test block (method, URL, headers,
+//! body, and a status assertion) in `<out>/<host>.ax`. Credentials are never
+//! written: `Authorization` and cookie values become placeholders.
+//!
+//! HTTPS traffic is tunneled through `CONNECT` so browsing keeps working, but
+//! it cannot be recorded because the proxy never sees the decrypted requests.
+//!
+//! The proxy handles one request per connection and always answers with
+//! `Connection: close`, which keeps the implementation small while staying
+//! compatible with browsers and command-line clients. Request bodies, sized
+//! by `Content-Length` or chunked, are limited to 16 MiB.
+
+use anyhow::{Context, Result};
+use owo_colors::OwoColorize;
+use reqwest::{Client, Method};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Headers that only make sense for a single hop and are never forwarded
+const HOP_BY_HOP: &[&str] = &[
+    "connection",
+    "proxy-connection",
+    "keep-alive",
+    "transfer-encoding",
+    "te",
+    "trailer",
+    "upgrade",
+    "proxy-authorization",
+];
+
+/// Larger request bodies are rejected
+const MAX_REQUEST_BODY: usize = 16 * 1024 * 1024;
+
+/// Headers left out of recorded tests because reqwest sets them itself
+const NOT_RECORDED: &[&str] = &["host", "content-length", "accept-encoding"];
+
+/// Methods the .ax grammar can express
+const RECORDABLE_METHODS: &[&str] = &["GET", "POST", "PUT", "DELETE", "PATCH"];
+
+struct Recorder {
+    out: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl Recorder {
+    /// Append a test block to the file of the request's host
+    fn record(&self, host: &str, block: &str) -> Result<()> {
+        let file_name: String = host
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+            .collect();
+        let path = self.out.join(format!("{}.ax", file_name));
+
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        writeln!(file, "{}", block)?;
+
+        Ok(())
+    }
+}
+
+/// Start the proxy on `127.0.0.1:<port>` and record traffic into `out`
+pub async fn serve(port: u16, out: PathBuf) -> Result<()> {
+    std::fs::create_dir_all(&out)
+        .with_context(|| format!("Failed to create {}", out.display()))?;
+
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .await
+        .with_context(|| format!("Failed to listen on port {}", port))?;
+    let client = Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?;
+    let recorder = Arc::new(Recorder {
+        out: out.clone(),
+        lock: Mutex::new(()),
+    });
+
+    println!(
+        "{} Recording proxy listening on {} → {}",
+        "●".green().bold(),
+        format!("http://127.0.0.1:{}", port).bold(),
+        out.display().dimmed()
+    );
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let client = client.clone();
+        let recorder = Arc::clone(&recorder);
+
+        tokio::spawn(async move {
+            if let Err(error) = handle_connection(stream, &client, &recorder).await {
+                eprintln!("{} {}", "proxy error:".red(), error);
+            }
+        });
+    }
+}
+
+async fn handle_connection(stream: TcpStream, client: &Client, recorder: &Recorder) -> Result<()> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        anyhow::bail!("Malformed request line: {:?}", request_line.trim_end());
+    };
+    let (method, target) = (method.to_string(), target.to_string());
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            headers.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    if method == "CONNECT" {
+        let mut upstream = TcpStream::connect(&target).await?;
+        let mut stream = reader.into_inner();
+        stream
+            .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+            .await?;
+        tokio::io::copy_bidirectional(&mut stream, &mut upstream).await?;
+        return Ok(());
+    }
+
+    let body = read_body(&mut reader, &headers).await?;
+
+    let url = if target.starts_with('/') {
+        let host = header(&headers, "host").context("Request without Host header")?;
+        format!("http://{}{}", host, target)
+    } else {
+        target
+    };
+    let url = url::Url::parse(&url).with_context(|| format!("Invalid URL: {}", url))?;
+
+    let mut upstream = client.request(Method::from_bytes(method.as_bytes())?, url.as_str());
+    for (key, value) in &headers {
+        let lower = key.to_ascii_lowercase();
+        if !HOP_BY_HOP.contains(&lower.as_str()) && lower != "host" {
+            upstream = upstream.header(key, value);
+        }
+    }
+    let response = upstream.body(body.clone()).send().await?;
+
+    let status = response.status();
+    let response_headers: Vec<(String, Vec<u8>)> = response
+        .headers()
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.as_bytes().to_vec()))
+        .collect();
+    let response_body = response.bytes().await?;
+
+    let mut stream = reader.into_inner();
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\n",
+        status.as_u16(),
+        status.canonical_reason().unwrap_or("")
+    )
+    .into_bytes();
+    for (key, value) in &response_headers {
+        if HOP_BY_HOP.contains(&key.as_str()) || key == "content-length" {
+            continue;
+        }
+        head.extend_from_slice(key.as_bytes());
+        head.extend_from_slice(b": ");
+        head.extend_from_slice(value);
+        head.extend_from_slice(b"\r\n");
+    }
+    head.extend_from_slice(
+        format!("content-length: {}\r\nconnection: close\r\n\r\n", response_body.len()).as_bytes(),
+    );
+    stream.write_all(&head).await?;
+    stream.write_all(&response_body).await?;
+    stream.flush().await?;
+
+    println!("{} {} {}", status.as_u16(), method.bold(), url);
+
+    if RECORDABLE_METHODS.contains(&method.as_str()) {
+        let block = to_test_block(&method, &url, &headers, &body, status.as_u16());
+        recorder.record(url.host_str().unwrap_or("unknown"), &block)?;
+    }
+
+    Ok(())
+}
+
+/// Body of a request with `headers`: `Content-Length` bytes, or the decoded
+/// chunks of a chunked request
+async fn read_body(
+    reader: &mut (impl AsyncBufRead + Unpin),
+    headers: &[(String, String)],
+) -> Result<Vec<u8>> {
+    let chunked = header(headers, "transfer-encoding").is_some_and(|value| {
+        value
+            .split(',')
+            .any(|coding| coding.trim().eq_ignore_ascii_case("chunked"))
+    });
+    if !chunked {
+        let length = header(headers, "content-length")
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(0);
+        if length > MAX_REQUEST_BODY {
+            anyhow::bail!("Request body too large: {} bytes", length);
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).await?;
+        return Ok(body);
+    }
+
+    let mut body = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).await?;
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16)
+            .with_context(|| format!("Invalid chunk size: {:?}", line.trim_end()))?;
+        if size == 0 {
+            break;
+        }
+        if size > MAX_REQUEST_BODY - body.len() {
+            anyhow::bail!("Request body too large: over {} bytes", MAX_REQUEST_BODY);
+        }
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..]).await?;
+        let mut end = [0; 2];
+        reader.read_exact(&mut end).await?;
+    }
+    // Trailers, up to the blank line ending the request
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+    }
+    Ok(body)
+}
+
+/// Case-insensitive header lookup
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Render an exchange as a `.ax` test block asserting the observed status.
+/// Recorded files are meant to be committed, so credentials become
+/// placeholders: `Authorization` keeps its scheme and takes `{{token}}`, and
+/// each cookie of `Cookie` moves to a `COOKIES` block as `{{cookie_<name>}}`.
+fn to_test_block(
+    method: &str,
+    url: &url::Url,
+    headers: &[(String, String)],
+    body: &[u8],
+    status: u16,
+) -> String {
+    let mut block = format!("TEST {} {}\n{} {}\n", method, url.path(), method, url);
+    let mut cookies = Vec::new();
+
+    for (key, value) in headers {
+        let lower = key.to_ascii_lowercase();
+        if HOP_BY_HOP.contains(&lower.as_str()) || NOT_RECORDED.contains(&lower.as_str()) {
+            continue;
+        }
+        match lower.as_str() {
+            "authorization" => match value.split_once(' ') {
+                Some((scheme, _)) => block.push_str(&format!("{}: {} {{{{token}}}}\n", key, scheme)),
+                None => block.push_str(&format!("{}: {{{{token}}}}\n", key)),
+            },
+            "cookie" => cookies.extend(
+                value
+                    .split(';')
+                    .filter_map(|pair| pair.split_once('='))
+                    .map(|(name, _)| name.trim().to_string()),
+            ),
+            _ => block.push_str(&format!("{}: {}\n", key, value)),
+        }
+    }
+
+    if !cookies.is_empty() {
+        block.push_str("COOKIES\n");
+        for name in cookies {
+            let variable: String = name
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+                .collect();
+            block.push_str(&format!("{} = {{{{cookie_{}}}}}\n", name, variable));
+        }
+        block.push_str("COOKIESEND\n");
+    }
+
+    // Binary bodies cannot be expressed in a BODY block
+    if let Ok(text) = std::str::from_utf8(body) {
+        if !text.trim().is_empty() {
+            block.push_str(&format!("\nBODY\n{}\nBODYEND\n", text.trim_end()));
+        }
+    }
+
+    block.push_str(&format!("\nEXPECT status == {}\nEND\n", status));
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_test_block_skips_hop_by_hop_headers() {
+        let url = url::Url::parse("http://api.local/users?page=2").unwrap();
+        let headers = vec![
+            ("Host".to_string(), "api.local".to_string()),
+            ("Proxy-Connection".to_string(), "keep-alive".to_string()),
+            ("Content-Type".to_string(), "application/json".to_string()),
+        ];
+        let block = to_test_block("POST", &url, &headers, br#"{"name": "a"}"#, 201);
+
+        assert_eq!(
+            block,
+            "TEST POST /users\n\
+             POST http://api.local/users?page=2\n\
+             Content-Type: application/json\n\
+             \n\
+             BODY\n\
+             {\"name\": \"a\"}\n\
+             BODYEND\n\
+             \n\
+             EXPECT status == 201\n\
+             END\n"
+        );
+    }
+
+    #[test]
+    fn to_test_block_replaces_credentials_with_placeholders() {
+        let url = url::Url::parse("http://api.local/me").unwrap();
+        let headers = vec![
+            ("Authorization".to_string(), "Bearer eyJhbGciOi".to_string()),
+            ("Cookie".to_string(), "session-id=abc123; theme=dark".to_string()),
+        ];
+        let block = to_test_block("GET", &url, &headers, b"", 200);
+
+        assert_eq!(
+            block,
+            "TEST GET /me\n\
+             GET http://api.local/me\n\
+             Authorization: Bearer {{token}}\n\
+             COOKIES\n\
+             session-id = {{cookie_session_id}}\n\
+             theme = {{cookie_theme}}\n\
+             COOKIESEND\n\
+             \n\
+             EXPECT status == 200\n\
+             END\n"
+        );
+        assert!(crate::parser::AxParser::parse_file(&block).is_ok());
+    }
+
+    #[tokio::test]
+    async fn read_body_decodes_chunks_and_caps_length() {
+        let chunked = vec![("Transfer-Encoding".to_string(), "chunked".to_string())];
+        let mut input: &[u8] = b"5\r\nhello\r\n6;ext=1\r\n world\r\n0\r\nX-Sum: 1\r\n\r\n";
+        assert_eq!(read_body(&mut input, &chunked).await.unwrap(), b"hello world");
+
+        let mut input: &[u8] = b"zz\r\n";
+        assert!(read_body(&mut input, &chunked).await.is_err());
+        let mut input: &[u8] = b"2000000\r\n";
+        assert!(read_body(&mut input, &chunked).await.is_err());
+
+        let length = vec![("Content-Length".to_string(), "17000000".to_string())];
+        let mut input: &[u8] = b"";
+        assert!(read_body(&mut input, &length).await.is_err());
+    }
+}
@@ -0,0 +1,44 @@
+use crate::domain::{Renderer, TestCase};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Fans every rendering event out to several renderers, in order
+pub struct CompositeRenderer {
+    renderers: Vec<Box<dyn Renderer>>,
+}
+
+impl CompositeRenderer {
+    pub fn new(renderers: Vec<Box<dyn Renderer>>) -> Self {
+        Self { renderers }
+    }
+}
+
+impl Renderer for CompositeRenderer {
+    fn start(&self, total: Option<usize>) {
+        for renderer in &self.renderers {
+            renderer.start(total);
+        }
+    }
+
+    fn test(&self, test: &TestCase, file: Option<&PathBuf>) {
+        for renderer in &self.renderers {
+            renderer.test(test, file);
+        }
+    }
+
+    fn skipped_block(&self, file: &Path, error: &anyhow::Error) {
+        for renderer in &self.renderers {
+            renderer.skipped_block(file, error);
+        }
+    }
+
+    fn summary(&self, tests: &[TestCase], total_duration: &Duration) {
+        for renderer in &self.renderers {
+            renderer.summary(tests, total_duration);
+        }
+    }
+
+    fn owns_stdout(&self) -> bool {
+        self.renderers.iter().any(|renderer| renderer.owns_stdout())
+    }
+}
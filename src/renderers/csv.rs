@@ -0,0 +1,101 @@
+use crate::domain::{Renderer, TestCase, TestResult};
+use crate::renderers::output::Output;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const HEADER: [&str; 5] = ["file", "name", "status", "duration_ms", "failed_assertions"];
+
+/// One CSV row per test, written once the run completes, for spreadsheets
+pub struct CsvRenderer {
+    out: Output,
+}
+
+impl CsvRenderer {
+    pub fn new(out: Output) -> Self {
+        Self { out }
+    }
+}
+
+/// Columns of `test`, as in [`HEADER`]; tests that did not run have no
+/// duration
+fn row(test: &TestCase) -> [String; 5] {
+    let file = test
+        .location
+        .as_ref()
+        .map(|location| location.file.display().to_string())
+        .unwrap_or_default();
+    let name = test.name.clone().unwrap_or_default();
+    let (status, duration, failed) = match &test.result {
+        Some(TestResult::Passed { duration }) => ("passed", Some(duration), 0),
+        Some(TestResult::Failed { duration, errors }) => ("failed", Some(duration), errors.len()),
+        Some(TestResult::Skipped { .. }) => ("skipped", None, 0),
+        Some(TestResult::NotRun { .. }) | None => ("not_run", None, 0),
+    };
+    [
+        file,
+        name,
+        status.to_string(),
+        duration
+            .map(|d| d.as_millis().to_string())
+            .unwrap_or_default(),
+        failed.to_string(),
+    ]
+}
+
+impl Renderer for CsvRenderer {
+    fn start(&self, _total: Option<usize>) {}
+
+    fn test(&self, _test: &TestCase, _file: Option<&PathBuf>) {}
+
+    fn owns_stdout(&self) -> bool {
+        self.out.is_stdout()
+    }
+
+    fn summary(&self, tests: &[TestCase], _total_duration: &Duration) {
+        match table(tests) {
+            Ok(bytes) => write!(self.out, "{}", String::from_utf8_lossy(&bytes)),
+            Err(error) => eprintln!("Failed to write CSV report: {}", error),
+        }
+        self.out.flush();
+    }
+}
+
+fn table(tests: &[TestCase]) -> csv::Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(HEADER)?;
+    for test in tests {
+        writer.write_record(row(test))?;
+    }
+    writer
+        .into_inner()
+        .map_err(|error| error.into_error().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::AxParser;
+    use std::path::Path;
+
+    #[test]
+    fn rows_per_status() {
+        let input = "TEST Create, then list\nGET https://example.com/a\n\nEND\n\
+                     TEST Skipped\nGET https://example.com/b\n\nEND\n";
+        let mut tests = AxParser::parse_file(input).unwrap();
+        tests[0].locate(Path::new("api/users.ax"));
+        tests[0].result = Some(TestResult::Failed {
+            duration: Duration::from_millis(42),
+            errors: Vec::new(),
+        });
+        tests[1].result = Some(TestResult::Skipped { reason: None });
+
+        assert_eq!(
+            row(&tests[0]),
+            ["api/users.ax", "Create, then list", "failed", "42", "0"].map(String::from)
+        );
+        assert_eq!(
+            row(&tests[1]),
+            ["", "Skipped", "skipped", "", "0"].map(String::from)
+        );
+    }
+}
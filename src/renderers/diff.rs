@@ -1,17 +1,38 @@
 use crate::domain::{AssertionFailure, TestCase, TestResult, Renderer};
+use crate::renderers::elide::{elide_pair, MAX_VALUE};
+use crate::renderers::output::Output;
 use owo_colors::OwoColorize;
 use std::path::PathBuf;
 use std::time::Duration;
 
-pub struct DiffRenderer;
+pub struct DiffRenderer {
+    out: Output,
+    /// Print long values in full instead of eliding them (`--full-diff`)
+    full_diff: bool,
+}
 
 impl DiffRenderer {
-    pub fn new() -> Self {
-        Self
+    pub fn new(out: Output, full_diff: bool) -> Self {
+        Self { out, full_diff }
+    }
+
+    fn fmt_annotations(test: &TestCase) -> String {
+        if test.annotations.is_empty() {
+            String::new()
+        } else {
+            format!(" [{}]", test.annotations.join(", "))
+        }
+    }
+
+    fn fmt_timing(duration: &Duration, test: &TestCase) -> String {
+        let mut parts = vec![format!("{:?}", duration)];
+        parts.extend(test.execution_notes());
+        parts.join(", ")
     }
 
     fn render_failure(&self, index: usize, failure: &AssertionFailure) {
-        println!(
+        writeln!(
+            self.out,
             "  {} {}",
             index.to_string().dimmed(),
             failure.path.bold()
@@ -27,13 +48,21 @@ impl DiffRenderer {
             .as_deref()
             .unwrap_or("<missing>");
 
-        println!(
+        let (expected, actual) = if self.full_diff {
+            (expected.to_string(), actual.to_string())
+        } else {
+            elide_pair(expected, actual, MAX_VALUE)
+        };
+
+        writeln!(
+            self.out,
             "    {} {}",
             "- expected:".red(),
             expected.red()
         );
 
-        println!(
+        writeln!(
+            self.out,
             "    {} {}\n",
             "+ actual:  ".green(),
             actual.green()
@@ -42,25 +71,37 @@ impl DiffRenderer {
 }
 
 impl Renderer for DiffRenderer {
+    fn start(&self, total: Option<usize>) {
+        writeln!(self.out, "\n{}", "Axotly — API tests".bold());
+        match total {
+            Some(total) => writeln!(self.out, "Running {} tests...", total),
+            None => writeln!(self.out, "Running tests..."),
+        }
+    }
+
     fn test(&self, test: &TestCase, _file: Option<&PathBuf>) {
         let name = test.name.as_deref().unwrap_or("<unnamed>");
 
         match &test.result {
             Some(TestResult::Passed { duration }) => {
-                println!(
-                    "{} {} ({:?})",
+                writeln!(
+                    self.out,
+                    "{} {} ({}){}",
                     "✔".green(),
                     name.bold(),
-                    duration
+                    Self::fmt_timing(duration, test),
+                    Self::fmt_annotations(test).yellow()
                 );
             }
 
             Some(TestResult::Failed { duration, errors }) => {
-                println!(
-                    "{} {} ({:?})",
+                writeln!(
+                    self.out,
+                    "{} {} ({}){}",
                     "✖".red(),
                     name.bold(),
-                    duration
+                    Self::fmt_timing(duration, test),
+                    Self::fmt_annotations(test).yellow()
                 );
 
                 for (i, failure) in errors.iter().enumerate() {
@@ -68,6 +109,14 @@ impl Renderer for DiffRenderer {
                 }
             }
 
+            Some(TestResult::NotRun { reason }) => {
+                writeln!(self.out, "{} {} (not run: {})", "-".yellow(), name.bold(), reason);
+            }
+
+            Some(TestResult::Skipped { .. }) => {
+                writeln!(self.out, "{} {} (skipped)", "○".dimmed(), name.bold());
+            }
+
             None => {}
         }
     }
@@ -78,25 +127,42 @@ impl Renderer for DiffRenderer {
             .iter()
             .filter(|t| matches!(t.result, Some(TestResult::Passed { .. })))
             .count();
-        let failed = total - passed;
+        let not_run = tests
+            .iter()
+            .filter(|t| matches!(t.result, Some(TestResult::NotRun { .. })))
+            .count();
+        let skipped = tests
+            .iter()
+            .filter(|t| matches!(t.result, Some(TestResult::Skipped { .. })))
+            .count();
+        let failed = total - passed - not_run - skipped;
 
-        println!("{}", "─".repeat(40).dimmed());
+        writeln!(self.out, "{}", "─".repeat(40).dimmed());
 
-        if failed == 0 {
-            println!(
+        if failed == 0 && not_run == 0 {
+            writeln!(
+                self.out,
                 "{} {} tests passed",
                 "✔".green(),
-                total.to_string().bold()
+                passed.to_string().bold()
             );
         } else {
-            println!(
+            writeln!(
+                self.out,
                 "{} {} passed, {} failed",
                 "✖".red(),
                 passed.to_string().bold(),
                 failed.to_string().bold()
             );
         }
-        println!(
+        if not_run > 0 {
+            writeln!(self.out, "{} {} not run", "-".yellow(), not_run.to_string().bold());
+        }
+        if skipped > 0 {
+            writeln!(self.out, "{} {} skipped", "○".dimmed(), skipped.to_string().bold());
+        }
+        writeln!(
+            self.out,
             "Completed in: {}",
             format!("{:.2?}", total_duration).bold()
         );
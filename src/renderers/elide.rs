@@ -0,0 +1,107 @@
+//! Shortening of long values in failure output.
+//!
+//! A failed comparison of two big JSON strings would otherwise print both in
+//! full. When either side is longer than [`MAX_VALUE`] characters, the
+//! prefix and suffix the two sides share are elided down to a little
+//! context around the first difference, and whatever is still too long is
+//! cut with a count of the characters left out. `--full-diff` turns this
+//! off.
+
+/// Longest value printed in full
+pub const MAX_VALUE: usize = 200;
+
+/// Characters of the shared prefix and suffix kept around a difference
+const CONTEXT: usize = 20;
+
+/// `text` cut to `max` characters, saying how many were left out
+pub fn shorten(text: &str, max: usize) -> String {
+    let count = text.chars().count();
+    if count <= max {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max).collect();
+    format!("{}… ({} more chars)", kept, count - max)
+}
+
+/// `expected` and `actual` with their common prefix and suffix elided, so
+/// the region where they differ stands out
+pub fn elide_pair(expected: &str, actual: &str, max: usize) -> (String, String) {
+    let expected: Vec<char> = expected.chars().collect();
+    let actual: Vec<char> = actual.chars().collect();
+    if expected.len() <= max && actual.len() <= max {
+        return (expected.iter().collect(), actual.iter().collect());
+    }
+
+    let prefix = expected
+        .iter()
+        .zip(&actual)
+        .take_while(|(e, a)| e == a)
+        .count();
+    let suffix = expected[prefix..]
+        .iter()
+        .rev()
+        .zip(actual[prefix..].iter().rev())
+        .take_while(|(e, a)| e == a)
+        .count();
+
+    let start = prefix.saturating_sub(CONTEXT);
+    let trailing = suffix.saturating_sub(CONTEXT);
+    (
+        cut(&expected, start, trailing, max),
+        cut(&actual, start, trailing, max),
+    )
+}
+
+/// `chars` without its first `start` and last `trailing` characters, which
+/// are marked with an ellipsis
+fn cut(chars: &[char], start: usize, trailing: usize, max: usize) -> String {
+    let end = chars.len() - trailing;
+    let middle: String = chars[start..end].iter().collect();
+
+    let mut out = String::new();
+    if start > 0 {
+        out.push('…');
+    }
+    out.push_str(&shorten(&middle, max));
+    if trailing > 0 {
+        out.push('…');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_values_are_kept() {
+        assert_eq!(shorten("abc", 3), "abc");
+        assert_eq!(shorten("abcdef", 3), "abc… (3 more chars)");
+        assert_eq!(
+            elide_pair("ok", "fine", 10),
+            ("ok".to_string(), "fine".to_string())
+        );
+    }
+
+    #[test]
+    fn common_prefix_and_suffix_are_elided() {
+        let head = "a".repeat(100);
+        let tail = "z".repeat(100);
+        let (expected, actual) = elide_pair(
+            &format!("{}admin{}", head, tail),
+            &format!("{}guest{}", head, tail),
+            50,
+        );
+
+        let context = |middle: &str| format!("…{}{}{}…", "a".repeat(20), middle, "z".repeat(20));
+        assert_eq!(expected, context("admin"));
+        assert_eq!(actual, context("guest"));
+    }
+
+    #[test]
+    fn long_differences_are_cut() {
+        let (expected, actual) = elide_pair(&"x".repeat(300), "y", 10);
+        assert_eq!(expected, format!("{}… (290 more chars)", "x".repeat(10)));
+        assert_eq!(actual, "y");
+    }
+}
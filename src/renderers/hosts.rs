@@ -0,0 +1,139 @@
+//! Results grouped by the host each test calls.
+//!
+//! Suites covering several services mix their tests in one run; grouping
+//! by `host[:port]` shows which service regressed. Each group counts its
+//! tests, the share of executed tests that failed, and the latency of its
+//! requests.
+
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::domain::test_case::{TestCase, TestResult};
+
+/// Tests of one host
+#[derive(Debug, Default)]
+pub struct HostGroup {
+    pub host: String,
+    pub total: usize,
+    pub passed: usize,
+    pub failed: usize,
+    /// Durations of the tests that ran, sorted
+    pub durations: Vec<Duration>,
+}
+
+impl HostGroup {
+    /// Failed share of the tests that ran, 0.0 when none did
+    pub fn error_rate(&self) -> f64 {
+        let ran = self.passed + self.failed;
+        if ran == 0 {
+            0.0
+        } else {
+            self.failed as f64 / ran as f64
+        }
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        let count = self.durations.len() as u32;
+        (count > 0).then(|| self.durations.iter().sum::<Duration>() / count)
+    }
+
+    /// Nearest-rank percentile, `p` in 0..=100
+    pub fn percentile(&self, p: usize) -> Option<Duration> {
+        percentile(&self.durations, p)
+    }
+
+    pub fn to_json(&self) -> Value {
+        let ms = |d: Option<Duration>| d.map(|d| d.as_millis() as u64);
+        json!({
+            "host": self.host,
+            "total": self.total,
+            "passed": self.passed,
+            "failed": self.failed,
+            "error_rate": self.error_rate(),
+            "mean_ms": ms(self.mean()),
+            "p95_ms": ms(self.percentile(95)),
+            "max_ms": ms(self.durations.last().copied()),
+        })
+    }
+}
+
+/// Nearest-rank percentile of `sorted`, `p` in 0..=100
+pub fn percentile(sorted: &[Duration], p: usize) -> Option<Duration> {
+    let rank = (sorted.len() * p).div_ceil(100).max(1);
+    sorted.get(rank - 1).copied()
+}
+
+/// `host[:port]` of the URL `test` requests
+pub fn host_of(test: &TestCase) -> String {
+    let url = &test.request.url;
+    let host = url.host_str().unwrap_or("(no host)");
+    match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    }
+}
+
+/// `tests` grouped by host, in host order
+pub fn group(tests: &[TestCase]) -> Vec<HostGroup> {
+    let mut groups: BTreeMap<String, HostGroup> = BTreeMap::new();
+    for test in tests {
+        let host = host_of(test);
+        let group = groups.entry(host.clone()).or_insert_with(|| HostGroup {
+            host,
+            ..HostGroup::default()
+        });
+        group.total += 1;
+        match &test.result {
+            Some(TestResult::Passed { duration }) => {
+                group.passed += 1;
+                group.durations.push(*duration);
+            }
+            Some(TestResult::Failed { duration, .. }) => {
+                group.failed += 1;
+                group.durations.push(*duration);
+            }
+            _ => {}
+        }
+    }
+
+    let mut groups: Vec<_> = groups.into_values().collect();
+    for group in &mut groups {
+        group.durations.sort();
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::AxParser;
+
+    #[test]
+    fn groups_by_host_and_port() {
+        let input = "TEST A\nGET https://users.example.com/1\n\nEND\n\
+                     TEST B\nGET https://users.example.com/2\n\nEND\n\
+                     TEST C\nGET http://localhost:8081/orders\n\nEND\n\
+                     TEST D\nGET https://users.example.com/3\n\nEND\n";
+        let mut tests = AxParser::parse_file(input).unwrap();
+        let ms = Duration::from_millis;
+        tests[0].result = Some(TestResult::Passed { duration: ms(30) });
+        tests[1].result = Some(TestResult::Failed {
+            duration: ms(10),
+            errors: Vec::new(),
+        });
+        tests[2].result = Some(TestResult::Passed { duration: ms(5) });
+        tests[3].result = Some(TestResult::Skipped { reason: None });
+
+        let groups = group(&tests);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].host, "localhost:8081");
+        let users = &groups[1];
+        assert_eq!(users.host, "users.example.com");
+        assert_eq!((users.total, users.passed, users.failed), (3, 1, 1));
+        assert_eq!(users.error_rate(), 0.5);
+        assert_eq!(users.mean(), Some(ms(20)));
+        assert_eq!(users.percentile(95), Some(ms(30)));
+        assert_eq!(users.to_json()["max_ms"], 30);
+    }
+}
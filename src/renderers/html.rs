@@ -0,0 +1,139 @@
+use crate::domain::{Renderer, TestCase, TestResult};
+use crate::renderers::escape_markup;
+use crate::renderers::output::Output;
+use crate::renderers::slo::{self, Slo};
+use std::path::PathBuf;
+use std::time::Duration;
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2rem;color:#222}\
+table{border-collapse:collapse;width:100%}\
+th,td{text-align:left;padding:.4rem .6rem;border-bottom:1px solid #ddd;vertical-align:top}\
+.passed{color:#1a7f37}.failed{color:#cf222e}\
+pre{margin:0;white-space:pre-wrap}";
+
+/// Self-contained HTML report, written once the run completes
+pub struct HtmlRenderer {
+    out: Output,
+    /// Latency objectives reported above the tests
+    slos: Vec<Slo>,
+}
+
+impl HtmlRenderer {
+    pub fn new(out: Output) -> Self {
+        Self {
+            out,
+            slos: Vec::new(),
+        }
+    }
+
+    pub fn with_slos(mut self, slos: Vec<Slo>) -> Self {
+        self.slos = slos;
+        self
+    }
+}
+
+impl Renderer for HtmlRenderer {
+    fn start(&self, _total: Option<usize>) {}
+
+    fn test(&self, _test: &TestCase, _file: Option<&PathBuf>) {}
+
+    fn owns_stdout(&self) -> bool {
+        self.out.is_stdout()
+    }
+
+    fn summary(&self, tests: &[TestCase], total_duration: &Duration) {
+        let passed = tests
+            .iter()
+            .filter(|t| matches!(t.result, Some(TestResult::Passed { .. })))
+            .count();
+        let failed = tests
+            .iter()
+            .filter(|t| matches!(t.result, Some(TestResult::Failed { .. })))
+            .count();
+
+        writeln!(self.out, "<!DOCTYPE html>");
+        writeln!(
+            self.out,
+            "<html><head><meta charset=\"utf-8\"><title>Axotly report</title><style>{}</style></head><body>",
+            STYLE
+        );
+        writeln!(self.out, "<h1>Axotly — API tests</h1>");
+        writeln!(
+            self.out,
+            "<p><span class=\"passed\">{} passed</span>, <span class=\"failed\">{} failed</span> in {:.2?}</p>",
+            passed,
+            failed,
+            total_duration
+        );
+
+        if !self.slos.is_empty() {
+            writeln!(
+                self.out,
+                "<h2>SLOs</h2><table><thead><tr><th>Objective</th><th>Measured</th><th>Tests</th><th>Status</th></tr></thead><tbody>"
+            );
+            for compliance in slo::evaluate(&self.slos, tests) {
+                let (class, label) = match compliance.met() {
+                    Some(true) => ("passed", "✓ met"),
+                    Some(false) => ("failed", "✗ missed"),
+                    None => ("", "no samples"),
+                };
+                writeln!(
+                    self.out,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"{}\">{}</td></tr>",
+                    escape_markup(&compliance.slo.to_string()),
+                    compliance
+                        .measured
+                        .map(|d| format!("{}ms", d.as_millis()))
+                        .unwrap_or_default(),
+                    compliance.samples,
+                    class,
+                    label
+                );
+            }
+            writeln!(self.out, "</tbody></table><h2>Tests</h2>");
+        }
+
+        writeln!(
+            self.out,
+            "<table><thead><tr><th>Test</th><th>Request</th><th>Result</th><th>Duration</th><th>Details</th></tr></thead><tbody>"
+        );
+        for test in tests {
+            let name = escape_markup(test.name.as_deref().unwrap_or("<unnamed>"));
+            let request = escape_markup(&format!("{} {}", test.request.method, test.request.url));
+
+            let (class, label, duration, details) = match &test.result {
+                Some(TestResult::Passed { duration }) => ("passed", "✓ passed", Some(duration), String::new()),
+                Some(TestResult::Failed { duration, errors }) => {
+                    let details = errors
+                        .iter()
+                        .map(|e| escape_markup(&e.to_string()))
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    ("failed", "✗ failed", Some(duration), details)
+                }
+                Some(TestResult::NotRun { reason }) => ("", "not run", None, escape_markup(reason)),
+                Some(TestResult::Skipped { reason }) => (
+                    "",
+                    "skipped",
+                    None,
+                    escape_markup(reason.as_deref().unwrap_or_default()),
+                ),
+                None => ("", "not run", None, String::new()),
+            };
+
+            writeln!(
+                self.out,
+                "<tr><td>{}</td><td><code>{}</code></td><td class=\"{}\">{}</td><td>{}</td><td><pre>{}</pre></td></tr>",
+                name,
+                request,
+                class,
+                label,
+                duration.map(|d| format!("{}ms", d.as_millis())).unwrap_or_default(),
+                details
+            );
+        }
+        writeln!(self.out, "</tbody></table>");
+        writeln!(self.out, "</body></html>");
+        self.out.flush();
+    }
+}
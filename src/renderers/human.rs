@@ -1,14 +1,49 @@
 use crate::domain::{TestCase, TestResult, Renderer};
+use crate::renderers::elide::{shorten, MAX_VALUE};
+use crate::renderers::hosts;
+use crate::renderers::hyperlink;
+use crate::renderers::output::Output;
 use crate::renderers::response::ResponseRenderer;
+use crate::renderers::slo::{self, Slo};
 use std::time::Duration;
 use owo_colors::OwoColorize;
 use std::path::PathBuf;
 
-pub struct HumanRenderer;
+pub struct HumanRenderer {
+    out: Output,
+    /// Print long failure messages in full (`--full-diff`)
+    full_diff: bool,
+    /// Latency objectives reported in the summary
+    slos: Vec<Slo>,
+}
 
 impl HumanRenderer {
-    pub fn new() -> Self {
-        Self
+    pub fn new(out: Output, full_diff: bool) -> Self {
+        Self {
+            out,
+            full_diff,
+            slos: Vec::new(),
+        }
+    }
+
+    pub fn with_slos(mut self, slos: Vec<Slo>) -> Self {
+        self.slos = slos;
+        self
+    }
+
+    fn fmt_annotations(test: &TestCase) -> String {
+        if test.annotations.is_empty() {
+            String::new()
+        } else {
+            format!(" [{}]", test.annotations.join(", "))
+        }
+    }
+
+    /// `(1.2s)`, or `(1.2s, 3 attempts, waited 2s)` when retried or paced
+    fn fmt_timing(duration: &Duration, test: &TestCase) -> String {
+        let mut parts = vec![Self::fmt_duration(duration)];
+        parts.extend(test.execution_notes());
+        format!("({})", parts.join(", "))
     }
 
     fn fmt_duration(d: &Duration) -> String {
@@ -21,36 +56,69 @@ impl HumanRenderer {
 }
 
 impl Renderer for HumanRenderer {
+    fn start(&self, total: Option<usize>) {
+        writeln!(self.out, "\n{}", "Axotly — API tests".bold());
+        match total {
+            Some(total) => writeln!(self.out, "Running {} tests...", total),
+            None => writeln!(self.out, "Running tests..."),
+        }
+    }
+
     fn test(&self, test: &TestCase, file: Option<&PathBuf>) {
         if let Some(file) = file {
-            println!("\n{}", file.to_string_lossy().dimmed());
+            let text = file.to_string_lossy().dimmed().to_string();
+            writeln!(self.out, "\n{}", self.out.link(&hyperlink::file_url(file, None), &text));
         }
 
         let name = test.name.as_deref().unwrap_or("<unnamed>");
 
         match &test.result {
             Some(TestResult::Passed { duration }) => {
-                println!(
-                    "{} {} {}",
+                writeln!(
+                    self.out,
+                    "{} {} {}{}",
                     "✓".green().bold(),
                     name.bold(),
-                    format!("({})", Self::fmt_duration(duration))
-                        .dimmed()
+                    Self::fmt_timing(duration, test).dimmed(),
+                    Self::fmt_annotations(test).yellow()
                 );
             }
 
             Some(TestResult::Failed { duration, errors: _ }) => {
-                println!(
-                    "{} {} {}",
+                writeln!(
+                    self.out,
+                    "{} {} {}{}",
                     "✗".red().bold(),
                     name.bold(),
-                    format!("({})", Self::fmt_duration(duration))
-                        .dimmed()
+                    Self::fmt_timing(duration, test).dimmed(),
+                    Self::fmt_annotations(test).yellow()
+                );
+            }
+
+            Some(TestResult::NotRun { reason }) => {
+                writeln!(
+                    self.out,
+                    "{} {} {}",
+                    "-".yellow().bold(),
+                    name.yellow(),
+                    format!("(not run: {})", reason).dimmed()
+                );
+            }
+
+            Some(TestResult::Skipped { reason }) => {
+                let reason = reason.as_deref().map(|r| format!(": {}", r)).unwrap_or_default();
+                writeln!(
+                    self.out,
+                    "{} {} {}",
+                    "○".dimmed(),
+                    name.dimmed(),
+                    format!("(skipped{})", reason).dimmed()
                 );
             }
 
             None => {
-                println!(
+                writeln!(
+                    self.out,
                     "{} {}",
                     "?".yellow(),
                     name.yellow()
@@ -62,7 +130,11 @@ impl Renderer for HumanRenderer {
     fn summary(&self, tests: &[TestCase], total_duration: &Duration) {
         let mut passed = 0;
         let mut failed = 0;
+        let mut minor = 0;
+        let mut not_run = 0;
+        let mut skipped = 0;
         let mut total = Duration::ZERO;
+        let iterations: Vec<_> = tests.iter().flat_map(|test| &test.iterations).collect();
 
         for test in tests {
             if let Some(result) = &test.result {
@@ -73,38 +145,70 @@ impl Renderer for HumanRenderer {
                     }
                     TestResult::Failed { duration, .. } => {
                         failed += 1;
+                        if result.is_minor_failure() {
+                            minor += 1;
+                        }
                         total += *duration;
                     }
+                    TestResult::NotRun { .. } => not_run += 1,
+                    TestResult::Skipped { .. } => skipped += 1,
                 }
             }
         }
 
         if failed > 0 {
-            println!("\n{}", "Failures".red().bold());
+            writeln!(self.out, "\n{}", "Failures".red().bold());
 
             let mut idx = 1;
             for test in tests {
                 if let Some(TestResult::Failed { errors, duration }) = &test.result {
                     let name = test.name.as_deref().unwrap_or("<unnamed>");
 
-                    println!(
+                    writeln!(
+                        self.out,
                         "\n{} {} {}",
                         format!("{})", idx).red().bold(),
                         name.bold(),
-                        format!("({})", Self::fmt_duration(duration))
-                            .dimmed()
+                        Self::fmt_timing(duration, test).dimmed()
                     );
 
                     for error in errors {
-                        println!(
-                            "  {} {}",
+                        let message = if self.full_diff {
+                            error.message.clone()
+                        } else {
+                            shorten(&error.message, MAX_VALUE)
+                        };
+                        let severity = if error.minor {
+                            format!("{} ", "(minor)".yellow())
+                        } else {
+                            String::new()
+                        };
+                        writeln!(
+                            self.out,
+                            "  {} {}{}",
                             "-".red(),
-                            error.message
+                            severity,
+                            message
                         );
+                        if let Some(location) = &error.location {
+                            let url = hyperlink::file_url(&location.file, Some(location.start));
+                            let location = if location.file.as_os_str().is_empty() {
+                                location.to_string()
+                            } else {
+                                self.out.link(&url, &location.to_string())
+                            };
+                            writeln!(self.out, "    {} {}", "at".dimmed(), location);
+                        }
                     }
 
-                    if let Some(response) = &test.response {
-                       ResponseRenderer::print_response(response);
+                    if test.is_sensitive() {
+                        writeln!(
+                            self.out,
+                            "  {}",
+                            "(response hidden: test has SENSITIVE assertions)".dimmed()
+                        );
+                    } else if let Some(response) = &test.response {
+                       ResponseRenderer::write_response(&self.out, response);
                     }
 
                     idx += 1;
@@ -112,26 +216,104 @@ impl Renderer for HumanRenderer {
             }
         }
         
-        println!("\n{}", "────────────────────────────────────".dimmed());
-        println!("{}", "Results".bold());
-        println!(
+        writeln!(self.out, "\n{}", "────────────────────────────────────".dimmed());
+        writeln!(self.out, "{}", "Results".bold());
+        writeln!(
+            self.out,
             "{} {}",
             "✓ Passed:".green(),
             passed.to_string().green().bold()
         );
-        println!(
-            "{} {}",
+        let minor = if minor > 0 {
+            format!(" ({} on MINOR assertions only, not failing the run)", minor)
+        } else {
+            String::new()
+        };
+        writeln!(
+            self.out,
+            "{} {}{}",
             "✗ Failed:".red(),
-            failed.to_string().red().bold()
+            failed.to_string().red().bold(),
+            minor.yellow()
         );
-        println!(
+        if not_run > 0 {
+            writeln!(
+                self.out,
+                "{} {}",
+                "- Not run:".yellow(),
+                not_run.to_string().yellow().bold()
+            );
+        }
+        if skipped > 0 {
+            writeln!(self.out, "{} {}", "○ Skipped:".dimmed(), skipped.to_string().bold());
+        }
+        if !iterations.is_empty() {
+            let passed = iterations.iter().filter(|i| i.passed).count();
+            writeln!(
+                self.out,
+                "{} {}/{} passed",
+                "↻ Repeated iterations:".cyan(),
+                passed.to_string().bold(),
+                iterations.len()
+            );
+        }
+        writeln!(
+            self.out,
             "{} {}",
             "⏱ Total requests duration:".magenta(),
             Self::fmt_duration(&total).magenta().bold()
         );
-        println!("{}", "────────────────────────────────────".dimmed());
+        let groups = hosts::group(tests);
+        if groups.len() > 1 {
+            writeln!(self.out, "{}", "By host:".bold());
+            for group in groups {
+                let rate = format!("{:.0}% errors", group.error_rate() * 100.0);
+                let latency = match (group.mean(), group.percentile(95)) {
+                    (Some(mean), Some(p95)) => format!(
+                        ", mean {}, p95 {}",
+                        Self::fmt_duration(&mean),
+                        Self::fmt_duration(&p95)
+                    ),
+                    _ => String::new(),
+                };
+                writeln!(
+                    self.out,
+                    "  {} {}/{} passed, {}{}",
+                    group.host.bold(),
+                    group.passed,
+                    group.total,
+                    if group.failed > 0 { rate.red().to_string() } else { rate },
+                    latency.dimmed()
+                );
+            }
+        }
+        if !self.slos.is_empty() {
+            writeln!(self.out, "{}", "SLOs:".bold());
+            for compliance in slo::evaluate(&self.slos, tests) {
+                let (mark, measured) = match (compliance.met(), compliance.measured) {
+                    (Some(true), Some(measured)) => {
+                        ("✓".green().to_string(), Self::fmt_duration(&measured))
+                    }
+                    (Some(false), Some(measured)) => (
+                        "✗".red().to_string(),
+                        Self::fmt_duration(&measured).red().to_string(),
+                    ),
+                    _ => ("-".dimmed().to_string(), "no samples".to_string()),
+                };
+                writeln!(
+                    self.out,
+                    "  {} {} (measured {} over {} tests)",
+                    mark,
+                    compliance.slo,
+                    measured,
+                    compliance.samples
+                );
+            }
+        }
+        writeln!(self.out, "{}", "────────────────────────────────────".dimmed());
         
-        println!(
+        writeln!(
+            self.out,
             "Test suite completed in: {}",
             format!("{:.2?}", total_duration).bold()
         );
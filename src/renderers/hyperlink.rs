@@ -0,0 +1,133 @@
+//! Clickable file paths and URLs in terminal output (OSC 8 hyperlinks).
+//!
+//! Output to stdout links the files tests come from, the failing line of
+//! each assertion, and request URLs when the terminal is known to support
+//! hyperlinks; elsewhere the plain text is written. `--no-hyperlinks` turns
+//! them off, and `FORCE_HYPERLINK=1` (or `0`) overrides the probe.
+//!
+//! Lines are linked as `file:///abs/path.ax#12`, the form kitty and several
+//! editors' terminals open at the line.
+
+use std::io::IsTerminal;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+static DISABLED: AtomicBool = AtomicBool::new(false);
+static SUPPORTED: OnceLock<bool> = OnceLock::new();
+
+/// Never emit hyperlinks (`--no-hyperlinks`); must run before output starts
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// Whether stdout gets hyperlinks: not disabled, a terminal, and one that
+/// supports them
+pub fn enabled() -> bool {
+    !DISABLED.load(Ordering::Relaxed)
+        && *SUPPORTED.get_or_init(|| {
+            probe(
+                |name| std::env::var(name).ok(),
+                std::io::stdout().is_terminal(),
+            )
+        })
+}
+
+/// Capability probe over the environment, as terminals advertise themselves
+fn probe(var: impl Fn(&str) -> Option<String>, terminal: bool) -> bool {
+    match var("FORCE_HYPERLINK").as_deref() {
+        Some("0") => return false,
+        Some(_) => return true,
+        None => {}
+    }
+    if !terminal || var("TERM").as_deref() == Some("dumb") {
+        return false;
+    }
+
+    if var("WT_SESSION").is_some() || var("KONSOLE_VERSION").is_some() {
+        return true;
+    }
+    // VTE-based terminals (GNOME Terminal, Tilix, ...) from 0.50
+    if let Some(version) = var("VTE_VERSION") {
+        return version.parse::<u32>().is_ok_and(|v| v >= 5000);
+    }
+    if let Some(program) = var("TERM_PROGRAM") {
+        if matches!(
+            program.as_str(),
+            "iTerm.app" | "WezTerm" | "vscode" | "Hyper" | "ghostty"
+        ) {
+            return true;
+        }
+    }
+    matches!(
+        var("TERM").as_deref(),
+        Some("xterm-kitty" | "alacritty" | "foot" | "xterm-ghostty")
+    )
+}
+
+/// `text` linking to `url`
+pub fn link(url: &str, text: &str) -> String {
+    format!("\u{1b}]8;;{}\u{1b}\\{}\u{1b}]8;;\u{1b}\\", url, text)
+}
+
+/// `file://` URL of `path`, made absolute, at `line` when given
+pub fn file_url(path: &Path, line: Option<usize>) -> String {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut url = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                url.push(byte as char)
+            }
+            // Windows separators and drive letters
+            b'\\' => url.push('/'),
+            b':' => url.push(':'),
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    if !url[7..].starts_with('/') {
+        url.insert(7, '/');
+    }
+    if let Some(line) = line {
+        url.push_str(&format!("#{}", line));
+    }
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn probe_with(vars: &[(&str, &str)], terminal: bool) -> bool {
+        let vars: HashMap<_, _> = vars.iter().copied().collect();
+        probe(|name| vars.get(name).map(|v| v.to_string()), terminal)
+    }
+
+    #[test]
+    fn probe_recognizes_terminals() {
+        assert!(probe_with(&[("TERM_PROGRAM", "iTerm.app")], true));
+        assert!(probe_with(&[("VTE_VERSION", "7200")], true));
+        assert!(!probe_with(&[("VTE_VERSION", "4600")], true));
+        assert!(!probe_with(&[("TERM", "xterm-256color")], true));
+        assert!(!probe_with(&[("TERM_PROGRAM", "iTerm.app")], false));
+        assert!(probe_with(&[("FORCE_HYPERLINK", "1")], false));
+        assert!(!probe_with(
+            &[("FORCE_HYPERLINK", "0"), ("WT_SESSION", "x")],
+            true
+        ));
+    }
+
+    #[test]
+    fn file_url_is_absolute_and_escaped() {
+        assert_eq!(
+            file_url(Path::new("/srv/api tests/users.ax"), Some(12)),
+            "file:///srv/api%20tests/users.ax#12"
+        );
+        assert!(file_url(Path::new("users.ax"), None).starts_with("file:///"));
+        assert_eq!(
+            link("https://a.test", "a"),
+            "\u{1b}]8;;https://a.test\u{1b}\\a\u{1b}]8;;\u{1b}\\"
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use crate::domain::{Renderer, TestCase, TestResult};
+use crate::renderers::escape_markup;
+use crate::renderers::output::Output;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// JUnit XML report, written once the run completes. Test blocks that do
+/// not parse are reported as test cases in error, named after their file.
+pub struct JunitRenderer {
+    out: Output,
+    /// File and error of each block left out, until the summary
+    skipped_blocks: Mutex<Vec<(String, String)>>,
+}
+
+impl JunitRenderer {
+    pub fn new(out: Output) -> Self {
+        Self {
+            out,
+            skipped_blocks: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl Renderer for JunitRenderer {
+    fn start(&self, _total: Option<usize>) {}
+
+    fn test(&self, _test: &TestCase, _file: Option<&PathBuf>) {}
+
+    fn skipped_block(&self, file: &Path, error: &anyhow::Error) {
+        self.skipped_blocks
+            .lock()
+            .unwrap()
+            .push((file.display().to_string(), format!("{:#}", error)));
+    }
+
+    fn owns_stdout(&self) -> bool {
+        self.out.is_stdout()
+    }
+
+    fn summary(&self, tests: &[TestCase], total_duration: &Duration) {
+        let failures = tests
+            .iter()
+            .filter(|t| matches!(t.result, Some(TestResult::Failed { .. })))
+            .count();
+        let time = total_duration.as_secs_f64();
+        let skipped_blocks = self.skipped_blocks.lock().unwrap();
+        let total = tests.len() + skipped_blocks.len();
+        let errors = skipped_blocks.len();
+
+        writeln!(self.out, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        writeln!(
+            self.out,
+            r#"<testsuites name="axotly" tests="{}" failures="{}" errors="{}" time="{:.3}">"#,
+            total,
+            failures,
+            errors,
+            time
+        );
+        writeln!(
+            self.out,
+            r#"  <testsuite name="axotly" tests="{}" failures="{}" errors="{}" time="{:.3}">"#,
+            total,
+            failures,
+            errors,
+            time
+        );
+
+        for test in tests {
+            let name = escape_markup(test.name.as_deref().unwrap_or("<unnamed>"));
+            let classname = escape_markup(&format!("{} {}", test.request.method, test.request.url));
+
+            match &test.result {
+                Some(TestResult::Passed { duration }) => {
+                    writeln!(
+                        self.out,
+                        r#"    <testcase name="{}" classname="{}" time="{:.3}"/>"#,
+                        name,
+                        classname,
+                        duration.as_secs_f64()
+                    );
+                }
+                Some(TestResult::Failed { duration, errors }) => {
+                    writeln!(
+                        self.out,
+                        r#"    <testcase name="{}" classname="{}" time="{:.3}">"#,
+                        name,
+                        classname,
+                        duration.as_secs_f64()
+                    );
+                    for error in errors {
+                        writeln!(
+                            self.out,
+                            r#"      <failure message="{}">{}</failure>"#,
+                            escape_markup(&error.message),
+                            escape_markup(&error.to_string())
+                        );
+                    }
+                    writeln!(self.out, "    </testcase>");
+                }
+                Some(TestResult::NotRun { reason }) => {
+                    writeln!(
+                        self.out,
+                        r#"    <testcase name="{}" classname="{}"><skipped message="{}"/></testcase>"#,
+                        name,
+                        classname,
+                        escape_markup(reason)
+                    );
+                }
+                Some(TestResult::Skipped { reason: Some(reason) }) => {
+                    writeln!(
+                        self.out,
+                        r#"    <testcase name="{}" classname="{}"><skipped message="{}"/></testcase>"#,
+                        name,
+                        classname,
+                        escape_markup(reason)
+                    );
+                }
+                Some(TestResult::Skipped { reason: None }) | None => {
+                    writeln!(
+                        self.out,
+                        r#"    <testcase name="{}" classname="{}"><skipped/></testcase>"#,
+                        name,
+                        classname
+                    );
+                }
+            }
+        }
+
+        for (file, error) in skipped_blocks.iter() {
+            writeln!(
+                self.out,
+                r#"    <testcase name="Skipped block" classname="{}"><error message="{}"/></testcase>"#,
+                escape_markup(file),
+                escape_markup(error)
+            );
+        }
+
+        writeln!(self.out, "  </testsuite>");
+        writeln!(self.out, "</testsuites>");
+        self.out.flush();
+    }
+}
@@ -0,0 +1,131 @@
+use crate::domain::{Renderer, TestCase, TestResult};
+use crate::renderers::elide::{elide_pair, shorten, MAX_VALUE};
+use crate::renderers::output::Output;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Compact Markdown summary meant to be posted as a pull request comment:
+/// a one-line verdict followed by a table of failed assertions. Test blocks
+/// that do not parse are listed before the verdict.
+pub struct MarkdownRenderer {
+    out: Output,
+    /// Put long values in the table in full (`--full-diff`)
+    full_diff: bool,
+    /// File and error of each block left out, until the summary
+    skipped_blocks: Mutex<Vec<(String, String)>>,
+}
+
+impl MarkdownRenderer {
+    pub fn new(out: Output, full_diff: bool) -> Self {
+        Self {
+            out,
+            full_diff,
+            skipped_blocks: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+/// Make text safe to place inside a Markdown table cell
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', "<br>")
+}
+
+impl Renderer for MarkdownRenderer {
+    fn start(&self, _total: Option<usize>) {}
+
+    fn test(&self, _test: &TestCase, _file: Option<&PathBuf>) {}
+
+    fn skipped_block(&self, file: &Path, error: &anyhow::Error) {
+        self.skipped_blocks
+            .lock()
+            .unwrap()
+            .push((file.display().to_string(), format!("{:#}", error)));
+    }
+
+    fn owns_stdout(&self) -> bool {
+        self.out.is_stdout()
+    }
+
+    fn summary(&self, tests: &[TestCase], total_duration: &Duration) {
+        let failed: Vec<&TestCase> = tests
+            .iter()
+            .filter(|t| matches!(t.result, Some(TestResult::Failed { .. })))
+            .collect();
+        let passed = tests.len() - failed.len();
+
+        writeln!(self.out, "### Axotly — API tests\n");
+
+        let skipped_blocks = self.skipped_blocks.lock().unwrap();
+        if !skipped_blocks.is_empty() {
+            writeln!(
+                self.out,
+                "⚠️ **{}** test blocks skipped due to syntax errors:\n",
+                skipped_blocks.len()
+            );
+            for (file, error) in skipped_blocks.iter() {
+                writeln!(self.out, "- `{}`: {}", file, error.replace('\n', " "));
+            }
+            writeln!(self.out);
+        }
+
+        if failed.is_empty() && !skipped_blocks.is_empty() {
+            writeln!(
+                self.out,
+                "❌ **{}** tests passed in {:.2?}, but the suite is invalid",
+                passed, total_duration
+            );
+            self.out.flush();
+            return;
+        }
+        if failed.is_empty() {
+            writeln!(
+                self.out,
+                "✅ All **{}** tests passed in {:.2?}",
+                passed, total_duration
+            );
+            self.out.flush();
+            return;
+        }
+
+        writeln!(
+            self.out,
+            "❌ **{}** failed, **{}** passed in {:.2?}\n",
+            failed.len(),
+            passed,
+            total_duration
+        );
+        writeln!(self.out, "| Test | Request | Assertion | Expected | Actual |");
+        writeln!(self.out, "| ---- | ------- | --------- | -------- | ------ |");
+
+        for test in failed {
+            let Some(TestResult::Failed { errors, .. }) = &test.result else {
+                continue;
+            };
+            let name = cell(test.name.as_deref().unwrap_or("<unnamed>"));
+            let request = cell(&format!("{} {}", test.request.method, test.request.url));
+
+            for error in errors {
+                let expected = error.expected.as_deref().unwrap_or("-");
+                let actual = error.actual.as_deref().unwrap_or("<missing>");
+                let (message, expected, actual) = if self.full_diff {
+                    (error.message.clone(), expected.to_string(), actual.to_string())
+                } else {
+                    let (expected, actual) = elide_pair(expected, actual, MAX_VALUE);
+                    (shorten(&error.message, MAX_VALUE), expected, actual)
+                };
+                writeln!(
+                    self.out,
+                    "| {} | `{}` | {} | `{}` | `{}` |",
+                    name,
+                    request,
+                    cell(&message),
+                    cell(&expected),
+                    cell(&actual)
+                );
+            }
+        }
+
+        self.out.flush();
+    }
+}
@@ -1,3 +1,33 @@
  pub mod human;
  pub mod diff;
+ pub mod elide;
  pub mod response;
+ pub mod output;
+ pub mod rotate;
+ pub mod composite;
+ pub mod csv;
+ pub mod junit;
+ pub mod html;
+ pub mod ndjson;
+ pub mod template;
+ pub mod markdown;
+ pub mod timeline;
+ pub mod hosts;
+ pub mod slo;
+ pub mod hyperlink;
+
+/// Escape text for inclusion in XML/HTML content and attributes
+pub fn escape_markup(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
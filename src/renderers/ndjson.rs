@@ -0,0 +1,149 @@
+use crate::domain::{Renderer, TestCase, TestResult};
+use crate::renderers::hosts::{self, HostGroup};
+use crate::renderers::output::Output;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Newline-delimited JSON event stream, one event per line:
+///
+/// - `{"event": "start", "total": 12}` (`null` with `--stream`)
+/// - `{"event": "test", "name": ..., "status": "passed" | "failed", ...}`
+/// - `{"event": "skipped_block", "file": ..., "error": ...}` for a test block
+///   left out because it does not parse
+/// - `{"event": "summary", "total": 12, "passed": 11, "failed": 1, ...}`
+///
+/// This is the format consumed by external renderers (`--renderer exec:CMD`).
+pub struct NdjsonRenderer {
+    out: Output,
+}
+
+impl NdjsonRenderer {
+    pub fn new(out: Output) -> Self {
+        Self { out }
+    }
+
+    fn emit(&self, event: Value) {
+        writeln!(self.out, "{}", event);
+        self.out.flush();
+    }
+
+    fn test_event(test: &TestCase, file: Option<&PathBuf>) -> Value {
+        let mut event = test_to_json(test);
+        event["event"] = json!("test");
+        let file = file
+            .cloned()
+            .or_else(|| test.location.as_ref().map(|location| location.file.clone()));
+        event["file"] = json!(file.map(|f| f.display().to_string()));
+        event
+    }
+}
+
+/// JSON representation of an executed test, shared by machine-readable renderers
+pub fn test_to_json(test: &TestCase) -> Value {
+    let (status, duration, errors) = match &test.result {
+        Some(TestResult::Passed { duration }) => ("passed", Some(duration), Vec::new()),
+        Some(TestResult::Failed { duration, errors }) => {
+            let errors = errors
+                .iter()
+                .map(|e| {
+                    json!({
+                        "path": e.path,
+                        "expected": e.expected,
+                        "actual": e.actual,
+                        "message": e.message,
+                        "severity": if e.minor { "minor" } else { "critical" },
+                    })
+                })
+                .collect::<Vec<_>>();
+            ("failed", Some(duration), errors)
+        }
+        Some(TestResult::Skipped { .. }) => ("skipped", None, Vec::new()),
+        Some(TestResult::NotRun { .. }) | None => ("not_run", None, Vec::new()),
+    };
+
+    json!({
+        "name": test.name,
+        "method": test.request.method,
+        "url": test.request.url.as_str(),
+        "status": status,
+        "duration_ms": duration.map(|d| d.as_millis() as u64),
+        "response_status": test.response.as_ref().map(|r| r.status),
+        "errors": errors,
+        "not_run_reason": match &test.result {
+            Some(TestResult::NotRun { reason }) => Some(reason),
+            _ => None,
+        },
+        "skip_reason": match &test.result {
+            Some(TestResult::Skipped { reason }) => reason.as_ref(),
+            _ => None,
+        },
+        "annotations": test.annotations,
+        "tags": test.tags,
+        "sensitive": test.is_sensitive(),
+        "attempts": test.retry.is_some().then_some(test.attempts),
+        "polls": test.pacing.polls,
+        "waited_ms": test.pacing.waited.as_millis() as u64,
+        "iterations": (!test.iterations.is_empty()).then(|| json!({
+            "total": test.iterations.len(),
+            "failed": test.iterations.iter().filter(|i| !i.passed).count(),
+        })),
+    })
+}
+
+/// JSON totals of a run, shared by machine-readable renderers
+pub fn summary_to_json(tests: &[TestCase], total_duration: &Duration) -> Value {
+    let passed = tests
+        .iter()
+        .filter(|t| matches!(t.result, Some(TestResult::Passed { .. })))
+        .count();
+    let failed = tests
+        .iter()
+        .filter(|t| matches!(t.result, Some(TestResult::Failed { .. })))
+        .count();
+    let not_run = tests
+        .iter()
+        .filter(|t| matches!(t.result, Some(TestResult::NotRun { .. })))
+        .count();
+
+    json!({
+        "total": tests.len(),
+        "passed": passed,
+        "failed": failed,
+        "not_run": not_run,
+        "skipped": tests
+            .iter()
+            .filter(|t| matches!(t.result, Some(TestResult::Skipped { .. })))
+            .count(),
+        "duration_ms": total_duration.as_millis() as u64,
+        "hosts": hosts::group(tests).iter().map(HostGroup::to_json).collect::<Vec<_>>(),
+    })
+}
+
+impl Renderer for NdjsonRenderer {
+    fn start(&self, total: Option<usize>) {
+        self.emit(json!({ "event": "start", "total": total }));
+    }
+
+    fn test(&self, test: &TestCase, file: Option<&PathBuf>) {
+        self.emit(Self::test_event(test, file));
+    }
+
+    fn skipped_block(&self, file: &Path, error: &anyhow::Error) {
+        self.emit(json!({
+            "event": "skipped_block",
+            "file": file.display().to_string(),
+            "error": format!("{:#}", error),
+        }));
+    }
+
+    fn owns_stdout(&self) -> bool {
+        self.out.is_stdout()
+    }
+
+    fn summary(&self, tests: &[TestCase], total_duration: &Duration) {
+        let mut event = summary_to_json(tests, total_duration);
+        event["event"] = json!("summary");
+        self.emit(event);
+    }
+}
@@ -0,0 +1,203 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::Mutex;
+
+use super::hyperlink;
+use crate::domain::Renderer;
+use super::rotate::{Rotation, RotatingFile};
+
+/// Destination a renderer writes to.
+///
+/// Renderers format with `writeln!(self.out, ...)`. Write errors (e.g. a
+/// closed pipe) are ignored so reporting never aborts a run. Output written
+/// to files is stripped of ANSI color codes, and only stdout gets hyperlinks.
+pub struct Output {
+    writer: Mutex<Box<dyn Write + Send>>,
+    plain: bool,
+    hyperlinks: bool,
+    stdout: bool,
+}
+
+impl Output {
+    pub fn stdout() -> Self {
+        Self {
+            writer: Mutex::new(Box::new(io::stdout())),
+            plain: false,
+            hyperlinks: hyperlink::enabled(),
+            stdout: true,
+        }
+    }
+
+    pub fn stderr() -> Self {
+        Self {
+            writer: Mutex::new(Box::new(io::stderr())),
+            plain: false,
+            hyperlinks: false,
+            stdout: false,
+        }
+    }
+
+    /// Where a run's own messages go next to `renderer`: stdout, unless the
+    /// renderer owns it
+    pub fn console(renderer: &dyn Renderer) -> Self {
+        if renderer.owns_stdout() {
+            Self::stderr()
+        } else {
+            Self::stdout()
+        }
+    }
+
+    pub fn file(path: &Path) -> io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self {
+            writer: Mutex::new(Box::new(BufWriter::new(file))),
+            plain: true,
+            hyperlinks: false,
+            stdout: false,
+        })
+    }
+
+    /// Append to a log file rotated as `rotation` says. Unbuffered, so each
+    /// `writeln!` reaches the file (and the rotation check) whole.
+    pub fn rotating(path: &Path, rotation: Rotation) -> io::Result<Self> {
+        Ok(Self {
+            writer: Mutex::new(Box::new(RotatingFile::open(path, rotation)?)),
+            plain: true,
+            hyperlinks: false,
+            stdout: false,
+        })
+    }
+
+    /// Pipe output into the stdin of `command` (program and whitespace-separated
+    /// arguments). The process inherits stdout/stderr and is awaited on drop.
+    pub fn command(command: &str) -> io::Result<Self> {
+        let mut parts = command.split_whitespace();
+        let program = parts
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty command"))?;
+
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take();
+
+        Ok(Self {
+            writer: Mutex::new(Box::new(ChildWriter { child, stdin })),
+            plain: true,
+            hyperlinks: false,
+            stdout: false,
+        })
+    }
+
+    pub fn is_stdout(&self) -> bool {
+        self.stdout
+    }
+
+    /// `text` linking to `url` where hyperlinks are shown, else just `text`
+    pub fn link(&self, url: &str, text: &str) -> String {
+        if self.hyperlinks {
+            hyperlink::link(url, text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    pub fn write_fmt(&self, args: fmt::Arguments<'_>) {
+        let text = args.to_string();
+        let text = if self.plain { strip_ansi(&text) } else { text };
+
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writer.write_all(text.as_bytes());
+    }
+
+    pub fn flush(&self) {
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writer.flush();
+    }
+}
+
+impl Drop for Output {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Writes into a child process and waits for it to exit once dropped
+struct ChildWriter {
+    child: Child,
+    stdin: Option<ChildStdin>,
+}
+
+impl Write for ChildWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.stdin {
+            Some(stdin) => stdin.write(buf),
+            None => Err(io::ErrorKind::BrokenPipe.into()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.stdin {
+            Some(stdin) => stdin.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for ChildWriter {
+    fn drop(&mut self) {
+        // Closing stdin signals end of stream to the child
+        self.stdin.take();
+        let _ = self.child.wait();
+    }
+}
+
+/// Remove `ESC [ ... <letter>` escape sequences
+fn strip_ansi(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            for next in chars.by_ref() {
+                if next.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            output.push(c);
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_ansi_removes_color_codes() {
+        assert_eq!(strip_ansi("\u{1b}[32m✓\u{1b}[0m ok"), "✓ ok");
+    }
+
+    #[test]
+    fn console_yields_stdout_to_machine_readable_renderers() {
+        use crate::renderers::composite::CompositeRenderer;
+        use crate::renderers::human::HumanRenderer;
+        use crate::renderers::ndjson::NdjsonRenderer;
+
+        let human = CompositeRenderer::new(vec![Box::new(HumanRenderer::new(Output::stdout(), false))]);
+        assert!(Output::console(&human).is_stdout());
+
+        let ndjson = CompositeRenderer::new(vec![
+            Box::new(HumanRenderer::new(Output::stderr(), false)),
+            Box::new(NdjsonRenderer::new(Output::stdout())),
+        ]);
+        assert!(!Output::console(&ndjson).is_stdout());
+    }
+}
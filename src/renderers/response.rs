@@ -4,11 +4,12 @@ use reqwest::StatusCode;
 use std::time::Duration;
 
 use crate::domain::http_request::HttpResponse;
+use crate::renderers::output::Output;
 
 pub struct ResponseRenderer;
 
 impl ResponseRenderer {
-    pub fn print_status(status: StatusCode) {
+    pub fn write_status(out: &Output, status: StatusCode) {
         let colored = match status.as_u16() {
             200..=299 => status.as_str().green().to_string(),
             300..=399 => status.as_str().cyan().to_string(),
@@ -16,10 +17,10 @@ impl ResponseRenderer {
             _ => status.as_str().red().to_string(),
         };
 
-        println!("{} {}", "Status:".bold(), colored);
+        writeln!(out, "{} {}", "Status:".bold(), colored);
     }
 
-    pub fn print_duration(elapsed: Duration) {
+    pub fn write_duration(out: &Output, elapsed: Duration) {
         let ms = elapsed.as_millis();
 
         let colored = if ms < 500 {
@@ -30,10 +31,10 @@ impl ResponseRenderer {
             format!("{ms} ms").red().to_string()
         };
 
-        println!("{} {}", "Duration:".bold(), colored);
+        writeln!(out, "{} {}", "Duration:".bold(), colored);
     }
 
-    pub fn print_method(method: &str) {
+    pub fn write_method(out: &Output, method: &str) {
         let colored = match method.to_uppercase().as_str() {
             "GET" => method.green().to_string(),
             "POST" => method.yellow().to_string(),
@@ -42,39 +43,51 @@ impl ResponseRenderer {
             _ => method.white().to_string(),
         };
 
-        println!("{} {}", "Method:".bold(), colored);
+        writeln!(out, "{} {}", "Method:".bold(), colored);
     }
 
-    pub fn print_url(url: &str) {
-        println!("{} {}", "URL:".bold(), url.underline());
+    pub fn write_url(out: &Output, url: &str) {
+        let text = url.underline().to_string();
+        writeln!(out, "{} {}", "URL:".bold(), out.link(url, &text));
     }
 
-    pub fn print_headers(headers: &std::collections::HashMap<String, String>) {
-        println!("\n{}", "Headers:".bold().purple().to_string());
+    pub fn write_headers(out: &Output, headers: &std::collections::HashMap<String, String>) {
+        writeln!(out, "\n{}", "Headers:".bold().purple());
         for (key, value) in headers {
-            println!(" {}: {}", key.blue().to_string(), value);
+            writeln!(out, " {}: {}", key.blue(), value);
         }
     }
 
-    pub fn print_body(body: &str) {
-        println!("\n{}", "Body:".bold().purple().to_string());
-        println!("{body}");
+    pub fn write_body(out: &Output, body: &str) {
+        writeln!(out, "\n{}", "Body:".bold().purple());
+        writeln!(out, "{body}");
     }
 
-    pub fn print_response(response: &HttpResponse) {
+    pub fn write_response(out: &Output, response: &HttpResponse) {
         let status = StatusCode::from_u16(response.status)
             .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
         let request = &response.request.clone().unwrap();
 
-        Self::print_method(&request.method);
-        Self::print_url(&request.url.to_string());
+        Self::write_method(out, &request.method);
+        Self::write_url(out, request.url.as_str());
 
-        Self::print_status(status);
-        Self::print_duration(response.duration);
-        Self::print_headers(&response.headers);
+        Self::write_status(out, status);
+        Self::write_duration(out, response.duration);
+        Self::write_headers(out, &response.headers);
 
         if let Some(body) = &response.body {
-            Self::print_body(body);
+            Self::write_body(out, body);
+        }
+
+        if !response.trailers.is_empty() {
+            writeln!(out, "\n{}", "Trailers:".bold().purple());
+            for (key, value) in &response.trailers {
+                writeln!(out, " {}: {}", key.blue(), value);
+            }
         }
     }
+
+    pub fn print_response(response: &HttpResponse) {
+        Self::write_response(&Output::stdout(), response);
+    }
 }
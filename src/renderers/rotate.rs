@@ -0,0 +1,143 @@
+//! Log files rotated by size and day (`--log`).
+//!
+//! Scheduled or long-running use (e.g. probing production every minute)
+//! would otherwise grow a single results file without bound. A
+//! [`RotatingFile`] appends to its path and, once the file would exceed the
+//! size limit or a new day (UTC) starts, renames it to `<path>.1`, shifting
+//! older files up (`<path>.1` → `<path>.2`, ...) and deleting the ones past
+//! the retention count.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// When a log is rotated and how many old files are kept
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rotation {
+    pub max_bytes: Option<u64>,
+    /// Also rotate when the UTC date changes
+    pub daily: bool,
+    /// Rotated files kept next to the live one
+    pub keep: usize,
+}
+
+/// Append-only file that rotates itself between writes. Each write lands in
+/// a single file, so line-sized writes are never split across files.
+pub struct RotatingFile {
+    path: PathBuf,
+    rotation: Rotation,
+    file: File,
+    size: u64,
+    day: NaiveDate,
+}
+
+fn today() -> NaiveDate {
+    Utc::now().date_naive()
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+impl RotatingFile {
+    pub fn open(path: &Path, rotation: Rotation) -> io::Result<Self> {
+        let file = open_append(path)?;
+        let metadata = file.metadata()?;
+        let day = metadata
+            .modified()
+            .map(|modified| DateTime::<Utc>::from(modified).date_naive())
+            .unwrap_or_else(|_| today());
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            rotation,
+            file,
+            size: metadata.len(),
+            day,
+        })
+    }
+
+    fn numbered(&self, number: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", number));
+        name.into()
+    }
+
+    fn due(&self, incoming: usize) -> bool {
+        let too_big = self
+            .rotation
+            .max_bytes
+            .is_some_and(|max| self.size > 0 && self.size + incoming as u64 > max);
+        let new_day = self.rotation.daily && self.size > 0 && today() != self.day;
+        too_big || new_day
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        if self.rotation.keep == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            for number in (1..self.rotation.keep).rev() {
+                let from = self.numbered(number);
+                if from.exists() {
+                    std::fs::rename(from, self.numbered(number + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.numbered(1))?;
+        }
+
+        self.file = open_append(&self.path)?;
+        self.size = 0;
+        self.day = today();
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.due(buf.len()) {
+            self.rotate()?;
+        }
+        self.file.write_all(buf)?;
+        self.size += buf.len() as u64;
+        self.day = today();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotates_by_size_and_keeps_the_newest() {
+        let dir = std::env::temp_dir().join(format!("axotly-rotate-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("results.log");
+
+        let rotation = Rotation {
+            max_bytes: Some(10),
+            daily: false,
+            keep: 2,
+        };
+        let mut log = RotatingFile::open(&path, rotation).unwrap();
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            log.write_all(line.as_bytes()).unwrap();
+        }
+        drop(log);
+
+        let read = |path: &Path| std::fs::read_to_string(path).unwrap();
+        assert_eq!(read(&path), "fourth\n");
+        assert_eq!(read(&dir.join("results.log.1")), "third\n");
+        assert_eq!(read(&dir.join("results.log.2")), "second\n");
+        assert!(!dir.join("results.log.3").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
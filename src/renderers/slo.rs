@@ -0,0 +1,174 @@
+//! Latency objectives checked against a run (`[[slo]]` in the config).
+//!
+//! An objective bounds a percentile of the durations of the tests with a
+//! tag, or calling a host, e.g. `p95 < 400ms` for the tests tagged
+//! `checkout`. The summary and the HTML report show whether each objective
+//! was met by the tests that ran; objectives do not change the exit code.
+
+use anyhow::{bail, Context, Result};
+use std::fmt;
+use std::time::Duration;
+
+use crate::domain::test_case::{TestCase, TestResult};
+use crate::duration::parse_duration;
+use crate::renderers::hosts;
+
+/// Tests an objective applies to
+#[derive(Debug, Clone, PartialEq)]
+pub enum Scope {
+    Tag(String),
+    /// `host` or `host:port`
+    Host(String),
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scope::Tag(tag) => write!(f, "tag {}", tag),
+            Scope::Host(host) => write!(f, "host {}", host),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Slo {
+    pub scope: Scope,
+    /// Percentile bounded, in 1..=100
+    pub percentile: usize,
+    /// The percentile must stay under this
+    pub threshold: Duration,
+}
+
+impl Slo {
+    /// Objective from a target like `p95 < 400ms`
+    pub fn parse(scope: Scope, target: &str) -> Result<Self> {
+        let Some((percentile, threshold)) = target.split_once('<') else {
+            bail!(
+                "Invalid SLO target '{}': expected e.g. 'p95 < 400ms'",
+                target
+            );
+        };
+        let percentile: usize = percentile
+            .trim()
+            .strip_prefix('p')
+            .and_then(|p| p.parse().ok())
+            .filter(|p| (1..=100).contains(p))
+            .with_context(|| format!("Invalid percentile in SLO target '{}'", target))?;
+        let threshold = parse_duration(threshold.trim())
+            .with_context(|| format!("Invalid duration in SLO target '{}'", target))?;
+        Ok(Self {
+            scope,
+            percentile,
+            threshold,
+        })
+    }
+
+    fn applies_to(&self, test: &TestCase) -> bool {
+        match &self.scope {
+            Scope::Tag(tag) => test.has_tag(tag),
+            Scope::Host(host) => {
+                let url = &test.request.url;
+                url.host_str() == Some(host.as_str()) || hosts::host_of(test) == *host
+            }
+        }
+    }
+}
+
+impl fmt::Display for Slo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: p{} < {}ms",
+            self.scope,
+            self.percentile,
+            self.threshold.as_millis()
+        )
+    }
+}
+
+/// How a run fared against one objective
+#[derive(Debug)]
+pub struct Compliance<'a> {
+    pub slo: &'a Slo,
+    /// Tests of the scope that ran
+    pub samples: usize,
+    /// The bounded percentile, `None` when no test of the scope ran
+    pub measured: Option<Duration>,
+}
+
+impl Compliance<'_> {
+    /// Whether the objective held; `None` without samples
+    pub fn met(&self) -> Option<bool> {
+        self.measured.map(|measured| measured < self.slo.threshold)
+    }
+}
+
+/// Each of `slos` measured against `tests`
+pub fn evaluate<'a>(slos: &'a [Slo], tests: &[TestCase]) -> Vec<Compliance<'a>> {
+    slos.iter()
+        .map(|slo| {
+            let mut durations: Vec<Duration> = tests
+                .iter()
+                .filter(|test| slo.applies_to(test))
+                .filter_map(|test| match &test.result {
+                    Some(TestResult::Passed { duration })
+                    | Some(TestResult::Failed { duration, .. }) => Some(*duration),
+                    _ => None,
+                })
+                .collect();
+            durations.sort();
+            Compliance {
+                slo,
+                samples: durations.len(),
+                measured: hosts::percentile(&durations, slo.percentile),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::AxParser;
+
+    #[test]
+    fn parse_targets() {
+        let slo = Slo::parse(Scope::Tag("checkout".into()), "p95 < 400ms").unwrap();
+        assert_eq!(slo.percentile, 95);
+        assert_eq!(slo.threshold, Duration::from_millis(400));
+        assert_eq!(slo.to_string(), "tag checkout: p95 < 400ms");
+
+        let scope = || Scope::Host("api.example.com".into());
+        assert!(Slo::parse(scope(), "p99<1s").is_ok());
+        assert!(Slo::parse(scope(), "p0 < 1s").is_err());
+        assert!(Slo::parse(scope(), "95 < 1s").is_err());
+        assert!(Slo::parse(scope(), "p95 400ms").is_err());
+    }
+
+    #[test]
+    fn measures_tests_in_scope() {
+        let input = "TEST Pay\nTAGS checkout\nGET https://shop.example.com/pay\n\nEND\n\
+                     TEST Cart\nTAGS checkout\nGET https://shop.example.com/cart\n\nEND\n\
+                     TEST Search\nGET http://localhost:8081/search\n\nEND\n";
+        let mut tests = AxParser::parse_file(input).unwrap();
+        let ms = Duration::from_millis;
+        tests[0].result = Some(TestResult::Passed { duration: ms(300) });
+        tests[1].result = Some(TestResult::Failed {
+            duration: ms(500),
+            errors: Vec::new(),
+        });
+        tests[2].result = Some(TestResult::Passed { duration: ms(50) });
+
+        let slos = vec![
+            Slo::parse(Scope::Tag("checkout".into()), "p50 < 400ms").unwrap(),
+            Slo::parse(Scope::Tag("checkout".into()), "p95 < 400ms").unwrap(),
+            Slo::parse(Scope::Host("localhost:8081".into()), "p95 < 100ms").unwrap(),
+            Slo::parse(Scope::Tag("admin".into()), "p95 < 100ms").unwrap(),
+        ];
+        let report = evaluate(&slos, &tests);
+        let met: Vec<_> = report.iter().map(Compliance::met).collect();
+        assert_eq!(met, vec![Some(true), Some(false), Some(true), None]);
+        assert_eq!(report[1].samples, 2);
+        assert_eq!(report[1].measured, Some(ms(500)));
+    }
+}
@@ -0,0 +1,53 @@
+use crate::domain::{Renderer, TestCase};
+use crate::renderers::ndjson::{summary_to_json, test_to_json};
+use crate::renderers::output::Output;
+use anyhow::{Context, Result};
+use owo_colors::OwoColorize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tera::Tera;
+
+const TEMPLATE_NAME: &str = "report";
+
+/// Renders the finished run through a user-provided Tera template.
+///
+/// The template receives `tests` (the same objects as the NDJSON `test`
+/// events) and `summary` (`total`, `passed`, `failed`, `duration_ms`).
+pub struct TemplateRenderer {
+    out: Output,
+    tera: Tera,
+}
+
+impl TemplateRenderer {
+    pub fn new(out: Output, template: &Path) -> Result<Self> {
+        let mut tera = Tera::default();
+        tera.add_template_file(template, Some(TEMPLATE_NAME))
+            .with_context(|| format!("Failed to load template {}", template.display()))?;
+
+        Ok(Self { out, tera })
+    }
+}
+
+impl Renderer for TemplateRenderer {
+    fn start(&self, _total: Option<usize>) {}
+
+    fn test(&self, _test: &TestCase, _file: Option<&PathBuf>) {}
+
+    fn owns_stdout(&self) -> bool {
+        self.out.is_stdout()
+    }
+
+    fn summary(&self, tests: &[TestCase], total_duration: &Duration) {
+        let mut context = tera::Context::new();
+        context.insert("tests", &tests.iter().map(test_to_json).collect::<Vec<_>>());
+        context.insert("summary", &summary_to_json(tests, total_duration));
+
+        match self.tera.render(TEMPLATE_NAME, &context) {
+            Ok(rendered) => {
+                write!(self.out, "{}", rendered);
+                self.out.flush();
+            }
+            Err(error) => eprintln!("{} {:?}", "Template rendering failed:".red(), error),
+        }
+    }
+}
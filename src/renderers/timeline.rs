@@ -0,0 +1,138 @@
+//! When each test ran, as an ASCII Gantt chart (`--timeline`).
+//!
+//! One row per test, in start order, with a bar spanning the part of the
+//! run it was executing. Staircases of bars show tests waiting on each
+//! other; the footer gives the peak number of tests running at once, to
+//! compare with the concurrency limit.
+
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+use crate::domain::test_case::{TestCase, TestResult};
+
+/// Longest test name shown before its bar
+const NAME_WIDTH: usize = 30;
+
+fn label(test: &TestCase) -> String {
+    let name = test.name.as_deref().unwrap_or("(unnamed)").trim();
+    if name.chars().count() <= NAME_WIDTH {
+        return name.to_string();
+    }
+    let kept: String = name.chars().take(NAME_WIDTH - 1).collect();
+    format!("{}…", kept)
+}
+
+/// Most spans overlapping at one instant
+fn peak_overlap(spans: &[(Instant, Instant)]) -> usize {
+    let mut events: Vec<(Instant, i32)> = spans
+        .iter()
+        .flat_map(|(start, end)| [(*start, 1), (*end, -1)])
+        .collect();
+    // Ends before starts at the same instant: back-to-back is not overlap
+    events.sort_by_key(|(at, change)| (*at, *change));
+
+    let mut running = 0;
+    let mut peak = 0;
+    for (_, change) in events {
+        running += change;
+        peak = peak.max(running);
+    }
+    peak as usize
+}
+
+/// The chart for `tests`, with bars `width` columns wide; empty when no test
+/// ran
+pub fn render(tests: &[TestCase], width: usize) -> String {
+    let mut ran: Vec<(&TestCase, (Instant, Instant))> = tests
+        .iter()
+        .filter_map(|test| Some((test, test.span?)))
+        .collect();
+    let Some(origin) = ran.iter().map(|(_, (start, _))| *start).min() else {
+        return String::new();
+    };
+    let end = ran.iter().map(|(_, (_, end))| *end).max().unwrap();
+    ran.sort_by_key(|(_, (start, _))| *start);
+
+    let total = end.duration_since(origin).max(Duration::from_millis(1));
+    let column = |at: Instant| {
+        let offset = at.duration_since(origin).as_secs_f64() / total.as_secs_f64();
+        ((offset * width as f64).round() as usize).min(width)
+    };
+
+    let mut out = format!("\nTimeline (0 – {:.2?})\n", total);
+    for (test, (start, stop)) in &ran {
+        let from = column(*start);
+        // Every test gets at least one cell, however short
+        let to = column(*stop).max(from + 1).min(width);
+        let from = from.min(to - 1);
+        let fill = match test.result {
+            Some(TestResult::Passed { .. }) => '█',
+            _ => '▒',
+        };
+        let bar: String = (0..width)
+            .map(|cell| {
+                if (from..to).contains(&cell) {
+                    fill
+                } else {
+                    '·'
+                }
+            })
+            .collect();
+        let _ = writeln!(
+            out,
+            "{:<name$} |{}| {:>6}ms",
+            label(test),
+            bar,
+            stop.duration_since(*start).as_millis(),
+            name = NAME_WIDTH
+        );
+    }
+
+    let spans: Vec<_> = ran.iter().map(|(_, span)| *span).collect();
+    let _ = writeln!(
+        out,
+        "Peak concurrency: {} of {} tests",
+        peak_overlap(&spans),
+        ran.len()
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::AxParser;
+
+    #[test]
+    fn overlapping_spans() {
+        let t = Instant::now();
+        let ms = |n| t + Duration::from_millis(n);
+        assert_eq!(peak_overlap(&[(ms(0), ms(10)), (ms(10), ms(20))]), 1);
+        assert_eq!(
+            peak_overlap(&[(ms(0), ms(10)), (ms(5), ms(20)), (ms(6), ms(7))]),
+            3
+        );
+    }
+
+    #[test]
+    fn bars_follow_start_and_end() {
+        let input = "TEST Slow\nGET https://example.com/1\n\nEND\nTEST Fast\nGET https://example.com/2\n\nEND\n";
+        let mut tests = AxParser::parse_file(input).unwrap();
+        let t = Instant::now();
+        let ms = |n| t + Duration::from_millis(n);
+        tests[0].span = Some((ms(0), ms(100)));
+        tests[1].span = Some((ms(50), ms(100)));
+        for test in &mut tests {
+            test.result = Some(TestResult::Passed {
+                duration: Duration::ZERO,
+            });
+        }
+
+        let chart = render(&tests, 10);
+        let rows: Vec<&str> = chart.lines().collect();
+        assert!(rows[2].starts_with("Slow"));
+        assert!(rows[2].contains("|██████████|"));
+        assert!(rows[3].contains("|·····█████|"));
+        assert_eq!(rows[4], "Peak concurrency: 2 of 2 tests");
+    }
+}
@@ -27,6 +27,7 @@
 //! 1. Determine whether the provided path is a file or directory.
 //! 2. Discover all `.ax` files (recursively for directories).
 //! 3. Parse each file into a list of [`TestCase`]s.
+//!    Every unique host is then resolved once (the DNS pre-flight).
 //! 4. Initialize the renderer and start timing.
 //! 5. Execute tests file-by-file using the [`Executor`].
 //! 6. Render each test result as it completes.
@@ -37,71 +38,460 @@
 //! - Test results are rendered immediately after execution.
 //! - File boundaries are printed to provide visual grouping.
 //! - Optional HTTP responses can be printed when `show_response` is enabled.
+//! - JSON response bodies are normalized (sorted keys, ignored paths) before
+//!   rendering when configured, so output stays stable across runs. The
+//!   ignored paths are left out of `EQUALS FILE` comparisons as well.
 //!
-//! Errors while reading or parsing files are surfaced immediately and stop
-//! execution.
+//! With `--changed-since <rev>`, only tests affected by the files changed in
+//! git since that revision run (see [`crate::changes`]).
+//!
+//! With `--artifacts <dir>`, the request, response, timing and variables of
+//! every failed test are written to `<dir>/<test-id>/` (see
+//! [`crate::artifacts`]).
+//!
+//! Tests that fail or do not run are recorded for `--rerun-failed` (see
+//! [`crate::last_run`]).
+//! With `--baseline <report>`, failures are marked new or pre-existing
+//! against an earlier run (see [`crate::baseline`]).
+//! Every outcome is also kept in a history, and `--order priority` runs the
+//! tests likely to fail first (see [`crate::history`]).
+//!
+//! When `run_timeout` is set, tests still running when it expires are
+//! cancelled and marked not run, and the run reports [`RunOutcome::TimedOut`].
+//!
+//! With `--stream`, files are parsed block by block on another thread while
+//! the tests parsed so far run, so the first tests start before later files
+//! are read and no file's whole parse tree is built. Each file's text is
+//! still read into memory whole (see [`crate::parser::blocks`]).
+//!
+//! The runner's own messages (banners, file headers, warnings, the timeline)
+//! go to stderr when a machine-readable renderer writes to stdout, so they
+//! never end up in its output.
+//!
+//! A test block that does not parse is left out with a diagnostic naming its
+//! line, the rest of its file still runs, the renderers report it, and the
+//! run ends as [`RunOutcome::Invalid`].
+//! Other errors while reading or parsing files are surfaced immediately and
+//! stop execution.
 
-use std::path::Path;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use std::time::Duration;
 use walkdir::WalkDir;
 use anyhow::{Result, Context};
+use clap::ValueEnum;
 
-use crate::domain::test_case::TestCase;
+use crate::domain::test_case::{TestCase, TestResult};
+use crate::domain::{Chaos, CircuitBreaker, ExecutionContext, Normalization};
 use crate::domain::renderer::Renderer;
+use crate::domain::signing::RequestSigner;
+use crate::domain::source::ExternalSource;
+use crate::domain::token::TokenRefresher;
+use crate::renderers::output::Output;
 use crate::renderers::response::ResponseRenderer;
+use crate::renderers::timeline;
+use crate::artifacts::Artifacts;
+use crate::changes;
+use crate::dns;
+use crate::env;
+use crate::exit::{ErrorKind, Kind};
 use crate::executor::Executor;
-use crate::parser::AxParser;
+use crate::history::History;
+use crate::baseline::Baseline;
+use crate::last_run::LastRun;
+use crate::parser::blocks::strip_comments;
+use crate::parser::{syntax, AxParser};
+use crate::template::{self, Variables};
 use owo_colors::OwoColorize;
 
+/// Columns of the `--timeline` bars
+const TIMELINE_WIDTH: usize = 50;
+
+/// Tests handed from the parser to the executor at a time with `--stream`
+const STREAM_CHUNK: usize = 50;
+
+/// Options controlling a single runner invocation
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    pub max_concurrency: usize,
+    pub show_response: bool,
+    /// Applied to response bodies before they are rendered
+    pub normalization: Normalization,
+    /// Client-side adverse conditions applied to every request
+    pub chaos: Chaos,
+    /// Headers added to every request that does not set them itself
+    pub default_headers: Vec<(String, String)>,
+    /// Cancel whatever is still running once the whole run takes this long
+    pub run_timeout: Option<Duration>,
+    /// Consecutive transport failures after which a host's tests fail fast
+    pub circuit_threshold: Option<u32>,
+    /// Resolve every host once before running (see [`dns`])
+    pub preresolve: bool,
+    /// Environment whose variables feed the `.ax` templates (see [`env`])
+    pub env: Option<String>,
+    /// Signs each request right before it is sent
+    pub signer: Option<Arc<dyn RequestSigner>>,
+    /// Keeps a captured token fresh during the run
+    pub refresher: Option<Arc<TokenRefresher>>,
+    /// External sources by assertion keyword, e.g. `MAILBOX`
+    pub sources: HashMap<String, Arc<dyn ExternalSource>>,
+    /// Run only the tests affected by files changed since this git revision
+    /// (see [`crate::changes`])
+    pub changed_since: Option<String>,
+    /// Write post-mortem files of failed tests here (see [`crate::artifacts`])
+    pub artifacts: Option<PathBuf>,
+    /// Run only the tests that failed last time (see [`crate::last_run`])
+    pub rerun_failed: bool,
+    /// Ask before sending each mutating request (see
+    /// [`crate::domain::confirm`])
+    pub confirm: bool,
+    /// Print when each test ran after the summary (see
+    /// [`crate::renderers::timeline`])
+    pub timeline: bool,
+    /// Run tests while their files are still being parsed (see
+    /// [`crate::parser::blocks`])
+    pub stream: bool,
+    /// Order in which files and tests run
+    pub order: Order,
+    /// Variables set over the environment's, e.g. those of an API version
+    /// (see [`crate::api_versions`])
+    pub overrides: Variables,
+    /// Report of an earlier run to tell new failures from known ones (see
+    /// [`crate::baseline`])
+    pub baseline: Option<PathBuf>,
+}
+
+/// Order of a run (`--order`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Order {
+    /// As written, file by file
+    #[default]
+    File,
+    /// Likely failures first, from past runs (see [`crate::history`])
+    Priority,
+}
+
+/// Consecutive tests of one file, `first_index` being the first one's index
+/// in it, with the blocks among them that were left out for not parsing
+struct Batch {
+    file: PathBuf,
+    first_index: usize,
+    tests: Vec<TestCase>,
+    skipped: Vec<anyhow::Error>,
+}
+
+/// Tests of each file, in discovery order
+type Discovered = Vec<(PathBuf, Vec<TestCase>)>;
+
+/// Blocks left out of each file because they do not parse, and why
+type Skipped = Vec<(PathBuf, Vec<anyhow::Error>)>;
+
+/// How a run ended, for its exit code (see [`crate::exit`])
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+    /// Every test passed, was skipped, or failed `MINOR` assertions only
+    Completed,
+    /// Some test failed a critical assertion or did not run
+    Failed,
+    /// Tests failed without any of them getting a response
+    Unreachable,
+    /// `run_timeout` expired; unfinished tests were marked not run
+    TimedOut,
+    /// Some test blocks did not parse and were left out, whatever ran
+    Invalid,
+}
+
 pub struct Runner;
 
 impl Runner {
     /// Run tests from a single file or folder and produce a single summary
     pub async fn run_path<P: AsRef<Path>>(
         path: P,
-        max_concurrency: usize,
+        options: &RunOptions,
         renderer: &dyn Renderer,
-        show_response: bool,
-    ) -> Result<()> {
+    ) -> Result<RunOutcome> {
         let path = path.as_ref();
+        Self::check_environment(path)?;
+        let console = Output::console(renderer);
 
         // Gather all tests with their file paths
-        let mut all_tests = Vec::new();
-        let mut all_results = Vec::new();
+        let variables = Self::variables(path, options)?;
+        if options.stream {
+            return Self::run_streamed(path, options, renderer, variables).await;
+        }
+        let (mut all_tests, mut skipped) = Self::discover_recovering(path, &variables)?;
 
-        if path.is_file() {
-            let tests = Self::load_tests_from_file(path)?;
-            all_tests.push((path.to_path_buf(), tests));
-        } else if path.is_dir() {
-            for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
-                let entry_path = entry.path();
-                if entry_path.extension().map(|ext| ext == "ax").unwrap_or(false) {
-                    let tests = Self::load_tests_from_file(entry_path)?;
-                    all_tests.push((entry_path.to_path_buf(), tests));
-                }
+        if Self::focus(&mut all_tests) {
+            let focused: usize = all_tests.iter().map(|(_, tests)| tests.len()).sum();
+            writeln!(
+                console,
+                "{} running {} test(s) marked ONLY",
+                "◎ Focused:".yellow().bold(),
+                focused
+            );
+        }
+
+        if let Some(since) = &options.changed_since {
+            changes::retain(&mut all_tests, &changes::changed_files(since)?);
+            if all_tests.is_empty() {
+                writeln!(console, "No tests affected by changes since {}", since);
+                return Ok(RunOutcome::Completed);
             }
-        } else {
-            anyhow::bail!("{} is neither a file nor a folder", path.display());
+            let affected: usize = all_tests.iter().map(|(_, tests)| tests.len()).sum();
+            writeln!(
+                console,
+                "{} running {} test(s) affected by changes since {}",
+                "Δ Changed:".yellow().bold(),
+                affected,
+                since
+            );
+        }
+
+        if options.rerun_failed {
+            LastRun::load()?.retain(&mut all_tests);
+            if all_tests.is_empty() {
+                writeln!(console, "No failed tests in the last run of {}", path.display());
+                return Ok(RunOutcome::Completed);
+            }
+            let failed: usize = all_tests.iter().map(|(_, tests)| tests.len()).sum();
+            writeln!(
+                console,
+                "{} {} test(s) that failed last time",
+                "↻ Re-running:".yellow().bold(),
+                failed
+            );
         }
 
         if all_tests.is_empty() {
-            println!("No tests found in {}", path.display());
-            return Ok(());
+            writeln!(console, "No tests found in {}", path.display());
+            return Ok(RunOutcome::Completed);
+        }
+
+        if options.order == Order::Priority {
+            // Outside git, only past failures count
+            let changed = changes::changed_files("HEAD").unwrap_or_default();
+            History::load()?.prioritize(&mut all_tests, &changed);
+        }
+
+        if options.preresolve {
+            dns::preresolve(&all_tests)
+                .await
+                .kind(ErrorKind::Network)?;
         }
 
         // Count total tests
         let total_tests: usize = all_tests.iter().map(|(_, tests)| tests.len()).sum();
+        let (sender, batches) = mpsc::channel(all_tests.len() + skipped.len());
+        for (file, tests) in all_tests {
+            let index = skipped.iter().position(|(skipped_file, _)| *skipped_file == file);
+            let _ = sender.try_send(Ok(Batch {
+                file,
+                first_index: 0,
+                tests,
+                skipped: index.map(|i| skipped.remove(i).1).unwrap_or_default(),
+            }));
+        }
+        // Files whose tests were all filtered out still report their skipped blocks
+        for (file, errors) in skipped {
+            let _ = sender.try_send(Ok(Batch {
+                file,
+                first_index: 0,
+                tests: Vec::new(),
+                skipped: errors,
+            }));
+        }
+        drop(sender);
+
+        Self::run_batches(batches, Some(total_tests), options, renderer, &variables).await
+    }
+
+    /// Run the `.ax` files under `path` while they are still being parsed,
+    /// block by block on another thread (see [`crate::parser::blocks`]).
+    /// `ONLY`, `--changed-since`, `--rerun-failed` and the DNS pre-flight
+    /// need every test up front, so they do not apply, and the number of
+    /// tests is not known when the run starts.
+    async fn run_streamed(
+        path: &Path,
+        options: &RunOptions,
+        renderer: &dyn Renderer,
+        variables: Variables,
+    ) -> Result<RunOutcome> {
+        let files = Self::files(path)?;
+
+        // A couple of chunks ahead at most, so memory stays bounded
+        let (sender, batches) = mpsc::channel(2);
+        let parse_variables = variables.clone();
+        let parsing = tokio::task::spawn_blocking(move || {
+            Self::parse_chunks(files, &parse_variables, sender)
+        });
+
+        let outcome = Self::run_batches(batches, None, options, renderer, &variables).await;
+        let _ = parsing.await;
+        outcome
+    }
+
+    /// Send the tests of `files` in chunks of [`STREAM_CHUNK`] as they are
+    /// parsed, leaving out blocks that do not parse. Stops at the first
+    /// other error or once nobody listens.
+    fn parse_chunks(
+        files: Vec<PathBuf>,
+        variables: &Variables,
+        sender: mpsc::Sender<Result<Batch>>,
+    ) {
+        for file in files {
+            let failed = |error: anyhow::Error| {
+                let error =
+                    ErrorKind::Invalid.wrap(error.context(format!("Failed to parse {}", file.display())));
+                let _ = sender.blocking_send(Err(error));
+            };
+            let source = match std::fs::read_to_string(&file) {
+                Ok(source) => source,
+                Err(error) => return failed(error.into()),
+            };
+            if let Err(error) = Self::warn_outdated(&file, &source) {
+                return failed(error);
+            }
+            let base_dir = file.parent().unwrap_or_else(|| Path::new("."));
+            let blocks = match AxParser::blocks(&source, variables, base_dir) {
+                Ok(blocks) => blocks,
+                Err(error) => return failed(error),
+            };
+
+            let mut batch = Batch {
+                file: file.clone(),
+                first_index: 0,
+                tests: Vec::new(),
+                skipped: Vec::new(),
+            };
+            for block in blocks {
+                let mut tests = match block {
+                    Ok(tests) => tests,
+                    Err(error) => {
+                        batch.skipped.push(error);
+                        continue;
+                    }
+                };
+                for test in &mut tests {
+                    test.resolve_paths(base_dir);
+                    test.locate(&file);
+                }
+                batch.tests.extend(tests);
+
+                if batch.tests.len() >= STREAM_CHUNK {
+                    let next = Batch {
+                        file: file.clone(),
+                        first_index: batch.first_index + batch.tests.len(),
+                        tests: Vec::new(),
+                        skipped: Vec::new(),
+                    };
+                    if sender.blocking_send(Ok(std::mem::replace(&mut batch, next))).is_err() {
+                        return;
+                    }
+                }
+            }
+            let pending = !batch.tests.is_empty() || !batch.skipped.is_empty();
+            if pending && sender.blocking_send(Ok(batch)).is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Run and render `batches` in order, then summarize the run
+    async fn run_batches(
+        mut batches: mpsc::Receiver<Result<Batch>>,
+        total_tests: Option<usize>,
+        options: &RunOptions,
+        renderer: &dyn Renderer,
+        variables: &Variables,
+    ) -> Result<RunOutcome> {
+        let mut all_results = Vec::new();
+        let mut last_run = LastRun::default();
+        let mut history = History::load().unwrap_or_default();
+        let mut skipped_blocks = 0;
+        let console = Output::console(renderer);
+        let baseline = options
+            .baseline
+            .as_deref()
+            .map(Baseline::load)
+            .transpose()
+            .kind(ErrorKind::Config)?;
+
         renderer.start(total_tests);
+        if options.chaos.is_enabled() {
+            writeln!(
+                console,
+                "{} {}",
+                "⚡ Chaos injection enabled:".yellow().bold(),
+                options.chaos.describe().yellow()
+            );
+        }
+
         let start_time = std::time::Instant::now();
+        let context = Self::context(options);
+        let signed = options.signer.as_ref().map(|signer| signer.headers());
+        let artifacts = options
+            .artifacts
+            .clone()
+            .map(|dir| Artifacts::new(dir, signed.unwrap_or_default()));
 
         // Run tests per file and render immediately
-        for (file_path, tests) in all_tests {
-            println!("\n{}", file_path.display().dimmed());
-            let results = Executor::run_tests(tests, max_concurrency).await;
+        while let Some(batch) = batches.recv().await {
+            let Batch {
+                file: file_path,
+                first_index,
+                mut tests,
+                skipped,
+            } = batch?;
+            if first_index == 0 {
+                writeln!(console, "\n{}", file_path.display().dimmed());
+            }
+            for error in &skipped {
+                writeln!(console, "{} {:#}", "! Skipped block:".yellow(), error);
+                renderer.skipped_block(&file_path, error);
+            }
+            skipped_blocks += skipped.len();
+            Self::apply_defaults(&mut tests, options);
+
+            let mut results =
+                Executor::run_tests(tests, options.max_concurrency, Arc::clone(&context)).await;
+            // Artifacts keep responses as received, before --ignore-path and
+            // --sort-keys touch them
+            if let Some(artifacts) = &artifacts {
+                let mut resolved = variables.clone();
+                resolved.extend(context.captured.lock().unwrap().clone());
+                for (index, test) in results.iter().enumerate() {
+                    if let Err(error) =
+                        artifacts.write(&file_path, first_index + index, test, &resolved)
+                    {
+                        writeln!(console, "{} {:#}", "! Cannot write artifacts:".yellow(), error);
+                    }
+                }
+            }
+            for test in &mut results {
+                if let Some(response) = &mut test.response {
+                    response.normalize(&options.normalization);
+                }
+            }
+            if let Some(baseline) = &baseline {
+                for test in &mut results {
+                    baseline.mark(&file_path, test);
+                }
+            }
             for test in &results {
+                last_run.record(&file_path, test);
+                history.record(&file_path, test);
                 renderer.test(test, None);
-                if show_response {
-                    if let Some(resp) = &test.response {
-                        ResponseRenderer::print_response(resp);
+                if options.show_response {
+                    if test.is_sensitive() {
+                        writeln!(
+                            console,
+                            "{}",
+                            "(response hidden: test has SENSITIVE assertions)".dimmed()
+                        );
+                    } else if let Some(resp) = &test.response {
+                        ResponseRenderer::write_response(&console, resp);
                     }
                 }
             }
@@ -110,15 +500,389 @@ impl Runner {
 
         let duration = start_time.elapsed();
         renderer.summary(&all_results, &duration);
+        if options.timeline {
+            write!(console, "{}", timeline::render(&all_results, TIMELINE_WIDTH));
+        }
+        if let Some(baseline) = &baseline {
+            baseline.report(&all_results, &console);
+        }
+
+        if let Err(error) = last_run.save().and_then(|_| history.save()) {
+            writeln!(console, "{} {:#}", "! Cannot record this run:".yellow(), error);
+        }
+        if skipped_blocks > 0 {
+            let blocks = if skipped_blocks == 1 {
+                "1 block skipped due to syntax error".to_string()
+            } else {
+                format!("{} blocks skipped due to syntax errors", skipped_blocks)
+            };
+            writeln!(console, "{} {}", "!".yellow(), blocks);
+        }
 
+        let not_run = all_results
+            .iter()
+            .filter(|t| matches!(t.result, Some(TestResult::NotRun { .. })))
+            .count();
+        let timed_out = context
+            .deadline
+            .is_some_and(|deadline| tokio::time::Instant::now() >= deadline);
+        if not_run > 0 && timed_out {
+            writeln!(
+                console,
+                "{} {} tests not run",
+                "⏱ Run timeout reached:".red().bold(),
+                not_run
+            );
+            return Ok(RunOutcome::TimedOut);
+        }
+
+        // A suite with blocks left out is invalid, whatever ran
+        if skipped_blocks > 0 {
+            return Ok(RunOutcome::Invalid);
+        }
+        Ok(Self::outcome(&all_results))
+    }
+
+    /// Outcome of a run that was not cut short, from its results
+    pub fn outcome(results: &[TestCase]) -> RunOutcome {
+        let ran: Vec<&TestCase> = results
+            .iter()
+            .filter(|test| !matches!(test.result, Some(TestResult::Skipped { .. })))
+            .collect();
+
+        if ran.iter().all(|test| match &test.result {
+            Some(TestResult::Passed { .. }) => true,
+            Some(result) => result.is_minor_failure(),
+            None => false,
+        }) {
+            RunOutcome::Completed
+        } else if ran.iter().all(|test| {
+            test.response.is_none() && matches!(test.result, Some(TestResult::Failed { .. }))
+        }) {
+            RunOutcome::Unreachable
+        } else {
+            RunOutcome::Failed
+        }
+    }
+
+    /// Variables of the run's environment, if any, for the suite at `path`, with
+    /// the run's overrides on top
+    pub fn variables(path: &Path, options: &RunOptions) -> Result<Variables> {
+        let mut variables = match &options.env {
+            Some(name) => env::load(name, path).kind(ErrorKind::Config)?,
+            None => Variables::new(),
+        };
+        variables.extend(options.overrides.clone());
+        Ok(variables)
+    }
+
+    /// Add the run's default headers to requests that do not set them, and
+    /// its ignored paths to `EQUALS FILE` comparisons
+    pub fn apply_defaults(tests: &mut [TestCase], options: &RunOptions) {
+        for test in tests {
+            let steps = test.steps.iter_mut().map(|step| &mut step.request);
+            for request in std::iter::once(&mut test.request).chain(steps) {
+                for (name, value) in &options.default_headers {
+                    request.default_header(name, value);
+                }
+            }
+
+            // `EQUALS FILE` already compares with sorted keys
+            let ignored = &options.normalization.ignore_paths;
+            let step_assertions = test.steps.iter_mut().flat_map(|step| &mut step.assertions);
+            for assertion in test.assertions.iter_mut().chain(step_assertions) {
+                assertion.ignore_paths(ignored);
+            }
+        }
+    }
+
+    /// State shared by the tests of one run; `run_timeout` starts now
+    pub fn context(options: &RunOptions) -> Arc<ExecutionContext> {
+        Arc::new(ExecutionContext {
+            chaos: options.chaos.clone(),
+            deadline: options
+                .run_timeout
+                .map(|timeout| tokio::time::Instant::now() + timeout),
+            circuits: CircuitBreaker::new(options.circuit_threshold),
+            captured: Default::default(),
+            signer: options.signer.clone(),
+            refresher: options.refresher.clone(),
+            confirm: options.confirm.then(Default::default),
+            webhooks: Default::default(),
+            shared: Default::default(),
+            sources: options.sources.clone(),
+        })
+    }
+
+    /// Keep only the tests marked `ONLY`, if there are any, dropping files
+    /// left empty. Returns whether anything was filtered out.
+    pub fn focus(all_tests: &mut Discovered) -> bool {
+        let focused = all_tests
+            .iter()
+            .any(|(_, tests)| tests.iter().any(|test| test.only));
+        if !focused {
+            return false;
+        }
+
+        for (_, tests) in all_tests.iter_mut() {
+            tests.retain(|test| test.only);
+        }
+        all_tests.retain(|(_, tests)| !tests.is_empty());
+        true
+    }
+
+    /// Discover and parse every `.ax` file under `path` (a file or folder),
+    /// with `variables` available to their templates
+    pub fn discover(path: &Path, variables: &Variables) -> Result<Discovered> {
+        let (all_tests, skipped) = Self::discover_recovering(path, variables)?;
+        if let Some((file, errors)) = skipped.into_iter().next() {
+            let error = errors.into_iter().next().unwrap();
+            return Err(error)
+                .with_context(|| format!("Failed to parse {}", file.display()))
+                .kind(ErrorKind::Invalid);
+        }
+        Ok(all_tests)
+    }
+
+    /// [`Runner::discover`], leaving out the blocks that do not parse
+    fn discover_recovering(
+        path: &Path,
+        variables: &Variables,
+    ) -> Result<(Discovered, Skipped)> {
+        let mut all_tests = Vec::new();
+        let mut skipped = Vec::new();
+        for file in Self::files(path)? {
+            let (tests, errors) =
+                Self::load_tests_from_file(&file, variables).kind(ErrorKind::Invalid)?;
+            if !errors.is_empty() {
+                skipped.push((file.clone(), errors));
+            }
+            all_tests.push((file, tests));
+        }
+        Ok((all_tests, skipped))
+    }
+
+    /// `path` itself, or the `.ax` files under it if it is a folder
+    fn files(path: &Path) -> Result<Vec<PathBuf>> {
+        if path.is_file() {
+            Ok(vec![path.to_path_buf()])
+        } else if path.is_dir() {
+            Ok(WalkDir::new(path)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .map(|entry| entry.into_path())
+                .filter(|entry_path| entry_path.extension().map(|ext| ext == "ax").unwrap_or(false))
+                .collect())
+        } else {
+            Err(anyhow::anyhow!("{} is neither a file nor a folder", path.display()))
+                .kind(ErrorKind::Invalid)
+        }
+    }
+
+    /// Fail before anything is sent when a `${NAME}` in the suite at `path`
+    /// names an environment variable that is not set, listing every such
+    /// variable with the files using it
+    fn check_environment(path: &Path) -> Result<()> {
+        let mut missing: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for file in Self::files(path)? {
+            let source = std::fs::read_to_string(&file)
+                .with_context(|| format!("Failed to read file {}", file.display()))?;
+            for name in template::missing_environment(&strip_comments(&source)) {
+                missing.entry(name).or_default().push(file.display().to_string());
+            }
+        }
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        let lines: Vec<String> = missing
+            .iter()
+            .map(|(name, files)| format!("  {} (used in {})", name, files.join(", ")))
+            .collect();
+        Err(anyhow::anyhow!("Environment variables not set:\n{}", lines.join("\n")))
+            .kind(ErrorKind::Config)
+    }
+
+    /// Point to `axotly migrate` if `input`, the contents of `path`, uses an
+    /// older syntax
+    fn warn_outdated(path: &Path, input: &str) -> Result<()> {
+        let outdated = syntax::outdated(input)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        if outdated {
+            eprintln!(
+                "{} {} uses an older syntax; run `axotly migrate --file {}`",
+                "! Outdated:".yellow(),
+                path.display(),
+                path.display()
+            );
+        }
         Ok(())
     }
 
-    /// Load tests from a single .ax file
-    fn load_tests_from_file(path: &Path) -> Result<Vec<TestCase>> {
+    /// Load tests from a single .ax file, and why any of its blocks were
+    /// left out
+    fn load_tests_from_file(
+        path: &Path,
+        variables: &Variables,
+    ) -> Result<(Vec<TestCase>, Vec<anyhow::Error>)> {
         let input = std::fs::read_to_string(path)
             .with_context(|| format!("Failed to read file {}", path.display()))?;
-        let tests = AxParser::parse_file(&input)?;
-        Ok(tests)
+        Self::warn_outdated(path, &input)?;
+        // Fixture and dataset paths inside the file are relative to the file
+        let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+        let (mut tests, skipped) = AxParser::parse_file_recovering(&input, variables, base_dir)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+        for test in &mut tests {
+            test.resolve_paths(base_dir);
+            test.locate(path);
+        }
+
+        Ok((tests, skipped))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Assertion, AssertionFailure};
+
+    #[test]
+    fn focus_keeps_only_marked_tests() {
+        let parse = |input: &str| AxParser::parse_file(input).unwrap();
+        let mut all_tests = vec![
+            (
+                PathBuf::from("a.ax"),
+                parse("TEST One\nGET https://example.com/1\n\nEND\n"),
+            ),
+            (
+                PathBuf::from("b.ax"),
+                parse("TEST Two\nONLY\nGET https://example.com/2\n\nEND\nTEST Three\nGET https://example.com/3\n\nEND\n"),
+            ),
+        ];
+
+        assert!(Runner::focus(&mut all_tests));
+        assert_eq!(all_tests.len(), 1);
+        assert_eq!(all_tests[0].1.len(), 1);
+        assert_eq!(all_tests[0].1[0].name.as_deref(), Some("Two"));
+
+        // Without ONLY markers nothing is filtered
+        assert!(!Runner::focus(&mut vec![(
+            PathBuf::from("a.ax"),
+            parse("TEST One\nGET https://example.com/1\n\nEND\n"),
+        )]));
+    }
+
+    #[test]
+    fn apply_defaults_ignores_normalized_paths_in_fixtures() {
+        let input = "TEST Users\n\
+                     GET https://example.com/users\n\
+                     \n\
+                     EXPECT body EQUALS FILE \"users.json\" IGNORING [\"body.id\"]\n\
+                     END\n";
+        let mut tests = AxParser::parse_file(input).unwrap();
+        let options = RunOptions {
+            normalization: Normalization::new(false, vec!["body.updated_at".into()]),
+            ..RunOptions::default()
+        };
+        Runner::apply_defaults(&mut tests, &options);
+
+        let Assertion::EqualsFile { ignoring, .. } = &tests[0].assertions[0] else {
+            panic!("expected EQUALS FILE, got {:?}", tests[0].assertions[0]);
+        };
+        assert_eq!(ignoring, &["body.id", "body.updated_at"]);
+    }
+
+    #[test]
+    fn outcome_tells_unreachable_from_failed() {
+        let input = "TEST One\nGET https://example.com/1\n\nEND\nTEST Two\nGET https://example.com/2\n\nEND\n";
+        let mut tests = AxParser::parse_file(input).unwrap();
+        let failed = || {
+            Some(TestResult::Failed {
+                duration: Duration::ZERO,
+                errors: Vec::new(),
+            })
+        };
+
+        tests[0].result = Some(TestResult::Passed {
+            duration: Duration::ZERO,
+        });
+        tests[1].result = Some(TestResult::Skipped { reason: None });
+        assert_eq!(Runner::outcome(&tests), RunOutcome::Completed);
+
+        tests[0].result = failed();
+        assert_eq!(Runner::outcome(&tests), RunOutcome::Unreachable);
+
+        tests[1].result = failed();
+        tests[1].response = Some(crate::domain::http_request::HttpResponse {
+            request: None,
+            duration: Duration::ZERO,
+            status: 500,
+            headers: HashMap::new(),
+            body: None,
+            bytes: Vec::new(),
+            cookies: Vec::new(),
+            trailers: HashMap::new(),
+        });
+        assert_eq!(Runner::outcome(&tests), RunOutcome::Failed);
+
+        // Failing only MINOR assertions does not fail the run
+        let failure = |minor| AssertionFailure {
+            path: "headers.Cache-Control".into(),
+            expected: None,
+            actual: None,
+            message: "Path 'headers.Cache-Control' not found".into(),
+            location: None,
+            minor,
+        };
+        tests[0].result = Some(TestResult::Passed {
+            duration: Duration::ZERO,
+        });
+        tests[1].result = Some(TestResult::Failed {
+            duration: Duration::ZERO,
+            errors: vec![failure(true)],
+        });
+        assert_eq!(Runner::outcome(&tests), RunOutcome::Completed);
+
+        tests[1].result = Some(TestResult::Failed {
+            duration: Duration::ZERO,
+            errors: vec![failure(true), failure(false)],
+        });
+        assert_eq!(Runner::outcome(&tests), RunOutcome::Failed);
+    }
+
+    /// Records the skipped blocks it is told about
+    #[derive(Default)]
+    struct Recording {
+        skipped_blocks: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl Renderer for Recording {
+        fn start(&self, _total: Option<usize>) {}
+        fn test(&self, _test: &TestCase, _file: Option<&PathBuf>) {}
+        fn skipped_block(&self, _file: &Path, error: &anyhow::Error) {
+            self.skipped_blocks.lock().unwrap().push(format!("{:#}", error));
+        }
+        fn summary(&self, _tests: &[TestCase], _total_duration: &Duration) {}
+    }
+
+    #[tokio::test]
+    async fn skipped_blocks_make_the_run_invalid() {
+        let file = std::env::temp_dir().join(format!("axotly-skipped-{}.ax", std::process::id()));
+        std::fs::write(&file, "TEST Broken
+GET
+
+END
+").unwrap();
+
+        let renderer = Recording::default();
+        let outcome = Runner::run_path(&file, &RunOptions::default(), &renderer).await;
+        std::fs::remove_file(&file).unwrap();
+
+        assert_eq!(outcome.unwrap(), RunOutcome::Invalid);
+        assert_eq!(crate::exit::for_outcome(RunOutcome::Invalid), crate::exit::INVALID);
+        let skipped = renderer.skipped_blocks.lock().unwrap();
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].contains("expected url"), "{}", skipped[0]);
     }
 }
@@ -0,0 +1,46 @@
+//! Token storage backed by the operating system keyring.
+//!
+//! Tokens are stored with `axotly auth login --name <name>` and referenced from
+//! `.ax` files as `{{secret.<name>}}`. Keeping them in the keyring avoids
+//! long-lived credentials in environment variables, dotfiles, and shell
+//! history.
+//!
+//! All entries live under a single keyring service (`axotly`) with the token
+//! name used as the account, so they can also be inspected or removed with
+//! the platform's own keychain tooling.
+
+use anyhow::{Context, Result};
+use keyring::Entry;
+
+const SERVICE: &str = "axotly";
+
+fn entry(name: &str) -> Result<Entry> {
+    Entry::new(SERVICE, name)
+        .with_context(|| format!("Failed to open keyring entry for secret '{}'", name))
+}
+
+/// Store (or replace) the token saved under `name`
+pub fn store(name: &str, token: &str) -> Result<()> {
+    entry(name)?
+        .set_password(token)
+        .with_context(|| format!("Failed to store secret '{}' in the keyring", name))
+}
+
+/// Load the token saved under `name`
+pub fn load(name: &str) -> Result<String> {
+    entry(name)?.get_password().map_err(|e| match e {
+        keyring::Error::NoEntry => anyhow::anyhow!(
+            "Secret '{}' not found. Run `axotly auth login --name {}` first",
+            name,
+            name
+        ),
+        other => anyhow::anyhow!("Failed to read secret '{}' from the keyring: {}", name, other),
+    })
+}
+
+/// Remove the token saved under `name`
+pub fn delete(name: &str) -> Result<()> {
+    entry(name)?
+        .delete_credential()
+        .with_context(|| format!("Failed to remove secret '{}' from the keyring", name))
+}
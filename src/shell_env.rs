@@ -0,0 +1,119 @@
+//! Shell exports of response values (`--emit-env`).
+//!
+//! In single-request mode, `--capture body.token=TOKEN` names a response
+//! value, and `--emit-env` prints it as `export TOKEN='...'` instead of the
+//! response, so scripts can chain calls with
+//! `eval "$(axotly https://api/login -m post --json ... --capture body.token=TOKEN --emit-env)"`.
+//! The status is always exported as `STATUS`. Paths are those of `EXPECT`
+//! and `CAPTURE`, e.g. `headers.Location`.
+
+use anyhow::{bail, Context, Result};
+
+use crate::domain::assertion::{resolve_path, Value};
+use crate::domain::http_request::HttpResponse;
+
+/// `--capture PATH=NAME`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShellCapture {
+    pub path: String,
+    pub name: String,
+}
+
+/// Parse `PATH=NAME`; the name must be a valid shell variable name
+pub fn parse_capture(input: &str) -> Result<ShellCapture> {
+    let Some((path, name)) = input.rsplit_once('=') else {
+        bail!(
+            "Invalid capture '{}': expected PATH=NAME, e.g. body.token=TOKEN",
+            input
+        );
+    };
+    let valid = name
+        .chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        bail!("Invalid variable name '{}' in capture '{}'", name, input);
+    }
+    Ok(ShellCapture {
+        path: path.trim().to_string(),
+        name: name.to_string(),
+    })
+}
+
+fn lookup(response: &HttpResponse, path: &str) -> Option<String> {
+    match resolve_path(response, path)? {
+        Value::String(text) => Some(text),
+        other => Some(other.to_string()),
+    }
+}
+
+/// `text` single-quoted for POSIX shells
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+/// One `export` line per capture, after `STATUS`. Fails if a path has no
+/// value, so a script stops instead of using an empty variable.
+pub fn exports(response: &HttpResponse, captures: &[ShellCapture]) -> Result<String> {
+    let mut out = format!("export STATUS={}\n", response.status);
+    for capture in captures {
+        let value = lookup(response, &capture.path)
+            .with_context(|| format!("Nothing at '{}' in the response", capture.path))?;
+        out.push_str(&format!("export {}={}\n", capture.name, quote(&value)));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn response() -> HttpResponse {
+        let body = r#"{"token": "it's-secret", "expires": 3600}"#;
+        HttpResponse {
+            request: None,
+            duration: std::time::Duration::from_millis(10),
+            status: 201,
+            headers: HashMap::from([("location".to_string(), "/users/7".to_string())]),
+            body: Some(body.to_string()),
+            bytes: body.as_bytes().to_vec(),
+            cookies: Vec::new(),
+            trailers: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn parse_captures() {
+        assert_eq!(
+            parse_capture("body.token=TOKEN").unwrap(),
+            ShellCapture {
+                path: "body.token".into(),
+                name: "TOKEN".into(),
+            }
+        );
+        assert!(parse_capture("body.token").is_err());
+        assert!(parse_capture("body.token=1TOKEN").is_err());
+        assert!(parse_capture("body.token=MY-TOKEN").is_err());
+    }
+
+    #[test]
+    fn exports_are_shell_quoted() {
+        let captures = [
+            parse_capture("body.token=TOKEN").unwrap(),
+            parse_capture("body.expires=TTL").unwrap(),
+            parse_capture("headers.Location=USER_URL").unwrap(),
+        ];
+        assert_eq!(
+            exports(&response(), &captures).unwrap(),
+            "export STATUS=201\n\
+             export TOKEN='it'\\''s-secret'\n\
+             export TTL='3600'\n\
+             export USER_URL='/users/7'\n"
+        );
+
+        let missing = [parse_capture("body.missing=X").unwrap()];
+        assert!(exports(&response(), &missing).is_err());
+    }
+}
@@ -0,0 +1,50 @@
+//! Byte sizes such as `512KB`, `10MB`, or `1GB` (binary multiples).
+//!
+//! Used by CLI options like `--log-max-size` and by `BODY STREAM` in `.ax`
+//! files, so sizes are written the same way everywhere.
+
+use anyhow::{bail, Context, Result};
+
+/// Parse `<integer>[unit]` where unit is one of `B`, `KB`, `MB`, `GB`
+/// (case-insensitive); a plain integer is a byte count
+pub fn parse_size(input: &str) -> Result<u64> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (amount, unit) = input.split_at(split);
+
+    let Ok(amount) = amount.parse::<u64>() else {
+        bail!("Invalid size '{}': expected e.g. 512KB, 10MB", input);
+    };
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "KB" => 1 << 10,
+        "MB" => 1 << 20,
+        "GB" => 1 << 30,
+        _ => bail!("Invalid size unit in '{}': use B, KB, MB, or GB", input),
+    };
+    amount
+        .checked_mul(multiplier)
+        .with_context(|| format!("Size '{}' is too large", input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_size_units() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("2KB").unwrap(), 2048);
+        assert_eq!(parse_size("10mb").unwrap(), 10 << 20);
+        assert_eq!(parse_size("1 GB").unwrap(), 1 << 30);
+    }
+
+    #[test]
+    fn parse_size_rejects_invalid() {
+        assert!(parse_size("10XB").is_err());
+        assert!(parse_size("MB").is_err());
+        assert!(parse_size("18446744073709551615GB").is_err());
+    }
+}
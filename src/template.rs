@@ -0,0 +1,857 @@
+//! `{{...}}` placeholder substitution for request text.
+//!
+//! Placeholders are resolved on the raw text of URLs, header values, and
+//! bodies before they are turned into an [`HttpRequest`], so substituted
+//! values take part in URL parsing like any hand-written text.
+//!
+//! Supported placeholders:
+//!
+//! - `{{<name>}}` — a variable, e.g. defined with `VAR <name> = value` in the
+//!   `.ax` file
+//! - `{{secret.<name>}}` — a token stored with `axotly auth login`, read from
+//!   the keyring only when the request is sent (see [`prerender`])
+//! - `{{repeat("a", 1048576)}}` — the text repeated the given number of times
+//! - `{{json_array(n=10000)}}` — a JSON array of the integers `0..n`
+//! - `{{now}}`, `{{now + 30d}}`, `{{now - 2h}}` — the current UTC time, shifted
+//!   by an offset in `w`, `d`, or any [duration] unit, as RFC 3339; a `now`
+//!   variable (set by `FREEZE TIME`) replaces the current time
+//! - `{{now("%Y-%m-%d")}}` — the current (or frozen) UTC time in a
+//!   [strftime] format
+//! - `{{uuid()}}` — a random version 4 UUID; with a `seed` variable (set by
+//!   `SEED`), derived from the seed, the text and the placeholder's position
+//!   in it, so the same test gives the same UUIDs every run
+//! - `{{randomInt(1, 100)}}` — a random integer between the bounds,
+//!   inclusive; seeded like `uuid()`
+//!
+//! Unless seeded, `uuid()` and `randomInt()` in a request are only filled in
+//! when it is sent (see [`prerender_request`]), so each `REPEAT` iteration,
+//! `RETRY` attempt and poll sends new values.
+//!
+//! The generators let boundary tests send large payloads without
+//! committing megabyte fixtures; output is capped at [`MAX_GENERATED`].
+//!
+//! Unknown placeholders are left untouched, except in a request about to be
+//! sent (see [`render_request`]), where a name no variable or capture defines
+//! fails the test.
+//!
+//! Before an `.ax` file is parsed, [`conditionals`] keeps or drops the text
+//! between `{{#if <condition>}}`, `{{else}}` and `{{/if}}`, so one file can
+//! cover small differences between environments:
+//!
+//! ```text
+//! {{#if env == "staging"}}X-Debug: 1{{/if}}
+//! ```
+//!
+//! A condition is `name == "value"`, `name != "value"`, or a bare `name`,
+//! true when the variable is set and not empty. Conditions see the
+//! environment's variables (and `env`, the `--env` name), not `VAR` lines,
+//! which are only read once the file is parsed. Lines left blank by a
+//! dropped fragment are removed.
+//!
+//! `${NAME}` reads the process environment variable `NAME` instead, e.g. a
+//! token a CI job provides: [`environment`] substitutes these in the file
+//! before anything else, and a run first checks with
+//! [`missing_environment`] that every one is set, listing those that are not
+//! before sending any request. `$${NAME}` is the literal text `${NAME}`.
+//! References in `#` comment lines are left alone (see
+//! [`crate::parser::blocks::strip_comments`]).
+//!
+//! [`HttpRequest`]: crate::domain::http_request::HttpRequest
+//! [duration]: crate::duration
+//! [strftime]: chrono::format::strftime
+
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+use crate::duration::parse_duration;
+use crate::secrets;
+
+/// Largest payload a generator placeholder may expand to (64 MiB)
+pub const MAX_GENERATED: usize = 64 * 1024 * 1024;
+
+/// Values for `{{name}}` placeholders
+pub type Variables = HashMap<String, String>;
+
+/// Substitute every known placeholder in `input`
+pub fn render(input: &str) -> Result<String> {
+    render_with(input, &Variables::new())
+}
+
+/// Substitute every known placeholder in `input`, including `variables`
+pub fn render_with(input: &str, variables: &Variables) -> Result<String> {
+    render_placeholders(input, variables, Pass::Render)
+}
+
+/// Substitute every placeholder of a request about to be sent: like
+/// [`render_with`], but a placeholder nothing resolves is an error, e.g. a
+/// misspelled variable, rather than text sent as written
+pub fn render_request(input: &str, variables: &Variables) -> Result<String> {
+    render_placeholders(input, variables, Pass::Send)
+}
+
+/// Substitute what a parsed test may hold: like [`render_with`], but
+/// `{{secret.<name>}}` is left for [`render_request`] when the request is sent,
+/// so keyring values stay out of parsed tests and commands that only parse
+/// never open the keyring
+pub fn prerender(input: &str, variables: &Variables) -> Result<String> {
+    render_placeholders(input, variables, Pass::Parse)
+}
+
+/// [`prerender`] for the text of a request: without a `seed` variable,
+/// `uuid()` and `randomInt()` are also left for [`render_request`], which
+/// gives them new values each time the request is sent
+pub fn prerender_request(input: &str, variables: &Variables) -> Result<String> {
+    render_placeholders(input, variables, Pass::Request)
+}
+
+/// When a text is rendered, which decides what happens to secrets and to
+/// placeholders nothing resolves
+#[derive(Clone, Copy, PartialEq)]
+enum Pass {
+    /// While parsing: secrets and unknown placeholders are kept
+    Parse,
+    /// While parsing a request: unseeded generators are kept too
+    Request,
+    /// Secrets are read, unknown placeholders kept
+    Render,
+    /// Right before sending: secrets are read, unknown placeholders fail
+    Send,
+}
+
+fn render_placeholders(input: &str, variables: &Variables, pass: Pass) -> Result<String> {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    let mut index = 0;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            // Unterminated placeholder: keep the remaining text verbatim
+            output.push_str(&rest[start..]);
+            return Ok(output);
+        };
+
+        let expr = after[..end].trim();
+        index += 1;
+        match resolve(expr, variables, (input, index), pass)? {
+            Some(value) => output.push_str(&value),
+            None if pass == Pass::Send && is_variable(expr) => {
+                bail!("Undefined variable '{}'", expr)
+            }
+            None if pass == Pass::Send => bail!("Unknown placeholder {{{{{}}}}}", expr),
+            None => output.push_str(&rest[start..start + end + 4]),
+        }
+
+        rest = &after[end + 2..];
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// Substitute only the `{{name}}` placeholders naming one of `variables`,
+/// leaving every other placeholder for a later render
+pub fn substitute(input: &str, variables: &Variables) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        match variables.get(after[..end].trim()) {
+            Some(value) => output.push_str(value),
+            None => output.push_str(&rest[start..start + end + 4]),
+        }
+        rest = &after[end + 2..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// Start, end and trimmed expression of each terminated `{{...}}` in `input`
+fn placeholder_spans(input: &str) -> impl Iterator<Item = (usize, usize, &str)> {
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        let start = input[offset..].find("{{")? + offset;
+        let end = input[start..].find("}}")? + start;
+        offset = end + 2;
+        Some((start, end + 2, input[start + 2..end].trim()))
+    })
+}
+
+/// Expressions of the terminated `{{...}}` placeholders in `input`, trimmed
+pub fn placeholders(input: &str) -> Vec<&str> {
+    let mut found = Vec::new();
+    let mut rest = input;
+
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        found.push(after[..end].trim());
+        rest = &after[end + 2..];
+    }
+
+    found
+}
+
+/// `input` with each `${NAME}` replaced by the process environment variable
+/// `NAME`; references to unset variables stay as written
+pub fn environment(input: &str) -> String {
+    interpolate_env(input, |name| std::env::var(name).ok())
+}
+
+/// Names of the `${NAME}` references in `input` that the process environment
+/// does not set, each once
+pub fn missing_environment(input: &str) -> Vec<String> {
+    let mut missing: Vec<String> = Vec::new();
+    interpolate_env(input, |name| {
+        let value = std::env::var(name).ok();
+        if value.is_none() && !missing.iter().any(|known| known == name) {
+            missing.push(name.to_string());
+        }
+        value
+    });
+    missing
+}
+
+fn interpolate_env(input: &str, mut lookup: impl FnMut(&str) -> Option<String>) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find("${") {
+        let escaped = rest[..start].ends_with('$');
+        output.push_str(&rest[..start - escaped as usize]);
+        let after = &rest[start + 2..];
+        let name = after
+            .find('}')
+            .map(|end| &after[..end])
+            .filter(|name| is_env_name(name));
+        match name {
+            Some(name) if !escaped => match lookup(name) {
+                Some(value) => output.push_str(&value),
+                None => output.push_str(&rest[start..start + name.len() + 3]),
+            },
+            Some(name) => {
+                output.push_str("${");
+                output.push_str(name);
+                output.push('}');
+            }
+            None => {
+                output.push_str(&rest[start - escaped as usize..start + 2]);
+                rest = after;
+                continue;
+            }
+        }
+        rest = &after[name.map_or(0, str::len) + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Whether the placeholder expression `expr` names a variable, e.g. one set
+/// by `VAR` or `CAPTURE`, rather than a secret or a generator
+pub fn is_variable(expr: &str) -> bool {
+    expr != "now" && is_env_name(expr)
+}
+
+/// Whether `name` can be a `${...}` environment variable name
+fn is_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// `input` with its `{{#if}}` blocks resolved against `variables`, and for
+/// each line of the result the line of `input` it comes from (from 1)
+pub fn conditionals(input: &str, variables: &Variables) -> Result<(String, Vec<usize>)> {
+    let mut output = String::with_capacity(input.len());
+    let mut origins = Vec::new();
+    // Whether each open block, and the blocks around it, keep their text
+    let mut open: Vec<(bool, bool)> = Vec::new();
+    let mut line = String::new();
+    let mut origin = 1;
+    // Whether a tag or dropped text was on the current output line
+    let mut touched = false;
+    let mut number = 1;
+
+    let mut rest = input;
+    while !rest.is_empty() {
+        let keep = open.last().is_none_or(|&(taken, outer)| taken && outer);
+        let tag = placeholder_spans(rest).find(|(_, _, expr)| {
+            expr.starts_with("#if ") || *expr == "else" || *expr == "/if"
+        });
+        let text_end = tag.map_or(rest.len(), |(start, _, _)| start);
+
+        for c in rest[..text_end].chars() {
+            if c == '\n' {
+                if keep {
+                    if !(touched && line.trim().is_empty()) {
+                        output.push_str(&line);
+                        output.push('\n');
+                        origins.push(if line.is_empty() { number } else { origin });
+                    }
+                    line.clear();
+                    touched = false;
+                }
+                number += 1;
+            } else if keep {
+                if line.is_empty() {
+                    origin = number;
+                }
+                line.push(c);
+            } else {
+                touched = true;
+            }
+        }
+
+        let Some((_, end, expr)) = tag else {
+            break;
+        };
+        touched = true;
+        if let Some(condition) = expr.strip_prefix("#if ") {
+            let holds = condition_holds(condition.trim(), variables)?;
+            open.push((holds, keep));
+        } else if expr == "else" {
+            let Some((taken, _)) = open.last_mut() else {
+                bail!("Line {}: {{{{else}}}} without {{{{#if}}}}", number);
+            };
+            *taken = !*taken;
+        } else if open.pop().is_none() {
+            bail!("Line {}: {{{{/if}}}} without {{{{#if}}}}", number);
+        }
+        rest = &rest[end..];
+    }
+
+    if !open.is_empty() {
+        bail!("{{{{#if}}}} without {{{{/if}}}}");
+    }
+    if !(line.is_empty() || touched && line.trim().is_empty()) {
+        output.push_str(&line);
+        origins.push(origin);
+    }
+    Ok((output, origins))
+}
+
+/// Whether `condition` (`name`, `name == "value"`, `name != "value"`) holds
+fn condition_holds(condition: &str, variables: &Variables) -> Result<bool> {
+    let value = |name: &str| variables.get(name.trim()).map(String::as_str);
+    let literal = |text: &str| {
+        let text = text.trim();
+        unquote(text).unwrap_or(text).to_string()
+    };
+
+    if let Some((name, expected)) = condition.split_once("==") {
+        return Ok(value(name) == Some(literal(expected).as_str()));
+    }
+    if let Some((name, expected)) = condition.split_once("!=") {
+        return Ok(value(name) != Some(literal(expected).as_str()));
+    }
+    if condition.is_empty() || condition.contains(char::is_whitespace) {
+        bail!("Invalid condition '{}': expected name == \"value\"", condition);
+    }
+    Ok(value(condition).is_some_and(|value| !value.is_empty()))
+}
+
+/// Value of a single placeholder expression, or `None` if it is unknown or
+/// left for a later `pass`; `site` is the text holding it and its position
+/// there
+fn resolve(
+    expr: &str,
+    variables: &Variables,
+    site: (&str, usize),
+    pass: Pass,
+) -> Result<Option<String>> {
+    if let Some(value) = variables.get(expr) {
+        return Ok(Some(value.clone()));
+    }
+
+    if let Some(name) = expr.strip_prefix("secret.") {
+        if matches!(pass, Pass::Parse | Pass::Request) {
+            return Ok(None);
+        }
+        return secrets::load(name).map(Some);
+    }
+
+    if let Some(offset) = expr
+        .strip_prefix("now")
+        .filter(|offset| offset.is_empty() || offset.trim_start().starts_with(['+', '-']))
+    {
+        return now_plus(current_time(variables)?, offset).map(Some);
+    }
+
+    let Some((name, args)) = expr.strip_suffix(')').and_then(|call| call.split_once('(')) else {
+        return Ok(None);
+    };
+    let args = split_args(args);
+    let seed = variables.get("seed").map(String::as_str);
+    let generator = matches!(name.trim(), "uuid" | "randomInt" | "random_int");
+    if generator && pass == Pass::Request && seed.is_none() {
+        return Ok(None);
+    }
+
+    let value = match name.trim() {
+        "repeat" => {
+            let [text, count] = args.as_slice() else {
+                bail!("repeat() takes a string and a count: {{{{{}}}}}", expr);
+            };
+            let text = unquote(text)
+                .with_context(|| format!("repeat() needs a quoted string, got {}", text))?;
+            let count = parse_count(count, "count")?;
+            check_size(text.len().saturating_mul(count), expr)?;
+            text.repeat(count)
+        }
+        "uuid" => {
+            if !args.is_empty() {
+                bail!("uuid() takes no arguments: {{{{{}}}}}", expr);
+            }
+            uuid(seed, site)
+        }
+        "now" => {
+            let [format] = args.as_slice() else {
+                bail!("now() takes a format: {{{{{}}}}}", expr);
+            };
+            let format = unquote(format)
+                .with_context(|| format!("now() needs a quoted format, got {}", format))?;
+            format_time(current_time(variables)?, format)?
+        }
+        "randomInt" | "random_int" => {
+            let [min, max] = args.as_slice() else {
+                bail!("randomInt() takes a minimum and a maximum: {{{{{}}}}}", expr);
+            };
+            let min: i64 = min.parse().with_context(|| format!("Invalid minimum '{}'", min))?;
+            let max: i64 = max.parse().with_context(|| format!("Invalid maximum '{}'", max))?;
+            if min > max {
+                bail!("randomInt() minimum {} is above its maximum {}", min, max);
+            }
+            random_int(min, max, seed, site).to_string()
+        }
+        "json_array" => {
+            let [n] = args.as_slice() else {
+                bail!("json_array() takes a single length: {{{{{}}}}}", expr);
+            };
+            let n = parse_count(n, "n")?;
+            json_array(n, expr)?
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(value))
+}
+
+/// The frozen `now` variable (set by `FREEZE TIME`), else the current time
+fn current_time(variables: &Variables) -> Result<DateTime<Utc>> {
+    match variables.get("now") {
+        Some(frozen) => Ok(DateTime::parse_from_rfc3339(frozen)
+            .with_context(|| format!("Invalid frozen time '{}': expected RFC 3339", frozen))?
+            .with_timezone(&Utc)),
+        None => Ok(Utc::now()),
+    }
+}
+
+/// `time` in the strftime `format`, rejecting unknown specifiers
+fn format_time(time: DateTime<Utc>, format: &str) -> Result<String> {
+    let items: Vec<_> = StrftimeItems::new(format).collect();
+    if items.contains(&Item::Error) {
+        bail!("Invalid time format '{}'", format);
+    }
+    Ok(time.format_with_items(items.into_iter()).to_string())
+}
+
+/// `now` shifted by `offset` (`""`, `+ 30d`, `- 90m`), formatted as RFC 3339
+fn now_plus(now: DateTime<Utc>, offset: &str) -> Result<String> {
+    let offset = offset.trim();
+    let shifted = if offset.is_empty() {
+        now
+    } else {
+        let (sign, amount) = match offset.split_at(1) {
+            ("+", amount) => (1, amount.trim()),
+            ("-", amount) => (-1, amount.trim()),
+            _ => bail!(
+                "Invalid time expression 'now{}': expected now + 30d",
+                offset
+            ),
+        };
+        let delta = parse_offset(amount)? * sign;
+        now.checked_add_signed(delta)
+            .with_context(|| format!("Time expression 'now {}' is out of range", offset))?
+    };
+
+    Ok(shifted.to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+/// Parse a time offset: days (`d`) and weeks (`w`) on top of regular durations
+fn parse_offset(amount: &str) -> Result<TimeDelta> {
+    let days = |count: &str, per: i64| -> Result<TimeDelta> {
+        let count: i64 = count
+            .parse()
+            .with_context(|| format!("Invalid time offset '{}'", amount))?;
+        TimeDelta::try_days(count * per)
+            .with_context(|| format!("Time offset '{}' is too large", amount))
+    };
+
+    if let Some(count) = amount.strip_suffix('d') {
+        return days(count, 1);
+    }
+    if let Some(count) = amount.strip_suffix('w') {
+        return days(count, 7);
+    }
+
+    let duration = parse_duration(amount)?;
+    TimeDelta::from_std(duration).with_context(|| format!("Time offset '{}' is too large", amount))
+}
+
+/// Split call arguments on commas that are not inside double quotes
+fn split_args(args: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut in_quotes = false;
+    let mut start = 0;
+
+    for (i, c) in args.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => {
+                parts.push(args[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    let last = args[start..].trim();
+    if !last.is_empty() || !parts.is_empty() {
+        parts.push(last);
+    }
+    parts
+}
+
+fn unquote(arg: &str) -> Option<&str> {
+    arg.strip_prefix('"')?.strip_suffix('"')
+}
+
+/// Parse a count given either positionally (`10`) or by name (`n=10`)
+fn parse_count(arg: &str, name: &str) -> Result<usize> {
+    let value = match arg.split_once('=') {
+        Some((key, value)) if key.trim() == name => value.trim(),
+        Some((key, _)) => bail!("Unknown argument '{}', expected '{}'", key.trim(), name),
+        None => arg,
+    };
+    value
+        .parse()
+        .with_context(|| format!("Invalid {} '{}'", name, value))
+}
+
+fn check_size(size: usize, expr: &str) -> Result<()> {
+    if size > MAX_GENERATED {
+        bail!(
+            "{{{{{}}}}} would generate {} bytes, more than the {} byte limit",
+            expr,
+            size,
+            MAX_GENERATED
+        );
+    }
+    Ok(())
+}
+
+/// Bytes derived from `seed` and the placeholder's `site`
+fn seeded_bytes(seed: &str, (text, index): (&str, usize)) -> [u8; 16] {
+    let digest = Sha256::new()
+        .chain_update(seed)
+        .chain_update([0])
+        .chain_update(text)
+        .chain_update(index.to_le_bytes())
+        .finalize();
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    bytes
+}
+
+/// A version 4 UUID: random, or derived from `seed` and `site` when seeded
+fn uuid(seed: Option<&str>, site: (&str, usize)) -> String {
+    let mut bytes = match seed {
+        Some(seed) => seeded_bytes(seed, site),
+        None => rand::thread_rng().gen(),
+    };
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// An integer in `min..=max`: random, or derived from `seed` and `site`
+fn random_int(min: i64, max: i64, seed: Option<&str>, site: (&str, usize)) -> i64 {
+    match seed {
+        Some(seed) => {
+            let span = (max as i128 - min as i128 + 1) as u128;
+            let value = u128::from_le_bytes(seeded_bytes(seed, site)) % span;
+            (min as i128 + value as i128) as i64
+        }
+        None => rand::thread_rng().gen_range(min..=max),
+    }
+}
+
+fn json_array(n: usize, expr: &str) -> Result<String> {
+    let mut output = String::from("[");
+    for i in 0..n {
+        if i > 0 {
+            output.push(',');
+        }
+        output.push_str(&i.to_string());
+        check_size(output.len(), expr)?;
+    }
+    output.push(']');
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn environment_references() {
+        let env = |name: &str| match name {
+            "API_TOKEN" => Some("s3cret".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            interpolate_env("Authorization: Bearer ${API_TOKEN}", env),
+            "Authorization: Bearer s3cret"
+        );
+        assert_eq!(
+            interpolate_env("`${a.b}` $${API_TOKEN} ${ x} ${REGION} $", env),
+            "`${a.b}` ${API_TOKEN} ${ x} ${REGION} $"
+        );
+
+        assert_eq!(
+            missing_environment("${AXOTLY_UNSET_VAR} ${PATH} $${ESCAPED} ${AXOTLY_UNSET_VAR}"),
+            ["AXOTLY_UNSET_VAR"]
+        );
+    }
+
+    #[test]
+    fn render_without_placeholders() {
+        assert_eq!(render("https://example.com/a").unwrap(), "https://example.com/a");
+    }
+
+    #[test]
+    fn render_keeps_unknown_placeholders() {
+        assert_eq!(render("a {{ other }} b").unwrap(), "a {{ other }} b");
+        assert_eq!(render("a {{ unterminated").unwrap(), "a {{ unterminated");
+        assert_eq!(render("{{ missing(1) }}").unwrap(), "{{ missing(1) }}");
+    }
+
+    #[test]
+    fn prerender_leaves_secrets_for_send_time() {
+        let variables = Variables::from([("host".to_string(), "api.example.com".to_string())]);
+        assert_eq!(
+            prerender("https://{{host}}/me?key={{ secret.staging }}", &variables).unwrap(),
+            "https://api.example.com/me?key={{ secret.staging }}"
+        );
+    }
+
+    #[test]
+    fn render_variables() {
+        let variables = Variables::from([("host".to_string(), "api.example.com".to_string())]);
+        assert_eq!(
+            render_with("https://{{host}}/users?q={{ host }}", &variables).unwrap(),
+            "https://api.example.com/users?q=api.example.com"
+        );
+        assert_eq!(render_with("{{port}}", &variables).unwrap(), "{{port}}");
+    }
+
+    #[test]
+    fn render_request_fails_on_unresolved_placeholders() {
+        let variables = Variables::from([("id".to_string(), "7".to_string())]);
+        assert_eq!(render_request("/items/{{id}}", &variables).unwrap(), "/items/7");
+
+        let error = render_request("/items/{{tpyo}}", &variables).unwrap_err();
+        assert_eq!(error.to_string(), "Undefined variable 'tpyo'");
+        assert!(render_request("{{ missing(1) }}", &variables).is_err());
+        assert!(render_request("a {{ unterminated", &variables).is_ok());
+    }
+
+    #[test]
+    fn substitute_only_given_variables() {
+        let row = Variables::from([("email".to_string(), "a@example.com".to_string())]);
+        assert_eq!(
+            substitute("{{ email }} {{now}} {{token}} {{email", &row),
+            "a@example.com {{now}} {{token}} {{email"
+        );
+    }
+
+    #[test]
+    fn find_placeholders() {
+        assert_eq!(placeholders("/users/{{ id }}?t={{token}}&x={{open"), vec!["id", "token"]);
+        assert!(placeholders("plain").is_empty());
+    }
+
+    #[test]
+    fn render_repeat() {
+        assert_eq!(render(r#"x{{repeat("ab", 3)}}y"#).unwrap(), "xabababy");
+        assert_eq!(render(r#"{{ repeat("a,b", 2) }}"#).unwrap(), "a,ba,b");
+        assert_eq!(
+            render(r#"{{repeat("a", 1048576)}}"#).unwrap().len(),
+            1048576
+        );
+        assert!(render(r#"{{repeat(a, 3)}}"#).is_err());
+        assert!(render(r#"{{repeat("a", 1000000000)}}"#).is_err());
+    }
+
+    #[test]
+    fn render_json_array() {
+        assert_eq!(render("{{json_array(n=3)}}").unwrap(), "[0,1,2]");
+        assert_eq!(render("{{json_array(0)}}").unwrap(), "[]");
+
+        let big = render("{{json_array(n=10000)}}").unwrap();
+        let value: serde_json::Value = serde_json::from_str(&big).unwrap();
+        assert_eq!(value.as_array().unwrap().len(), 10000);
+
+        assert!(render("{{json_array(len=3)}}").is_err());
+    }
+
+    #[test]
+    fn now_arithmetic() {
+        let now = DateTime::parse_from_rfc3339("2024-01-31T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(now_plus(now, "").unwrap(), "2024-01-31T12:00:00Z");
+        assert_eq!(now_plus(now, " + 30d").unwrap(), "2024-03-01T12:00:00Z");
+        assert_eq!(now_plus(now, "- 2w").unwrap(), "2024-01-17T12:00:00Z");
+        assert_eq!(now_plus(now, "+90m").unwrap(), "2024-01-31T13:30:00Z");
+        assert!(now_plus(now, "* 2d").is_err());
+        assert!(now_plus(now, "+ 2y").is_err());
+    }
+
+    #[test]
+    fn conditional_fragments() {
+        let variables = Variables::from([("env".to_string(), "staging".to_string())]);
+        let input = "GET https://example.com\n\
+                     {{#if env == \"staging\"}}X-Debug: 1{{/if}}\n\
+                     {{#if env != staging}}X-Prod: 1{{/if}}\n\
+                     Accept: */*\n\
+                     {{#if token}}\n\
+                     Authorization: Bearer {{token}}\n\
+                     {{else}}\n\
+                     X-Anonymous: {{#if env}}yes{{/if}}\n\
+                     {{/if}}\n\
+                     \n\
+                     END\n";
+        let (output, origins) = conditionals(input, &variables).unwrap();
+        assert_eq!(
+            output,
+            "GET https://example.com\nX-Debug: 1\nAccept: */*\nX-Anonymous: yes\n\nEND\n"
+        );
+        assert_eq!(origins, vec![1, 2, 4, 8, 10, 11]);
+    }
+
+    #[test]
+    fn unbalanced_conditionals() {
+        let variables = Variables::new();
+        assert!(conditionals("{{#if a}}x", &variables).is_err());
+        assert!(conditionals("x{{/if}}", &variables).is_err());
+        assert!(conditionals("{{else}}", &variables).is_err());
+        assert!(conditionals("{{#if a b}}x{{/if}}", &variables).is_err());
+        assert_eq!(
+            conditionals("{{ name }}\n", &variables).unwrap(),
+            ("{{ name }}\n".to_string(), vec![1])
+        );
+    }
+
+    #[test]
+    fn frozen_now() {
+        let variables = Variables::from([("now".to_string(), "2024-01-01T00:00:00Z".to_string())]);
+        assert_eq!(
+            render_with("{{now}} {{ now + 1d }}", &variables).unwrap(),
+            "2024-01-01T00:00:00Z 2024-01-02T00:00:00Z"
+        );
+        let invalid = Variables::from([("now".to_string(), "yesterday".to_string())]);
+        assert!(render_with("{{now - 1h}}", &invalid).is_err());
+    }
+
+    #[test]
+    fn render_uuid() {
+        let random = render("{{uuid()}}").unwrap();
+        assert_eq!(random.len(), 36);
+        assert_eq!(&random[14..15], "4");
+        assert_ne!(random, render("{{uuid()}}").unwrap());
+
+        let seeded = Variables::from([("seed".to_string(), "42".to_string())]);
+        let first = render_with("{{uuid()}} {{uuid()}}", &seeded).unwrap();
+        assert_eq!(first, render_with("{{uuid()}} {{uuid()}}", &seeded).unwrap());
+        let (a, b) = first.split_once(' ').unwrap();
+        assert_ne!(a, b);
+        assert!(render("{{uuid(4)}}").is_err());
+    }
+
+    #[test]
+    fn prerender_request_leaves_unseeded_generators() {
+        let input = "/items/{{uuid()}}?n={{randomInt(1, 9)}}&at={{now}}";
+        let kept = prerender_request(input, &Variables::new()).unwrap();
+        assert!(kept.starts_with("/items/{{uuid()}}?n={{randomInt(1, 9)}}&at=20"));
+        let send = || render_request(&kept, &Variables::new()).unwrap();
+        assert_ne!(send(), send());
+
+        let seeded = Variables::from([("seed".to_string(), "42".to_string())]);
+        assert!(placeholders(&prerender_request(input, &seeded).unwrap()).is_empty());
+        assert!(placeholders(&prerender(input, &Variables::new()).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn render_formatted_now() {
+        let variables = Variables::from([("now".to_string(), "2024-03-05T08:09:00Z".to_string())]);
+        assert_eq!(
+            render_with(r#"{{now("%Y-%m-%d")}} {{ now("%H:%M") }}"#, &variables).unwrap(),
+            "2024-03-05 08:09"
+        );
+        assert!(render(r#"{{now("%Q")}}"#).is_err());
+        assert!(render("{{now(%Y)}}").is_err());
+    }
+
+    #[test]
+    fn render_random_int() {
+        for _ in 0..50 {
+            let value: i64 = render("{{randomInt(1, 3)}}").unwrap().parse().unwrap();
+            assert!((1..=3).contains(&value));
+        }
+        assert_eq!(render("{{randomInt(-2,-2)}}").unwrap(), "-2");
+        assert!(render("{{randomInt(5, 1)}}").is_err());
+        assert!(render("{{randomInt(1)}}").is_err());
+
+        let seeded = Variables::from([("seed".to_string(), "42".to_string())]);
+        let input = "{{randomInt(1, 1000000)}}";
+        assert_eq!(
+            render_with(input, &seeded).unwrap(),
+            render_with(input, &seeded).unwrap()
+        );
+        let full = render_with("{{randomInt(-9223372036854775808, 9223372036854775807)}}", &seeded);
+        assert!(full.unwrap().parse::<i64>().is_ok());
+    }
+
+    #[test]
+    fn render_now() {
+        let rendered = render("{{ now + 1d }}").unwrap();
+        assert!(DateTime::parse_from_rfc3339(&rendered).is_ok());
+    }
+}
@@ -0,0 +1,278 @@
+//! State of the TUI: the test list, its statuses, the filter and selection.
+//!
+//! Kept free of terminal code so key handling and event bookkeeping can be
+//! tested directly.
+
+use std::path::PathBuf;
+
+use crate::domain::test_case::{TestCase, TestResult};
+use crate::executor::TestEvent;
+
+/// Where a test stands in the session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Idle,
+    Queued,
+    Running,
+    Passed,
+    Failed,
+    Skipped,
+    NotRun,
+}
+
+impl Status {
+    fn of(result: Option<&TestResult>) -> Self {
+        match result {
+            Some(TestResult::Passed { .. }) => Status::Passed,
+            Some(TestResult::Failed { .. }) => Status::Failed,
+            Some(TestResult::Skipped { .. }) => Status::Skipped,
+            Some(TestResult::NotRun { .. }) => Status::NotRun,
+            None => Status::Idle,
+        }
+    }
+
+    /// Waiting for or holding a permit
+    pub fn is_busy(self) -> bool {
+        matches!(self, Status::Queued | Status::Running)
+    }
+}
+
+/// One test of the suite
+#[derive(Debug)]
+pub struct Entry {
+    pub file: PathBuf,
+    pub name: String,
+    pub tags: Vec<String>,
+    pub status: Status,
+    /// Last completed run, shown in the details pane
+    pub last: Option<TestCase>,
+    /// Picked with space for the next re-run
+    pub marked: bool,
+}
+
+impl Entry {
+    /// Whether `test`, parsed again from the entry's file, is this entry
+    pub fn is(&self, test: &TestCase) -> bool {
+        display_name(test) == self.name
+    }
+}
+
+fn display_name(test: &TestCase) -> &str {
+    test.name.as_deref().unwrap_or("<unnamed>")
+}
+
+#[derive(Debug, Default)]
+pub struct App {
+    pub entries: Vec<Entry>,
+    pub filter: String,
+    /// Keys go to the filter instead of commands
+    pub filtering: bool,
+    /// Position of the highlighted test among [`App::visible`] ones
+    pub selected: usize,
+    /// First line shown in the details pane
+    pub scroll: u16,
+    /// Last notice for the status bar (e.g. a re-run that failed to load)
+    pub message: Option<String>,
+}
+
+impl App {
+    pub fn new(all_tests: &[(PathBuf, Vec<TestCase>)]) -> Self {
+        let entries = all_tests
+            .iter()
+            .flat_map(|(file, tests)| {
+                tests.iter().map(move |test| Entry {
+                    file: file.clone(),
+                    name: display_name(test).to_string(),
+                    tags: test.tags.clone(),
+                    status: Status::Idle,
+                    last: None,
+                    marked: false,
+                })
+            })
+            .collect();
+
+        App {
+            entries,
+            ..Default::default()
+        }
+    }
+
+    /// Indices of the entries matching the filter (name, file or tag,
+    /// case-insensitive)
+    pub fn visible(&self) -> Vec<usize> {
+        let filter = self.filter.to_lowercase();
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| {
+                filter.is_empty()
+                    || entry.name.to_lowercase().contains(&filter)
+                    || entry
+                        .file
+                        .to_string_lossy()
+                        .to_lowercase()
+                        .contains(&filter)
+                    || entry.tags.iter().any(|tag| tag.to_lowercase() == filter)
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Index of the highlighted entry, if any test is visible
+    pub fn selected_entry(&self) -> Option<usize> {
+        self.visible().get(self.selected).copied()
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        let count = self.visible().len();
+        if count == 0 {
+            return;
+        }
+        self.selected = self.selected.saturating_add_signed(delta).min(count - 1);
+        self.scroll = 0;
+    }
+
+    pub fn scroll_details(&mut self, delta: i16) {
+        self.scroll = self.scroll.saturating_add_signed(delta);
+    }
+
+    pub fn toggle_mark(&mut self) {
+        if let Some(index) = self.selected_entry() {
+            self.entries[index].marked = !self.entries[index].marked;
+        }
+    }
+
+    pub fn set_filter(&mut self, filter: String) {
+        self.filter = filter;
+        self.selected = 0;
+        self.scroll = 0;
+    }
+
+    /// Tests a re-run applies to: the marked ones, else the highlighted one
+    pub fn rerun_targets(&self) -> Vec<usize> {
+        let marked: Vec<usize> = (0..self.entries.len())
+            .filter(|&index| self.entries[index].marked)
+            .collect();
+        if marked.is_empty() {
+            self.selected_entry().into_iter().collect()
+        } else {
+            marked
+        }
+    }
+
+    pub fn failed(&self) -> Vec<usize> {
+        (0..self.entries.len())
+            .filter(|&index| self.entries[index].status == Status::Failed)
+            .collect()
+    }
+
+    /// Mark `ids` as about to run, clearing their marks
+    pub fn queue(&mut self, ids: &[usize]) {
+        for &id in ids {
+            let entry = &mut self.entries[id];
+            entry.status = Status::Queued;
+            entry.marked = false;
+        }
+    }
+
+    /// Record progress of entry `id`
+    pub fn apply(&mut self, id: usize, event: TestEvent) {
+        let entry = &mut self.entries[id];
+        match event {
+            TestEvent::Started(_) => entry.status = Status::Running,
+            TestEvent::Finished(_, test) => {
+                entry.status = Status::of(test.result.as_ref());
+                entry.last = Some(*test);
+            }
+        }
+    }
+
+    /// How many tests have each status
+    pub fn count(&self, status: Status) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| entry.status == status)
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::AxParser;
+    use std::time::Duration;
+
+    fn app() -> App {
+        let input = r#"TEST Login
+TAGS auth
+POST https://example.com/login
+
+END
+
+TEST Health
+GET https://example.com/health
+
+END
+"#
+        .to_string();
+        let tests = AxParser::parse_file(&input).unwrap();
+        App::new(&[(PathBuf::from("api.ax"), tests)])
+    }
+
+    #[test]
+    fn filter_matches_name_file_and_tags() {
+        let mut app = app();
+        assert_eq!(app.visible(), vec![0, 1]);
+
+        app.set_filter("HEALTH".into());
+        assert_eq!(app.visible(), vec![1]);
+        assert_eq!(app.selected_entry(), Some(1));
+
+        app.set_filter("auth".into());
+        assert_eq!(app.visible(), vec![0]);
+
+        app.set_filter("nothing".into());
+        assert_eq!(app.selected_entry(), None);
+        app.move_selection(1);
+        assert_eq!(app.selected, 0);
+    }
+
+    #[test]
+    fn rerun_targets_marked_or_selected() {
+        let mut app = app();
+        app.move_selection(5);
+        assert_eq!(app.rerun_targets(), vec![1]);
+
+        app.move_selection(-1);
+        app.toggle_mark();
+        app.move_selection(1);
+        app.toggle_mark();
+        assert_eq!(app.rerun_targets(), vec![0, 1]);
+
+        app.queue(&[0, 1]);
+        assert!(app.entries.iter().all(|entry| !entry.marked));
+        assert_eq!(app.count(Status::Queued), 2);
+    }
+
+    #[test]
+    fn events_update_status() {
+        let mut app = app();
+        app.queue(&[0]);
+        app.apply(0, TestEvent::Started(0));
+        assert_eq!(app.entries[0].status, Status::Running);
+
+        let mut test = AxParser::parse_file(
+            "TEST Login\nPOST https://example.com/login\n\nEND\n",
+        )
+        .unwrap()
+        .remove(0);
+        test.result = Some(TestResult::Failed {
+            duration: Duration::from_millis(5),
+            errors: Vec::new(),
+        });
+        app.apply(0, TestEvent::Finished(0, Box::new(test)));
+        assert_eq!(app.entries[0].status, Status::Failed);
+        assert!(app.entries[0].last.is_some());
+        assert_eq!(app.failed(), vec![0]);
+    }
+}
@@ -0,0 +1,252 @@
+//! Interactive terminal dashboard (`axotly tui --file tests/`).
+//!
+//! The suite starts running as soon as the dashboard opens. The test list
+//! updates live from the executor's event stream ([`TestEvent`]), the details
+//! pane shows the request and response of the highlighted test, and tests
+//! can be filtered and re-run without leaving the terminal.
+//!
+//! A re-run parses the test's file again, so edits made in the meantime are
+//! picked up. Every run of the session shares one [`ExecutionContext`], so
+//! values captured by earlier tests stay available to the ones re-run.
+
+mod app;
+mod ui;
+
+use anyhow::{bail, Result};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::DefaultTerminal;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+use crate::domain::{ExecutionContext, TestCase};
+use crate::executor::{Executor, TestEvent};
+use crate::runner::{RunOptions, Runner};
+use crate::template::Variables;
+use app::App;
+
+/// Progress of an entry of the dashboard, by entry index
+type EntryEvent = (usize, TestEvent);
+
+/// What a session needs to start runs
+struct Session {
+    options: RunOptions,
+    variables: Variables,
+    context: Arc<ExecutionContext>,
+    events: mpsc::UnboundedSender<EntryEvent>,
+}
+
+/// Open the dashboard for the suite at `path` and run it
+pub async fn run(path: &Path, options: RunOptions) -> Result<()> {
+    let variables = Runner::variables(path, &options)?;
+    let mut all_tests = Runner::discover(path, &variables)?;
+    Runner::focus(&mut all_tests);
+    if all_tests.is_empty() {
+        bail!("No tests found in {}", path.display());
+    }
+
+    let (events, entry_events) = mpsc::unbounded_channel();
+    let session = Session {
+        context: Runner::context(&options),
+        options,
+        variables,
+        events,
+    };
+
+    let mut app = App::new(&all_tests);
+    let batch = all_tests
+        .into_iter()
+        .flat_map(|(_, tests)| tests)
+        .enumerate()
+        .collect();
+    session.launch(&mut app, batch);
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app, &session, entry_events).await;
+    ratatui::restore();
+    result
+}
+
+async fn event_loop(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    session: &Session,
+    mut entry_events: mpsc::UnboundedReceiver<EntryEvent>,
+) -> Result<()> {
+    let mut input = read_input();
+
+    loop {
+        terminal.draw(|frame| ui::draw(frame, app))?;
+
+        tokio::select! {
+            Some((id, event)) = entry_events.recv() => app.apply(id, event),
+            Some(event) = input.recv() => {
+                if let Event::Key(key) = event {
+                    if !handle_key(app, session, key) {
+                        return Ok(());
+                    }
+                }
+            }
+            else => return Ok(()),
+        }
+    }
+}
+
+/// Terminal events, read on a dedicated thread since reading blocks
+fn read_input() -> mpsc::UnboundedReceiver<Event> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        while let Ok(event) = event::read() {
+            let release = matches!(&event, Event::Key(key) if key.kind != KeyEventKind::Press);
+            if !release && sender.send(event).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// Apply a key press; returns false to quit
+fn handle_key(app: &mut App, session: &Session, key: KeyEvent) -> bool {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        return false;
+    }
+
+    if app.filtering {
+        match key.code {
+            KeyCode::Enter => app.filtering = false,
+            KeyCode::Esc => {
+                app.filtering = false;
+                app.set_filter(String::new());
+            }
+            KeyCode::Backspace => {
+                let mut filter = app.filter.clone();
+                filter.pop();
+                app.set_filter(filter);
+            }
+            KeyCode::Char(c) => app.set_filter(format!("{}{}", app.filter, c)),
+            _ => {}
+        }
+        return true;
+    }
+
+    app.message = None;
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => return false,
+        KeyCode::Up | KeyCode::Char('k') => app.move_selection(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.move_selection(1),
+        KeyCode::PageUp => app.scroll_details(-10),
+        KeyCode::PageDown => app.scroll_details(10),
+        KeyCode::Char(' ') => app.toggle_mark(),
+        KeyCode::Char('/') => app.filtering = true,
+        KeyCode::Char('r') => {
+            let ids = app.rerun_targets();
+            session.rerun(app, ids);
+        }
+        KeyCode::Char('f') => {
+            let ids = app.failed();
+            session.rerun(app, ids);
+        }
+        KeyCode::Char('a') => {
+            let ids = (0..app.entries.len()).collect();
+            session.rerun(app, ids);
+        }
+        _ => {}
+    }
+    true
+}
+
+impl Session {
+    /// Run `batch` (entry index, test) in the background, reporting on
+    /// `self.events`
+    fn launch(&self, app: &mut App, batch: Vec<(usize, TestCase)>) {
+        let (ids, mut tests): (Vec<usize>, Vec<TestCase>) = batch.into_iter().unzip();
+        Runner::apply_defaults(&mut tests, &self.options);
+        app.queue(&ids);
+
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        tokio::spawn(Executor::stream_tests(
+            tests,
+            self.options.max_concurrency,
+            Arc::clone(&self.context),
+            sender,
+        ));
+
+        let events = self.events.clone();
+        let normalization = self.options.normalization.clone();
+        tokio::spawn(async move {
+            while let Some(event) = receiver.recv().await {
+                let event = match event {
+                    TestEvent::Started(index) => (ids[index], TestEvent::Started(index)),
+                    TestEvent::Finished(index, mut test) => {
+                        if let Some(response) = &mut test.response {
+                            response.normalize(&normalization);
+                        }
+                        (ids[index], TestEvent::Finished(index, test))
+                    }
+                };
+                if events.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Run `ids` again from a fresh parse of their files, leaving alone the
+    /// ones still running
+    fn rerun(&self, app: &mut App, ids: Vec<usize>) {
+        let (ids, busy): (Vec<usize>, Vec<usize>) = ids
+            .into_iter()
+            .partition(|&id| !app.entries[id].status.is_busy());
+        if !busy.is_empty() {
+            app.message = Some(format!(
+                "{} test(s) still running were left alone",
+                busy.len()
+            ));
+        }
+        if ids.is_empty() {
+            return;
+        }
+
+        match self.reload(app, &ids) {
+            Ok(batch) => self.launch(app, batch),
+            Err(error) => app.message = Some(format!("Cannot re-run: {:#}", error)),
+        }
+    }
+
+    /// Fresh copies of the tests `ids`, parsed again from their files
+    fn reload(&self, app: &App, ids: &[usize]) -> Result<Vec<(usize, TestCase)>> {
+        let mut files: HashMap<PathBuf, Vec<Option<TestCase>>> = HashMap::new();
+        let mut batch = Vec::with_capacity(ids.len());
+
+        for &id in ids {
+            let entry = &app.entries[id];
+            if !files.contains_key(&entry.file) {
+                let tests = Runner::discover(&entry.file, &self.variables)?
+                    .into_iter()
+                    .flat_map(|(_, tests)| tests)
+                    .map(Some)
+                    .collect();
+                files.insert(entry.file.clone(), tests);
+            }
+
+            let test = files.get_mut(&entry.file).and_then(|tests| {
+                tests
+                    .iter_mut()
+                    .find(|test| test.as_ref().is_some_and(|test| entry.is(test)))?
+                    .take()
+            });
+            let Some(test) = test else {
+                bail!(
+                    "'{}' is no longer in {}; restart to pick up added or renamed tests",
+                    entry.name,
+                    entry.file.display()
+                );
+            };
+            batch.push((id, test));
+        }
+
+        Ok(batch)
+    }
+}
@@ -0,0 +1,247 @@
+//! Drawing of the TUI: a status header, the test list, the details pane of
+//! the highlighted test and a key help line.
+
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::Frame;
+use std::collections::HashMap;
+
+use super::app::{App, Entry, Status};
+use crate::domain::http_request::{Body, FormPart};
+use crate::domain::test_case::TestResult;
+
+/// Longer bodies are cut in the details pane
+const MAX_BODY_LINES: usize = 500;
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let [header, main, footer] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(0),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [list, details] =
+        Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(main);
+
+    frame.render_widget(summary(app), header);
+
+    let visible = app.visible();
+    let items: Vec<ListItem> = visible
+        .iter()
+        .map(|&index| list_item(&app.entries[index]))
+        .collect();
+    let title = if app.filter.is_empty() {
+        format!(" Tests ({}) ", visible.len())
+    } else {
+        format!(" Tests ({}/{}) ", visible.len(), app.entries.len())
+    };
+    let mut state = ListState::default().with_selected(app.selected_entry().map(|_| app.selected));
+    frame.render_stateful_widget(
+        List::new(items)
+            .block(Block::bordered().title(title))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+        list,
+        &mut state,
+    );
+
+    let lines = app
+        .selected_entry()
+        .map(|index| details_lines(&app.entries[index]))
+        .unwrap_or_default();
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(Block::bordered().title(" Details "))
+            .wrap(Wrap { trim: false })
+            .scroll((app.scroll, 0)),
+        details,
+    );
+
+    frame.render_widget(footer_line(app), footer);
+}
+
+fn summary(app: &App) -> Line<'static> {
+    let busy = app.count(Status::Queued) + app.count(Status::Running);
+    Line::from(vec![
+        " Axotly ".bold(),
+        format!("✓ {} ", app.count(Status::Passed)).green(),
+        format!("✗ {} ", app.count(Status::Failed)).red(),
+        format!("○ {} ", app.count(Status::Skipped)).yellow(),
+        format!("● {} running", busy).cyan(),
+    ])
+}
+
+fn footer_line(app: &App) -> Line<'static> {
+    if app.filtering {
+        return Line::from(vec![
+            " Filter: ".bold(),
+            Span::raw(app.filter.clone()),
+            "█  (enter to keep, esc to clear)".dark_gray(),
+        ]);
+    }
+    if let Some(message) = &app.message {
+        return Line::from(format!(" {}", message).yellow());
+    }
+    Line::from(
+        " ↑↓ select · space mark · r re-run · f re-run failed · a re-run all · / filter · pgup/pgdn scroll · q quit"
+            .dark_gray(),
+    )
+}
+
+fn symbol(status: Status) -> Span<'static> {
+    match status {
+        Status::Idle => "·".dark_gray(),
+        Status::Queued => "…".dark_gray(),
+        Status::Running => "●".cyan(),
+        Status::Passed => "✓".green(),
+        Status::Failed => "✗".red(),
+        Status::Skipped => "○".yellow(),
+        Status::NotRun => "⏱".red(),
+    }
+}
+
+fn list_item(entry: &Entry) -> ListItem<'static> {
+    let mark = if entry.marked { "▸ " } else { "  " };
+    ListItem::new(Line::from(vec![
+        Span::raw(mark),
+        symbol(entry.status),
+        Span::raw(format!(" {}", entry.name)),
+    ]))
+}
+
+fn heading(text: &str) -> Line<'static> {
+    Line::from(text.to_string().bold().fg(Color::Magenta))
+}
+
+fn header_lines(headers: &HashMap<String, String>) -> Vec<Line<'static>> {
+    let mut headers: Vec<_> = headers.iter().collect();
+    headers.sort();
+    headers
+        .into_iter()
+        .map(|(name, value)| {
+            Line::from(vec![format!("{}: ", name).blue(), Span::raw(value.clone())])
+        })
+        .collect()
+}
+
+/// Body text, pretty-printed when it is JSON, at most [`MAX_BODY_LINES`]
+fn body_lines(text: &str) -> Vec<Line<'static>> {
+    let text = serde_json::from_str::<serde_json::Value>(text)
+        .ok()
+        .and_then(|json| serde_json::to_string_pretty(&json).ok())
+        .unwrap_or_else(|| text.to_string());
+
+    let mut lines: Vec<Line> = text
+        .lines()
+        .take(MAX_BODY_LINES)
+        .map(|line| Line::raw(line.to_string()))
+        .collect();
+    if text.lines().count() > MAX_BODY_LINES {
+        lines.push(Line::from("… (truncated)".dark_gray()));
+    }
+    lines
+}
+
+fn details_lines(entry: &Entry) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::from(entry.name.clone().bold()),
+        Line::from(entry.file.display().to_string().dark_gray()),
+    ];
+    if !entry.tags.is_empty() {
+        lines.push(Line::from(
+            format!("Tags: {}", entry.tags.join(", ")).dark_gray(),
+        ));
+    }
+    lines.push(Line::default());
+
+    let Some(test) = &entry.last else {
+        lines.push(Line::from(match entry.status {
+            Status::Queued => "Queued…",
+            Status::Running => "Running…",
+            _ => "Not run yet",
+        }));
+        return lines;
+    };
+
+    if entry.status.is_busy() {
+        lines.push(Line::from(
+            "Running again… (showing the previous run)".cyan(),
+        ));
+    }
+    match &test.result {
+        Some(TestResult::Passed { duration }) => lines.push(Line::from(
+            format!("✓ Passed in {} ms", duration.as_millis()).green(),
+        )),
+        Some(TestResult::Failed { duration, errors }) => {
+            lines.push(Line::from(
+                format!("✗ Failed in {} ms", duration.as_millis()).red(),
+            ));
+            for error in errors {
+                for (index, text) in error.to_string().lines().enumerate() {
+                    let prefix = if index == 0 { "  • " } else { "    " };
+                    lines.push(Line::from(format!("{}{}", prefix, text).red()));
+                }
+            }
+        }
+        Some(TestResult::Skipped { reason }) => lines.push(Line::from(
+            format!(
+                "○ Skipped{}",
+                reason
+                    .as_deref()
+                    .map(|r| format!(": {}", r))
+                    .unwrap_or_default()
+            )
+            .yellow(),
+        )),
+        Some(TestResult::NotRun { reason }) => {
+            lines.push(Line::from(format!("⏱ Not run: {}", reason).red()))
+        }
+        None => {}
+    }
+    for annotation in &test.annotations {
+        lines.push(Line::from(annotation.clone().dark_gray()));
+    }
+
+    let request = &test.request;
+    lines.push(Line::default());
+    lines.push(heading("Request"));
+    lines.push(Line::from(vec![
+        request.method.clone().bold(),
+        Span::raw(format!(" {}", request.url)),
+    ]));
+    lines.extend(header_lines(&request.headers));
+    match &request.body {
+        Some(Body::Text(text)) => lines.extend(body_lines(text)),
+        Some(Body::Json(json)) => lines.extend(body_lines(&json.to_string())),
+        Some(Body::Bytes(bytes)) => {
+            lines.push(Line::from(format!("<{} bytes>", bytes.len()).dark_gray()))
+        }
+        Some(Body::Stream(stream)) => {
+            lines.push(Line::from(format!("<stream: {}>", stream).dark_gray()))
+        }
+        Some(Body::Multipart(parts)) => lines.extend(parts.iter().map(|part| match part {
+            FormPart::Text { name, value } => Line::raw(format!("{} = {}", name, value)),
+            FormPart::File { name, path } => {
+                Line::from(format!("{} file={}", name, path.display()).dark_gray())
+            }
+        })),
+        None => {}
+    }
+
+    if let Some(response) = &test.response {
+        lines.push(Line::default());
+        lines.push(heading("Response"));
+        lines.push(Line::from(vec![
+            response.status.to_string().bold(),
+            format!("  {} ms", response.duration.as_millis()).dark_gray(),
+        ]));
+        lines.extend(header_lines(&response.headers));
+        if let Some(body) = &response.body {
+            lines.push(Line::default());
+            lines.extend(body_lines(body));
+        }
+    }
+
+    lines
+}